@@ -1,19 +1,251 @@
 //! Logic for unpacking and packing Winamp skins from archive (zip) format
 
 use std::collections::HashMap;
+use std::fmt::{self, Write as _};
 use std::fs;
 use std::fs::File;
-use std::io::{Cursor, Read, Write};
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 use std::path::Path;
 
 use zip::ZipArchive;
 
-use crate::error::Result;
-use crate::sprites::SpriteManager;
+use crate::cursor::CursorRole;
+use crate::error::{Result, WszError};
+use crate::options::ResourceLimits;
+use crate::sprites::{SpriteManager, EXTRA_BITMAPS, OTHER_BITMAPS, TEXT_FILES};
+use crate::util::{json_array, json_string};
 use image::ImageFormat;
 
+/// Extensions of the sprite images `pack_wsz_dir` reads. WebP sprites need the `webp` feature.
+const SPRITE_EXTENSIONS: [&str; 3] = ["png", "bmp", "webp"];
+/// Extensions of readmes and other documents
+const DOCUMENT_EXTENSIONS: [&str; 7] = ["txt", "nfo", "diz", "htm", "html", "rtf", "doc"];
+/// Extensions of text files that are read by programs rather than people
+const SETTINGS_EXTENSIONS: [&str; 2] = ["ini", "xml"];
+/// Extensions of screenshots and other images
+const IMAGE_EXTENSIONS: [&str; 6] = ["bmp", "png", "jpg", "jpeg", "gif", "ico"];
+/// Extensions of cursors, besides the names in CursorRole
+const CURSOR_EXTENSIONS: [&str; 2] = ["cur", "ani"];
+
 pub type WszArchive = HashMap<String, Vec<u8>>;
 
+/// Date and time a file in an archive was last modified, as zip files store it: in local time, to two seconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ZipTimestamp {
+    /// Year, from 1980 to 2107
+    pub year: u16,
+    /// Month, from 1 to 12
+    pub month: u8,
+    /// Day of the month, from 1
+    pub day: u8,
+    /// Hour, from 0 to 23
+    pub hour: u8,
+    /// Minute, from 0 to 59
+    pub minute: u8,
+    /// Second, from 0 to 58
+    pub second: u8,
+}
+
+impl ZipTimestamp {
+    fn from_zip(time: zip::DateTime) -> Self {
+        Self {
+            year: time.year(),
+            month: time.month(),
+            day: time.day(),
+            hour: time.hour(),
+            minute: time.minute(),
+            second: time.second(),
+        }
+    }
+
+    /// None if the timestamp is outside what zip files can store
+    fn to_zip(self) -> Option<zip::DateTime> {
+        zip::DateTime::from_date_and_time(self.year, self.month, self.day, self.hour, self.minute, self.second).ok()
+    }
+}
+
+impl fmt::Display for ZipTimestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// A file in an archive, as listed in the archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// Name of the file, including any directories
+    pub name: String,
+    /// When the file was last modified, or None if the archive does not say
+    pub modified: Option<ZipTimestamp>,
+}
+
+/// What a file in a skin is, judged by its name. `EntryKind` and `Wsz::strip` both classify files with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FileKind {
+    /// A sprite sheet or another bitmap Winamp reads, e.g. GENEX.BMP or VIDEO.BMP
+    Bitmap,
+    /// viscolor.txt, pledit.txt or region.txt
+    Config,
+    /// A readme or other document
+    Document,
+    /// An .ini, .xml or other text file that is not a document
+    Settings,
+    /// A screenshot or other image
+    Image,
+    /// A cursor
+    Cursor,
+    /// An AVS preset or AVS.BMP
+    Avs,
+    Other,
+}
+
+impl FileKind {
+    /// Classifies a file by its name and extension, ignoring case
+    pub(crate) fn of(path: &str) -> Self {
+        let name = path.split('/').next_back().unwrap_or(path);
+        let is_named = |known: &str| known.eq_ignore_ascii_case(name);
+        let sheets = SpriteManager::sprite_sheet_names();
+        let mut bitmaps = sheets
+            .iter()
+            .map(String::as_str)
+            .chain(OTHER_BITMAPS)
+            .chain(EXTRA_BITMAPS);
+        if bitmaps.any(is_named) {
+            return FileKind::Bitmap;
+        }
+        if TEXT_FILES.into_iter().any(is_named) {
+            return FileKind::Config;
+        }
+        if is_named("avs.bmp") {
+            return FileKind::Avs;
+        }
+        if CursorRole::ALL.iter().map(|role| role.file_name()).any(is_named) {
+            return FileKind::Cursor;
+        }
+
+        let extension = name
+            .rsplit_once('.')
+            .map_or(String::new(), |(_, ext)| ext.to_lowercase());
+        let extension = extension.as_str();
+        if extension == "avs" {
+            FileKind::Avs
+        } else if CURSOR_EXTENSIONS.contains(&extension) {
+            FileKind::Cursor
+        } else if DOCUMENT_EXTENSIONS.contains(&extension) {
+            FileKind::Document
+        } else if SETTINGS_EXTENSIONS.contains(&extension) {
+            FileKind::Settings
+        } else if IMAGE_EXTENSIONS.contains(&extension) {
+            FileKind::Image
+        } else {
+            FileKind::Other
+        }
+    }
+}
+
+/// What an archive entry is, judged by its name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntryKind {
+    /// A sprite sheet, or another bitmap Winamp reads such as GENEX.BMP or VIDEO.BMP
+    Sheet,
+    /// A text configuration file, readme or other document
+    Text,
+    /// A cursor
+    Cursor,
+    /// Anything else, e.g. screenshots and AVS presets
+    Other,
+}
+
+impl EntryKind {
+    /// Classify an archive entry
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Name of the entry, including any directories
+    ///
+    /// # Returns
+    ///
+    /// The kind of file, by name and extension, ignoring case
+    pub fn of(path: &str) -> Self {
+        match FileKind::of(path) {
+            FileKind::Bitmap => EntryKind::Sheet,
+            FileKind::Config | FileKind::Document | FileKind::Settings => EntryKind::Text,
+            FileKind::Cursor => EntryKind::Cursor,
+            FileKind::Image | FileKind::Avs | FileKind::Other => EntryKind::Other,
+        }
+    }
+}
+
+impl fmt::Display for EntryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntryKind::Sheet => write!(f, "sheet"),
+            EntryKind::Text => write!(f, "text"),
+            EntryKind::Cursor => write!(f, "cursor"),
+            EntryKind::Other => write!(f, "other"),
+        }
+    }
+}
+
+/// A file in an archive with the sizes and checksum its zip entry records, as `list_wsz` gives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveListing {
+    /// Name of the file, including any directories
+    pub name: String,
+    /// What the file is
+    pub kind: EntryKind,
+    /// Size of the file in bytes
+    pub size: u64,
+    /// Size of the file in the archive in bytes
+    pub compressed_size: u64,
+    /// CRC-32 of the file's contents, as the archive records it
+    pub crc32: u32,
+    /// Compression method, e.g. "Deflated" or "Stored"
+    pub compression: String,
+    /// When the file was last modified, or None if the archive does not say
+    pub modified: Option<ZipTimestamp>,
+}
+
+/// Details of an archive besides its files' contents: the order of the files, when each was modified, and the
+/// archive's comment
+///
+/// Old skins are often dated only by their files' timestamps, so keeping them lets collections repack skins without
+/// losing when they were made.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArchiveProvenance {
+    /// Files in the order they are stored in the archive. Folders are left out.
+    pub entries: Vec<ArchiveEntry>,
+    /// Comment stored at the end of the archive, usually text in the author's code page
+    pub comment: Vec<u8>,
+}
+
+impl ArchiveProvenance {
+    /// Get when a file was last modified
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the file, as in the archive
+    ///
+    /// # Returns
+    ///
+    /// The timestamp, or None if the file is not in the archive or has no timestamp
+    pub fn get_modified(&self, name: &str) -> Option<ZipTimestamp> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .and_then(|entry| entry.modified)
+    }
+
+    /// Get the latest timestamp of any file, which is usually when the skin was finished
+    pub fn get_latest_modified(&self) -> Option<ZipTimestamp> {
+        self.entries.iter().filter_map(|entry| entry.modified).max()
+    }
+}
+
 /// Unpacks a Winamp skin file (.wsz) into memory
 ///
 /// # Arguments
@@ -24,9 +256,113 @@ pub type WszArchive = HashMap<String, Vec<u8>>;
 ///
 /// A Result containing a HashMap of file names to their contents as bytes
 pub fn unpack_wsz<P: AsRef<Path>>(path: P) -> Result<WszArchive> {
-    let file = File::open(path)?;
-    let mut archive = ZipArchive::new(file)?;
+    unpack_wsz_reader(File::open(path)?)
+}
+
+/// Unpacks a Winamp skin file (.wsz) from a byte array in memory
+///
+/// # Arguments
+///
+/// * `data` - Byte slice containing the WSZ file data
+///
+/// # Returns
+///
+/// A Result containing a HashMap of file names to their contents as bytes
+pub fn unpack_wsz_bytes(data: &[u8]) -> Result<WszArchive> {
+    unpack_wsz_reader(Cursor::new(data))
+}
+
+/// Unpacks a Winamp skin file (.wsz) without blocking an async runtime
+///
+/// The file is read with `tokio::fs` and unzipped on tokio's blocking thread pool. Requires the `tokio` feature.
+///
+/// # Arguments
+///
+/// * `path` - Path to the Winamp skin file
+///
+/// # Returns
+///
+/// A Result containing a HashMap of file names to their contents as bytes
+#[cfg(feature = "tokio")]
+pub async fn unpack_wsz_async<P: AsRef<Path>>(path: P) -> Result<WszArchive> {
+    let data = tokio::fs::read(path).await?;
+    tokio::task::spawn_blocking(move || unpack_wsz_bytes(&data))
+        .await
+        .map_err(std::io::Error::from)?
+}
+
+/// Unpacks a Winamp skin file (.wsz) from any seekable stream, such as a downloaded file or a member of another
+/// archive
+///
+/// # Arguments
+///
+/// * `reader` - Stream containing the WSZ file data
+///
+/// # Returns
+///
+/// A Result containing a HashMap of file names to their contents as bytes
+pub fn unpack_wsz_reader<R: Read + Seek>(reader: R) -> Result<WszArchive> {
+    unpack_wsz_reader_with_limits(reader, &ResourceLimits::default())
+}
+
+/// Unpacks a Winamp skin file (.wsz) from any seekable stream, refusing archives larger than the limits
+///
+/// Sizes are checked as files are read, so archives that misstate their sizes are caught too.
+///
+/// # Arguments
+///
+/// * `reader` - Stream containing the WSZ file data
+/// * `limits` - Limits on the number and size of files. `max_image_pixels` is not checked here.
+///
+/// # Returns
+///
+/// A Result containing a HashMap of file names to their contents as bytes
+pub fn unpack_wsz_reader_with_limits<R: Read + Seek>(reader: R, limits: &ResourceLimits) -> Result<WszArchive> {
+    unpack_wsz_reader_with_provenance(reader, limits).map(|(contents, _)| contents)
+}
+
+/// Unpacks a Winamp skin file (.wsz) from any seekable stream, keeping the order of the files, their timestamps and
+/// the archive's comment
+///
+/// # Arguments
+///
+/// * `reader` - Stream containing the WSZ file data
+/// * `limits` - Limits on the number and size of files, as for `unpack_wsz_reader_with_limits`
+///
+/// # Returns
+///
+/// A Result containing a HashMap of file names to their contents as bytes, and the archive's provenance
+pub fn unpack_wsz_reader_with_provenance<R: Read + Seek>(
+    mut reader: R,
+    limits: &ResourceLimits,
+) -> Result<(WszArchive, ArchiveProvenance)> {
+    if let Some(max_size) = limits.max_archive_size {
+        let start = reader.stream_position()?;
+        let end = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(start))?;
+        if end.saturating_sub(start) > max_size {
+            return Err(WszError::LimitExceeded(format!(
+                "archive is larger than {} bytes",
+                max_size
+            )));
+        }
+    }
+
+    let mut archive = ZipArchive::new(reader)?;
     let mut contents = HashMap::new();
+    let mut provenance = ArchiveProvenance {
+        entries: Vec::new(),
+        comment: archive.comment().to_vec(),
+    };
+    let mut total_size: u64 = 0;
+
+    if let Some(max_files) = limits.max_files.filter(|&max_files| archive.len() > max_files) {
+        return Err(WszError::LimitExceeded(format!(
+            "archive has {} files, more than {}",
+            archive.len(),
+            max_files
+        )));
+    }
 
     for i in 0..archive.len() {
         let mut file = archive.by_index(i)?;
@@ -37,52 +373,174 @@ pub fn unpack_wsz<P: AsRef<Path>>(path: P) -> Result<WszArchive> {
             continue;
         }
 
+        // read one byte past the limit to tell files at the limit from larger ones
         let mut data = Vec::new();
-        file.read_to_end(&mut data)?;
+        match limits.max_file_size {
+            Some(max_size) => {
+                (&mut file).take(max_size.saturating_add(1)).read_to_end(&mut data)?;
+                if data.len() as u64 > max_size {
+                    return Err(WszError::LimitExceeded(format!(
+                        "{} is larger than {} bytes",
+                        name, max_size
+                    )));
+                }
+            }
+            None => {
+                file.read_to_end(&mut data)?;
+            }
+        }
+
+        total_size += data.len() as u64;
+        if let Some(max_total) = limits.max_total_size.filter(|&max_total| total_size > max_total) {
+            return Err(WszError::LimitExceeded(format!(
+                "archive is larger than {} bytes uncompressed",
+                max_total
+            )));
+        }
+        provenance.entries.push(ArchiveEntry {
+            name: name.clone(),
+            modified: file.last_modified().map(ZipTimestamp::from_zip),
+        });
         contents.insert(name, data);
     }
 
-    Ok(contents)
+    Ok((contents, provenance))
 }
 
-/// Unpacks a Winamp skin file (.wsz) from a byte array in memory
+/// Lists the files in a Winamp skin file (.wsz) without unpacking them
 ///
 /// # Arguments
 ///
-/// * `data` - Byte slice containing the WSZ file data
+/// * `path` - Path to the Winamp skin file
 ///
 /// # Returns
 ///
-/// A Result containing a HashMap of file names to their contents as bytes
-pub fn unpack_wsz_bytes(data: &[u8]) -> Result<WszArchive> {
-    // Create a cursor to read the data from memory
-    let cursor = Cursor::new(data);
-
-    // Open the zip archive from the cursor
-    let mut archive = ZipArchive::new(cursor)?;
-    let mut contents = HashMap::new();
+/// A Result containing each file's name, kind, sizes, CRC and compression, in the order they are stored. Folders
+/// are left out.
+pub fn list_wsz<P: AsRef<Path>>(path: P) -> Result<Vec<ArchiveListing>> {
+    list_wsz_reader(File::open(path)?)
+}
 
-    // Process each file in the archive
+/// Lists the files in a Winamp skin file (.wsz) from any seekable stream without unpacking them, like `list_wsz`
+///
+/// # Arguments
+///
+/// * `reader` - Stream containing the WSZ file data
+///
+/// # Returns
+///
+/// A Result containing each file's name, kind, sizes, CRC and compression, in the order they are stored
+pub fn list_wsz_reader<R: Read + Seek>(reader: R) -> Result<Vec<ArchiveListing>> {
+    let mut archive = ZipArchive::new(reader)?;
+    let mut listing = Vec::with_capacity(archive.len());
     for i in 0..archive.len() {
-        let mut file = archive.by_index(i)?;
-        let name = file.name().to_string();
-
-        // Skip directories
+        // entries are not decompressed, so files in unsupported formats can still be listed
+        let file = archive.by_index_raw(i)?;
         if file.is_dir() {
             continue;
         }
-
-        // Read the file contents
-        let mut data = Vec::new();
-        file.read_to_end(&mut data)?;
-        contents.insert(name, data);
+        listing.push(ArchiveListing {
+            name: file.name().to_string(),
+            kind: EntryKind::of(file.name()),
+            size: file.size(),
+            compressed_size: file.compressed_size(),
+            crc32: file.crc32(),
+            compression: file.compression().to_string(),
+            modified: file.last_modified().map(ZipTimestamp::from_zip),
+        });
     }
+    Ok(listing)
+}
 
-    Ok(contents)
+/// Describes an archive's files as JSON
+///
+/// The document has an `entries` array with each file's `name`, `type` ("sheet", "text", "cursor" or "other"),
+/// `size`, `compressed_size`, `crc32` as 8 hex digits, `compression` method and `modified` time or null.
+///
+/// # Arguments
+///
+/// * `listing` - Files, as `list_wsz` gives
+///
+/// # Returns
+///
+/// The JSON document
+pub fn listing_json(listing: &[ArchiveListing]) -> String {
+    let items: Vec<String> = listing
+        .iter()
+        .map(|entry| {
+            let modified = entry
+                .modified
+                .map_or("null".to_string(), |modified| json_string(&modified.to_string()));
+            format!(
+                concat!(
+                    r#"    {{"name": {}, "type": "{}", "size": {}, "compressed_size": {}, "crc32": "{:08x}", "#,
+                    r#""compression": {}, "modified": {}}}"#
+                ),
+                json_string(&entry.name),
+                entry.kind,
+                entry.size,
+                entry.compressed_size,
+                entry.crc32,
+                json_string(&entry.compression),
+                modified
+            )
+        })
+        .collect();
+
+    let mut json = String::new();
+    let _ = writeln!(json, "{{");
+    let _ = writeln!(json, r#"  "entries": {}"#, json_array(&items));
+    let _ = writeln!(json, "}}");
+    json
+}
+
+/// Downloads a Winamp skin file (.wsz), stopping as soon as it is larger than `limits.max_archive_size`
+///
+/// Requires the `http` feature.
+///
+/// # Arguments
+///
+/// * `url` - URL of the skin, over HTTP or HTTPS
+/// * `limits` - Limits on the skin's size. Only `max_archive_size` is checked here.
+///
+/// # Returns
+///
+/// A Result containing the WSZ file data, or an error if the server does not return it
+#[cfg(feature = "http")]
+pub fn download_wsz(url: &str, limits: &ResourceLimits) -> Result<Vec<u8>> {
+    let response = ureq::get(url).call().map_err(|err| WszError::Http(err.to_string()))?;
+    let too_large = |max_size| WszError::LimitExceeded(format!("{} is larger than {} bytes", url, max_size));
+
+    let length = response
+        .header("Content-Length")
+        .and_then(|length| length.parse::<u64>().ok());
+    let mut data = Vec::new();
+    match limits.max_archive_size {
+        Some(max_size) => {
+            // servers may leave out or misstate the length, so the download is cut off at the limit too
+            if length.is_some_and(|length| length > max_size) {
+                return Err(too_large(max_size));
+            }
+            response
+                .into_reader()
+                .take(max_size.saturating_add(1))
+                .read_to_end(&mut data)?;
+            if data.len() as u64 > max_size {
+                return Err(too_large(max_size));
+            }
+        }
+        None => {
+            response.into_reader().read_to_end(&mut data)?;
+        }
+    }
+    Ok(data)
 }
 
 /// Packs a directory containing Winamp sprite files back into a WSZ file
 ///
+/// Sprites are read from a folder per sprite sheet, e.g. MAIN/MAIN_PLAY_BUTTON.png, as PNG, BMP or WebP images.
+/// Sprites that cannot be decoded and sheets that cannot be drawn are left out of the skin.
+///
 /// # Arguments
 ///
 /// * `dir_path` - Path to the directory containing the extracted skin
@@ -92,12 +550,121 @@ pub fn unpack_wsz_bytes(data: &[u8]) -> Result<WszArchive> {
 ///
 /// A Result indicating success or failure
 pub fn pack_wsz_dir<P: AsRef<Path>>(dir_path: P, output_path: P) -> Result<()> {
-    let dir_path = dir_path.as_ref();
-    let output_path = output_path.as_ref();
+    pack_wsz_dir_with_warnings(dir_path, output_path).map(|_| ())
+}
 
+/// Packs a directory containing Winamp sprite files back into a WSZ file, like `pack_wsz_dir`, listing what was left
+/// out
+///
+/// # Arguments
+///
+/// * `dir_path` - Path to the directory containing the extracted skin
+/// * `output_path` - Path where the WSZ file will be saved
+///
+/// # Returns
+///
+/// A warning for each sprite or sheet that was left out, or an error if the skin cannot be written
+pub fn pack_wsz_dir_with_warnings<P: AsRef<Path>>(dir_path: P, output_path: P) -> Result<Vec<String>> {
     // Create a file to write the ZIP/WSZ data
-    let file = File::create(output_path)?;
-    let mut zip = zip::ZipWriter::new(file);
+    let file = File::create(output_path.as_ref())?;
+    pack_dir_to(dir_path.as_ref(), file)
+}
+
+/// Packs a directory containing Winamp sprite files into WSZ file data in memory, like `pack_wsz_dir`
+///
+/// # Arguments
+///
+/// * `dir_path` - Path to the directory containing the extracted skin
+///
+/// # Returns
+///
+/// The contents of a .wsz file, and a warning for each sprite or sheet that was left out
+pub fn pack_wsz_dir_bytes<P: AsRef<Path>>(dir_path: P) -> Result<(Vec<u8>, Vec<String>)> {
+    let mut data = Vec::new();
+    let warnings = pack_dir_to(dir_path.as_ref(), Cursor::new(&mut data))?;
+    Ok((data, warnings))
+}
+
+/// Packs a directory of whole sprite sheet images into a WSZ file, for skins drawn a sheet at a time
+///
+/// Images named after a sprite sheet or GENEX.BMP, e.g. MAIN.png or eqmain.webp, are converted to 24 bit BMPs
+/// named the way Winamp's base skin names them, e.g. MAIN.BMP, dropping any transparency. BMPs are kept as they
+/// are. Other files at the top of the directory, such as pledit.txt and cursors, are added unchanged, and folders
+/// are skipped.
+///
+/// # Arguments
+///
+/// * `dir_path` - Path to the directory of sprite sheets
+/// * `output_path` - Path where the WSZ file will be saved
+///
+/// # Returns
+///
+/// A Result indicating success, or an error if an image cannot be decoded or two images are the same sheet
+pub fn pack_wsz_sheets_dir<P: AsRef<Path>>(dir_path: P, output_path: P) -> Result<()> {
+    fs::write(output_path, pack_wsz_sheets_dir_bytes(dir_path)?)?;
+    Ok(())
+}
+
+/// Packs a directory of whole sprite sheet images into WSZ file data in memory, like `pack_wsz_sheets_dir`
+///
+/// # Arguments
+///
+/// * `dir_path` - Path to the directory of sprite sheets
+///
+/// # Returns
+///
+/// The contents of a .wsz file, with files in name order
+pub fn pack_wsz_sheets_dir_bytes<P: AsRef<Path>>(dir_path: P) -> Result<Vec<u8>> {
+    let sheet_names = SpriteManager::sprite_sheet_names();
+    let mut archive = WszArchive::new();
+    let mut sources: HashMap<String, String> = HashMap::new();
+
+    let mut entries = fs::read_dir(dir_path.as_ref())?
+        .filter_map(|e| e.ok())
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|a| a.path());
+    for entry in entries {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let (stem, extension) = file_name.rsplit_once('.').unwrap_or((&file_name, ""));
+        let sheet = format!("{}.BMP", stem.to_uppercase());
+        let is_sheet = sheet_names
+            .iter()
+            .map(String::as_str)
+            .chain(OTHER_BITMAPS)
+            .any(|name| name == sheet);
+        let is_image = SPRITE_EXTENSIONS
+            .iter()
+            .any(|sprite_extension| extension.eq_ignore_ascii_case(sprite_extension));
+        if !(is_sheet && is_image) {
+            archive.insert(file_name, fs::read(&path)?);
+            continue;
+        }
+
+        if let Some(other) = sources.insert(sheet.clone(), file_name.clone()) {
+            return Err(WszError::ArgumentError(format!(
+                "both {} and {} are {}",
+                other, file_name, sheet
+            )));
+        }
+        let data = if extension.eq_ignore_ascii_case("bmp") {
+            fs::read(&path)?
+        } else {
+            crate::encode_bmp(&image::open(&path)?)?
+        };
+        archive.insert(sheet, data);
+    }
+
+    pack_wsz_bytes(&archive)
+}
+
+/// Packs a directory containing Winamp sprite files into a zip written to `writer`, returning what was left out
+fn pack_dir_to<W: Write + Seek>(dir_path: &Path, writer: W) -> Result<Vec<String>> {
+    let mut zip = zip::ZipWriter::new(writer);
+    let mut warnings = Vec::new();
 
     // Options for file compression
     let options = zip::write::SimpleFileOptions::default()
@@ -111,7 +678,7 @@ pub fn pack_wsz_dir<P: AsRef<Path>>(dir_path: P, output_path: P) -> Result<()> {
     let mut entries = fs::read_dir(dir_path)?.filter_map(|e| e.ok()).collect::<Vec<_>>();
 
     // Sort entries for consistent output
-    entries.sort_by(|a, b| a.path().cmp(&b.path()));
+    entries.sort_by_key(|a| a.path());
 
     // First, collect all sprites by BMP file
     let mut sprites_by_sheet = HashMap::new();
@@ -150,7 +717,12 @@ pub fn pack_wsz_dir<P: AsRef<Path>>(dir_path: P, output_path: P) -> Result<()> {
 
             for subentry in subdir_entries {
                 let sprite_path = subentry.path();
-                if sprite_path.is_file() && sprite_path.extension().map_or(false, |ext| ext == "png") {
+                let is_image = sprite_path.extension().is_some_and(|ext| {
+                    SPRITE_EXTENSIONS
+                        .iter()
+                        .any(|sprite_extension| ext.eq_ignore_ascii_case(sprite_extension))
+                });
+                if sprite_path.is_file() && is_image {
                     let sprite_name = sprite_path
                         .file_stem()
                         .and_then(|s| s.to_str())
@@ -169,7 +741,7 @@ pub fn pack_wsz_dir<P: AsRef<Path>>(dir_path: P, output_path: P) -> Result<()> {
                             sprite_images.insert(sprite_name, rgba_img);
                         }
                         Err(e) => {
-                            eprintln!("Error loading sprite {}: {}", sprite_path.display(), e);
+                            warnings.push(format!("Error loading sprite {}: {}", sprite_path.display(), e));
                         }
                     }
                 }
@@ -192,14 +764,14 @@ pub fn pack_wsz_dir<P: AsRef<Path>>(dir_path: P, output_path: P) -> Result<()> {
                 sprite_sheet.write_to(&mut cursor, ImageFormat::Bmp)?;
 
                 // Add the BMP to the ZIP
-                let file_name = format!("{}", sheet_name);
+                let file_name = sheet_name.to_string();
                 zip.start_file(&file_name, options)?;
                 zip.write_all(&bmp_data)?;
 
                 added_files.insert(file_name);
             }
             Err(e) => {
-                eprintln!("Error reconstructing {}.BMP: {}", sheet_name, e);
+                warnings.push(format!("Error reconstructing {}: {}", sheet_name, e));
             }
         }
     }
@@ -233,5 +805,69 @@ pub fn pack_wsz_dir<P: AsRef<Path>>(dir_path: P, output_path: P) -> Result<()> {
     // Finalize the ZIP file
     zip.finish()?;
 
-    Ok(())
+    Ok(warnings)
+}
+
+/// Packs archive contents into a WSZ file in memory
+///
+/// # Arguments
+///
+/// * `archive` - File names and their contents. Names may include directories.
+///
+/// # Returns
+///
+/// A Result containing the WSZ file data, with files in name order
+pub fn pack_wsz_bytes(archive: &WszArchive) -> Result<Vec<u8>> {
+    pack_wsz_bytes_with_provenance(archive, &ArchiveProvenance::default())
+}
+
+/// Packs archive contents into a WSZ file in memory, restoring the order of the files, their timestamps and the
+/// comment of the archive they came from
+///
+/// Files keep their timestamps by name, even if their contents have changed.
+///
+/// # Arguments
+///
+/// * `archive` - File names and their contents. Names may include directories.
+/// * `provenance` - Details of the original archive, e.g. from `unpack_wsz_reader_with_provenance`
+///
+/// # Returns
+///
+/// A Result containing the WSZ file data. Files in `provenance` come first in their original order, followed by
+/// new files in name order.
+pub fn pack_wsz_bytes_with_provenance(archive: &WszArchive, provenance: &ArchiveProvenance) -> Result<Vec<u8>> {
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    zip.set_raw_comment(provenance.comment.clone().into_boxed_slice());
+
+    // Options for file compression
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .unix_permissions(0o644);
+
+    let mut entries: Vec<(&str, Option<ZipTimestamp>)> = Vec::new();
+    let mut listed = std::collections::HashSet::new();
+    for entry in &provenance.entries {
+        if archive.contains_key(&entry.name) && listed.insert(entry.name.as_str()) {
+            entries.push((&entry.name, entry.modified));
+        }
+    }
+    // Sort new names for consistent output
+    let mut names = archive
+        .keys()
+        .map(String::as_str)
+        .filter(|name| !listed.contains(name))
+        .collect::<Vec<_>>();
+    names.sort();
+    entries.extend(names.into_iter().map(|name| (name, None)));
+
+    for (name, modified) in entries {
+        let options = match modified.and_then(ZipTimestamp::to_zip) {
+            Some(modified) => options.last_modified_time(modified),
+            None => options,
+        };
+        zip.start_file(name, options)?;
+        zip.write_all(&archive[name])?;
+    }
+
+    Ok(zip.finish()?.into_inner())
 }
@@ -0,0 +1,265 @@
+//! Detection of sprites whose artwork runs over into the sprite next to them
+//!
+//! A classic authoring mistake is drawing a sprite one pixel too wide or too tall, so that its last row or column
+//! lands in the neighboring sprite's rectangle. Winamp then draws that line of pixels as part of the wrong sprite.
+//!
+//! Where two sprites touch, the sharpest change in color is normally right at the boundary between them. A sprite
+//! bleeds into its neighbor when its artwork carries on smoothly across the boundary and the sharp change is one
+//! line further into the neighbor instead. Sprites with a matching border on both sides are not reported, since
+//! the line is then likely the neighbor's own border.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::sprites::{SpriteDefinition, SpriteImage, SpriteManager};
+use crate::Wsz;
+
+/// Mean difference per channel below which two lines of pixels count as the same artwork
+const SMOOTH: f32 = 12.0;
+/// Mean difference per channel above which two lines of pixels count as an edge
+const EDGE: f32 = 40.0;
+/// Fewest pixels two sprites must share along their boundary to be compared
+const MIN_LENGTH: u32 = 3;
+
+/// A line of pixels on a sprite sheet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SheetLine {
+    /// Part of a column of pixels
+    Column {
+        /// X position of the column
+        x: u32,
+        /// Y position of the first pixel
+        y: u32,
+        /// Number of pixels
+        length: u32,
+    },
+    /// Part of a row of pixels
+    Row {
+        /// Y position of the row
+        y: u32,
+        /// X position of the first pixel
+        x: u32,
+        /// Number of pixels
+        length: u32,
+    },
+}
+
+impl fmt::Display for SheetLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SheetLine::Column { x, y, length } => write!(f, "column {} (y {} to {})", x, y, y + length - 1),
+            SheetLine::Row { y, x, length } => write!(f, "row {} (x {} to {})", y, x, x + length - 1),
+        }
+    }
+}
+
+/// A sprite whose artwork runs into a neighboring sprite's rectangle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpriteBleed {
+    /// Sprite sheet both sprites are on, e.g. "CBUTTONS.BMP"
+    pub sheet: String,
+    /// Sprite whose artwork runs over
+    pub sprite: String,
+    /// Sprite it runs into
+    pub into: String,
+    /// Line of `into` that looks like part of `sprite`, in sheet coordinates
+    pub line: SheetLine,
+}
+
+impl fmt::Display for SpriteBleed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} bleeds into {} at {}",
+            self.sheet, self.sprite, self.into, self.line
+        )
+    }
+}
+
+/// Mean difference per channel between two lines of pixels
+fn difference(a: &[[u8; 3]], b: &[[u8; 3]]) -> f32 {
+    let total: u32 = a
+        .iter()
+        .zip(b)
+        .map(|(a, b)| {
+            (0..3)
+                .map(|channel| a[channel].abs_diff(b[channel]) as u32)
+                .sum::<u32>()
+        })
+        .sum();
+    total as f32 / (a.len() * 3) as f32
+}
+
+/// Reads `length` pixels of a sprite's column `x` from row `y`, or None if they are outside the sprite
+fn column(sprite: &SpriteImage, x: u32, y: u32, length: u32) -> Option<Vec<[u8; 3]>> {
+    if x >= sprite.width() || y + length > sprite.height() {
+        return None;
+    }
+    Some((y..y + length).map(|y| rgb(sprite, x, y)).collect())
+}
+
+/// Reads `length` pixels of a sprite's row `y` from column `x`, or None if they are outside the sprite
+fn row(sprite: &SpriteImage, x: u32, y: u32, length: u32) -> Option<Vec<[u8; 3]>> {
+    if y >= sprite.height() || x + length > sprite.width() {
+        return None;
+    }
+    Some((x..x + length).map(|x| rgb(sprite, x, y)).collect())
+}
+
+fn rgb(sprite: &SpriteImage, x: u32, y: u32) -> [u8; 3] {
+    let [r, g, b, _] = sprite.get_pixel(x, y).0;
+    [r, g, b]
+}
+
+/// Lines of two touching sprites across their boundary: the first sprite's far edge and its two lines nearest the
+/// boundary, then the second sprite's two lines nearest the boundary and its far edge
+struct Boundary([Vec<[u8; 3]>; 6]);
+
+impl Boundary {
+    /// Reads the lines along a vertical boundary, `first` being on the left
+    fn vertical(first: &SpriteImage, second: &SpriteImage, first_y: u32, second_y: u32, length: u32) -> Option<Self> {
+        let (width, second_width) = (first.width(), second.width());
+        if width < 3 || second_width < 3 {
+            return None;
+        }
+        Some(Self([
+            column(first, 0, first_y, length)?,
+            column(first, width - 2, first_y, length)?,
+            column(first, width - 1, first_y, length)?,
+            column(second, 0, second_y, length)?,
+            column(second, 1, second_y, length)?,
+            column(second, second_width - 1, second_y, length)?,
+        ]))
+    }
+
+    /// Reads the lines along a horizontal boundary, `first` being on top
+    fn horizontal(first: &SpriteImage, second: &SpriteImage, first_x: u32, second_x: u32, length: u32) -> Option<Self> {
+        let (height, second_height) = (first.height(), second.height());
+        if height < 3 || second_height < 3 {
+            return None;
+        }
+        Some(Self([
+            row(first, first_x, 0, length)?,
+            row(first, first_x, height - 2, length)?,
+            row(first, first_x, height - 1, length)?,
+            row(second, second_x, 0, length)?,
+            row(second, second_x, 1, length)?,
+            row(second, second_x, second_height - 1, length)?,
+        ]))
+    }
+
+    /// Whether the first sprite's artwork runs into the second sprite's nearest line
+    fn first_bleeds(&self) -> bool {
+        let [_, inner, edge, bled, next, far] = &self.0;
+        bleeds(inner, edge, bled, next, far)
+    }
+
+    /// Whether the second sprite's artwork runs into the first sprite's nearest line
+    fn second_bleeds(&self) -> bool {
+        let [far, next, bled, edge, inner, _] = &self.0;
+        bleeds(inner, edge, bled, next, far)
+    }
+}
+
+/// Whether a sprite's artwork carries on from its `inner` and `edge` lines into a neighbor's `bled` line, with the
+/// neighbor's own artwork starting at its `next` line. `far` is the neighbor's opposite edge, which matches `bled`
+/// when the neighbor simply has a border on both sides.
+fn bleeds(inner: &[[u8; 3]], edge: &[[u8; 3]], bled: &[[u8; 3]], next: &[[u8; 3]], far: &[[u8; 3]]) -> bool {
+    difference(inner, edge) <= SMOOTH
+        && difference(edge, bled) <= SMOOTH
+        && difference(bled, next) >= EDGE
+        && difference(bled, far) > SMOOTH
+}
+
+/// Finds sprites whose artwork runs into a neighbor, sorted by sheet and sprite names
+pub(crate) fn sprite_bleed(skin: &Wsz) -> Vec<SpriteBleed> {
+    let sprite_manager = SpriteManager::new();
+    let mut definitions: Vec<&SpriteDefinition> = sprite_manager
+        .get_sprite_definitions()
+        .values()
+        .filter(|def| skin.sprites.contains_key(&def.name))
+        .collect();
+    definitions.sort_by(|a, b| (&a.sprite_sheet, &a.name).cmp(&(&b.sprite_sheet, &b.name)));
+    // some sprites are other names for the same rectangle, which would be reported twice
+    let mut rectangles = HashSet::new();
+    definitions.retain(|def| rectangles.insert((&def.sprite_sheet, def.x, def.y, def.width, def.height)));
+
+    let mut bleeds = Vec::new();
+    for (index, a) in definitions.iter().enumerate() {
+        for b in &definitions[index + 1..] {
+            if a.sprite_sheet != b.sprite_sheet {
+                continue;
+            }
+            let (a_sprite, b_sprite) = (&skin.sprites[&a.name], &skin.sprites[&b.name]);
+            for (first, second, first_sprite, second_sprite) in [(a, b, a_sprite, b_sprite), (b, a, b_sprite, a_sprite)]
+            {
+                bleeds.extend(check_pair(first, second, first_sprite, second_sprite));
+            }
+        }
+    }
+    bleeds.sort_by(|a, b| (&a.sheet, &a.sprite, &a.into).cmp(&(&b.sheet, &b.sprite, &b.into)));
+    bleeds
+}
+
+/// Checks two sprites where `second` is right of or below `first`
+fn check_pair(
+    first: &SpriteDefinition,
+    second: &SpriteDefinition,
+    first_sprite: &SpriteImage,
+    second_sprite: &SpriteImage,
+) -> Vec<SpriteBleed> {
+    let overlap = |start_a: u32, length_a: u32, start_b: u32, length_b: u32| {
+        let start = start_a.max(start_b);
+        let end = (start_a + length_a).min(start_b + length_b);
+        (end >= start + MIN_LENGTH).then(|| (start, end - start))
+    };
+    let bleed = |sprite: &SpriteDefinition, into: &SpriteDefinition, line: SheetLine| SpriteBleed {
+        sheet: sprite.sprite_sheet.clone(),
+        sprite: sprite.name.clone(),
+        into: into.name.clone(),
+        line,
+    };
+
+    let mut bleeds = Vec::new();
+    if first.x + first.width == second.x {
+        if let Some((y, length)) = overlap(first.y, first.height, second.y, second.height) {
+            let boundary = Boundary::vertical(first_sprite, second_sprite, y - first.y, y - second.y, length);
+            if let Some(boundary) = boundary {
+                if boundary.first_bleeds() {
+                    bleeds.push(bleed(first, second, SheetLine::Column { x: second.x, y, length }));
+                } else if boundary.second_bleeds() {
+                    bleeds.push(bleed(
+                        second,
+                        first,
+                        SheetLine::Column {
+                            x: second.x - 1,
+                            y,
+                            length,
+                        },
+                    ));
+                }
+            }
+        }
+    }
+    if first.y + first.height == second.y {
+        if let Some((x, length)) = overlap(first.x, first.width, second.x, second.width) {
+            let boundary = Boundary::horizontal(first_sprite, second_sprite, x - first.x, x - second.x, length);
+            if let Some(boundary) = boundary {
+                if boundary.first_bleeds() {
+                    bleeds.push(bleed(first, second, SheetLine::Row { y: second.y, x, length }));
+                } else if boundary.second_bleeds() {
+                    bleeds.push(bleed(
+                        second,
+                        first,
+                        SheetLine::Row {
+                            y: second.y - 1,
+                            x,
+                            length,
+                        },
+                    ));
+                }
+            }
+        }
+    }
+    bleeds
+}
@@ -0,0 +1,162 @@
+//! Building skins programmatically
+//!
+//! WszBuilder collects sprites, text settings and other files, then packs them into a `Wsz` or a .wsz file.
+//! Sprite sheets are drawn from the sprites that were set, so only the sprites a skin changes need to be given.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::archive::{self, WszArchive};
+use crate::error::{Result, WszError};
+use crate::sprites::{SpriteImage, SpriteManager};
+use crate::text::pledit::PleditSettings;
+use crate::text::region::Regions;
+use crate::text::viscolor::VisColors;
+use crate::Wsz;
+
+/// Builder for a Winamp skin
+#[derive(Debug, Clone, Default)]
+pub struct WszBuilder {
+    files: WszArchive,
+    sprites: HashMap<String, SpriteImage>,
+    vis_colors: Option<VisColors>,
+    pledit: Option<PleditSettings>,
+    regions: Option<Regions>,
+}
+
+impl WszBuilder {
+    /// Start an empty skin, which has no files until some are set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a skin with the visualization colors and playlist settings of Winamp's base skin
+    ///
+    /// The base skin's bitmaps are not included in this crate, so sprites still have to be set.
+    pub fn winamp_default() -> Self {
+        Self::new()
+            .with_vis_colors(VisColors::winamp_default())
+            .with_pledit_settings(PleditSettings::winamp_default())
+    }
+
+    /// Start from an existing skin, to use as a template
+    ///
+    /// # Arguments
+    ///
+    /// * `skin` - Skin whose files the new skin starts with
+    ///
+    /// # Returns
+    ///
+    /// A builder that builds a copy of the skin until something is changed
+    pub fn from_skin(skin: &Wsz) -> Result<Self> {
+        Ok(Self {
+            files: skin.to_archive()?,
+            ..Self::default()
+        })
+    }
+
+    /// Set a sprite, replacing the template's
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the sprite, e.g. "MAIN_PLAY_BUTTON"
+    /// * `image` - The sprite's image
+    pub fn with_sprite(mut self, name: &str, image: SpriteImage) -> Self {
+        self.sprites.insert(name.to_string(), image);
+        self
+    }
+
+    /// Set the visualization colors (viscolor.txt)
+    pub fn with_vis_colors(mut self, vis_colors: VisColors) -> Self {
+        self.vis_colors = Some(vis_colors);
+        self
+    }
+
+    /// Set the playlist editor settings (pledit.txt)
+    pub fn with_pledit_settings(mut self, pledit: PleditSettings) -> Self {
+        self.pledit = Some(pledit);
+        self
+    }
+
+    /// Set the window regions (region.txt)
+    pub fn with_regions(mut self, regions: Regions) -> Self {
+        self.regions = Some(regions);
+        self
+    }
+
+    /// Add a file as is, such as a readme, a cursor or a whole sprite sheet
+    ///
+    /// Sprites, viscolor.txt, pledit.txt and region.txt set on the builder take precedence over files added this
+    /// way.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Path of the file in the archive, e.g. "readme.txt"
+    /// * `data` - Contents of the file
+    pub fn with_file(mut self, name: &str, data: Vec<u8>) -> Self {
+        self.files.insert(name.to_string(), data);
+        self
+    }
+
+    /// Build the skin
+    ///
+    /// # Returns
+    ///
+    /// The skin, or an error if a sprite name is not known
+    pub fn build(self) -> Result<Wsz> {
+        Wsz::from_archive(&self.to_archive()?)
+    }
+
+    /// Build the skin as .wsz file data
+    ///
+    /// # Returns
+    ///
+    /// The contents of a .wsz file
+    pub fn to_bytes(self) -> Result<Vec<u8>> {
+        archive::pack_wsz_bytes(&self.to_archive()?)
+    }
+
+    /// Build the skin and write it to a .wsz file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to write the .wsz file to
+    ///
+    /// # Returns
+    ///
+    /// A Result indicating success or failure
+    pub fn save<P: AsRef<Path>>(self, path: P) -> Result<()> {
+        std::fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+
+    /// Build the skin as archive contents
+    ///
+    /// # Returns
+    ///
+    /// A map of file names to their contents
+    pub fn to_archive(self) -> Result<WszArchive> {
+        let sprite_manager = SpriteManager::new();
+        if let Some(name) = self
+            .sprites
+            .keys()
+            .find(|name| sprite_manager.get_sprite_definition(name).is_none())
+        {
+            return Err(WszError::ArgumentError(format!("Sprite {} not found", name)));
+        }
+
+        // load the files as a skin, then apply the changes so sheets are redrawn and text files rewritten
+        let mut skin = Wsz::from_archive(&self.files)?;
+        skin.sprites.extend(self.sprites);
+        if let Some(vis_colors) = self.vis_colors {
+            skin.set_vis_colors(vis_colors);
+        }
+        if let Some(pledit) = self.pledit {
+            skin.set_pledit_settings(pledit);
+        }
+        if let Some(regions) = self.regions {
+            skin.set_regions(regions);
+        }
+        skin.to_archive()
+    }
+}
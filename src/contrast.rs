@@ -0,0 +1,225 @@
+//! Contrast of a skin's text and visualizer colors, for checking that they are readable
+//!
+//! Ratios are computed as WCAG 2 defines them. Playlist text is held to the thresholds for normal text. Spectrum
+//! bars are graphics rather than text, so they are held to the lower thresholds WCAG gives large text.
+
+use std::fmt;
+
+use image::Rgb;
+
+use crate::text::pledit::PleditSettings;
+use crate::text::viscolor::VisColors;
+
+/// Least contrast for normal text at WCAG level AA
+pub const TEXT_AA: f64 = 4.5;
+/// Least contrast for normal text at WCAG level AAA
+pub const TEXT_AAA: f64 = 7.0;
+/// Least contrast for large text and graphics at WCAG level AA
+pub const GRAPHICS_AA: f64 = 3.0;
+/// Least contrast for large text at WCAG level AAA, also used for graphics
+pub const GRAPHICS_AAA: f64 = 4.5;
+
+/// Colors that are checked against each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContrastPair {
+    /// Playlist normal text on the normal background
+    PlaylistNormal,
+    /// Playlist current track text on the normal background
+    PlaylistCurrent,
+    /// Playlist normal text on the selected background
+    PlaylistNormalSelected,
+    /// Playlist current track text on the selected background
+    PlaylistCurrentSelected,
+    /// Minibrowser text (mbFG) on the minibrowser background (mbBG)
+    Minibrowser,
+    /// A spectrum analyzer color on the visualizer background, indexed like `VisColors::vis_color`
+    Spectrum(usize),
+}
+
+impl ContrastPair {
+    /// Whether the foreground is text, which needs more contrast than graphics
+    pub fn is_text(&self) -> bool {
+        !matches!(self, ContrastPair::Spectrum(_))
+    }
+}
+
+impl fmt::Display for ContrastPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContrastPair::PlaylistNormal => write!(f, "pledit.txt Normal on NormalBG"),
+            ContrastPair::PlaylistCurrent => write!(f, "pledit.txt Current on NormalBG"),
+            ContrastPair::PlaylistNormalSelected => write!(f, "pledit.txt Normal on SelectedBG"),
+            ContrastPair::PlaylistCurrentSelected => write!(f, "pledit.txt Current on SelectedBG"),
+            ContrastPair::Minibrowser => write!(f, "pledit.txt mbFG on mbBG"),
+            ContrastPair::Spectrum(value) => write!(f, "viscolor.txt spectrum color {} on background", value),
+        }
+    }
+}
+
+/// The contrast between one pair of colors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContrastCheck {
+    /// Which colors were checked
+    pub pair: ContrastPair,
+    /// Color drawn on top
+    pub foreground: Rgb<u8>,
+    /// Color drawn behind
+    pub background: Rgb<u8>,
+    /// Contrast ratio, from 1.0 (none) to 21.0 (black on white)
+    pub ratio: f64,
+    /// Whether the ratio meets WCAG level AA
+    pub passes_aa: bool,
+    /// Whether the ratio meets WCAG level AAA
+    pub passes_aaa: bool,
+}
+
+impl ContrastCheck {
+    fn new(pair: ContrastPair, foreground: Rgb<u8>, background: Rgb<u8>) -> Self {
+        let ratio = contrast_ratio(foreground, background);
+        let (aa, aaa) = if pair.is_text() {
+            (TEXT_AA, TEXT_AAA)
+        } else {
+            (GRAPHICS_AA, GRAPHICS_AAA)
+        };
+        Self {
+            pair,
+            foreground,
+            background,
+            ratio,
+            passes_aa: ratio >= aa,
+            passes_aaa: ratio >= aaa,
+        }
+    }
+}
+
+impl fmt::Display for ContrastCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = if self.passes_aaa {
+            "AAA"
+        } else if self.passes_aa {
+            "AA"
+        } else {
+            "fail"
+        };
+        write!(f, "{}: {:.2}:1 ({})", self.pair, self.ratio, level)
+    }
+}
+
+/// The contrast of every pair of colors `Wsz::contrast_report` checks
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContrastReport {
+    checks: Vec<ContrastCheck>,
+}
+
+impl ContrastReport {
+    /// Get every check, playlist text first
+    pub fn checks(&self) -> &[ContrastCheck] {
+        &self.checks
+    }
+
+    /// Get the check for a pair of colors, if the skin has both colors
+    pub fn get(&self, pair: ContrastPair) -> Option<&ContrastCheck> {
+        self.checks.iter().find(|check| check.pair == pair)
+    }
+
+    /// Get the checks that do not meet WCAG level AA
+    pub fn failures(&self) -> impl Iterator<Item = &ContrastCheck> {
+        self.checks.iter().filter(|check| !check.passes_aa)
+    }
+
+    /// Whether every check meets WCAG level AA
+    pub fn passes_aa(&self) -> bool {
+        self.checks.iter().all(|check| check.passes_aa)
+    }
+
+    /// Whether every check meets WCAG level AAA
+    pub fn passes_aaa(&self) -> bool {
+        self.checks.iter().all(|check| check.passes_aaa)
+    }
+
+    /// Get the check with the lowest ratio, or None if nothing was checked
+    pub fn worst(&self) -> Option<&ContrastCheck> {
+        self.checks.iter().min_by(|a, b| a.ratio.total_cmp(&b.ratio))
+    }
+}
+
+impl fmt::Display for ContrastReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            writeln!(f, "{}", check)?;
+        }
+        Ok(())
+    }
+}
+
+/// Computes the relative luminance of a color as WCAG 2 defines it
+///
+/// # Arguments
+///
+/// * `color` - An sRGB color
+///
+/// # Returns
+///
+/// The luminance, from 0.0 (black) to 1.0 (white)
+pub fn relative_luminance(color: Rgb<u8>) -> f64 {
+    let linear = |value: u8| {
+        let value = value as f64 / 255.0;
+        if value <= 0.04045 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let [r, g, b] = color.0;
+    0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b)
+}
+
+/// Computes the contrast ratio between two colors as WCAG 2 defines it
+///
+/// # Arguments
+///
+/// * `a` - One color
+/// * `b` - The other color. The order of the colors does not matter.
+///
+/// # Returns
+///
+/// The ratio, from 1.0 (the same luminance) to 21.0 (black and white)
+pub fn contrast_ratio(a: Rgb<u8>, b: Rgb<u8>) -> f64 {
+    let (a, b) = (relative_luminance(a), relative_luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// Checks the playlist text and spectrum colors of a skin
+///
+/// Playlist colors the skin does not set are checked with Winamp's defaults, as Winamp draws them. The
+/// minibrowser is only checked when both of its colors are set, and the spectrum only when viscolor.txt has a
+/// background color.
+pub(crate) fn contrast_report(pledit: &PleditSettings, vis_colors: &VisColors) -> ContrastReport {
+    let normal_bg = pledit.effective_normal_bg();
+    let selected_bg = pledit.effective_selected_bg();
+    let mut checks = vec![
+        ContrastCheck::new(ContrastPair::PlaylistNormal, pledit.effective_normal(), normal_bg),
+        ContrastCheck::new(ContrastPair::PlaylistCurrent, pledit.effective_current(), normal_bg),
+        ContrastCheck::new(
+            ContrastPair::PlaylistNormalSelected,
+            pledit.effective_normal(),
+            selected_bg,
+        ),
+        ContrastCheck::new(
+            ContrastPair::PlaylistCurrentSelected,
+            pledit.effective_current(),
+            selected_bg,
+        ),
+    ];
+    if let (Some(fg), Some(bg)) = (pledit.mb_fg, pledit.mb_bg) {
+        checks.push(ContrastCheck::new(ContrastPair::Minibrowser, fg, bg));
+    }
+    if let Some(bg) = vis_colors.bg_color() {
+        for value in 0..16 {
+            if let Some(color) = vis_colors.vis_color(value) {
+                checks.push(ContrastCheck::new(ContrastPair::Spectrum(value), color, bg));
+            }
+        }
+    }
+    ContrastReport { checks }
+}
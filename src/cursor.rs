@@ -0,0 +1,594 @@
+//! Decoder for the .cur cursor files in Winamp skins
+//!
+//! A .cur file uses the ICO container: a directory of images, each stored either as a PNG or as a headerless
+//! BMP with a 1-bit transparency mask below the color data. Cursor entries also give the hotspot, the pixel
+//! that lines up with the pointer position.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use image::{ImageFormat, Rgba, RgbaImage};
+
+use crate::archive::WszArchive;
+use crate::error::{Result, WszError};
+use crate::util::{json_array, json_string};
+
+/// Type field of an ICO directory for icons
+const ICO_TYPE_ICON: u16 = 1;
+
+/// Type field of an ICO directory for cursors
+const ICO_TYPE_CURSOR: u16 = 2;
+
+const ICO_DIR_SIZE: usize = 6;
+const ICO_ENTRY_SIZE: usize = 16;
+const BITMAP_INFO_HEADER_SIZE: usize = 40;
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+/// Largest width and height of a cursor image, well above the 256 pixels an ICO directory entry can describe
+const MAX_CURSOR_SIZE: u32 = 1024;
+
+/// BMP compression types that store pixels uncompressed
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+
+/// Where in Winamp a cursor is shown, named after the window and element it is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CursorRole {
+    /// Main window, anywhere without a more specific cursor (NORMAL.CUR)
+    Normal,
+    /// Main window close button (CLOSE.CUR)
+    Close,
+    /// Main window minimize button (MIN.CUR)
+    Min,
+    /// Main window shade button (WINBUT.CUR)
+    WindowButton,
+    /// Main window title bar (TITLEBAR.CUR)
+    Titlebar,
+    /// Main window menu button (MAINMENU.CUR)
+    MainMenu,
+    /// Song title display (SONGNAME.CUR)
+    SongName,
+    /// Seeking bar (POSBAR.CUR)
+    Posbar,
+    /// Volume and balance sliders (VOLBAL.CUR)
+    Volume,
+    /// Main window in shade mode (WSNORMAL.CUR)
+    ShadeNormal,
+    /// Close button in shade mode (WSCLOSE.CUR)
+    ShadeClose,
+    /// Minimize button in shade mode (WSMIN.CUR)
+    ShadeMin,
+    /// Shade button in shade mode (WSWINBUT.CUR)
+    ShadeWindowButton,
+    /// Seeking bar in shade mode (WSPOSBAR.CUR)
+    ShadePosbar,
+    /// Menu button in shade mode (MMENU.CUR)
+    ShadeMainMenu,
+    /// Equalizer window (EQNORMAL.CUR)
+    EqNormal,
+    /// Equalizer close button (EQCLOSE.CUR)
+    EqClose,
+    /// Equalizer title bar (EQTITLE.CUR)
+    EqTitlebar,
+    /// Equalizer sliders (EQSLID.CUR)
+    EqSlider,
+    /// Playlist window (PNORMAL.CUR)
+    PlaylistNormal,
+    /// Playlist close button (PCLOSE.CUR)
+    PlaylistClose,
+    /// Playlist title bar (PTBAR.CUR)
+    PlaylistTitlebar,
+    /// Playlist shade button (PWINBUT.CUR)
+    PlaylistWindowButton,
+    /// Playlist scrollbar (PVSCROLL.CUR)
+    PlaylistScroll,
+    /// Playlist resize corner (PSIZE.CUR)
+    PSize,
+    /// Playlist in shade mode (PWSNORM.CUR)
+    PlaylistShadeNormal,
+    /// Playlist resize corner in shade mode (PWSSIZE.CUR)
+    PlaylistShadeSize,
+}
+
+impl CursorRole {
+    /// Every role
+    pub const ALL: [CursorRole; 27] = [
+        Self::Normal,
+        Self::Close,
+        Self::Min,
+        Self::WindowButton,
+        Self::Titlebar,
+        Self::MainMenu,
+        Self::SongName,
+        Self::Posbar,
+        Self::Volume,
+        Self::ShadeNormal,
+        Self::ShadeClose,
+        Self::ShadeMin,
+        Self::ShadeWindowButton,
+        Self::ShadePosbar,
+        Self::ShadeMainMenu,
+        Self::EqNormal,
+        Self::EqClose,
+        Self::EqTitlebar,
+        Self::EqSlider,
+        Self::PlaylistNormal,
+        Self::PlaylistClose,
+        Self::PlaylistTitlebar,
+        Self::PlaylistWindowButton,
+        Self::PlaylistScroll,
+        Self::PSize,
+        Self::PlaylistShadeNormal,
+        Self::PlaylistShadeSize,
+    ];
+
+    /// Name of the file Winamp loads the cursor from
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Normal => "NORMAL.CUR",
+            Self::Close => "CLOSE.CUR",
+            Self::Min => "MIN.CUR",
+            Self::WindowButton => "WINBUT.CUR",
+            Self::Titlebar => "TITLEBAR.CUR",
+            Self::MainMenu => "MAINMENU.CUR",
+            Self::SongName => "SONGNAME.CUR",
+            Self::Posbar => "POSBAR.CUR",
+            Self::Volume => "VOLBAL.CUR",
+            Self::ShadeNormal => "WSNORMAL.CUR",
+            Self::ShadeClose => "WSCLOSE.CUR",
+            Self::ShadeMin => "WSMIN.CUR",
+            Self::ShadeWindowButton => "WSWINBUT.CUR",
+            Self::ShadePosbar => "WSPOSBAR.CUR",
+            Self::ShadeMainMenu => "MMENU.CUR",
+            Self::EqNormal => "EQNORMAL.CUR",
+            Self::EqClose => "EQCLOSE.CUR",
+            Self::EqTitlebar => "EQTITLE.CUR",
+            Self::EqSlider => "EQSLID.CUR",
+            Self::PlaylistNormal => "PNORMAL.CUR",
+            Self::PlaylistClose => "PCLOSE.CUR",
+            Self::PlaylistTitlebar => "PTBAR.CUR",
+            Self::PlaylistWindowButton => "PWINBUT.CUR",
+            Self::PlaylistScroll => "PVSCROLL.CUR",
+            Self::PSize => "PSIZE.CUR",
+            Self::PlaylistShadeNormal => "PWSNORM.CUR",
+            Self::PlaylistShadeSize => "PWSSIZE.CUR",
+        }
+    }
+
+    /// Returns the role of a cursor file, ignoring case and any directory, or None for other files
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let file_name = Path::new(name).file_name()?.to_str()?;
+        Self::ALL
+            .into_iter()
+            .find(|role| role.file_name().eq_ignore_ascii_case(file_name))
+    }
+}
+
+/// Find and decode the cursors in the archive contents
+///
+/// Files that cannot be decoded are left out, so Winamp's default cursor would be used for them.
+///
+/// # Arguments
+///
+/// * `archive` - Archive to find the cursor files in
+///
+/// # Returns
+///
+/// The decoded cursors by role, for the roles the skin has a cursor for
+pub fn cursors_from_archive(archive: &WszArchive) -> HashMap<CursorRole, CursorImage> {
+    let mut cursors = HashMap::new();
+    for (name, data) in archive.iter() {
+        let Some(role) = CursorRole::from_file_name(name) else {
+            continue;
+        };
+        if cursors.contains_key(&role) {
+            continue;
+        }
+        if let Ok(cursor) = CursorImage::from_bytes(data) {
+            cursors.insert(role, cursor);
+        }
+    }
+    cursors
+}
+
+/// Decode every .cur file in the archive contents, including ones Winamp has no role for
+///
+/// # Arguments
+///
+/// * `archive` - Archive to find the cursor files in
+///
+/// # Returns
+///
+/// Each file's name, without its directory, with the decoded cursor or the reason it could not be decoded,
+/// sorted by name. Files with the same name in different directories are listed once.
+pub fn decode_cursor_files(archive: &WszArchive) -> Vec<(String, Result<CursorImage>)> {
+    let mut files: Vec<(String, &[u8])> = Vec::new();
+    for (name, data) in archive.iter() {
+        let Some(file_name) = Path::new(name).file_name().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let is_cursor = Path::new(file_name)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("cur"));
+        if is_cursor && !files.iter().any(|(seen, _)| seen.eq_ignore_ascii_case(file_name)) {
+            files.push((file_name.to_string(), data));
+        }
+    }
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+    files
+        .into_iter()
+        .map(|(name, data)| (name, CursorImage::from_bytes(data)))
+        .collect()
+}
+
+/// Describes cursors as JSON
+///
+/// The document has a `cursors` array with each cursor's `file` name, the Winamp `role` file name it is used as
+/// or null, its `width` and `height`, and its `hotspot` as `x` and `y`.
+///
+/// # Arguments
+///
+/// * `cursors` - File names with their decoded cursors
+///
+/// # Returns
+///
+/// The JSON document
+pub fn cursors_json(cursors: &[(String, CursorImage)]) -> String {
+    let items: Vec<String> = cursors
+        .iter()
+        .map(|(file, cursor)| {
+            let role =
+                CursorRole::from_file_name(file).map_or("null".to_string(), |role| json_string(role.file_name()));
+            format!(
+                r#"    {{"file": {}, "role": {}, "width": {}, "height": {}, "hotspot": {{"x": {}, "y": {}}}}}"#,
+                json_string(file),
+                role,
+                cursor.image.width(),
+                cursor.image.height(),
+                cursor.hotspot.0,
+                cursor.hotspot.1
+            )
+        })
+        .collect();
+
+    let mut json = String::new();
+    let _ = writeln!(json, "{{");
+    let _ = writeln!(json, r#"  "cursors": {}"#, json_array(&items));
+    let _ = writeln!(json, "}}");
+    json
+}
+
+/// A decoded cursor image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorImage {
+    /// The cursor, transparent where the mask hides it
+    pub image: RgbaImage,
+    /// Position of the pointer within the image, as (x, y)
+    pub hotspot: (u32, u32),
+}
+
+impl CursorImage {
+    /// Decode a .cur file, choosing its best image
+    ///
+    /// The largest image is used, and among images of the same size, the one with the most colors.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Contents of the .cur file
+    ///
+    /// # Returns
+    ///
+    /// The decoded cursor
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let cursors = decode_cur(data)?;
+        let best = cursors
+            .into_iter()
+            .enumerate()
+            .max_by_key(|(i, (cursor, bit_count))| {
+                (
+                    cursor.image.width() as u64 * cursor.image.height() as u64,
+                    *bit_count,
+                    usize::MAX - i,
+                )
+            })
+            .map(|(_, (cursor, _))| cursor);
+        best.ok_or_else(|| invalid("file has no images"))
+    }
+
+    /// Find a cursor file in the archive contents, at any path, and decode it
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - Archive to find the file in
+    /// * `file_name` - Name of the file, e.g. "CLOSE.CUR", matched ignoring case
+    ///
+    /// # Returns
+    ///
+    /// The decoded cursor, or `NotFound` if the skin does not have the file
+    pub fn from_archive(archive: &WszArchive, file_name: &str) -> Result<Self> {
+        let data = archive
+            .iter()
+            .find(|(name, _)| {
+                Path::new(name)
+                    .file_name()
+                    .and_then(|s| s.to_str())
+                    .is_some_and(|s| s.eq_ignore_ascii_case(file_name))
+            })
+            .map(|(_, data)| data)
+            .ok_or(WszError::NotFound(file_name.to_string()))?;
+        Self::from_bytes(data)
+    }
+}
+
+/// Decode every image in a .cur or .ico file
+///
+/// Icons have no hotspot, so theirs is (0, 0).
+///
+/// # Arguments
+///
+/// * `data` - Contents of the file
+///
+/// # Returns
+///
+/// The images in the order they are listed, each with its bit depth
+pub fn decode_cur(data: &[u8]) -> Result<Vec<(CursorImage, u16)>> {
+    if data.len() < ICO_DIR_SIZE || read_u16(data, 0)? != 0 {
+        return Err(invalid("not a cursor or icon file"));
+    }
+    let file_type = read_u16(data, 2)?;
+    if file_type != ICO_TYPE_CURSOR && file_type != ICO_TYPE_ICON {
+        return Err(invalid(&format!("unknown file type {}", file_type)));
+    }
+    let count = read_u16(data, 4)? as usize;
+
+    let mut cursors = Vec::with_capacity(count);
+    for i in 0..count {
+        let entry = ICO_DIR_SIZE + i * ICO_ENTRY_SIZE;
+        let hotspot = match file_type {
+            ICO_TYPE_CURSOR => (read_u16(data, entry + 4)? as u32, read_u16(data, entry + 6)? as u32),
+            _ => (0, 0),
+        };
+        let size = read_u32(data, entry + 8)? as usize;
+        let offset = read_u32(data, entry + 12)? as usize;
+        let image_data = offset
+            .checked_add(size)
+            .and_then(|end| data.get(offset..end))
+            .ok_or_else(|| invalid(&format!("image {} lies outside the file", i)))?;
+
+        let (image, bit_count) = if image_data.starts_with(&PNG_SIGNATURE) {
+            // checked before decoding, so a small file cannot claim a huge image
+            let reader = image::ImageReader::with_format(std::io::Cursor::new(image_data), ImageFormat::Png);
+            let (width, height) = reader.into_dimensions()?;
+            check_size(width, height)?;
+            (
+                image::load_from_memory_with_format(image_data, ImageFormat::Png)?.to_rgba8(),
+                32,
+            )
+        } else {
+            decode_dib(image_data)?
+        };
+        cursors.push((CursorImage { image, hotspot }, bit_count));
+    }
+    Ok(cursors)
+}
+
+/// Decodes a headerless BMP as stored in ICO files, whose height covers both the color data and the mask
+///
+/// Pixels the mask makes transparent are transparent. Pixels that Windows would draw by inverting the screen
+/// behind the cursor are drawn opaque black.
+fn decode_dib(data: &[u8]) -> Result<(RgbaImage, u16)> {
+    let header_size = read_u32(data, 0)? as usize;
+    if header_size < BITMAP_INFO_HEADER_SIZE {
+        return Err(invalid("unsupported bitmap header"));
+    }
+    let width = read_u32(data, 4)? as i32;
+    let height = (read_u32(data, 8)? as i32).unsigned_abs() / 2;
+    let bit_count = read_u16(data, 14)?;
+    let compression = read_u32(data, 16)?;
+    let colors_used = read_u32(data, 32)? as usize;
+    if width <= 0 {
+        return Err(invalid(&format!("unsupported image size {}x{}", width, height)));
+    }
+    let width = width as u32;
+    check_size(width, height)?;
+    if compression != BI_RGB && !(compression == BI_BITFIELDS && bit_count == 32) {
+        return Err(invalid(&format!("unsupported compression {}", compression)));
+    }
+
+    let palette_len = match bit_count {
+        1 | 4 | 8 if colors_used == 0 => 1 << bit_count,
+        1 | 4 | 8 => colors_used.min(1 << bit_count),
+        24 | 32 => 0,
+        _ => return Err(invalid(&format!("unsupported bit depth {}", bit_count))),
+    };
+    // 32-bit images with BI_BITFIELDS have three color masks after the header
+    let palette_start = header_size + if compression == BI_BITFIELDS { 12 } else { 0 };
+    let palette: Vec<[u8; 3]> = (0..palette_len)
+        .map(|i| {
+            let entry = palette_start + i * 4;
+            data.get(entry..entry + 3)
+                .map(|bgr| [bgr[2], bgr[1], bgr[0]])
+                .ok_or_else(|| invalid("palette is truncated"))
+        })
+        .collect::<Result<_>>()?;
+
+    let color_stride = (width as usize * bit_count as usize).div_ceil(32) * 4;
+    let mask_stride = (width as usize).div_ceil(32) * 4;
+    let color_start = palette_start + palette_len * 4;
+    let mask_start = color_start + color_stride * height as usize;
+    if data.len() < mask_start {
+        return Err(invalid("image data is truncated"));
+    }
+    // some 32-bit cursors leave out the mask, relying on their alpha channel
+    let has_mask = data.len() >= mask_start + mask_stride * height as usize;
+    if !has_mask && bit_count != 32 {
+        return Err(invalid("transparency mask is truncated"));
+    }
+
+    let mut image = RgbaImage::new(width, height);
+    let mut any_alpha = false;
+    for y in 0..height {
+        // rows are stored bottom-up
+        let row = (height - 1 - y) as usize;
+        let color_row = &data[color_start + row * color_stride..][..color_stride];
+        for x in 0..width {
+            let x_idx = x as usize;
+            let pixel = match bit_count {
+                1 | 4 | 8 => {
+                    let bits = bit_count as usize;
+                    let byte = color_row[x_idx * bits / 8];
+                    let shift = 8 - bits - (x_idx * bits % 8);
+                    let index = ((byte >> shift) & ((1 << bits) - 1) as u8) as usize;
+                    let [r, g, b] = *palette.get(index).unwrap_or(&[0, 0, 0]);
+                    Rgba([r, g, b, 255])
+                }
+                24 => {
+                    let bgr = &color_row[x_idx * 3..x_idx * 3 + 3];
+                    Rgba([bgr[2], bgr[1], bgr[0], 255])
+                }
+                _ => {
+                    let bgra = &color_row[x_idx * 4..x_idx * 4 + 4];
+                    any_alpha |= bgra[3] != 0;
+                    Rgba([bgra[2], bgra[1], bgra[0], bgra[3]])
+                }
+            };
+            image.put_pixel(x, y, pixel);
+        }
+    }
+
+    // 32-bit images with an alpha channel ignore the mask
+    if has_mask && !(bit_count == 32 && any_alpha) {
+        for y in 0..height {
+            let row = (height - 1 - y) as usize;
+            let mask_row = &data[mask_start + row * mask_stride..][..mask_stride];
+            for x in 0..width {
+                let masked = mask_row[x as usize / 8] & (0x80 >> (x % 8)) != 0;
+                let pixel = image.get_pixel_mut(x, y);
+                if !masked {
+                    pixel.0[3] = 255;
+                } else if pixel.0[..3] == [0, 0, 0] {
+                    pixel.0[3] = 0;
+                } else {
+                    *pixel = Rgba([0, 0, 0, 255]);
+                }
+            }
+        }
+    }
+
+    Ok((image, bit_count))
+}
+
+/// Checks that an image is a size cursors can be, at most 1024 pixels on a side
+fn check_size(width: u32, height: u32) -> Result<()> {
+    if width == 0 || height == 0 || width > MAX_CURSOR_SIZE || height > MAX_CURSOR_SIZE {
+        return Err(invalid(&format!("unsupported image size {}x{}", width, height)));
+    }
+    Ok(())
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| invalid("file is truncated"))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| invalid("file is truncated"))
+}
+
+fn invalid(message: &str) -> WszError {
+    WszError::InvalidCursor(message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a .cur file holding one image
+    fn cur_file(hotspot: (u16, u16), image: &[u8], size: u32, offset: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend(0u16.to_le_bytes());
+        data.extend(ICO_TYPE_CURSOR.to_le_bytes());
+        data.extend(1u16.to_le_bytes());
+        data.extend([0, 0, 0, 0]);
+        data.extend(hotspot.0.to_le_bytes());
+        data.extend(hotspot.1.to_le_bytes());
+        data.extend(size.to_le_bytes());
+        data.extend(offset.to_le_bytes());
+        data.extend(image);
+        data
+    }
+
+    /// Builds a headerless BMP as ICO files store them, with rows given bottom-up
+    fn dib(width: i32, height: i32, bit_count: u16, palette: &[[u8; 4]], colors: &[u8], mask: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend((BITMAP_INFO_HEADER_SIZE as u32).to_le_bytes());
+        data.extend(width.to_le_bytes());
+        data.extend((height * 2).to_le_bytes());
+        data.extend(1u16.to_le_bytes());
+        data.extend(bit_count.to_le_bytes());
+        data.extend(BI_RGB.to_le_bytes());
+        data.extend([0; 20]);
+        data.extend(palette.iter().flatten());
+        data.extend(colors);
+        data.extend(mask);
+        data
+    }
+
+    /// A 2x2 1-bit image: white and black on top, black and white below, with the top right pixel masked
+    fn one_bit_dib() -> Vec<u8> {
+        let palette = [[0, 0, 0, 0], [255, 255, 255, 0]];
+        let colors = [0x40, 0, 0, 0, 0x80, 0, 0, 0];
+        let mask = [0, 0, 0, 0, 0x40, 0, 0, 0];
+        dib(2, 2, 1, &palette, &colors, &mask)
+    }
+
+    #[test]
+    fn decodes_one_bit_cursor_with_hotspot() {
+        let image = one_bit_dib();
+        let data = cur_file((1, 0), &image, image.len() as u32, 22);
+        let cursor = CursorImage::from_bytes(&data).unwrap();
+        assert_eq!(cursor.hotspot, (1, 0));
+        assert_eq!((cursor.image.width(), cursor.image.height()), (2, 2));
+        assert_eq!(cursor.image.get_pixel(0, 0), &Rgba([255, 255, 255, 255]));
+        assert_eq!(cursor.image.get_pixel(1, 0).0[3], 0);
+        assert_eq!(cursor.image.get_pixel(0, 1), &Rgba([0, 0, 0, 255]));
+        assert_eq!(cursor.image.get_pixel(1, 1), &Rgba([255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn decodes_thirty_two_bit_cursor_with_mask() {
+        // no alpha, so the mask hides the black pixel on the left
+        let colors = [0, 0, 0, 0, 10, 20, 30, 0];
+        let mask = [0x80, 0, 0, 0];
+        let image = dib(2, 1, 32, &[], &colors, &mask);
+        let data = cur_file((0, 0), &image, image.len() as u32, 22);
+        let cursor = CursorImage::from_bytes(&data).unwrap();
+        assert_eq!(cursor.image.get_pixel(0, 0).0[3], 0);
+        assert_eq!(cursor.image.get_pixel(1, 0), &Rgba([30, 20, 10, 255]));
+    }
+
+    #[test]
+    fn decodes_thirty_two_bit_image_without_mask() {
+        let (image, bit_count) = decode_dib(&dib(1, 1, 32, &[], &[10, 20, 30, 128], &[])).unwrap();
+        assert_eq!(bit_count, 32);
+        assert_eq!(image.get_pixel(0, 0), &Rgba([30, 20, 10, 128]));
+    }
+
+    #[test]
+    fn rejects_truncated_mask() {
+        let image = one_bit_dib();
+        assert!(decode_dib(&image[..image.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn rejects_image_past_end_of_file() {
+        let image = one_bit_dib();
+        let data = cur_file((0, 0), &image, image.len() as u32 + 1, 22);
+        assert!(CursorImage::from_bytes(&data).is_err());
+        let data = cur_file((0, 0), &image, 1, u32::MAX);
+        assert!(CursorImage::from_bytes(&data).is_err());
+    }
+}
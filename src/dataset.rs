@@ -0,0 +1,290 @@
+//! Bulk export of screenshots and metadata for a collection of skins
+//!
+//! `export_dataset` renders every skin in a folder and writes one record per skin, for building machine learning
+//! datasets or loading a collection into a museum's catalog. Screenshots are named by fingerprint, so copies of a
+//! skin under different names share one image.
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::{Result, WszError};
+use crate::library;
+use crate::options::{ResourceLimits, WszOptions};
+use crate::palette::{self, PaletteColor};
+use crate::render::{PlayerState, RenderOptions};
+use crate::util::json_string;
+use crate::validate::Severity;
+use crate::Wsz;
+
+/// Folder in the output folder the screenshots are written to
+const SCREENSHOT_DIR: &str = "screenshots";
+
+/// Format of the file the records are written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatasetFormat {
+    /// One JSON object per line, written to skins.jsonl. Palettes are arrays of `color` and `share` objects.
+    Jsonl,
+    /// Comma-separated values with a header row, written to skins.csv. Palettes are written as
+    /// "#rrggbb:share" pairs separated by spaces.
+    Csv,
+}
+
+impl DatasetFormat {
+    fn file_name(&self) -> &'static str {
+        match self {
+            DatasetFormat::Jsonl => "skins.jsonl",
+            DatasetFormat::Csv => "skins.csv",
+        }
+    }
+}
+
+/// Options controlling what `export_dataset` writes
+#[derive(Debug, Clone)]
+pub struct DatasetOptions {
+    /// Format of the records
+    pub format: DatasetFormat,
+    /// Whether to write screenshots. When false, the records are written alone.
+    pub screenshots: bool,
+    /// State the screenshots are rendered in. By default the player shows the classic demo track.
+    pub render: RenderOptions,
+    /// Number of colors in each skin's palette
+    pub palette_size: usize,
+    /// Limits skins are loaded with, so broken archives in the collection fail instead of using up memory
+    pub limits: ResourceLimits,
+}
+
+impl DatasetOptions {
+    fn new() -> Self {
+        Self {
+            format: DatasetFormat::Jsonl,
+            screenshots: true,
+            render: RenderOptions {
+                player: PlayerState::demo(),
+                ..Default::default()
+            },
+            palette_size: 8,
+            limits: ResourceLimits::untrusted(),
+        }
+    }
+}
+
+impl Default for DatasetOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What `export_dataset` wrote
+#[derive(Debug, Default)]
+pub struct DatasetSummary {
+    /// Number of skins written
+    pub exported: usize,
+    /// Skins that could not be loaded or rendered, with the reason. They have no record.
+    pub failed: Vec<(PathBuf, WszError)>,
+}
+
+/// A value in a record
+enum Value {
+    Text(Option<String>),
+    Number(Option<u64>),
+    Bool(bool),
+    Palette(Vec<PaletteColor>),
+}
+
+/// Names of the fields of each record, in order
+const FIELDS: [&str; 27] = [
+    "path",
+    "fingerprint",
+    "screenshot",
+    "name",
+    "author",
+    "url",
+    "email",
+    "date",
+    "file_count",
+    "total_size",
+    "compressed_size",
+    "has_equalizer",
+    "has_playlist",
+    "has_minibrowser",
+    "has_cursors",
+    "has_avs",
+    "has_genex",
+    "has_regions",
+    "is_modern",
+    "sprite_count",
+    "parse_warnings",
+    "validation_errors",
+    "validation_warnings",
+    "width",
+    "height",
+    "dominant_hue",
+    "palette",
+];
+
+/// Renders every .wsz and .wal skin in a folder and its subfolders and writes a record about each one
+///
+/// The output folder gets a screenshots folder of PNGs named by fingerprint and a skins.jsonl or skins.csv file.
+/// Each record has the skin's path relative to `input`, its fingerprint, the screenshot's path relative to
+/// `output`, the details from its readme, what it contains, counts of sprites and problems, the screenshot's size,
+/// the dominant hue and its palette.
+///
+/// # Arguments
+///
+/// * `input` - Folder of skins
+/// * `output` - Folder to write to, created if needed
+/// * `options` - What to write
+///
+/// # Returns
+///
+/// What was written, or an error if the folders cannot be read or written
+pub fn export_dataset<P: AsRef<Path>, Q: AsRef<Path>>(
+    input: P,
+    output: Q,
+    options: &DatasetOptions,
+) -> Result<DatasetSummary> {
+    let (input, output) = (input.as_ref(), output.as_ref());
+    let mut paths = Vec::new();
+    library::find_skins(input, &mut paths)?;
+    paths.sort();
+
+    fs::create_dir_all(output)?;
+    if options.screenshots {
+        fs::create_dir_all(output.join(SCREENSHOT_DIR))?;
+    }
+    let mut records = BufWriter::new(File::create(output.join(options.format.file_name()))?);
+    if options.format == DatasetFormat::Csv {
+        writeln!(records, "{}", FIELDS.join(","))?;
+    }
+
+    let load_options = WszOptions {
+        decode_cursors: false,
+        keep_raw_files: false,
+        limits: options.limits,
+        ..Default::default()
+    };
+    let mut summary = DatasetSummary::default();
+    for path in paths {
+        let relative = path.strip_prefix(input).unwrap_or(&path).to_path_buf();
+        let record = Wsz::from_file_path_with_options(&path, &load_options)
+            .and_then(|skin| record(&skin, &relative, output, options));
+        match record {
+            Ok(record) => {
+                let line = match options.format {
+                    DatasetFormat::Jsonl => jsonl_line(&record),
+                    DatasetFormat::Csv => csv_line(&record),
+                };
+                writeln!(records, "{}", line)?;
+                summary.exported += 1;
+            }
+            Err(err) => summary.failed.push((relative, err)),
+        }
+    }
+    records.flush()?;
+    Ok(summary)
+}
+
+/// Renders a skin, writes its screenshot and collects its record's values in the order of FIELDS
+fn record(skin: &Wsz, path: &Path, output: &Path, options: &DatasetOptions) -> Result<Vec<Value>> {
+    let fingerprint = skin.fingerprint();
+    let screenshot = skin.render_screenshot_with_options(&options.render)?;
+    let screenshot_path = options
+        .screenshots
+        .then(|| format!("{}/{}.png", SCREENSHOT_DIR, fingerprint));
+    if let Some(screenshot_path) = &screenshot_path {
+        screenshot.save(output.join(screenshot_path))?;
+    }
+    // nearest-neighbor scaling repeats every pixel the same number of times, so shares do not depend on the scale
+    let palette = palette::palette(&screenshot, options.palette_size);
+
+    let report = skin.validate();
+    let count = |severity| report.issues_with_severity(severity).count() as u64;
+    let metadata = skin.metadata();
+    let text = |value: &Option<String>| Value::Text(value.clone());
+    Ok(vec![
+        Value::Text(Some(path.to_string_lossy().replace('\\', "/"))),
+        Value::Text(Some(fingerprint)),
+        Value::Text(screenshot_path),
+        text(&metadata.name),
+        text(&metadata.author),
+        text(&metadata.url),
+        text(&metadata.email),
+        text(&metadata.date),
+        Value::Number(Some(metadata.file_count as u64)),
+        Value::Number(Some(metadata.total_size)),
+        Value::Number(metadata.compressed_size),
+        Value::Bool(metadata.has_equalizer),
+        Value::Bool(metadata.has_playlist),
+        Value::Bool(metadata.has_minibrowser),
+        Value::Bool(metadata.has_cursors),
+        Value::Bool(metadata.has_avs),
+        Value::Bool(metadata.has_genex),
+        Value::Bool(metadata.has_regions),
+        Value::Bool(metadata.is_modern),
+        Value::Number(Some(skin.sprites.len() as u64)),
+        Value::Number(Some(skin.parse_warnings.len() as u64)),
+        Value::Number(Some(count(Severity::Error))),
+        Value::Number(Some(count(Severity::Warning))),
+        Value::Number(Some(screenshot.width() as u64)),
+        Value::Number(Some(screenshot.height() as u64)),
+        Value::Text(palette::dominant_hue(&palette).map(|hue| hue.to_string())),
+        Value::Palette(palette),
+    ])
+}
+
+fn hex(color: &PaletteColor) -> String {
+    let [r, g, b] = color.color.0;
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn jsonl_line(record: &[Value]) -> String {
+    let fields: Vec<String> = FIELDS
+        .iter()
+        .zip(record)
+        .map(|(field, value)| {
+            let value = match value {
+                Value::Text(Some(text)) => json_string(text),
+                Value::Number(Some(number)) => number.to_string(),
+                Value::Text(None) | Value::Number(None) => "null".to_string(),
+                Value::Bool(value) => value.to_string(),
+                Value::Palette(palette) => format!(
+                    "[{}]",
+                    palette
+                        .iter()
+                        .map(|color| format!(r#"{{"color":"{}","share":{}}}"#, hex(color), color.share))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                ),
+            };
+            format!("{}:{}", json_string(field), value)
+        })
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+/// Quotes a CSV field if it has commas, quotes or line breaks
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_line(record: &[Value]) -> String {
+    record
+        .iter()
+        .map(|value| match value {
+            Value::Text(text) => csv_field(text.as_deref().unwrap_or_default()),
+            Value::Number(number) => number.map_or(String::new(), |number| number.to_string()),
+            Value::Bool(value) => value.to_string(),
+            Value::Palette(palette) => palette
+                .iter()
+                .map(|color| format!("{}:{}", hex(color), color.share))
+                .collect::<Vec<_>>()
+                .join(" "),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
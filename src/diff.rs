@@ -0,0 +1,378 @@
+//! Differences between two skins
+//!
+//! `Wsz::diff` compares sprites pixel by pixel, each visualization color, each pledit.txt and region.txt setting,
+//! and the files in the skin that are not sprite sheets or text configuration.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use image::{Rgb, Rgba};
+
+use crate::sprites::{SpriteImage, SpriteManager};
+use crate::text::region::RegionType;
+use crate::Wsz;
+
+/// Files compared as settings rather than as files
+const CONFIG_FILES: [&str; 3] = ["viscolor.txt", "pledit.txt", "region.txt"];
+/// Color of the pixels that differ in a difference image
+const DIFFERENCE_COLOR: Rgba<u8> = Rgba([255, 0, 255, 255]);
+
+/// Whether something was added, removed or changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// Only in the other skin
+    Added,
+    /// Only in this skin
+    Removed,
+    /// In both skins, but different
+    Changed,
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeKind::Added => write!(f, "added"),
+            ChangeKind::Removed => write!(f, "removed"),
+            ChangeKind::Changed => write!(f, "changed"),
+        }
+    }
+}
+
+/// A sprite that differs between the skins
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpriteChange {
+    /// Name of the sprite
+    pub name: String,
+    /// Whether the sprite was added, removed or changed
+    pub kind: ChangeKind,
+    /// Size of the sprite in this skin
+    pub old_size: Option<(u32, u32)>,
+    /// Size of the sprite in the other skin
+    pub new_size: Option<(u32, u32)>,
+    /// Number of pixels that differ, when the sprite is the same size in both skins
+    pub changed_pixels: Option<usize>,
+}
+
+/// A visualization color that differs between the skins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VisColorChange {
+    /// Index of the color in viscolor.txt, starting from 0
+    pub index: usize,
+    /// Color in this skin
+    pub old: Option<Rgb<u8>>,
+    /// Color in the other skin
+    pub new: Option<Rgb<u8>>,
+}
+
+/// A pledit.txt or region.txt setting that differs between the skins
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingChange {
+    /// Section the setting is in, e.g. "Text"
+    pub section: String,
+    /// Key of the setting, e.g. "Normal"
+    pub key: String,
+    /// Value in this skin, as it would be written to the file
+    pub old: Option<String>,
+    /// Value in the other skin, as it would be written to the file
+    pub new: Option<String>,
+}
+
+/// A window region that differs between the skins
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionChange {
+    /// Window the region is for
+    pub region: RegionType,
+    /// Polygons in this skin
+    pub old: Option<Vec<Vec<(u32, u32)>>>,
+    /// Polygons in the other skin
+    pub new: Option<Vec<Vec<(u32, u32)>>>,
+}
+
+/// A file other than a sprite sheet or text configuration that differs between the skins
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    /// Path of the file in the archive
+    pub name: String,
+    /// Whether the file was added, removed or changed
+    pub kind: ChangeKind,
+}
+
+/// Everything that differs between two skins, each list sorted by name or position
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SkinDiff {
+    /// Sprites that differ
+    pub sprites: Vec<SpriteChange>,
+    /// Visualization colors (viscolor.txt) that differ
+    pub vis_colors: Vec<VisColorChange>,
+    /// Playlist editor settings (pledit.txt) that differ
+    pub pledit: Vec<SettingChange>,
+    /// Window regions (region.txt) that differ
+    pub regions: Vec<RegionChange>,
+    /// Keys in region.txt that Winamp does not use that differ
+    pub region_settings: Vec<SettingChange>,
+    /// Other files that differ, such as readmes and cursors
+    pub files: Vec<FileChange>,
+}
+
+impl SkinDiff {
+    /// Whether the skins are the same
+    pub fn is_empty(&self) -> bool {
+        self.sprites.is_empty()
+            && self.vis_colors.is_empty()
+            && self.pledit.is_empty()
+            && self.regions.is_empty()
+            && self.region_settings.is_empty()
+            && self.files.is_empty()
+    }
+}
+
+impl fmt::Display for SkinDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let size = |size: Option<(u32, u32)>| size.map_or("none".to_string(), |(w, h)| format!("{}x{}", w, h));
+        let color =
+            |color: Option<Rgb<u8>>| color.map_or("none".to_string(), |Rgb([r, g, b])| format!("{},{},{}", r, g, b));
+        let value = |value: &Option<String>| value.clone().unwrap_or_else(|| "none".to_string());
+
+        for sprite in &self.sprites {
+            match sprite.changed_pixels {
+                Some(pixels) => writeln!(f, "sprite {} changed: {} pixel(s)", sprite.name, pixels)?,
+                None => writeln!(
+                    f,
+                    "sprite {} {}: {} -> {}",
+                    sprite.name,
+                    sprite.kind,
+                    size(sprite.old_size),
+                    size(sprite.new_size)
+                )?,
+            }
+        }
+        for change in &self.vis_colors {
+            writeln!(
+                f,
+                "viscolor.txt color {}: {} -> {}",
+                change.index,
+                color(change.old),
+                color(change.new)
+            )?;
+        }
+        for (file, changes) in [("pledit.txt", &self.pledit), ("region.txt", &self.region_settings)] {
+            for change in changes {
+                writeln!(
+                    f,
+                    "{} [{}] {}: {} -> {}",
+                    file,
+                    change.section,
+                    change.key,
+                    value(&change.old),
+                    value(&change.new)
+                )?;
+            }
+        }
+        for change in &self.regions {
+            let polygons = |polygons: &Option<Vec<Vec<(u32, u32)>>>| {
+                polygons
+                    .as_ref()
+                    .map_or("none".to_string(), |polygons| format!("{} polygon(s)", polygons.len()))
+            };
+            writeln!(
+                f,
+                "region.txt [{}]: {} -> {}",
+                change.region.section_name(),
+                polygons(&change.old),
+                polygons(&change.new)
+            )?;
+        }
+        for file in &self.files {
+            writeln!(f, "file {} {}", file.name, file.kind)?;
+        }
+        Ok(())
+    }
+}
+
+/// Draws an image showing how a sprite differs between two skins
+///
+/// The image has three panels separated by a transparent column: the old sprite, the new sprite, and the new sprite
+/// darkened with each differing pixel in magenta. Where the sprites are different sizes, pixels only one of them
+/// covers count as differing.
+///
+/// # Arguments
+///
+/// * `old` - The sprite in this skin
+/// * `new` - The sprite in the other skin
+///
+/// # Returns
+///
+/// The difference image
+pub fn difference_image(old: &SpriteImage, new: &SpriteImage) -> SpriteImage {
+    let width = old.width().max(new.width());
+    let height = old.height().max(new.height());
+    let mut image = SpriteImage::new(old.width() + new.width() + width + 2, height);
+    image::imageops::replace(&mut image, old, 0, 0);
+    image::imageops::replace(&mut image, new, old.width() as i64 + 1, 0);
+
+    let left = old.width() + new.width() + 2;
+    for y in 0..height {
+        for x in 0..width {
+            let old_pixel = old.get_pixel_checked(x, y);
+            let new_pixel = new.get_pixel_checked(x, y);
+            let pixel = match (old_pixel, new_pixel) {
+                (Some(a), Some(b)) if a == b => {
+                    let Rgba([r, g, b, a]) = *b;
+                    Rgba([r / 3, g / 3, b / 3, a])
+                }
+                _ => DIFFERENCE_COLOR,
+            };
+            image.put_pixel(left + x, y, pixel);
+        }
+    }
+    image
+}
+
+/// Compares two skins
+pub(crate) fn diff(old: &Wsz, new: &Wsz) -> SkinDiff {
+    SkinDiff {
+        sprites: diff_sprites(old, new),
+        vis_colors: diff_vis_colors(old, new),
+        pledit: diff_settings(
+            old.pledit.txt_entries().into_iter(),
+            new.pledit.txt_entries().into_iter(),
+        ),
+        regions: diff_regions(old, new),
+        region_settings: diff_settings(region_entries(old), region_entries(new)),
+        files: diff_files(old, new),
+    }
+}
+
+fn diff_sprites(old: &Wsz, new: &Wsz) -> Vec<SpriteChange> {
+    let names: BTreeSet<&String> = old.sprites.keys().chain(new.sprites.keys()).collect();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let old_sprite = old.sprites.get(name);
+            let new_sprite = new.sprites.get(name);
+            let old_size = old_sprite.map(|sprite| sprite.dimensions());
+            let new_size = new_sprite.map(|sprite| sprite.dimensions());
+            let (kind, changed_pixels) = match (old_sprite, new_sprite) {
+                (Some(_), None) => (ChangeKind::Removed, None),
+                (None, Some(_)) => (ChangeKind::Added, None),
+                (Some(old_sprite), Some(new_sprite)) if old_size == new_size => {
+                    let pixels = old_sprite
+                        .pixels()
+                        .zip(new_sprite.pixels())
+                        .filter(|(a, b)| a != b)
+                        .count();
+                    if pixels == 0 {
+                        return None;
+                    }
+                    (ChangeKind::Changed, Some(pixels))
+                }
+                _ => (ChangeKind::Changed, None),
+            };
+            Some(SpriteChange {
+                name: name.clone(),
+                kind,
+                old_size,
+                new_size,
+                changed_pixels,
+            })
+        })
+        .collect()
+}
+
+fn diff_vis_colors(old: &Wsz, new: &Wsz) -> Vec<VisColorChange> {
+    (0..old.vis_colors.len().max(new.vis_colors.len()))
+        .filter_map(|index| {
+            let change = VisColorChange {
+                index,
+                old: old.vis_colors.get(index),
+                new: new.vis_colors.get(index),
+            };
+            (change.old != change.new).then_some(change)
+        })
+        .collect()
+}
+
+fn diff_regions(old: &Wsz, new: &Wsz) -> Vec<RegionChange> {
+    [
+        RegionType::Main,
+        RegionType::MainShade,
+        RegionType::Equalizer,
+        RegionType::EqualizerShade,
+    ]
+    .into_iter()
+    .filter_map(|region| {
+        let change = RegionChange {
+            region,
+            old: old.regions.get(region).cloned(),
+            new: new.regions.get(region).cloned(),
+        };
+        (change.old != change.new).then_some(change)
+    })
+    .collect()
+}
+
+/// Lists the custom keys of region.txt as (section, key, value)
+fn region_entries(skin: &Wsz) -> impl Iterator<Item = (String, String, String)> + '_ {
+    skin.regions.custom.iter().map(|(key, value)| {
+        let (section, key) = key.split_once('.').unwrap_or(("", key));
+        (section.to_string(), key.to_string(), value.clone())
+    })
+}
+
+/// Compares settings given as (section, key, value), matching sections and keys ignoring case like Winamp does
+fn diff_settings(
+    old: impl Iterator<Item = (String, String, String)>,
+    new: impl Iterator<Item = (String, String, String)>,
+) -> Vec<SettingChange> {
+    let mut settings: BTreeMap<(String, String), SettingChange> = BTreeMap::new();
+    let entries = old.map(|entry| (false, entry)).chain(new.map(|entry| (true, entry)));
+    for (is_new, (section, key, value)) in entries {
+        let change = settings
+            .entry((section.to_lowercase(), key.to_lowercase()))
+            .or_insert(SettingChange {
+                section,
+                key,
+                old: None,
+                new: None,
+            });
+        if is_new {
+            change.new = Some(value);
+        } else {
+            change.old = Some(value);
+        }
+    }
+    settings
+        .into_values()
+        .filter(|change| change.old != change.new)
+        .collect()
+}
+
+fn diff_files(old: &Wsz, new: &Wsz) -> Vec<FileChange> {
+    let sheets = SpriteManager::sprite_sheet_names();
+    let is_extra = |name: &String| {
+        let file_name = name.split('/').next_back().unwrap();
+        !sheets.iter().any(|sheet| sheet.eq_ignore_ascii_case(file_name))
+            && !CONFIG_FILES.iter().any(|config| config.eq_ignore_ascii_case(file_name))
+    };
+    let names: BTreeSet<&String> = old
+        .files
+        .keys()
+        .chain(new.files.keys())
+        .filter(|name| is_extra(name))
+        .collect();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let kind = match (old.files.get(name), new.files.get(name)) {
+                (Some(_), None) => ChangeKind::Removed,
+                (None, Some(_)) => ChangeKind::Added,
+                (Some(a), Some(b)) if a != b => ChangeKind::Changed,
+                _ => return None,
+            };
+            Some(FileChange {
+                name: name.clone(),
+                kind,
+            })
+        })
+        .collect()
+}
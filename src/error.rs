@@ -29,6 +29,18 @@ pub enum WszError {
     #[error("Invalid format on line {line}: {error}")]
     InvalidFormat { line: usize, error: String },
 
+    /// Cursor files that cannot be decoded
+    #[error("Invalid cursor: {0}")]
+    InvalidCursor(String),
+
+    /// Downloads that fail, from the connection or an HTTP error status
+    #[error("HTTP error: {0}")]
+    Http(String),
+
+    /// Archives or images larger than the limits they were loaded with
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(String),
+
     /// Missing section in the file
     #[error("Missing section: {0}")]
     MissingSection(String),
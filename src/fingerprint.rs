@@ -0,0 +1,111 @@
+//! Content fingerprints for recognizing the same skin in different archives
+//!
+//! The fingerprint covers what Winamp draws and reads: the pixels of every sprite, GENEX.BMP, and the settings
+//! in viscolor.txt, pledit.txt and region.txt. File names, zip metadata, entry order, compression, image
+//! encoding, text formatting and pixels outside every sprite make no difference.
+
+use sha1::{Digest, Sha1};
+
+use crate::sprites;
+use crate::text::region::RegionType;
+use crate::Wsz;
+
+/// Hashes a skin's content
+pub(crate) fn fingerprint(skin: &Wsz) -> String {
+    let mut hasher = Sha1::new();
+
+    // every value is length-prefixed or fixed size, so different content never hashes the same bytes
+    let write_str = |hasher: &mut Sha1, value: &str| {
+        hasher.update((value.len() as u64).to_le_bytes());
+        hasher.update(value.as_bytes());
+    };
+    let write_image = |hasher: &mut Sha1, image: &sprites::SpriteImage| {
+        hasher.update(image.width().to_le_bytes());
+        hasher.update(image.height().to_le_bytes());
+        hasher.update(image.as_raw());
+    };
+
+    // sheets left out by WszOptions are decoded, so the fingerprint does not depend on what was loaded
+    let sprites = skin.all_sprites();
+    let mut names: Vec<&String> = sprites.keys().collect();
+    names.sort();
+    hasher.update(b"sprites");
+    hasher.update((names.len() as u64).to_le_bytes());
+    for name in names {
+        write_str(&mut hasher, name);
+        write_image(&mut hasher, &sprites[name]);
+    }
+
+    let genex = skin
+        .files
+        .iter()
+        .find(|(name, _)| name.split('/').next_back().unwrap().eq_ignore_ascii_case("GENEX.BMP"))
+        .and_then(|(_, data)| image::load_from_memory(data).ok());
+    hasher.update(b"genex");
+    match genex {
+        Some(genex) => {
+            hasher.update([1]);
+            write_image(&mut hasher, &genex.to_rgba8());
+        }
+        None => hasher.update([0]),
+    }
+
+    hasher.update(b"viscolor");
+    hasher.update((skin.vis_colors.len() as u64).to_le_bytes());
+    for index in 0..skin.vis_colors.len() {
+        hasher.update(skin.vis_colors.get(index).map_or([0; 3], |color| color.0));
+    }
+
+    // keys are matched ignoring case, like Winamp does
+    let mut pledit: Vec<(String, String, String)> = skin
+        .pledit
+        .txt_entries()
+        .into_iter()
+        .map(|(section, key, value)| (section.to_lowercase(), key.to_lowercase(), value))
+        .collect();
+    pledit.sort();
+    hasher.update(b"pledit");
+    hasher.update((pledit.len() as u64).to_le_bytes());
+    for (section, key, value) in &pledit {
+        write_str(&mut hasher, section);
+        write_str(&mut hasher, key);
+        write_str(&mut hasher, value);
+    }
+
+    hasher.update(b"region");
+    for region in [
+        RegionType::Main,
+        RegionType::MainShade,
+        RegionType::Equalizer,
+        RegionType::EqualizerShade,
+    ] {
+        match skin.regions.get(region) {
+            Some(polygons) => {
+                hasher.update([1]);
+                hasher.update((polygons.len() as u64).to_le_bytes());
+                for polygon in polygons {
+                    hasher.update((polygon.len() as u64).to_le_bytes());
+                    for (x, y) in polygon {
+                        hasher.update(x.to_le_bytes());
+                        hasher.update(y.to_le_bytes());
+                    }
+                }
+            }
+            None => hasher.update([0]),
+        }
+    }
+    let mut custom: Vec<(String, &String)> = skin
+        .regions
+        .custom
+        .iter()
+        .map(|(key, value)| (key.to_lowercase(), value))
+        .collect();
+    custom.sort();
+    hasher.update((custom.len() as u64).to_le_bytes());
+    for (key, value) in custom {
+        write_str(&mut hasher, &key);
+        write_str(&mut hasher, value);
+    }
+
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
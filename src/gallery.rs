@@ -0,0 +1,375 @@
+//! Static HTML galleries of a collection of skins
+//!
+//! `export_gallery` renders every skin in a folder and writes a page of thumbnails that link to the full screenshots
+//! and to copies of the skins, so a collection can be shared from any static web host. `export_thumbnails` writes
+//! just the thumbnails, rendering several skins at once.
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+
+use image::imageops::FilterType;
+
+use crate::error::{Result, WszError};
+use crate::library;
+use crate::options::{ResourceLimits, WszOptions};
+use crate::render::thumbnail::{make_thumbnail, ThumbnailFit, ThumbnailOptions};
+use crate::render::{PlayerState, RenderOptions};
+use crate::Wsz;
+
+/// Folder in the output folder the screenshots are written to
+const SCREENSHOT_DIR: &str = "screenshots";
+/// Folder in the output folder the thumbnails are written to
+const THUMBNAIL_DIR: &str = "thumbnails";
+/// Folder in the output folder the skins are copied to
+const SKIN_DIR: &str = "skins";
+/// Stylesheet of the page
+const STYLE: &str = "body { font-family: sans-serif; background: #222; color: #ddd; margin: 2em; }
+a { color: #8cf; }
+.skins { display: flex; flex-wrap: wrap; gap: 1.5em; }
+.skin { width: 14em; }
+.skin img { display: block; margin-bottom: 0.5em; }
+.author { color: #999; }
+";
+
+/// Options controlling what `export_gallery` writes
+#[derive(Debug, Clone)]
+pub struct GalleryOptions {
+    /// Title of the page
+    pub title: String,
+    /// State the screenshots are rendered in. By default the player shows the classic demo track.
+    pub render: RenderOptions,
+    /// Width of the box thumbnails are fitted into
+    pub thumbnail_width: u32,
+    /// Height of the box thumbnails are fitted into
+    pub thumbnail_height: u32,
+    /// Limits skins are loaded with, so broken archives in the collection fail instead of using up memory
+    pub limits: ResourceLimits,
+}
+
+impl GalleryOptions {
+    fn new() -> Self {
+        Self {
+            title: "Winamp skins".to_string(),
+            render: RenderOptions {
+                player: PlayerState::demo(),
+                ..Default::default()
+            },
+            thumbnail_width: 184,
+            thumbnail_height: 232,
+            limits: ResourceLimits::untrusted(),
+        }
+    }
+}
+
+impl Default for GalleryOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What `export_gallery` wrote
+#[derive(Debug, Default)]
+pub struct GallerySummary {
+    /// Number of skins in the gallery
+    pub exported: usize,
+    /// Skins that could not be loaded or rendered, with the reason. They are left out of the gallery.
+    pub failed: Vec<(PathBuf, WszError)>,
+}
+
+/// Options controlling what `export_thumbnails` writes
+#[derive(Debug, Clone)]
+pub struct ThumbnailFolderOptions {
+    /// Width of the thumbnails
+    pub width: u32,
+    /// Height of the thumbnails
+    pub height: u32,
+    /// How skins are rendered and fitted into the thumbnails
+    pub thumbnail: ThumbnailOptions,
+    /// Limits skins are loaded with, so broken archives in the collection fail instead of using up memory
+    pub limits: ResourceLimits,
+    /// Number of skins rendered at once. 0 uses one thread per CPU.
+    pub threads: usize,
+}
+
+impl ThumbnailFolderOptions {
+    fn new() -> Self {
+        Self {
+            width: 137,
+            height: 80,
+            thumbnail: ThumbnailOptions::default(),
+            limits: ResourceLimits::untrusted(),
+            threads: 0,
+        }
+    }
+}
+
+impl Default for ThumbnailFolderOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What `export_thumbnails` wrote
+#[derive(Debug, Default)]
+pub struct ThumbnailSummary {
+    /// Each skin with a thumbnail and its fingerprint, which the thumbnail is named after, sorted by path. Skins with
+    /// the same fingerprint share a thumbnail.
+    pub thumbnails: Vec<(PathBuf, String)>,
+    /// Skins that could not be loaded or rendered, with the reason, sorted by path
+    pub failed: Vec<(PathBuf, WszError)>,
+}
+
+/// A skin on the page
+struct Card {
+    name: String,
+    author: Option<String>,
+    fingerprint: String,
+    skin_path: String,
+    thumbnail_size: (u32, u32),
+}
+
+/// Renders every .wsz and .wal skin in a folder and its subfolders and writes a gallery page of them
+///
+/// The output folder gets an index.html, a screenshots and a thumbnails folder of PNGs named by fingerprint, and a
+/// skins folder with a copy of each skin at its path relative to `input`, which the page links to for download.
+/// Skins are listed by path.
+///
+/// # Arguments
+///
+/// * `input` - Folder of skins
+/// * `output` - Folder to write to, created if needed
+/// * `options` - How to render the gallery
+///
+/// # Returns
+///
+/// What was written, or an error if the folders cannot be read or written
+pub fn export_gallery<P: AsRef<Path>, Q: AsRef<Path>>(
+    input: P,
+    output: Q,
+    options: &GalleryOptions,
+) -> Result<GallerySummary> {
+    let (input, output) = (input.as_ref(), output.as_ref());
+    let mut paths = Vec::new();
+    library::find_skins(input, &mut paths)?;
+    paths.sort();
+
+    for dir in [SCREENSHOT_DIR, THUMBNAIL_DIR, SKIN_DIR] {
+        fs::create_dir_all(output.join(dir))?;
+    }
+
+    let load_options = WszOptions {
+        decode_cursors: false,
+        keep_raw_files: false,
+        limits: options.limits,
+        ..Default::default()
+    };
+    let mut summary = GallerySummary::default();
+    let mut cards = Vec::new();
+    for path in paths {
+        let relative = path.strip_prefix(input).unwrap_or(&path).to_path_buf();
+        let card = Wsz::from_file_path_with_options(&path, &load_options)
+            .and_then(|skin| card(&skin, &path, &relative, output, options));
+        match card {
+            Ok(card) => {
+                cards.push(card);
+                summary.exported += 1;
+            }
+            Err(err) => summary.failed.push((relative, err)),
+        }
+    }
+
+    fs::write(output.join("index.html"), page(&options.title, &cards))?;
+    Ok(summary)
+}
+
+/// Renders a thumbnail of every .wsz and .wal skin in a folder and its subfolders
+///
+/// Thumbnails are PNGs named by the skin's fingerprint, so skins with the same file name in different folders do not
+/// overwrite each other, and a copy of a skin is not rendered again once its thumbnail is saved.
+///
+/// # Arguments
+///
+/// * `input` - Folder of skins
+/// * `output` - Folder to write the thumbnails to, created if needed
+/// * `options` - Size of the thumbnails and how to render them
+///
+/// # Returns
+///
+/// The thumbnail of each skin and the skins that failed, or an error if the folders cannot be read or created
+pub fn export_thumbnails<P: AsRef<Path>, Q: AsRef<Path>>(
+    input: P,
+    output: Q,
+    options: &ThumbnailFolderOptions,
+) -> Result<ThumbnailSummary> {
+    let (input, output) = (input.as_ref(), output.as_ref());
+    let mut paths = Vec::new();
+    library::find_skins(input, &mut paths)?;
+    paths.sort();
+    fs::create_dir_all(output)?;
+
+    let threads = match options.threads {
+        0 => thread::available_parallelism().map_or(1, |threads| threads.get()),
+        threads => threads,
+    };
+    let next = AtomicUsize::new(0);
+    let rendered = Mutex::new(HashSet::new());
+    let summary = Mutex::new(ThumbnailSummary::default());
+    thread::scope(|scope| {
+        for _ in 0..threads.min(paths.len()) {
+            scope.spawn(|| {
+                while let Some(path) = paths.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let result = thumbnail_file(path, output, options, &rendered);
+                    let mut summary = summary.lock().unwrap_or_else(PoisonError::into_inner);
+                    match result {
+                        Ok(fingerprint) => summary.thumbnails.push((path.clone(), fingerprint)),
+                        Err(err) => summary.failed.push((path.clone(), err)),
+                    }
+                }
+            });
+        }
+    });
+
+    let mut summary = summary.into_inner().unwrap_or_else(PoisonError::into_inner);
+    summary.thumbnails.sort_by(|a, b| a.0.cmp(&b.0));
+    summary.failed.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(summary)
+}
+
+/// Writes one skin's thumbnail, unless a skin with the same fingerprint already has one saved
+fn thumbnail_file(
+    path: &Path,
+    output: &Path,
+    options: &ThumbnailFolderOptions,
+    rendered: &Mutex<HashSet<String>>,
+) -> Result<String> {
+    let load_options = WszOptions {
+        decode_cursors: false,
+        keep_raw_files: false,
+        limits: options.limits,
+        ..Default::default()
+    };
+    let skin = Wsz::from_file_path_with_options(path, &load_options)?;
+    let fingerprint = skin.fingerprint();
+    let is_new = !rendered
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .contains(&fingerprint);
+    if is_new {
+        let thumbnail = skin.render_thumbnail_with_options(options.width, options.height, &options.thumbnail)?;
+        thumbnail.save(output.join(format!("{}.png", fingerprint)))?;
+        // only once it is saved, so a duplicate of a skin that failed still gets a thumbnail
+        rendered
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(fingerprint.clone());
+    }
+    Ok(fingerprint)
+}
+
+/// Writes a skin's screenshot, thumbnail and copy, and collects what the page shows about it
+fn card(skin: &Wsz, path: &Path, relative: &Path, output: &Path, options: &GalleryOptions) -> Result<Card> {
+    let fingerprint = skin.fingerprint();
+    let screenshot = skin.render_screenshot_with_options(&options.render)?;
+    screenshot.save(output.join(SCREENSHOT_DIR).join(format!("{}.png", fingerprint)))?;
+    let thumbnail = make_thumbnail(
+        &screenshot,
+        options.thumbnail_width,
+        options.thumbnail_height,
+        ThumbnailFit::Contain,
+        FilterType::Lanczos3,
+    );
+    thumbnail.save(output.join(THUMBNAIL_DIR).join(format!("{}.png", fingerprint)))?;
+
+    let copy = output.join(SKIN_DIR).join(relative);
+    if let Some(parent) = copy.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(path, &copy)?;
+
+    let metadata = skin.metadata();
+    let stem = relative.file_stem().map(|stem| stem.to_string_lossy().to_string());
+    Ok(Card {
+        name: metadata.name.clone().or(stem).unwrap_or_default(),
+        author: metadata.author.clone(),
+        fingerprint,
+        skin_path: relative.to_string_lossy().replace('\\', "/"),
+        thumbnail_size: thumbnail.dimensions(),
+    })
+}
+
+/// Escapes text for use in HTML content and quoted attributes
+fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Percent-encodes a relative path for use in a link, keeping its slashes
+fn url_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => encoded.push(byte as char),
+            byte => {
+                let _ = write!(encoded, "%{:02X}", byte);
+            }
+        }
+    }
+    encoded
+}
+
+/// Writes the gallery page
+fn page(title: &str, cards: &[Card]) -> String {
+    let title = html_escape(title);
+    let mut html = String::new();
+    let _ = writeln!(html, "<!DOCTYPE html>");
+    let _ = writeln!(html, "<html lang=\"en\">");
+    let _ = writeln!(html, "<head>");
+    let _ = writeln!(html, "<meta charset=\"utf-8\">");
+    let _ = writeln!(html, "<title>{}</title>", title);
+    let _ = writeln!(html, "<style>\n{}</style>", STYLE);
+    let _ = writeln!(html, "</head>");
+    let _ = writeln!(html, "<body>");
+    let _ = writeln!(html, "<h1>{}</h1>", title);
+    let _ = writeln!(html, "<p>{} skin(s)</p>", cards.len());
+    let _ = writeln!(html, "<div class=\"skins\">");
+    for card in cards {
+        let name = html_escape(&card.name);
+        let (width, height) = card.thumbnail_size;
+        let _ = writeln!(html, "<div class=\"skin\">");
+        let _ = writeln!(
+            html,
+            r#"<a href="{}/{}.png"><img src="{}/{}.png" width="{}" height="{}" alt="{}"></a>"#,
+            SCREENSHOT_DIR, card.fingerprint, THUMBNAIL_DIR, card.fingerprint, width, height, name
+        );
+        let _ = writeln!(html, "<div class=\"name\">{}</div>", name);
+        if let Some(author) = &card.author {
+            let _ = writeln!(html, "<div class=\"author\">by {}</div>", html_escape(author));
+        }
+        let _ = writeln!(
+            html,
+            "<a href=\"{}/{}\" download>Download</a>",
+            SKIN_DIR,
+            html_escape(&url_path(&card.skin_path))
+        );
+        let _ = writeln!(html, "</div>");
+    }
+    let _ = writeln!(html, "</div>");
+    let _ = writeln!(html, "</body>");
+    let _ = writeln!(html, "</html>");
+    html
+}
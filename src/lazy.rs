@@ -0,0 +1,213 @@
+//! Skins that decode sprite sheets only when their sprites are needed
+//!
+//! Decoding every sheet is most of the work of loading a skin. LazyWsz parses the text files up front like `Wsz`
+//! does, but decodes each sprite sheet the first time one of its sprites is asked for.
+
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::archive::{self, WszArchive};
+use crate::cursor::{CursorImage, CursorRole};
+use crate::error::Result;
+use crate::options::WszOptions;
+use crate::sprites::genex::GenExColors;
+use crate::sprites::{SpriteImage, SpriteManager};
+use crate::text::pledit::PleditSettings;
+use crate::text::readme::SkinMetadata;
+use crate::text::region::Regions;
+use crate::text::viscolor::VisColors;
+use crate::text::{ParseMode, ParseWarning};
+use crate::Wsz;
+
+/// A Winamp skin whose sprite sheets are decoded on first use
+///
+/// Call `into_wsz` to render or edit the skin.
+#[derive(Debug)]
+pub struct LazyWsz {
+    // everything but the sprites
+    skin: Wsz,
+    sprite_manager: SpriteManager,
+    sheets: HashMap<String, OnceLock<HashMap<String, SpriteImage>>>,
+}
+
+impl LazyWsz {
+    /// Open a .wsz file without decoding its sprite sheets
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the .wsz file
+    ///
+    /// # Returns
+    ///
+    /// A new LazyWsz instance
+    pub fn from_file_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut lazy = Self::from_archive(&archive::unpack_wsz(path)?)?;
+        lazy.skin.metadata.file_name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned());
+        lazy.skin.metadata.compressed_size = std::fs::metadata(path).ok().map(|file| file.len());
+        Ok(lazy)
+    }
+
+    /// Open a skin from a byte slice without decoding its sprite sheets
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The byte slice containing the Winamp skin
+    ///
+    /// # Returns
+    ///
+    /// A new LazyWsz instance
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let mut lazy = Self::from_archive(&archive::unpack_wsz_bytes(data)?)?;
+        lazy.skin.metadata.compressed_size = Some(data.len() as u64);
+        Ok(lazy)
+    }
+
+    /// Open a skin from any seekable stream without decoding its sprite sheets
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The stream containing the Winamp skin, read from its current position
+    ///
+    /// # Returns
+    ///
+    /// A new LazyWsz instance
+    pub fn from_reader<R: Read + Seek>(mut reader: R) -> Result<Self> {
+        let start = reader.stream_position()?;
+        let end = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(start))?;
+        let mut lazy = Self::from_archive(&archive::unpack_wsz_reader(reader)?)?;
+        lazy.skin.metadata.compressed_size = Some(end.saturating_sub(start));
+        Ok(lazy)
+    }
+
+    /// Open a skin from an WszArchive without decoding its sprite sheets
+    ///
+    /// Text files are parsed leniently, like `Wsz::from_archive`.
+    pub fn from_archive(archive: &WszArchive) -> Result<Self> {
+        Self::from_archive_with_mode(archive, ParseMode::Lenient)
+    }
+
+    /// Open a skin from an WszArchive without decoding its sprite sheets, choosing how strictly text files are
+    /// parsed
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - The archive to open
+    /// * `mode` - How to handle lines in text files that cannot be parsed
+    ///
+    /// # Returns
+    ///
+    /// A new LazyWsz instance
+    pub fn from_archive_with_mode(archive: &WszArchive, mode: ParseMode) -> Result<Self> {
+        Ok(Self {
+            skin: Wsz::without_sprites(
+                archive,
+                &WszOptions {
+                    parse_mode: mode,
+                    ..Default::default()
+                },
+            )?,
+            sprite_manager: SpriteManager::new(),
+            sheets: SpriteManager::sprite_sheet_names()
+                .into_iter()
+                .map(|sheet| (sheet, OnceLock::new()))
+                .collect(),
+        })
+    }
+
+    /// Get a sprite, decoding its sprite sheet if no sprite from it has been asked for yet
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the sprite to get
+    ///
+    /// # Returns
+    ///
+    /// The sprite if it exists, otherwise None
+    pub fn get_sprite(&self, name: &str) -> Option<&SpriteImage> {
+        let def = self.sprite_manager.get_sprite_definition(name)?;
+        self.get_sheet_sprites(&def.sprite_sheet)?.get(name)
+    }
+
+    /// Get all sprites from one sprite sheet, decoding it if it has not been yet
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet_name` - Name of the sprite sheet, e.g. "MAIN.BMP"
+    ///
+    /// # Returns
+    ///
+    /// A map of sprite names to sprite images, empty if the skin does not have the sheet or it cannot be decoded,
+    /// or None if the sheet name is not known
+    pub fn get_sheet_sprites(&self, sheet_name: &str) -> Option<&HashMap<String, SpriteImage>> {
+        let sheet = self.sheets.get(sheet_name)?;
+        Some(sheet.get_or_init(|| {
+            // missing or broken sheets have no sprites, like in Wsz
+            self.sprite_manager
+                .extract_sprite_sheet_from_archive(&self.skin.files, sheet_name)
+                .unwrap_or_default()
+        }))
+    }
+
+    /// Get the visualization colors (viscolor.txt)
+    pub fn get_vis_colors(&self) -> &VisColors {
+        self.skin.get_vis_colors()
+    }
+
+    /// Get the playlist editor settings (pledit.txt)
+    pub fn get_pledit_settings(&self) -> &PleditSettings {
+        self.skin.get_pledit_settings()
+    }
+
+    /// Get the regions (region.txt)
+    pub fn get_regions(&self) -> &Regions {
+        self.skin.get_regions()
+    }
+
+    /// Get the plugin window colors (GENEX.BMP)
+    pub fn get_genex_colors(&self) -> Option<&GenExColors> {
+        self.skin.get_genex_colors()
+    }
+
+    /// Get the cursor for one part of the skin
+    pub fn get_cursor(&self, role: CursorRole) -> Option<&CursorImage> {
+        self.skin.get_cursor(role)
+    }
+
+    /// Get the cursors (.cur files)
+    pub fn get_cursors(&self) -> &HashMap<CursorRole, CursorImage> {
+        self.skin.get_cursors()
+    }
+
+    /// Get the skin's name, author and other details, as `Wsz::metadata` does
+    pub fn metadata(&self) -> &SkinMetadata {
+        self.skin.metadata()
+    }
+
+    /// Get the problems in the skin's text files that were skipped while loading it
+    pub fn get_parse_warnings(&self) -> &[ParseWarning] {
+        self.skin.get_parse_warnings()
+    }
+
+    /// Decode any sprite sheets that have not been yet and return the complete skin
+    ///
+    /// # Returns
+    ///
+    /// A Wsz instance, the same as loading the skin with `Wsz` would give
+    pub fn into_wsz(self) -> Wsz {
+        for sheet_name in self.sheets.keys() {
+            self.get_sheet_sprites(sheet_name);
+        }
+        let mut skin = self.skin;
+        skin.sprites = self
+            .sheets
+            .into_values()
+            .filter_map(|sheet| sheet.into_inner())
+            .flatten()
+            .collect();
+        skin
+    }
+}
@@ -6,9 +6,30 @@
 //! Most of this code heavily inspired by <https://github.com/captbaritone/webamp>
 
 pub mod archive;
+pub mod bleed;
+pub mod builder;
+pub mod contrast;
+pub mod cursor;
+pub mod dataset;
+pub mod diff;
 pub mod error;
+mod fingerprint;
+pub mod gallery;
+pub mod lazy;
+pub mod library;
+pub mod lint;
+pub mod options;
+pub mod palette;
+pub mod phash;
+pub mod recolor;
+pub mod render;
+pub mod repair;
+pub mod scaffold;
 pub mod sprites;
+pub mod strip;
 pub mod text;
+mod util;
+pub mod validate;
 
 use error::{Result, WszError};
 
@@ -21,6 +42,19 @@ pub struct Wsz {
     vis_colors: text::viscolor::VisColors,
     pledit: text::pledit::PleditSettings,
     regions: text::region::Regions,
+    parse_warnings: Vec<text::ParseWarning>,
+    metadata: text::readme::SkinMetadata,
+    genex_colors: Option<sprites::genex::GenExColors>,
+    cursors: std::collections::HashMap<cursor::CursorRole, cursor::CursorImage>,
+    /// Archive the skin was loaded from, with any replaced sprite sheets, so files that are not edited are saved
+    /// unchanged
+    files: archive::WszArchive,
+    /// Whether `files` has every file of the archive, or only those the skin is decoded from
+    keeps_raw_files: bool,
+    /// File order, timestamps and comment of the archive the skin was loaded from, restored when it is saved
+    provenance: archive::ArchiveProvenance,
+    /// Sprite sheets left out by WszOptions, which are saved unchanged
+    unloaded_sheets: Vec<String>,
 }
 
 impl Wsz {
@@ -34,8 +68,84 @@ impl Wsz {
     ///
     /// A new Wsz instance
     pub fn from_file_path<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
-        let contents = archive::unpack_wsz(path)?;
-        Self::from_archive(&contents)
+        Self::from_file_path_with_options(path, &options::WszOptions::default())
+    }
+
+    /// Create a new Wsz from a .wsz file, choosing what is loaded
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the .wsz file
+    /// * `options` - What to load, and limits on the skin's size
+    ///
+    /// # Returns
+    ///
+    /// A new Wsz instance
+    pub fn from_file_path_with_options<P: AsRef<std::path::Path>>(
+        path: P,
+        options: &options::WszOptions,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path)?;
+        let compressed_size = file.metadata().ok().map(|metadata| metadata.len());
+        let (contents, provenance) = archive::unpack_wsz_reader_with_provenance(file, &options.limits)?;
+        let mut skin = Self::from_archive_with_options(&contents, options)?;
+        skin.provenance = provenance;
+        skin.metadata.file_name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned());
+        skin.metadata.compressed_size = compressed_size;
+        Ok(skin)
+    }
+
+    /// Create a new Wsz from a .wsz file without blocking an async runtime
+    ///
+    /// Requires the `tokio` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the .wsz file
+    ///
+    /// # Returns
+    ///
+    /// A new Wsz instance
+    #[cfg(feature = "tokio")]
+    pub async fn from_file_path_async<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        Self::from_file_path_with_options_async(path, &options::WszOptions::default()).await
+    }
+
+    /// Create a new Wsz from a .wsz file without blocking an async runtime, choosing what is loaded
+    ///
+    /// The file is read with `tokio::fs`, and unzipped and decoded on tokio's blocking thread pool. Files larger than
+    /// `options.limits.max_archive_size` are not read. Requires the `tokio` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the .wsz file
+    /// * `options` - What to load, and limits on the skin's size
+    ///
+    /// # Returns
+    ///
+    /// A new Wsz instance
+    #[cfg(feature = "tokio")]
+    pub async fn from_file_path_with_options_async<P: AsRef<std::path::Path>>(
+        path: P,
+        options: &options::WszOptions,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(max_size) = options.limits.max_archive_size {
+            if tokio::fs::metadata(path).await?.len() > max_size {
+                return Err(WszError::LimitExceeded(format!(
+                    "archive is larger than {} bytes",
+                    max_size
+                )));
+            }
+        }
+        let data = tokio::fs::read(path).await?;
+        let options = options.clone();
+        let mut skin = tokio::task::spawn_blocking(move || Self::from_bytes_with_options(&data, &options))
+            .await
+            .map_err(std::io::Error::from)??;
+        skin.metadata.file_name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned());
+        Ok(skin)
     }
 
     /// Create a new Wsz from a byte slice
@@ -48,12 +158,122 @@ impl Wsz {
     ///
     /// A new Wsz instance
     pub fn from_bytes(data: &[u8]) -> Result<Self> {
-        let contents = archive::unpack_wsz_bytes(data)?;
-        Self::from_archive(&contents)
+        Self::from_bytes_with_options(data, &options::WszOptions::default())
+    }
+
+    /// Create a new Wsz from a byte slice, choosing what is loaded
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The byte slice containing the Winamp skin
+    /// * `options` - What to load, and limits on the skin's size
+    ///
+    /// # Returns
+    ///
+    /// A new Wsz instance
+    pub fn from_bytes_with_options(data: &[u8], options: &options::WszOptions) -> Result<Self> {
+        let (contents, provenance) =
+            archive::unpack_wsz_reader_with_provenance(std::io::Cursor::new(data), &options.limits)?;
+        let mut skin = Self::from_archive_with_options(&contents, options)?;
+        skin.provenance = provenance;
+        skin.metadata.compressed_size = Some(data.len() as u64);
+        Ok(skin)
+    }
+
+    /// Download a skin and create a new Wsz from it
+    ///
+    /// The skin is loaded with `ResourceLimits::untrusted`. Requires the `http` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - URL of the .wsz file, over HTTP or HTTPS
+    ///
+    /// # Returns
+    ///
+    /// A new Wsz instance, or an error if the download fails or the skin is over the limits
+    #[cfg(feature = "http")]
+    pub fn from_url(url: &str) -> Result<Self> {
+        let options = options::WszOptions {
+            limits: options::ResourceLimits::untrusted(),
+            ..Default::default()
+        };
+        Self::from_url_with_options(url, &options)
+    }
+
+    /// Download a skin and create a new Wsz from it, choosing what is loaded
+    ///
+    /// The download is streamed into memory and stopped as soon as it is larger than `options.limits`'
+    /// `max_archive_size`. Requires the `http` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - URL of the .wsz file, over HTTP or HTTPS
+    /// * `options` - What to load, and limits on the skin's size
+    ///
+    /// # Returns
+    ///
+    /// A new Wsz instance, or an error if the download fails or the skin is over the limits
+    #[cfg(feature = "http")]
+    pub fn from_url_with_options(url: &str, options: &options::WszOptions) -> Result<Self> {
+        let data = archive::download_wsz(url, &options.limits)?;
+        let mut skin = Self::from_bytes_with_options(&data, options)?;
+        // the last part of the URL's path, without a query, fragment or extension
+        let path = url.split(['?', '#']).next().unwrap_or_default();
+        let path = path
+            .split_once("://")
+            .map_or(path, |(_, rest)| rest.split_once('/').map_or("", |(_, path)| path));
+        skin.metadata.file_name = path.rsplit('/').next().filter(|name| !name.is_empty()).map(|name| {
+            std::path::Path::new(name)
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned()
+        });
+        Ok(skin)
+    }
+
+    /// Create a new Wsz from any seekable stream, such as a downloaded file or a member of another archive
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The stream containing the Winamp skin, read from its current position
+    ///
+    /// # Returns
+    ///
+    /// A new Wsz instance
+    pub fn from_reader<R: std::io::Read + std::io::Seek>(reader: R) -> Result<Self> {
+        Self::from_reader_with_options(reader, &options::WszOptions::default())
+    }
+
+    /// Create a new Wsz from any seekable stream, choosing what is loaded
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The stream containing the Winamp skin, read from its current position
+    /// * `options` - What to load, and limits on the skin's size
+    ///
+    /// # Returns
+    ///
+    /// A new Wsz instance
+    pub fn from_reader_with_options<R: std::io::Read + std::io::Seek>(
+        mut reader: R,
+        options: &options::WszOptions,
+    ) -> Result<Self> {
+        let start = reader.stream_position()?;
+        let end = reader.seek(std::io::SeekFrom::End(0))?;
+        reader.seek(std::io::SeekFrom::Start(start))?;
+        let (contents, provenance) = archive::unpack_wsz_reader_with_provenance(reader, &options.limits)?;
+        let mut skin = Self::from_archive_with_options(&contents, options)?;
+        skin.provenance = provenance;
+        skin.metadata.compressed_size = Some(end.saturating_sub(start));
+        Ok(skin)
     }
 
     /// Create a new Wsz from an WszArchive
     ///
+    /// Text files are parsed leniently: lines that cannot be parsed are skipped and reported by
+    /// `get_parse_warnings`.
+    ///
     /// # Arguments
     ///
     /// * `archive` - The archive to create the Wsz from
@@ -62,20 +282,84 @@ impl Wsz {
     ///
     /// A new Wsz instance
     pub fn from_archive(archive: &archive::WszArchive) -> Result<Self> {
+        Self::from_archive_with_mode(archive, text::ParseMode::Lenient)
+    }
+
+    /// Create a new Wsz from an WszArchive, choosing how strictly text files are parsed
+    ///
+    /// In strict mode, errors in viscolor.txt and pledit.txt fail the whole skin, while a region.txt that cannot
+    /// be parsed is ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - The archive to create the Wsz from
+    /// * `mode` - How to handle lines in text files that cannot be parsed
+    ///
+    /// # Returns
+    ///
+    /// A new Wsz instance
+    pub fn from_archive_with_mode(archive: &archive::WszArchive, mode: text::ParseMode) -> Result<Self> {
+        let options = options::WszOptions {
+            parse_mode: mode,
+            ..Default::default()
+        };
+        Self::from_archive_with_options(archive, &options)
+    }
+
+    /// Create a new Wsz from an WszArchive, choosing what is loaded
+    ///
+    /// The archive is already unpacked, so only the `max_image_pixels` limit applies.
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - The archive to create the Wsz from
+    /// * `options` - What to load, and limits on the skin's size
+    ///
+    /// # Returns
+    ///
+    /// A new Wsz instance, or an error if a sheet in `options` is not a known sprite sheet
+    pub fn from_archive_with_options(archive: &archive::WszArchive, options: &options::WszOptions) -> Result<Self> {
+        options.check_sheets()?;
+        let mut skin = Self::without_sprites(archive, options)?;
         let sprite_manager = sprites::SpriteManager::new();
-        let sprites = sprite_manager.extract_all_sprites_from_archive(archive)?;
+        for sheet_name in sprites::SpriteManager::sprite_sheet_names() {
+            if !options.loads_sheet(&sheet_name) {
+                skin.unloaded_sheets.push(sheet_name);
+                continue;
+            }
+            if let Some(key) = sprites::find_sprite_sheet(archive, &sheet_name) {
+                options.limits.check_image(key, &archive[key])?;
+            }
+            // some sheets may be missing
+            if let Ok(sheet_sprites) = sprite_manager.extract_sprite_sheet_from_archive(archive, &sheet_name) {
+                skin.sprites.extend(sheet_sprites);
+            }
+        }
+        Ok(skin)
+    }
+
+    /// Loads everything but the sprites, which are the slowest part to decode
+    pub(crate) fn without_sprites(archive: &archive::WszArchive, options: &options::WszOptions) -> Result<Self> {
+        let mode = options.parse_mode;
+        let mut parse_warnings = Vec::new();
 
         // allow these to be not found
-        let vis_colors = match text::viscolor::VisColors::from_archive(archive) {
-            Ok(vis_colors) => vis_colors,
+        let vis_colors = match text::viscolor::VisColors::from_archive_with_mode(archive, mode) {
+            Ok((vis_colors, warnings)) => {
+                parse_warnings.extend(warnings);
+                vis_colors
+            }
             Err(WszError::NotFound(_)) => text::viscolor::VisColors::default(),
             Err(e) => {
                 return Err(e);
             }
         };
 
-        let pledit = match text::pledit::PleditSettings::from_archive(archive) {
-            Ok(pledit) => pledit,
+        let pledit = match text::pledit::PleditSettings::from_archive_with_mode(archive, mode) {
+            Ok((pledit, warnings)) => {
+                parse_warnings.extend(warnings);
+                pledit
+            }
             Err(WszError::NotFound(_)) => text::pledit::PleditSettings::default(),
             Err(e) => {
                 return Err(e);
@@ -83,16 +367,62 @@ impl Wsz {
         };
 
         // discard any regions errors
-        let regions = match text::region::Regions::from_archive(archive) {
-            Ok(regions) => regions,
+        let regions = match text::region::Regions::from_archive_with_mode(archive, mode) {
+            Ok((regions, warnings)) => {
+                parse_warnings.extend(warnings);
+                regions
+            }
             Err(_) => text::region::Regions::default(),
         };
 
+        // most skins have a readme, but it is not required
+        let metadata = text::readme::SkinMetadata::from_skin_archive(archive);
+
+        // only skins that style plugin windows have GENEX.BMP
+        let genex = archive
+            .iter()
+            .find(|(name, _)| name.split('/').next_back().unwrap().eq_ignore_ascii_case("GENEX.BMP"));
+        if let Some((name, data)) = genex {
+            options.limits.check_image(name, data)?;
+        }
+        let genex_colors = sprites::genex::GenExColors::from_archive(archive).ok();
+
+        // skins without cursors use Winamp's defaults
+        let cursors = if options.decode_cursors {
+            cursor::cursors_from_archive(archive)
+        } else {
+            std::collections::HashMap::new()
+        };
+
+        // without the raw files, keep only what the skin is decoded from and saved with
+        let files = if options.keep_raw_files {
+            archive.clone()
+        } else {
+            archive
+                .iter()
+                .filter(|(name, _)| {
+                    matches!(
+                        archive::FileKind::of(name),
+                        archive::FileKind::Bitmap | archive::FileKind::Config
+                    )
+                })
+                .map(|(name, data)| (name.clone(), data.clone()))
+                .collect()
+        };
+
         Ok(Self {
-            sprites,
+            sprites: std::collections::HashMap::new(),
             vis_colors,
             pledit,
             regions,
+            parse_warnings,
+            metadata,
+            genex_colors,
+            cursors,
+            files,
+            keeps_raw_files: options.keep_raw_files,
+            provenance: archive::ArchiveProvenance::default(),
+            unloaded_sheets: Vec::new(),
         })
     }
 
@@ -109,6 +439,25 @@ impl Wsz {
         self.sprites.get(name)
     }
 
+    /// Get the sprites of an element of a window, grouped by the state they are drawn in
+    ///
+    /// # Arguments
+    ///
+    /// * `window` - The window the element is in
+    /// * `element` - The element, e.g. `Element::PlayButton`
+    ///
+    /// # Returns
+    ///
+    /// The element's sprites, or None if the window has no such element. States the skin has no sprite for are None.
+    pub fn element(
+        &self,
+        window: sprites::WindowType,
+        element: sprites::element::Element,
+    ) -> Option<sprites::element::ElementSprites<'_>> {
+        sprites::element::element_sprite_names(window, element)
+            .map(|names| sprites::element::ElementSprites::new(names, &self.sprites))
+    }
+
     /// Get all sprites from the archive
     ///
     /// # Returns
@@ -118,6 +467,69 @@ impl Wsz {
         &self.sprites
     }
 
+    /// Gets every sprite, decoding the sheets left out by `WszOptions::sheets` from the archive
+    pub(crate) fn all_sprites(&self) -> std::borrow::Cow<'_, std::collections::HashMap<String, sprites::SpriteImage>> {
+        if self.unloaded_sheets.is_empty() {
+            return std::borrow::Cow::Borrowed(&self.sprites);
+        }
+        let sprite_manager = sprites::SpriteManager::new();
+        let mut all = std::collections::HashMap::new();
+        for sheet_name in &self.unloaded_sheets {
+            // some sheets may be missing
+            if let Ok(sheet_sprites) = sprite_manager.extract_sprite_sheet_from_archive(&self.files, sheet_name) {
+                all.extend(sheet_sprites);
+            }
+        }
+        // sprites set since loading replace the sheet's
+        all.extend(self.sprites.iter().map(|(name, sprite)| (name.clone(), sprite.clone())));
+        std::borrow::Cow::Owned(all)
+    }
+
+    /// Get a file from the archive as it was loaded, such as a readme, a cursor or an original sprite sheet
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Path of the file in the archive, or just its name to find it in any directory. Case is
+    ///   ignored if there is no exact match, and the shallowest match wins, then the first in alphabetical order.
+    ///
+    /// # Returns
+    ///
+    /// The file's contents, or None if the archive does not have it or the skin was loaded without
+    /// `WszOptions::keep_raw_files`. Sheets replaced with `replace_sheet` have their new contents.
+    pub fn raw_file(&self, name: &str) -> Option<&[u8]> {
+        if !self.keeps_raw_files {
+            return None;
+        }
+        let find = |matches: fn(&str, &str) -> bool| {
+            self.files
+                .iter()
+                .filter(|(key, _)| matches(key, name))
+                .min_by_key(|(key, _)| (key.matches('/').count(), key.as_str()))
+                .map(|(_, data)| data)
+        };
+        let data = self
+            .files
+            .get(name)
+            .or_else(|| find(|key, name| key.eq_ignore_ascii_case(name)))
+            .or_else(|| find(|key, name| key.split('/').next_back().unwrap().eq_ignore_ascii_case(name)));
+        data.map(Vec::as_slice)
+    }
+
+    /// Get every file from the archive as it was loaded
+    ///
+    /// # Returns
+    ///
+    /// A map of file paths to their contents, empty if the skin was loaded without `WszOptions::keep_raw_files`.
+    /// Sheets replaced with `replace_sheet` have their new contents.
+    pub fn raw_files(&self) -> &archive::WszArchive {
+        static NO_FILES: std::sync::OnceLock<archive::WszArchive> = std::sync::OnceLock::new();
+        if self.keeps_raw_files {
+            &self.files
+        } else {
+            NO_FILES.get_or_init(archive::WszArchive::new)
+        }
+    }
+
     /// Get the visualization colors (viscolor.txt)
     ///
     /// # Returns
@@ -145,17 +557,854 @@ impl Wsz {
         &self.regions
     }
 
-    /// Render a screenshot of the skin
+    /// Get the plugin window colors (GENEX.BMP)
     ///
     /// # Returns
     ///
-    /// A WindowImage instance
-    pub fn render_screenshot(&self) -> Result<sprites::WindowImage> {
-        let mut window_defs = sprites::SpriteWindowManager::new();
-        if let Some(bg_color) = self.pledit.normal_bg {
-            window_defs.set_bg_color(bg_color);
-        }
-        let window = window_defs.draw_all_sprites(&self.sprites)?;
-        Ok(window)
+    /// The GenExColors instance, or None if the skin has no usable GENEX.BMP
+    pub fn get_genex_colors(&self) -> Option<&sprites::genex::GenExColors> {
+        self.genex_colors.as_ref()
+    }
+
+    /// Get the cursor for one part of the skin
+    ///
+    /// # Arguments
+    ///
+    /// * `role` - The part of the skin the cursor is shown over
+    ///
+    /// # Returns
+    ///
+    /// The cursor if the skin has a usable one, otherwise None, in which case Winamp uses its default cursor
+    pub fn get_cursor(&self, role: cursor::CursorRole) -> Option<&cursor::CursorImage> {
+        self.cursors.get(&role)
+    }
+
+    /// Get the cursors (.cur files)
+    ///
+    /// # Returns
+    ///
+    /// A map of cursor roles to decoded cursors. Roles the skin has no usable cursor for are missing.
+    pub fn get_cursors(&self) -> &std::collections::HashMap<cursor::CursorRole, cursor::CursorImage> {
+        &self.cursors
+    }
+
+    /// Get the skin's name, author and other details found in its readme, along with its file count, size and
+    /// which optional parts it has
+    ///
+    /// These are worked out when the skin is loaded, and do not change when it is edited.
+    ///
+    /// # Returns
+    ///
+    /// The details. The readme's are None if the skin has no readme.
+    pub fn metadata(&self) -> &text::readme::SkinMetadata {
+        &self.metadata
+    }
+
+    /// Get the problems in the skin's text files that were skipped while loading it
+    ///
+    /// # Returns
+    ///
+    /// The warnings, in the order they were found. Empty if the skin was loaded in strict mode.
+    pub fn get_parse_warnings(&self) -> &[text::ParseWarning] {
+        &self.parse_warnings
+    }
+
+    /// Get how a sprite sheet is stored: its bit depth, compression and, for 8 bit and smaller sheets, its palette
+    ///
+    /// This describes the file in the skin's archive, which is unchanged by `set_sprite` until the skin is saved.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet_name` - Name of the sprite sheet (case insensitive), e.g. "MAIN.BMP"
+    ///
+    /// # Returns
+    ///
+    /// The sheet's details, or None if the skin does not have the sheet or it is not a BMP
+    pub fn get_sheet_info(&self, sheet_name: &str) -> Option<sprites::bmp::BitmapInfo> {
+        sprites::bmp::sprite_sheet_info(&self.files, sheet_name)
+    }
+
+    /// Decode a whole sprite sheet, including pixels outside every sprite
+    ///
+    /// This decodes the file in the skin's archive, which is unchanged by `set_sprite` until the skin is saved.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet_name` - Name of the sprite sheet (case insensitive), e.g. "MAIN.BMP"
+    ///
+    /// # Returns
+    ///
+    /// The sheet, None if the skin does not have it, or an error if it cannot be decoded
+    pub fn get_sheet_image(&self, sheet_name: &str) -> Result<Option<image::DynamicImage>> {
+        let Some(key) = sprites::find_sprite_sheet(&self.files, sheet_name) else {
+            return Ok(None);
+        };
+        let image = image::ImageReader::new(std::io::Cursor::new(&self.files[key]))
+            .with_guessed_format()?
+            .decode()?;
+        Ok(Some(image))
+    }
+
+    /// Get the order of the files in the archive the skin was loaded from, when each was last modified, and the
+    /// archive's comment
+    ///
+    /// # Returns
+    ///
+    /// The provenance, which is empty if the skin was not loaded from a .wsz file
+    pub fn get_provenance(&self) -> &archive::ArchiveProvenance {
+        &self.provenance
+    }
+
+    /// Check the skin for missing or undersized sprite sheets, sprites of the wrong size, missing text files and
+    /// problems in the text files
+    ///
+    /// # Returns
+    ///
+    /// A ValidationReport listing each problem with its severity
+    pub fn validate(&self) -> validate::ValidationReport {
+        validate::validate(self)
+    }
+
+    /// Check the skin for everything `validate` finds, plus sprites that bleed into their neighbors, text files that
+    /// cannot be decoded and playlist fonts that are not installed with Windows
+    ///
+    /// # Returns
+    ///
+    /// A LintReport listing each problem with its severity, most serious first
+    pub fn lint(&self) -> lint::LintReport {
+        lint::lint(self)
+    }
+
+    /// Check the contrast of the playlist text colors and the spectrum analyzer colors against their backgrounds
+    ///
+    /// # Returns
+    ///
+    /// A ContrastReport with the WCAG contrast ratio of each pair and whether it passes levels AA and AAA
+    pub fn contrast_report(&self) -> contrast::ContrastReport {
+        contrast::contrast_report(&self.pledit, &self.vis_colors)
+    }
+
+    /// Compute a perceptual hash of the skin's main window, for finding copies of the skin that were re-saved or
+    /// lightly edited
+    ///
+    /// # Returns
+    ///
+    /// The hash. Compare hashes with `PerceptualHash::distance`, or group skins with `phash::cluster`.
+    pub fn phash(&self) -> Result<phash::PerceptualHash> {
+        phash::phash(self)
+    }
+
+    /// Find sprites whose artwork runs one row or column into a neighboring sprite on the same sheet, usually from
+    /// drawing the sprite a pixel too large
+    ///
+    /// # Returns
+    ///
+    /// Each sprite that bleeds, with the sprite it runs into and the line of pixels affected, sorted by sheet
+    pub fn sprite_bleed(&self) -> Vec<bleed::SpriteBleed> {
+        bleed::sprite_bleed(self)
+    }
+
+    /// Pack the skin's sprites into a single texture atlas
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Packing options
+    ///
+    /// # Returns
+    ///
+    /// The atlas image and the position of each sprite in it
+    pub fn atlas(&self, options: &sprites::atlas::AtlasOptions) -> sprites::atlas::Atlas {
+        sprites::atlas::build_atlas(&self.sprites, options)
+    }
+
+    /// Find the skin's most common colors, as they appear in its default screenshot
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - Most colors to return
+    ///
+    /// # Returns
+    ///
+    /// The colors, most common first, with the share of the visible pixels each covers
+    pub fn palette(&self, count: usize) -> Result<Vec<palette::PaletteColor>> {
+        Ok(palette::palette(&self.render_screenshot()?, count))
+    }
+
+    /// List the colors the skin sets in viscolor.txt and pledit.txt
+    ///
+    /// # Returns
+    ///
+    /// The 24 visualization colors followed by the playlist colors, with Winamp's defaults for colors the skin
+    /// does not set
+    pub fn color_swatches(&self) -> Vec<render::swatches::Swatch> {
+        render::swatches::color_swatches(&self.vis_colors, &self.pledit)
+    }
+
+    /// Draw a chart of the skin's visualization and playlist colors, labeled in its TEXT.BMP font
+    ///
+    /// # Returns
+    ///
+    /// The chart
+    pub fn render_color_swatches(&self) -> sprites::WindowImage {
+        render::swatches::render_swatches(&self.sprites, &self.color_swatches())
     }
+
+    /// Compare the skin with another, such as a newer version of it
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The skin to compare with
+    ///
+    /// # Returns
+    ///
+    /// A SkinDiff listing what differs, with this skin as the old side and `other` as the new side
+    pub fn diff(&self, other: &Wsz) -> diff::SkinDiff {
+        diff::diff(self, other)
+    }
+
+    /// Compute a fingerprint of the skin's content, for finding the same skin under different file names
+    ///
+    /// The fingerprint is a hash of the sprites' pixels, GENEX.BMP's pixels, and the settings in viscolor.txt,
+    /// pledit.txt and region.txt. It ignores file names, zip metadata, entry order, compression, how the images
+    /// and text files are encoded, and pixels of sprite sheets outside every sprite. Sheets left out by
+    /// `WszOptions::sheets` are decoded to compute it, so it is the same however the skin was loaded.
+    ///
+    /// # Returns
+    ///
+    /// The fingerprint as 40 lowercase hex digits
+    pub fn fingerprint(&self) -> String {
+        fingerprint::fingerprint(self)
+    }
+
+    /// Fill in whatever this skin is missing from another skin, like Winamp falls back to its base skin
+    ///
+    /// Missing sprite sheets, sprites, cursors and GENEX.BMP are taken from `base`, along with visualization
+    /// colors past the end of viscolor.txt and pledit.txt settings that are not set. Regions are not, since
+    /// Winamp draws windows without a region as plain rectangles rather than using the base skin's.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - The skin to take missing parts from
+    ///
+    /// # Returns
+    ///
+    /// The merged skin. Its metadata describes the merged files, and it has no compressed size. It can only be
+    /// saved if both skins were loaded with `WszOptions::keep_raw_files`.
+    pub fn merged_over(&self, base: &Wsz) -> Wsz {
+        let mut merged = self.clone();
+        merged.keeps_raw_files = self.keeps_raw_files && base.keeps_raw_files;
+        let sprite_manager = sprites::SpriteManager::new();
+
+        // copies a file from the base skin, unless this skin has one by the same name in any directory
+        let copy_file = |files: &mut archive::WszArchive, file_name: &str| {
+            let find_file = |archive: &archive::WszArchive| {
+                archive
+                    .keys()
+                    .find(|name| name.split('/').next_back().unwrap().eq_ignore_ascii_case(file_name))
+                    .cloned()
+            };
+            if find_file(files).is_none() {
+                if let Some(key) = find_file(&base.files) {
+                    files.insert(key.clone(), base.files[&key].clone());
+                }
+            }
+        };
+
+        // take whole sheets, so pixels outside the sprites come along too
+        for sheet_name in sprites::SpriteManager::sprite_sheet_names() {
+            let has_sprites = merged.sprites.keys().any(|name| {
+                sprite_manager
+                    .get_sprite_definition(name)
+                    .is_some_and(|def| def.sprite_sheet == sheet_name)
+            });
+            if !has_sprites {
+                copy_file(&mut merged.files, &sheet_name);
+            }
+        }
+        for (name, sprite) in &base.sprites {
+            merged.sprites.entry(name.clone()).or_insert_with(|| sprite.clone());
+        }
+
+        if merged.genex_colors.is_none() && base.genex_colors.is_some() {
+            merged.genex_colors = base.genex_colors;
+            copy_file(&mut merged.files, "GENEX.BMP");
+        }
+        for (role, cursor) in &base.cursors {
+            if !merged.cursors.contains_key(role) {
+                merged.cursors.insert(*role, cursor.clone());
+                copy_file(&mut merged.files, role.file_name());
+            }
+        }
+
+        for index in merged.vis_colors.len()..base.vis_colors.len() {
+            if let Some(color) = base.vis_colors.get(index) {
+                // base colors come from viscolor.txt, so are always in range
+                let _ = merged.vis_colors.set(index, color);
+            }
+        }
+        let (pledit, base_pledit) = (&mut merged.pledit, &base.pledit);
+        pledit.normal = pledit.normal.or(base_pledit.normal);
+        pledit.current = pledit.current.or(base_pledit.current);
+        pledit.normal_bg = pledit.normal_bg.or(base_pledit.normal_bg);
+        pledit.selected_bg = pledit.selected_bg.or(base_pledit.selected_bg);
+        pledit.font = pledit.font.take().or_else(|| base_pledit.font.clone());
+        pledit.font_size = pledit.font_size.or(base_pledit.font_size);
+        pledit.mb_fg = pledit.mb_fg.or(base_pledit.mb_fg);
+        pledit.mb_bg = pledit.mb_bg.or(base_pledit.mb_bg);
+
+        let file_name = merged.metadata.file_name.take();
+        merged.metadata = text::readme::SkinMetadata::from_skin_archive(&merged.files);
+        merged.metadata.file_name = file_name;
+        merged
+    }
+
+    /// Fix common problems that stop the skin from loading or displaying correctly
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - What to fix, and the skin to fill in missing sprite sheets and sprites from
+    ///
+    /// # Returns
+    ///
+    /// A RepairLog listing every change, empty if nothing needed fixing
+    pub fn repair(&mut self, options: &repair::RepairOptions) -> Result<repair::RepairLog> {
+        repair::repair(self, options)
+    }
+
+    /// Shift the hue, saturation and lightness of every sprite, along with the visualization, playlist and GENEX.BMP
+    /// colors
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - How to change the colors
+    ///
+    /// # Returns
+    ///
+    /// A Result indicating success, or an error if GENEX.BMP cannot be encoded
+    pub fn recolor(&mut self, options: &recolor::RecolorOptions) -> Result<()> {
+        recolor::recolor(self, options)
+    }
+
+    /// Remove files Winamp does not need, such as readmes, screenshots, cursors and AVS presets, and optionally
+    /// re-encode the sprite sheets to make the skin as small as possible
+    ///
+    /// Sprite sheets redrawn on save because their sprites were changed are written as 24 bit BMPs, so strip the
+    /// skin after editing it.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Which kinds of files to remove, and whether to re-encode the sprite sheets
+    ///
+    /// # Returns
+    ///
+    /// A StripSummary of the files removed and re-encoded and the skin's size before and after, or an error if a
+    /// sheet cannot be encoded
+    pub fn strip(&mut self, options: &strip::StripOptions) -> Result<strip::StripSummary> {
+        strip::strip(self, options)
+    }
+
+    /// Replace a sprite
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the sprite to replace, e.g. "MAIN_PLAY_BUTTON"
+    /// * `image` - The new sprite image
+    ///
+    /// # Returns
+    ///
+    /// A Result indicating success, or an error if the sprite name is not known
+    pub fn set_sprite(&mut self, name: &str, image: sprites::SpriteImage) -> Result<()> {
+        if sprites::SpriteManager::new().get_sprite_definition(name).is_none() {
+            return Err(WszError::ArgumentError(format!("Sprite {} not found", name)));
+        }
+        self.sprites.insert(name.to_string(), image);
+        Ok(())
+    }
+
+    /// Replace the visualization colors (viscolor.txt)
+    pub fn set_vis_colors(&mut self, vis_colors: text::viscolor::VisColors) {
+        self.vis_colors = vis_colors;
+    }
+
+    /// Replace the playlist editor settings (pledit.txt)
+    pub fn set_pledit_settings(&mut self, pledit: text::pledit::PleditSettings) {
+        self.pledit = pledit;
+    }
+
+    /// Replace the regions (region.txt)
+    pub fn set_regions(&mut self, regions: text::region::Regions) {
+        self.regions = regions;
+    }
+
+    /// Replace the file order, timestamps and comment the skin is saved with
+    pub fn set_provenance(&mut self, provenance: archive::ArchiveProvenance) {
+        self.provenance = provenance;
+    }
+
+    /// Replace a whole sprite sheet, replacing every sprite on it
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet_name` - Name of the sprite sheet (case insensitive), e.g. "MAIN.BMP"
+    /// * `image` - The new sprite sheet
+    ///
+    /// # Returns
+    ///
+    /// A Result indicating success, or an error if the sheet name is not known
+    pub fn replace_sheet(&mut self, sheet_name: &str, image: &image::DynamicImage) -> Result<()> {
+        let sheet_name = sheet_name.to_uppercase();
+        if !sprites::SpriteManager::sprite_sheet_names().contains(&sheet_name) {
+            return Err(WszError::ArgumentError(format!(
+                "{} not a known sprite sheet",
+                sheet_name
+            )));
+        }
+
+        // keep the sheet itself, so pixels outside every sprite are saved too
+        let key = sprites::find_sprite_sheet(&self.files, &sheet_name)
+            .cloned()
+            .unwrap_or_else(|| sheet_name.clone());
+        self.files.insert(key, encode_bmp(image)?);
+        self.unloaded_sheets.retain(|name| *name != sheet_name);
+
+        let sprite_manager = sprites::SpriteManager::new();
+        self.sprites.retain(|name, _| {
+            sprite_manager
+                .get_sprite_definition(name)
+                .map_or(true, |def| def.sprite_sheet != sheet_name)
+        });
+        let sheet_sprites = sprite_manager.extract_sprite_sheet_from_archive(&self.files, &sheet_name)?;
+        self.sprites.extend(sheet_sprites);
+        Ok(())
+    }
+
+    /// Write the skin to a .wsz file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to write the .wsz file to
+    ///
+    /// # Returns
+    ///
+    /// A Result indicating success, or an error if the skin was loaded without `WszOptions::keep_raw_files`
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+
+    /// Write the skin as .wsz file data
+    ///
+    /// Files are written in the order of the archive the skin was loaded from, with their original timestamps and
+    /// the archive's comment. Files that were added are written after them.
+    ///
+    /// # Returns
+    ///
+    /// The contents of a .wsz file, or an error if the skin was loaded without `WszOptions::keep_raw_files`
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        archive::pack_wsz_bytes_with_provenance(&self.to_archive()?, &self.provenance)
+    }
+
+    /// Write the skin as archive contents
+    ///
+    /// Sprite sheets are redrawn from the current sprites and the text files are serialized from the current
+    /// settings. Sheets and text files that have not changed, and every other file in the skin, are kept exactly
+    /// as they were loaded.
+    ///
+    /// # Returns
+    ///
+    /// A map of file names to their contents, or an error if the skin was loaded without
+    /// `WszOptions::keep_raw_files`, as its readme, cursors and other files would be lost
+    pub fn to_archive(&self) -> Result<archive::WszArchive> {
+        if !self.keeps_raw_files {
+            return Err(WszError::ArgumentError(
+                "skin was loaded without keep_raw_files, so it cannot be saved".to_string(),
+            ));
+        }
+        let mut files = self.files.clone();
+        let sprite_manager = sprites::SpriteManager::new();
+
+        for sheet_name in sprites::SpriteManager::sprite_sheet_names() {
+            let sheet_sprites: std::collections::HashMap<String, sprites::SpriteImage> = self
+                .sprites
+                .iter()
+                .filter(|(name, _)| {
+                    sprite_manager
+                        .get_sprite_definition(name)
+                        .is_some_and(|def| def.sprite_sheet == sheet_name)
+                })
+                .map(|(name, sprite)| (name.clone(), sprite.clone()))
+                .collect();
+            // sheets that were not loaded keep their pixels, apart from any sprites set since
+            if sheet_sprites.is_empty() && self.unloaded_sheets.contains(&sheet_name) {
+                continue;
+            }
+            let original = sprite_manager
+                .extract_sprite_sheet_from_archive(&self.files, &sheet_name)
+                .unwrap_or_default();
+            if sheet_sprites == original {
+                continue;
+            }
+
+            let key = sprites::find_sprite_sheet(&self.files, &sheet_name).cloned();
+            if sheet_sprites.is_empty() {
+                if let Some(key) = key {
+                    files.remove(&key);
+                }
+                continue;
+            }
+
+            // draw over the original so pixels outside every sprite survive
+            let existing = key
+                .as_ref()
+                .and_then(|key| image::load_from_memory(&self.files[key]).ok());
+            let sheet = match existing {
+                Some(existing) => sprite_manager.update_sprite_sheet(&existing, &sheet_sprites, &sheet_name)?,
+                None => sprite_manager.construct_sprite_sheet(&sheet_sprites, &sheet_name)?,
+            };
+
+            files.insert(key.unwrap_or(sheet_name), encode_bmp(&sheet)?);
+        }
+
+        let mut config = text::config::SkinConfig::default();
+        config.set_vis_colors(self.vis_colors.clone());
+        config.set_pledit(self.pledit.clone());
+        config.set_regions(self.regions.clone());
+        let (original, _) = text::config::SkinConfig::from_archive_with_mode(&self.files, text::ParseMode::Lenient)?;
+        config.write_changes_into(&original, &mut files);
+
+        Ok(files)
+    }
+
+    /// Render a screenshot of the skin
+    ///
+    /// # Returns
+    ///
+    /// A WindowImage instance
+    pub fn render_screenshot(&self) -> Result<sprites::WindowImage> {
+        self.render_screenshot_with_options(&render::RenderOptions::default())
+    }
+
+    /// Render a screenshot of the skin in a particular state
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Options controlling the rendered state
+    ///
+    /// # Returns
+    ///
+    /// A WindowImage instance
+    pub fn render_screenshot_with_options(&self, options: &render::RenderOptions) -> Result<sprites::WindowImage> {
+        let window_defs = self.window_manager(options);
+        let mut window = window_defs.draw_all_sprites(&self.sprites)?;
+        if let Some(deficiency) = options.color_vision {
+            render::color_vision::simulate(&mut window, deficiency);
+        }
+        render::scale_image(window, options.scale)
+    }
+
+    /// Render a thumbnail of the skin, downscaled to fit inside a box and letterboxed with transparency
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Width of the thumbnail
+    /// * `height` - Height of the thumbnail
+    ///
+    /// # Returns
+    ///
+    /// A WindowImage of exactly `width` x `height`
+    pub fn render_thumbnail(&self, width: u32, height: u32) -> Result<sprites::WindowImage> {
+        self.render_thumbnail_with_options(width, height, &render::thumbnail::ThumbnailOptions::default())
+    }
+
+    /// Render a thumbnail of the skin with a particular fit, filter and state
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Width of the thumbnail's box
+    /// * `height` - Height of the thumbnail's box
+    /// * `options` - Thumbnail options
+    ///
+    /// # Returns
+    ///
+    /// A WindowImage instance
+    pub fn render_thumbnail_with_options(
+        &self,
+        width: u32,
+        height: u32,
+        options: &render::thumbnail::ThumbnailOptions,
+    ) -> Result<sprites::WindowImage> {
+        let mut window = self.window_manager(&options.render).draw_all_sprites(&self.sprites)?;
+        if let Some(deficiency) = options.render.color_vision {
+            render::color_vision::simulate(&mut window, deficiency);
+        }
+        Ok(render::thumbnail::make_thumbnail(
+            &window,
+            width,
+            height,
+            options.fit,
+            options.filter,
+        ))
+    }
+
+    /// Render the skin as ANSI truecolor text for previewing in a terminal
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Options controlling the rendered state
+    /// * `max_columns` - Width limit in terminal columns. Wider renders are downscaled to fit.
+    ///
+    /// # Returns
+    ///
+    /// The preview text, two pixel rows per line
+    pub fn render_ansi(&self, options: &render::RenderOptions, max_columns: Option<u32>) -> Result<String> {
+        let screenshot = self.render_screenshot_with_options(options)?;
+        Ok(render::ansi::image_to_ansi(&screenshot, max_columns))
+    }
+
+    /// Render each window of the layout as a separate image so callers can composite and move windows themselves
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Options controlling the rendered state
+    /// * `split_layers` - Whether to return each layer of each window as its own image
+    ///
+    /// # Returns
+    ///
+    /// The rendered windows in layout order, bottom-most first, with offsets scaled like the images
+    pub fn render_layers(
+        &self,
+        options: &render::RenderOptions,
+        split_layers: bool,
+    ) -> Result<Vec<render::RenderedLayer>> {
+        let window_defs = self.window_manager(options);
+        let layers = window_defs.draw_layers(&self.sprites, split_layers)?;
+        let scale = options.scale.max(1);
+        layers
+            .into_iter()
+            .map(|mut layer| {
+                if let Some(deficiency) = options.color_vision {
+                    render::color_vision::simulate(&mut layer.image, deficiency);
+                }
+                let offset = |value: u32| {
+                    value.checked_mul(scale).ok_or_else(|| {
+                        WszError::ArgumentError(format!("layer offset {} scaled by {} is too large", value, scale))
+                    })
+                };
+                Ok(render::RenderedLayer {
+                    x: offset(layer.x)?,
+                    y: offset(layer.y)?,
+                    image: render::scale_image(layer.image, scale)?,
+                    ..layer
+                })
+            })
+            .collect()
+    }
+
+    /// Render the skin as an SVG document, embedding each sprite as an image clipped to the skin's regions
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Options controlling the rendered state. `scale` sets the document's displayed size and
+    ///   `color_vision` is ignored.
+    ///
+    /// # Returns
+    ///
+    /// The SVG document
+    pub fn render_svg(&self, options: &render::RenderOptions) -> Result<String> {
+        let window_defs = self.window_manager(options);
+        render::svg::render_svg(&window_defs, &self.sprites, &self.regions, options.scale)
+    }
+
+    /// Render the skin as an OpenRaster document with each window sprite on its own layer
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Options controlling the rendered state. `scale` and `color_vision` are ignored.
+    ///
+    /// # Returns
+    ///
+    /// The .ora file contents
+    pub fn render_ora(&self, options: &render::RenderOptions) -> Result<Vec<u8>> {
+        let window_defs = self.window_manager(options);
+        render::ora::render_ora(&window_defs, &self.sprites)
+    }
+
+    /// Render only the parts of the composite that changed since the renderer's previous frame
+    ///
+    /// # Arguments
+    ///
+    /// * `renderer` - Incremental renderer holding the previous frame. Reuse it between calls.
+    /// * `options` - Options controlling the rendered state. `scale` and `color_vision` are ignored.
+    ///
+    /// # Returns
+    ///
+    /// The areas that changed and their new pixels. The full frame is available from `renderer.frame()`.
+    pub fn render_incremental(
+        &self,
+        renderer: &mut render::incremental::IncrementalRenderer,
+        options: &render::RenderOptions,
+    ) -> Result<Vec<render::incremental::DirtyRect>> {
+        let window_defs = self.window_manager(options);
+        renderer.render(&window_defs, &self.sprites)
+    }
+
+    /// Describe the rendered windows and the bounds of each of their elements as JSON
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Options the screenshot was rendered with. Rectangles are scaled by `scale`.
+    ///
+    /// # Returns
+    ///
+    /// The JSON document
+    pub fn element_bounds_json(&self, options: &render::RenderOptions) -> String {
+        let window_defs = self.window_manager(options);
+        render::json::element_bounds_json(&window_defs, &self.sprites, options.scale)
+    }
+
+    /// Find the element at a point in a rendered window, e.g. to map a mouse position to a control
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Options the window was rendered with
+    /// * `window_type` - Window the point is in
+    /// * `x` - X position relative to the window, unscaled
+    /// * `y` - Y position relative to the window, unscaled
+    ///
+    /// # Returns
+    ///
+    /// The top-most window sprite covering the point, or None if the point hits no element
+    pub fn element_at(
+        &self,
+        options: &render::RenderOptions,
+        window_type: sprites::WindowType,
+        x: u32,
+        y: u32,
+    ) -> Option<sprites::SpriteWindowDefinition> {
+        self.window_manager(options).element_at(window_type, x, y).cloned()
+    }
+
+    /// Builds the window manager for a render, with the skin's colors, render options and player sprites applied
+    fn window_manager(&self, options: &render::RenderOptions) -> sprites::SpriteWindowManager {
+        let mut window_defs = sprites::SpriteWindowManager::new();
+        window_defs.set_background(&render::skin_background(&self.vis_colors, &self.pledit));
+        window_defs.set_regions(&self.regions);
+        window_defs.apply_render_options(options);
+        render::add_player_sprites(&mut window_defs, &self.sprites, &self.vis_colors, &options.player);
+        window_defs
+    }
+
+    /// Render an animated GIF of the skin with a scrolling title, blinking work indicator and moving visualizer
+    ///
+    /// Requires the `animation` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Animation options
+    ///
+    /// # Returns
+    ///
+    /// The GIF file contents
+    #[cfg(feature = "animation")]
+    pub fn render_gif(&self, options: &render::animation::AnimationOptions) -> Result<Vec<u8>> {
+        let frames = render::animation::render_frames(self, options)?;
+        render::animation::encode_gif(&frames, options.frame_delay_ms)
+    }
+
+    /// Render an animated PNG of the skin, like `render_gif` but without GIF's 256 color limit
+    ///
+    /// Requires the `animation` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Animation options
+    ///
+    /// # Returns
+    ///
+    /// The APNG file contents
+    #[cfg(feature = "animation")]
+    pub fn render_apng(&self, options: &render::animation::AnimationOptions) -> Result<Vec<u8>> {
+        let frames = render::animation::render_frames(self, options)?;
+        render::animation::encode_apng(&frames, options.frame_delay_ms)
+    }
+
+    /// Render the minibrowser window
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Width of the window. Rounded down to the nearest size Winamp allows.
+    /// * `height` - Height of the window. Rounded down to the nearest size Winamp allows.
+    ///
+    /// # Returns
+    ///
+    /// A WindowImage instance containing only the minibrowser window
+    pub fn render_minibrowser(&self, width: u32, height: u32) -> Result<sprites::WindowImage> {
+        let mut window_defs = sprites::SpriteWindowManager::new();
+        window_defs.set_background(&render::skin_background(&self.vis_colors, &self.pledit));
+        window_defs.set_minibrowser_size(width, height);
+        window_defs.draw_window(sprites::WindowType::Minibrowser, &self.sprites)
+    }
+
+    /// Render a general purpose window, the frame Winamp draws around plugin windows
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Width of the window. Rounded down to the nearest size Winamp allows.
+    /// * `height` - Height of the window. Rounded down to the nearest size Winamp allows.
+    /// * `title` - Title drawn in the title bar
+    ///
+    /// # Returns
+    ///
+    /// A WindowImage instance containing only the general purpose window
+    pub fn render_general_window(&self, width: u32, height: u32, title: &str) -> Result<sprites::WindowImage> {
+        let mut window_defs = sprites::SpriteWindowManager::new();
+        window_defs.set_background(&render::skin_background(&self.vis_colors, &self.pledit));
+        window_defs.set_general_window(width, height, title);
+        window_defs.draw_window(sprites::WindowType::General, &self.sprites)
+    }
+
+    /// Render just the playlist window at a particular size
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Width of the playlist window. Rounded down to a multiple of 25 pixels above 275.
+    /// * `height` - Height of the playlist window. Rounded down to a multiple of 29 pixels above 116.
+    ///
+    /// # Returns
+    ///
+    /// A WindowImage instance containing only the playlist window
+    pub fn render_playlist(&self, width: u32, height: u32) -> Result<sprites::WindowImage> {
+        let mut window_defs = sprites::SpriteWindowManager::new();
+        window_defs.set_background(&render::skin_background(&self.vis_colors, &self.pledit));
+        window_defs.set_playlist_size(width, height);
+        window_defs.apply_render_options(&render::RenderOptions::default());
+        window_defs.draw_window(sprites::WindowType::Playlist, &self.sprites)
+    }
+}
+
+/// Skins are equal when they look and behave the same in Winamp: their sprites are equal pixel for pixel and their
+/// visualization colors, playlist settings, regions, GENEX.BMP colors and cursors are equal.
+///
+/// How the archives were packed is not compared, so a skin equals a copy of itself that was re-zipped, had its text
+/// files reformatted or has a different readme. Sprite sheets left out by `WszOptions::sheets` are decoded to be
+/// compared, while skins loaded without cursors only equal skins that are missing the same ones.
+impl PartialEq for Wsz {
+    fn eq(&self, other: &Self) -> bool {
+        self.all_sprites() == other.all_sprites()
+            && self.vis_colors == other.vis_colors
+            && self.pledit == other.pledit
+            && self.regions == other.regions
+            && self.genex_colors == other.genex_colors
+            && self.cursors == other.cursors
+    }
+}
+
+impl Eq for Wsz {}
+
+/// Encodes a sprite sheet as a 24-bit BMP, since Winamp's bitmaps have no alpha channel
+fn encode_bmp(image: &image::DynamicImage) -> Result<Vec<u8>> {
+    let mut bmp = Vec::new();
+    image::DynamicImage::ImageRgb8(image.to_rgb8())
+        .write_to(&mut std::io::Cursor::new(&mut bmp), image::ImageFormat::Bmp)?;
+    Ok(bmp)
 }
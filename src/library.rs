@@ -0,0 +1,463 @@
+//! Indexes of folders of skins, for catalogs and skin browsers
+//!
+//! SkinLibrary finds every .wsz and .wal file under a folder and records each skin's metadata, fingerprint and
+//! palette. The index can be saved to a file, and scanning again only opens skins that were added or changed since.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use image::Rgb;
+
+use crate::error::{Result, WszError};
+use crate::options::{ResourceLimits, WszOptions};
+use crate::palette::{self, ColorFamily, PaletteColor};
+use crate::text::readme::SkinMetadata;
+use crate::Wsz;
+
+/// First line of an index file, changed whenever the format is
+const INDEX_HEADER: &str = "wsz-library 1";
+/// Extensions of the files that are indexed
+const SKIN_EXTENSIONS: [&str; 2] = ["wsz", "wal"];
+/// Number of colors kept in each skin's palette
+const PALETTE_SIZE: usize = 16;
+
+/// A skin in a SkinLibrary
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkinEntry {
+    /// Path of the skin file, relative to the library's folder
+    pub path: PathBuf,
+    /// Details from the skin's readme, and what the skin contains
+    pub metadata: SkinMetadata,
+    /// Fingerprint of the skin's content, as `Wsz::fingerprint` gives
+    pub fingerprint: String,
+    /// Most common colors, most common first, as `Wsz::palette` gives. Empty if the skin could not be rendered.
+    pub palette: Vec<PaletteColor>,
+    // size and modification time of the file when it was indexed, to tell whether it has changed since
+    size: u64,
+    modified: Option<Duration>,
+}
+
+impl SkinEntry {
+    /// Get the hue that covers the most of the skin, ignoring black, gray and white
+    ///
+    /// # Returns
+    ///
+    /// The family, or None if the skin is almost entirely black, gray and white
+    pub fn dominant_hue(&self) -> Option<ColorFamily> {
+        palette::dominant_hue(&self.palette)
+    }
+}
+
+/// What `SkinLibrary::scan` found
+#[derive(Debug, Default)]
+pub struct ScanSummary {
+    /// Skins that were not in the index before
+    pub added: Vec<PathBuf>,
+    /// Skins whose files changed since they were indexed
+    pub updated: Vec<PathBuf>,
+    /// Skins whose files are gone
+    pub removed: Vec<PathBuf>,
+    /// Number of skins that had not changed
+    pub unchanged: usize,
+    /// Files that could not be loaded as skins, with the reason. They are left out of the index.
+    pub failed: Vec<(PathBuf, WszError)>,
+}
+
+/// An index of the skins in a folder and its subfolders
+#[derive(Debug, Clone)]
+pub struct SkinLibrary {
+    root: PathBuf,
+    index_path: Option<PathBuf>,
+    entries: BTreeMap<PathBuf, SkinEntry>,
+}
+
+impl SkinLibrary {
+    /// Start an empty library of a folder, kept in memory only
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The folder the skins are in
+    ///
+    /// # Returns
+    ///
+    /// A library with no skins until it is scanned
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            index_path: None,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Open the library of a folder, loading its index file if it has one
+    ///
+    /// The index is saved to `index_path` after every scan.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The folder the skins are in
+    /// * `index_path` - The index file, which need not exist yet
+    ///
+    /// # Returns
+    ///
+    /// The library, or an error if the index file exists but cannot be read
+    pub fn open<P: AsRef<Path>, Q: AsRef<Path>>(root: P, index_path: Q) -> Result<Self> {
+        let mut library = Self::new(root);
+        let index_path = index_path.as_ref();
+        if index_path.exists() {
+            library.entries = read_index(&fs::read_to_string(index_path)?)?;
+        }
+        library.index_path = Some(index_path.to_path_buf());
+        Ok(library)
+    }
+
+    /// Get the folder the skins are in
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Find new, changed and removed skins and update the index, then save it if the library has an index file
+    ///
+    /// Only skins whose size or modification time changed since they were indexed are opened. Skins are loaded
+    /// with `ResourceLimits::untrusted`, so broken archives fail instead of using up memory. Skins that cannot be read
+    /// are listed in `ScanSummary::failed` and the scan goes on.
+    ///
+    /// # Returns
+    ///
+    /// What changed, or an error if the folder cannot be read or the index cannot be saved
+    pub fn scan(&mut self) -> Result<ScanSummary> {
+        let mut summary = ScanSummary::default();
+        let mut paths = Vec::new();
+        find_skins(&self.root, &mut paths)?;
+        paths.sort();
+
+        let options = WszOptions {
+            decode_cursors: false,
+            keep_raw_files: false,
+            limits: ResourceLimits::untrusted(),
+            ..Default::default()
+        };
+        let mut found = BTreeMap::new();
+        for path in paths {
+            let relative = path.strip_prefix(&self.root).unwrap_or(&path).to_path_buf();
+            let file = match fs::metadata(&path) {
+                Ok(file) => file,
+                Err(err) => {
+                    // like a skin that fails to load, it leaves the index without being reported as removed
+                    self.entries.remove(&relative);
+                    summary.failed.push((relative, err.into()));
+                    continue;
+                }
+            };
+            let size = file.len();
+            let modified = file
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok());
+
+            if let Some(entry) = self.entries.remove(&relative) {
+                if entry.size == size && entry.modified == modified {
+                    summary.unchanged += 1;
+                    found.insert(relative, entry);
+                    continue;
+                }
+                summary.updated.push(relative.clone());
+            } else {
+                summary.added.push(relative.clone());
+            }
+
+            match Wsz::from_file_path_with_options(&path, &options) {
+                Ok(skin) => {
+                    let entry = SkinEntry {
+                        path: relative.clone(),
+                        metadata: skin.metadata().clone(),
+                        fingerprint: skin.fingerprint(),
+                        palette: skin.palette(PALETTE_SIZE).unwrap_or_default(),
+                        size,
+                        modified,
+                    };
+                    found.insert(relative, entry);
+                }
+                Err(err) => {
+                    summary.added.retain(|added| *added != relative);
+                    summary.updated.retain(|updated| *updated != relative);
+                    summary.failed.push((relative, err));
+                }
+            }
+        }
+        // whatever was not found again is gone
+        summary.removed = std::mem::replace(&mut self.entries, found).into_keys().collect();
+
+        if let Some(index_path) = &self.index_path {
+            self.save_index(index_path)?;
+        }
+        Ok(summary)
+    }
+
+    /// Write the index to a file, to open later with `SkinLibrary::open`
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file to write
+    ///
+    /// # Returns
+    ///
+    /// A Result indicating success or failure
+    pub fn save_index<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        fs::write(path, write_index(&self.entries))?;
+        Ok(())
+    }
+
+    /// Get every skin, sorted by path
+    pub fn entries(&self) -> impl Iterator<Item = &SkinEntry> {
+        self.entries.values()
+    }
+
+    /// Get a skin by its path relative to the library's folder
+    pub fn get<P: AsRef<Path>>(&self, path: P) -> Option<&SkinEntry> {
+        self.entries.get(path.as_ref())
+    }
+
+    /// Get the number of skins
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the library has no skins
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Find the skins that match a condition, e.g. `|skin| skin.metadata.has_equalizer` for skins with equalizer
+    /// graphics
+    ///
+    /// # Arguments
+    ///
+    /// * `predicate` - The condition
+    ///
+    /// # Returns
+    ///
+    /// The matching skins, sorted by path
+    pub fn query<F: Fn(&SkinEntry) -> bool>(&self, predicate: F) -> Vec<&SkinEntry> {
+        self.entries.values().filter(|entry| predicate(entry)).collect()
+    }
+
+    /// Find the skins whose main hue is in a family, e.g. skins dominated by blue
+    ///
+    /// # Arguments
+    ///
+    /// * `family` - The family, as `SkinEntry::dominant_hue` gives
+    ///
+    /// # Returns
+    ///
+    /// The matching skins, sorted by path
+    pub fn dominated_by(&self, family: ColorFamily) -> Vec<&SkinEntry> {
+        self.query(|entry| entry.dominant_hue() == Some(family))
+    }
+
+    /// Find the skins with a fingerprint, e.g. copies of a skin under other names
+    pub fn with_fingerprint(&self, fingerprint: &str) -> Vec<&SkinEntry> {
+        self.query(|entry| entry.fingerprint == fingerprint)
+    }
+
+    /// Find skins that are in the library more than once
+    ///
+    /// # Returns
+    ///
+    /// Groups of skins with the same fingerprint, each sorted by path
+    pub fn duplicates(&self) -> Vec<Vec<&SkinEntry>> {
+        let mut groups: BTreeMap<&str, Vec<&SkinEntry>> = BTreeMap::new();
+        for entry in self.entries.values() {
+            groups.entry(&entry.fingerprint).or_default().push(entry);
+        }
+        groups.into_values().filter(|group| group.len() > 1).collect()
+    }
+}
+
+/// Adds the skin files in a folder and its subfolders to `paths`
+pub(crate) fn find_skins(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        // symlinked folders are not followed, so links back up the tree cannot loop
+        if entry.file_type()?.is_dir() {
+            find_skins(&path, paths)?;
+        } else if path.extension().is_some_and(|extension| {
+            SKIN_EXTENSIONS
+                .iter()
+                .any(|skin_extension| extension.eq_ignore_ascii_case(skin_extension))
+        }) {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Capability flags of SkinMetadata, by the name they are saved under
+fn flags(metadata: &mut SkinMetadata) -> [(&'static str, &mut bool); 8] {
+    [
+        ("equalizer", &mut metadata.has_equalizer),
+        ("playlist", &mut metadata.has_playlist),
+        ("cursors", &mut metadata.has_cursors),
+        ("avs", &mut metadata.has_avs),
+        ("minibrowser", &mut metadata.has_minibrowser),
+        ("genex", &mut metadata.has_genex),
+        ("regions", &mut metadata.has_regions),
+        ("modern", &mut metadata.is_modern),
+    ]
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(c) => unescaped.push(c),
+            None => {}
+        }
+    }
+    unescaped
+}
+
+/// Writes the index as a header line and then one line of tab-separated fields per skin
+fn write_index(entries: &BTreeMap<PathBuf, SkinEntry>) -> String {
+    let mut index = format!("{}\n", INDEX_HEADER);
+    for entry in entries.values() {
+        let mut metadata = entry.metadata.clone();
+        let text = |value: &Option<String>| value.as_deref().map_or(String::new(), escape);
+        let fields = [
+            escape(&entry.path.to_string_lossy().replace('\\', "/")),
+            entry.size.to_string(),
+            entry.modified.map_or(String::new(), |modified| {
+                format!("{}.{:09}", modified.as_secs(), modified.subsec_nanos())
+            }),
+            entry.fingerprint.clone(),
+            text(&metadata.name),
+            text(&metadata.author),
+            text(&metadata.url),
+            text(&metadata.email),
+            text(&metadata.date),
+            text(&metadata.source),
+            metadata.file_count.to_string(),
+            metadata.total_size.to_string(),
+            flags(&mut metadata)
+                .into_iter()
+                .filter(|(_, set)| **set)
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>()
+                .join(","),
+            entry
+                .palette
+                .iter()
+                .map(|color| {
+                    let [r, g, b] = color.color.0;
+                    format!("{:02x}{:02x}{:02x}:{}", r, g, b, color.share)
+                })
+                .collect::<Vec<_>>()
+                .join(","),
+        ];
+        index.push_str(&fields.join("\t"));
+        index.push('\n');
+    }
+    index
+}
+
+/// Reads an index written by `write_index`. Lines that cannot be read are skipped, so those skins are indexed again
+/// on the next scan.
+fn read_index(content: &str) -> Result<BTreeMap<PathBuf, SkinEntry>> {
+    let mut lines = content.lines();
+    if lines.next() != Some(INDEX_HEADER) {
+        return Err(WszError::InvalidFormat {
+            line: 1,
+            error: "Not a skin library index".to_string(),
+        });
+    }
+    Ok(lines
+        .filter_map(read_entry)
+        .map(|entry| (entry.path.clone(), entry))
+        .collect())
+}
+
+fn read_entry(line: &str) -> Option<SkinEntry> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    let [path, size, modified, fingerprint, name, author, url, email, date, source, file_count, total_size, flag_names, colors] =
+        fields[..]
+    else {
+        return None;
+    };
+    let text = |value: &str| (!value.is_empty()).then(|| unescape(value));
+
+    let path = PathBuf::from(unescape(path));
+    let size = size.parse().ok()?;
+    let modified = match modified.split_once('.') {
+        Some((secs, nanos)) => Some(Duration::new(secs.parse().ok()?, nanos.parse().ok()?)),
+        None if modified.is_empty() => None,
+        None => return None,
+    };
+
+    let mut metadata = SkinMetadata {
+        name: text(name),
+        author: text(author),
+        url: text(url),
+        email: text(email),
+        date: text(date),
+        source: text(source),
+        file_name: path.file_stem().map(|stem| stem.to_string_lossy().into_owned()),
+        file_count: file_count.parse().ok()?,
+        total_size: total_size.parse().ok()?,
+        compressed_size: Some(size),
+        ..Default::default()
+    };
+    for flag in flag_names.split(',').filter(|flag| !flag.is_empty()) {
+        for (name, set) in flags(&mut metadata) {
+            if name == flag {
+                *set = true;
+            }
+        }
+    }
+
+    let palette = colors
+        .split(',')
+        .filter(|color| !color.is_empty())
+        .map(|color| {
+            let (hex, share) = color.split_once(':')?;
+            let rgb = u32::from_str_radix(hex, 16).ok()?;
+            Some(PaletteColor {
+                color: Rgb([(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8]),
+                share: share.parse().ok()?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(SkinEntry {
+        path,
+        metadata,
+        fingerprint: fingerprint.to_string(),
+        palette,
+        size,
+        modified,
+    })
+}
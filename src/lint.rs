@@ -0,0 +1,190 @@
+//! Checks for skin authors, combining validation with heuristics for common authoring mistakes
+//!
+//! `Wsz::validate` reports what stops a skin from working. `Wsz::lint` adds what works but looks wrong on other
+//! people's machines, such as sprites drawn into their neighbors, text files saved in an encoding Winamp cannot
+//! read, and playlist fonts that only the author has installed.
+
+use std::cmp::Reverse;
+use std::fmt::{self, Write};
+
+use crate::bleed::SpriteBleed;
+use crate::sprites::{self, TEXT_FILES};
+use crate::text::encoding::decode_text;
+use crate::util::{json_array, json_string};
+use crate::validate::{Severity, ValidationIssueKind};
+use crate::Wsz;
+
+/// Fonts installed with every version of Windows Winamp runs on
+const STANDARD_FONTS: [&str; 14] = [
+    "Arial",
+    "Arial Black",
+    "Comic Sans MS",
+    "Courier",
+    "Courier New",
+    "Georgia",
+    "Impact",
+    "Lucida Console",
+    "MS Sans Serif",
+    "MS Serif",
+    "Tahoma",
+    "Times New Roman",
+    "Trebuchet MS",
+    "Verdana",
+];
+
+/// Kinds of problems `Wsz::lint` looks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintKind {
+    /// A problem `Wsz::validate` found
+    Validation(ValidationIssueKind),
+    /// A sprite's artwork runs into its neighbor
+    SpriteBleed(SpriteBleed),
+    /// A text configuration file holds bytes that are not text in any encoding it could be in, usually from
+    /// being saved by a word processor or corrupted
+    UnreadableTextFile {
+        /// Name of the file, e.g. "pledit.txt"
+        file: String,
+    },
+    /// The playlist font is not installed with Windows, so most users see Winamp's substitute
+    NonstandardFont {
+        /// Font named in pledit.txt
+        font: String,
+    },
+}
+
+/// A problem found by `Wsz::lint`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintFinding {
+    /// How serious the problem is
+    pub severity: Severity,
+    /// What the problem is
+    pub kind: LintKind,
+}
+
+impl fmt::Display for LintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LintKind::Validation(kind) => write!(f, "{}", kind),
+            LintKind::SpriteBleed(bleed) => write!(f, "{}", bleed),
+            LintKind::UnreadableTextFile { file } => write!(f, "{} is not readable text", file),
+            LintKind::NonstandardFont { font } => {
+                write!(f, "pledit.txt font {} is not installed with Windows", font)
+            }
+        }
+    }
+}
+
+impl fmt::Display for LintFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.kind)
+    }
+}
+
+/// Everything `Wsz::lint` found
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LintReport {
+    findings: Vec<LintFinding>,
+}
+
+impl LintReport {
+    /// Get all of the problems, most serious first
+    pub fn findings(&self) -> &[LintFinding] {
+        &self.findings
+    }
+
+    /// Count the problems of a particular severity
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .count()
+    }
+
+    /// Whether any problem is an error
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+
+    /// Whether nothing at all was found
+    pub fn is_empty(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    /// Describes the report as JSON
+    ///
+    /// The document has the number of `errors`, `warnings` and `infos`, and a `findings` array with each problem's
+    /// `severity` and `message`, most serious first.
+    ///
+    /// # Returns
+    ///
+    /// The JSON document
+    pub fn to_json(&self) -> String {
+        let findings: Vec<String> = self
+            .findings
+            .iter()
+            .map(|finding| {
+                format!(
+                    r#"    {{"severity": "{}", "message": {}}}"#,
+                    finding.severity,
+                    json_string(&finding.kind.to_string())
+                )
+            })
+            .collect();
+
+        let mut json = String::new();
+        let _ = writeln!(json, "{{");
+        let _ = writeln!(json, r#"  "errors": {},"#, self.count(Severity::Error));
+        let _ = writeln!(json, r#"  "warnings": {},"#, self.count(Severity::Warning));
+        let _ = writeln!(json, r#"  "infos": {},"#, self.count(Severity::Info));
+        let _ = writeln!(json, r#"  "findings": {}"#, json_array(&findings));
+        let _ = writeln!(json, "}}");
+        json
+    }
+}
+
+/// Checks a skin for problems
+pub(crate) fn lint(skin: &Wsz) -> LintReport {
+    let mut findings: Vec<LintFinding> = skin
+        .validate()
+        .issues()
+        .iter()
+        .map(|issue| LintFinding {
+            severity: issue.severity,
+            kind: LintKind::Validation(issue.kind.clone()),
+        })
+        .collect();
+
+    findings.extend(skin.sprite_bleed().into_iter().map(|bleed| LintFinding {
+        severity: Severity::Warning,
+        kind: LintKind::SpriteBleed(bleed),
+    }));
+
+    for file in TEXT_FILES {
+        let Some(key) = sprites::find_sprite_sheet(&skin.files, file) else {
+            continue;
+        };
+        let (text, _) = decode_text(&skin.files[key]);
+        if text.chars().any(|c| c == char::REPLACEMENT_CHARACTER || c == '\0') {
+            findings.push(LintFinding {
+                severity: Severity::Warning,
+                kind: LintKind::UnreadableTextFile { file: file.to_string() },
+            });
+        }
+    }
+
+    if let Some(font) = &skin.pledit.font {
+        if !STANDARD_FONTS
+            .iter()
+            .any(|standard| standard.eq_ignore_ascii_case(font.trim()))
+        {
+            findings.push(LintFinding {
+                severity: Severity::Warning,
+                kind: LintKind::NonstandardFont { font: font.clone() },
+            });
+        }
+    }
+
+    // stable, so problems of the same severity stay in the order they were found
+    findings.sort_by_key(|finding| Reverse(finding.severity));
+    LintReport { findings }
+}
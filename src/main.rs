@@ -1,9 +1,30 @@
+use image::DynamicImage;
 use std::env;
 use std::fs;
+use std::io::{self, BufRead, BufReader, Cursor, Write};
+use std::net::{TcpListener, TcpStream};
 use std::path::{Path, PathBuf};
 use std::process;
-use wsz::archive::{pack_wsz_dir, unpack_wsz};
-use wsz::sprites::SpriteManager;
+use std::str::FromStr;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use wsz::archive::{
+    list_wsz, listing_json, pack_wsz_dir_bytes, pack_wsz_dir_with_warnings, pack_wsz_sheets_dir_bytes, unpack_wsz,
+    WszArchive,
+};
+use wsz::cursor::{cursors_json, decode_cursor_files};
+use wsz::diff::difference_image;
+use wsz::gallery::{export_gallery, export_thumbnails, GalleryOptions, ThumbnailFolderOptions};
+use wsz::recolor::RecolorOptions;
+use wsz::render::layout::Layout;
+use wsz::render::swatches::{render_swatches, swatches_json};
+use wsz::render::{scale_image, text, Channels, PlaybackStatus, PlayerState, RenderOptions};
+use wsz::repair::RepairOptions;
+use wsz::scaffold::{create_skin_dir, ScaffoldOptions};
+use wsz::sprites::atlas::AtlasOptions;
+use wsz::sprites::{SpriteImage, SpriteManager, SpriteWindowManager, WindowType};
+use wsz::validate::{Severity, ValidationIssueKind};
 use wsz::Wsz;
 
 /// Extracts the name of a file without its extension
@@ -15,144 +36,1802 @@ fn get_filename_without_extension(path: &str) -> String {
         .to_string()
 }
 
+/// Output of a command run with `--json`, printed as one JSON document when the command ends
+#[derive(Debug, Default)]
+struct JsonOutput {
+    /// Name of the command
+    command: String,
+    /// Progress messages, in the order they were given
+    messages: Vec<String>,
+    /// Problems that did not stop the command
+    warnings: Vec<String>,
+    /// Results of the command, as keys and JSON values
+    fields: Vec<(String, String)>,
+}
+
+impl JsonOutput {
+    /// Writes the document, with the error the command stopped with if any
+    fn to_json(&self, error: Option<&str>) -> String {
+        let mut entries = vec![
+            format!("  \"command\": {}", json_string(&self.command)),
+            format!("  \"ok\": {}", error.is_none()),
+        ];
+        if let Some(error) = error {
+            entries.push(format!("  \"error\": {}", json_string(error)));
+        }
+        entries.push(format!("  \"messages\": {}", json_strings(&self.messages)));
+        entries.push(format!("  \"warnings\": {}", json_strings(&self.warnings)));
+        for (key, value) in &self.fields {
+            entries.push(format!("  {}: {}", json_string(key), value));
+        }
+        format!("{{\n{}\n}}", entries.join(",\n"))
+    }
+}
+
+/// Largest --scale accepted by screenshot and render-text
+const MAX_SCALE: u32 = 16;
+
+/// Output of the running command when `--json` is given, otherwise None
+static JSON_OUTPUT: Mutex<Option<JsonOutput>> = Mutex::new(None);
+
+fn json_output() -> MutexGuard<'static, Option<JsonOutput>> {
+    JSON_OUTPUT.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Whether the output is a JSON document rather than text
+fn is_json() -> bool {
+    json_output().is_some()
+}
+
+/// Quotes and escapes a string for use in JSON
+fn json_string(value: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Writes strings as a JSON array on one line
+fn json_strings(items: &[String]) -> String {
+    let items: Vec<String> = items.iter().map(|item| json_string(item)).collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// Prints a progress message, or adds it to the JSON document
+fn say(message: impl Into<String>) {
+    let message = message.into();
+    match json_output().as_mut() {
+        Some(output) => output.messages.push(message),
+        None => println!("{}", message),
+    }
+}
+
+/// Reports a problem that does not stop the command
+fn warn(message: impl Into<String>) {
+    let message = message.into();
+    match json_output().as_mut() {
+        Some(output) => output.warnings.push(message),
+        None => eprintln!("{}", message),
+    }
+}
+
+/// Adds a result to the JSON document. Text output reports results with `say` instead.
+///
+/// # Arguments
+///
+/// * `key` - Key of the result
+/// * `value` - The result as JSON
+fn record(key: &str, value: String) {
+    if let Some(output) = json_output().as_mut() {
+        // nest documents such as Atlas::to_json one level deeper
+        output
+            .fields
+            .push((key.to_string(), value.trim_end().replace('\n', "\n  ")));
+    }
+}
+
+/// Reports an error and exits
+fn fail(message: impl Into<String>) -> ! {
+    let message = message.into();
+    let output = json_output().take();
+    match output {
+        Some(output) => println!("{}", output.to_json(Some(&message))),
+        None => eprintln!("{}", message),
+    }
+    process::exit(1);
+}
+
+/// Reports a mistake in the command line and exits. Text output also shows the usage.
+fn usage_error(program: &str, message: impl Into<String>) -> ! {
+    if is_json() {
+        fail(message);
+    }
+    eprintln!("{}", message.into());
+    print_usage(program);
+    process::exit(1);
+}
+
 fn print_usage(program: &str) {
     eprintln!("Usage:");
-    eprintln!("  Extract:       {} --extract <path_to_wsz_file>", program);
-    eprintln!("  Pack:          {} --pack <directory_to_pack>", program);
-    eprintln!("  Screenshot:    {} --screenshot <path_to_wsz_file>", program);
+    eprintln!("  Extract:       {} --extract <path_to_wsz_file> [options]", program);
+    eprintln!(
+        "  Pack:          {} --pack [--from-sheets] <directory_to_pack> [-o <path>] [--validate [--force]]",
+        program
+    );
+    eprintln!("  Screenshot:    {} --screenshot <path_to_wsz_file> [options]", program);
+    eprintln!("  Preview:       {} --preview <path_to_wsz_file> [columns]", program);
+    eprintln!(
+        "  Diff:          {} diff <old_wsz_file> <new_wsz_file> [--image-diff <directory>]",
+        program
+    );
+    eprintln!("  Atlas:         {} atlas <path_to_wsz_file> [options]", program);
+    eprintln!("  Sheets:        {} sheets <path_to_wsz_file> [options]", program);
+    eprintln!("  Cursors:       {} cursors <path_to_wsz_file> [options]", program);
+    eprintln!("  Convert:       {} convert <input> <output>", program);
+    eprintln!("  Gallery:       {} gallery <directory_of_skins> [options]", program);
+    eprintln!(
+        "  Render-text:   {} render-text <path_to_wsz_file> <text> [options]",
+        program
+    );
+    eprintln!("  Palette:       {} palette <path_to_wsz_file> [options]", program);
+    eprintln!("  Watch:         {} watch <directory> [options]", program);
+    eprintln!(
+        "  Serve:         {} serve <path_to_wsz_file_or_directory> [options]",
+        program
+    );
+    eprintln!("  Repair:        {} repair <path_to_wsz_file> [options]", program);
+    eprintln!("  Recolor:       {} recolor <path_to_wsz_file> [options]", program);
+    eprintln!(
+        "  Lint:          {} lint <path_to_wsz_file_or_directory> [options]",
+        program
+    );
+    eprintln!("  New:           {} new <directory> [options]", program);
+    eprintln!("  Ls:            {} ls <path_to_wsz_file> [options]", program);
+    eprintln!("  Thumbs:        {} thumbs <directory_of_skins> [options]", program);
+    eprintln!();
+    eprintln!("Extract options:");
+    eprintln!("  --only <sheets>          Sprite sheets to extract, e.g. MAIN,EQMAIN,PLEDIT");
+    eprintln!("  --format <format>        Image format of the sprites: png (default), bmp or webp");
+    eprintln!("  --skip-cursors           Leave out cursor files");
+    eprintln!("  --skip-text              Leave out text files, such as pledit.txt and readmes");
+    eprintln!();
+    eprintln!("Screenshot options:");
+    eprintln!("  --output <path>          File to write, by default screenshot.png");
+    eprintln!("  --scale <n>              Integer scale factor, from 1 to 16");
+    eprintln!("  --windows <list>         Windows to stack top to bottom, by default main,eq,pl");
+    eprintln!("  --shade                  Draw the windows in shade mode");
+    eprintln!("  --demo                   Show the classic DJ Mike Llama demo playing");
+    eprintln!("  --playing, --paused, --stopped");
+    eprintln!("                           Playback status");
+    eprintln!("  --title <text>           Title shown in the marquee");
+    eprintln!("  --time <seconds>         Elapsed time");
+    eprintln!("  --volume <0-100>         Volume slider position");
+    eprintln!("  --balance <-100-100>     Balance slider position");
+    eprintln!("  --kbps <n>, --khz <n>    Bitrate and sample rate");
+    eprintln!("  --mono, --stereo         Lit channel indicator");
+    eprintln!();
+    eprintln!("Atlas options:");
+    eprintln!("  -o, --output <path>      Image to write, by default <skin>_atlas.png");
+    eprintln!("  --coords <path>          Also write each sprite's position as JSON");
+    eprintln!("  --padding <n>            Transparent pixels around each sprite");
+    eprintln!("  --power-of-two           Round the atlas size up to powers of two");
+    eprintln!("  --max-width <n>          Width to pack sprites into, by default 512");
+    eprintln!();
+    eprintln!("Sheets options:");
+    eprintln!("  -o, --output <dir>       Folder to write to, by default <skin>_sheets");
+    eprintln!();
+    eprintln!("Cursors options:");
+    eprintln!("  -o, --output <dir>       Folder to write to, by default <skin>_cursors");
+    eprintln!();
+    eprintln!("Convert formats:");
+    eprintln!("  .wsz -> folder           Extract the sprites, like extract");
+    eprintln!("  folder -> .wsz           Pack a folder of sprites, like pack");
+    eprintln!("  .wsz -> .wsz             Rewrite the skin with its sheets and text files normalized");
+    eprintln!();
+    eprintln!("Gallery options:");
+    eprintln!("  -o, --output <dir>       Folder to write the site to, by default site");
+    eprintln!("  --title <text>           Title of the page, by default \"Winamp skins\"");
+    eprintln!();
+    eprintln!("Render-text options:");
+    eprintln!("  -o, --output <path>      Image to write, by default text.png");
+    eprintln!("  --scale <n>              Integer scale factor, from 1 to 16");
+    eprintln!();
+    eprintln!("Palette options:");
+    eprintln!("  -o, --output <path>      Chart to write, by default palette.png, or none with --json");
+    eprintln!();
+    eprintln!("Watch options:");
+    eprintln!("  -o, --output <path>      Screenshot to keep up to date, by default preview.png");
+    eprintln!("  --skin <path>            Skin to pack to, by default <directory>.wsz");
+    eprintln!("  --interval <ms>          How often to look for changes, by default 500");
+    eprintln!();
+    eprintln!("Serve options:");
+    eprintln!("  --port <n>               Port to serve the preview on, by default 8080");
+    eprintln!();
+    eprintln!("Repair options:");
+    eprintln!("  -o, --output <path>      Skin to write, by default <skin>_fixed.wsz");
+    eprintln!("  --base <path>            Skin to copy missing sprite sheets and sprites from");
+    eprintln!("  --keep-names             Do not rename files to the case Winamp uses");
+    eprintln!("  --keep-bitmaps           Do not re-encode unusual BMPs as 24 bit BMPs");
+    eprintln!("  --no-default-text        Do not write missing viscolor.txt and pledit.txt");
+    eprintln!();
+    eprintln!("Recolor options:");
+    eprintln!("  -o, --output <path>      Skin to write, by default <skin>_recolored.wsz");
+    eprintln!("  --hue-shift <degrees>    Degrees to turn every hue by, e.g. 120");
+    eprintln!("  --saturation <factor>    Factor to multiply saturation by, 1 keeps it");
+    eprintln!("  --brightness <factor>    Factor to multiply lightness by, 1 keeps it");
+    eprintln!();
+    eprintln!("Lint options:");
+    eprintln!("  --max-warnings <n>       Fail if there are more than n warnings. Errors always fail.");
+    eprintln!();
+    eprintln!("New options:");
+    eprintln!("  --base <path>            Skin to copy sprites, GENEX.BMP and text files from");
+    eprintln!("  --name <name>            Name written to readme.txt, by default the folder's name");
+    eprintln!();
+    eprintln!("Ls options:");
+    eprintln!("  -l, --long               Also show each file's compression method and modification time");
+    eprintln!();
+    eprintln!("Thumbs options:");
+    eprintln!("  -o, --output <dir>       Folder to write to, by default thumbs");
+    eprintln!("  --size <width>x<height>  Size of the thumbnails, by default 137x80");
+    eprintln!("  --threads <n>            Skins to render at once, by default one per CPU");
+    eprintln!();
+    eprintln!("Global options:");
+    eprintln!("  --json                   Print results, messages and errors as one JSON document on stdout");
 }
 
-fn pack_sprites(args: &Vec<String>) {
+fn pack_sprites(args: &[String]) {
     if args.len() < 3 {
-        eprintln!("Error: No directory specified for packing");
-        print_usage(&args[0]);
-        process::exit(1);
+        usage_error(&args[0], "Error: No directory specified for packing");
     }
 
-    let dir_path = Path::new(&args[2]);
-    println!("Packing directory: {}", dir_path.display());
+    let mut dir_path: Option<&Path> = None;
+    let mut output_path = None;
+    let mut validate = false;
+    let mut force = false;
+    let mut from_sheets = false;
+    let mut flags = args[2..].iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "-o" | "--output" => output_path = Some(flag_value::<PathBuf>(flag, flags.next())),
+            "--validate" => validate = true,
+            "--force" => force = true,
+            "--from-sheets" => from_sheets = true,
+            dir if dir_path.is_none() && !dir.starts_with('-') => dir_path = Some(Path::new(dir)),
+            _ => {
+                usage_error(&args[0], format!("Error: Unknown pack option: {}", flag));
+            }
+        }
+    }
+    let Some(dir_path) = dir_path else {
+        usage_error(&args[0], "Error: No directory specified for packing");
+    };
+    say(format!("Packing directory: {}", dir_path.display()));
 
     // Create output WSZ path by adding .wsz extension to directory name
-    let wsz_name = format!(
-        "{}.wsz",
-        dir_path.file_name().and_then(|s| s.to_str()).unwrap_or("skin")
-    );
+    let output_path = output_path.unwrap_or_else(|| {
+        let wsz_name = format!(
+            "{}.wsz",
+            dir_path.file_name().and_then(|s| s.to_str()).unwrap_or("skin")
+        );
+        dir_path.with_file_name(wsz_name)
+    });
+
+    let data = if from_sheets {
+        pack_wsz_sheets_dir_bytes(dir_path)
+    } else {
+        pack_wsz_dir_bytes(dir_path).map(|(data, warnings)| {
+            warnings.into_iter().for_each(warn);
+            data
+        })
+    };
+    let data = data.unwrap_or_else(|err| {
+        fail(format!("Error packing WSZ file: {}", err));
+    });
 
-    let output_path = dir_path.with_file_name(wsz_name);
+    if validate {
+        let report = Wsz::from_bytes(&data)
+            .unwrap_or_else(|err| {
+                fail(format!("Error loading packed skin: {}", err));
+            })
+            .validate();
+        for issue in report.issues() {
+            say(issue.to_string());
+        }
+        record("validation", report.to_json());
+        if report.has_errors() && !force {
+            fail("Error: The skin has errors and was not written. Use --force to write it anyway.");
+        }
+    }
 
-    match pack_wsz_dir(dir_path, &output_path) {
-        Ok(()) => println!("Successfully packed WSZ file!"),
+    let size = data.len();
+    match fs::write(&output_path, data) {
+        Ok(()) => {
+            say(format!("Successfully packed WSZ file to {}!", output_path.display()));
+            record("output", json_string(&output_path.to_string_lossy()));
+            record("bytes", size.to_string());
+        }
         Err(err) => {
-            eprintln!("Error packing WSZ file: {}", err);
-            process::exit(1);
+            fail(format!("Error writing WSZ file: {}", err));
         }
     }
 }
 
-fn extract_sprites(args: &Vec<String>) {
+/// Image formats sprites can be extracted as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum SpriteFormat {
+    #[default]
+    Png,
+    /// 24 bit BMP, like the sheets themselves
+    Bmp,
+    /// Lossless WebP. Requires the `webp` feature.
+    Webp,
+}
+
+impl SpriteFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            SpriteFormat::Png => "png",
+            SpriteFormat::Bmp => "bmp",
+            SpriteFormat::Webp => "webp",
+        }
+    }
+
+    /// Writes a sprite in this format
+    fn save(&self, sprite: &SpriteImage, path: &Path) -> image::ImageResult<()> {
+        match self {
+            // sprites are opaque, and some tools cannot read BMPs with an alpha channel
+            SpriteFormat::Bmp => DynamicImage::ImageRgba8(sprite.clone()).to_rgb8().save(path),
+            SpriteFormat::Png | SpriteFormat::Webp => sprite.save(path),
+        }
+    }
+}
+
+impl FromStr for SpriteFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "png" => Ok(SpriteFormat::Png),
+            "bmp" => Ok(SpriteFormat::Bmp),
+            "webp" if cfg!(feature = "webp") => Ok(SpriteFormat::Webp),
+            "webp" => Err("WebP support needs the webp feature".to_string()),
+            _ => Err(format!("Unknown format: {}", s)),
+        }
+    }
+}
+
+/// What extract writes
+#[derive(Debug, Default)]
+struct ExtractOptions {
+    /// Image format of the sprites
+    format: SpriteFormat,
+    /// Sprite sheets to extract, e.g. "MAIN.BMP", or None for all of them
+    sheets: Option<Vec<String>>,
+    /// Leave out cursor files
+    skip_cursors: bool,
+    /// Leave out text files, such as pledit.txt and readmes
+    skip_text: bool,
+}
+
+impl ExtractOptions {
+    /// Whether a file that is not a sprite sheet is written
+    fn keeps_file(&self, name: &str) -> bool {
+        let extension = Path::new(name)
+            .extension()
+            .and_then(|s| s.to_str())
+            .map(str::to_lowercase)
+            .unwrap_or_default();
+        let is_cursor = extension == "cur" || extension == "ani";
+        !(self.skip_cursors && is_cursor || self.skip_text && extension == "txt")
+    }
+}
+
+fn extract_sprites(args: &[String]) {
+    if args.len() < 3 {
+        usage_error(&args[0], "Error: No skin specified for extraction");
+    }
+
     // Extract mode
     let wsz_path = &args[2];
 
+    let mut options = ExtractOptions::default();
+    let mut flags = args[3..].iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "--only" => {
+                let known = SpriteManager::sprite_sheet_names();
+                let list: String = flag_value(flag, flags.next());
+                let sheets = list
+                    .split(',')
+                    .map(|name| {
+                        let name = name.trim().to_uppercase();
+                        let sheet = if name.ends_with(".BMP") {
+                            name
+                        } else {
+                            format!("{}.BMP", name)
+                        };
+                        if !known.contains(&sheet) {
+                            fail(format!("Error: Unknown sprite sheet: {}", sheet));
+                        }
+                        sheet
+                    })
+                    .collect();
+                options.sheets = Some(sheets);
+            }
+            "--format" => {
+                let format: String = flag_value(flag, flags.next());
+                options.format = format.parse().unwrap_or_else(|err| {
+                    fail(format!("Error: {}", err));
+                });
+            }
+            "--skip-cursors" => options.skip_cursors = true,
+            "--skip-text" => options.skip_text = true,
+            _ => {
+                usage_error(&args[0], format!("Error: Unknown extract option: {}", flag));
+            }
+        }
+    }
+
     // Extract base directory name from wsz filename
     let base_dir = get_filename_without_extension(wsz_path);
 
     // Try to unzip the file
     match unpack_wsz(wsz_path) {
-        Ok(contents) => {
-            println!("Found {} files", contents.len());
-
-            let sprite_manager = SpriteManager::new();
-            let sprite_sheets = SpriteManager::sprite_sheet_names();
-
-            for sheet in sprite_sheets {
-                // Extract sprites from this BMP
-                match sprite_manager.extract_sprite_sheet_from_archive(&contents, &sheet) {
-                    Ok(sprites) => {
-                        if !sprites.is_empty() {
-                            // Get BMP name without path and extension for directory name and sprite lookup
-                            let bmp_base = Path::new(&sheet)
-                                .file_stem()
-                                .and_then(|s| s.to_str())
-                                .unwrap_or(&sheet)
-                                .to_uppercase();
-
-                            let sprite_dir = PathBuf::from(&base_dir).join(&bmp_base);
-                            fs::create_dir_all(&sprite_dir)
-                                .expect(&format!("Failed to create directory for {}", bmp_base));
-
-                            // Save each sprite as a PNG file in appropriate directory
-                            for (name, sprite_img) in &sprites {
-                                if sprite_img.width() == 0 || sprite_img.height() == 0 {
-                                    println!("Skipping sprite {} because it has no size", name);
-                                    continue;
-                                }
-
-                                let output_path = sprite_dir.join(format!("{}.png", name));
-                                sprite_img
-                                    .save(&output_path)
-                                    .expect(&format!("Failed to save sprite {}", name));
-                            }
-
-                            println!("Extracted {} sprites from {}", sprites.len(), sheet);
-                        } else {
-                            println!("No sprites found in {}", sheet);
+        Ok(contents) => extract_archive(&contents, Path::new(&base_dir), &options),
+        Err(err) => {
+            fail(format!("Error unpacking WSZ file: {}", err));
+        }
+    }
+}
+
+/// Writes each sprite sheet's sprites as images in a folder named after the sheet, and every other file alongside them
+fn extract_archive(contents: &WszArchive, base_dir: &Path, options: &ExtractOptions) {
+    fs::create_dir_all(base_dir)
+        .unwrap_or_else(|err| fail(format!("Failed to create directory {}: {}", base_dir.display(), err)));
+    say(format!("Found {} files", contents.len()));
+    let mut extracted = Vec::new();
+    let mut saved = Vec::new();
+
+    let sprite_manager = SpriteManager::new();
+    let sprite_sheets = SpriteManager::sprite_sheet_names();
+
+    for sheet in &sprite_sheets {
+        if options.sheets.as_ref().is_some_and(|sheets| !sheets.contains(sheet)) {
+            continue;
+        }
+
+        // Extract sprites from this BMP
+        match sprite_manager.extract_sprite_sheet_from_archive(contents, sheet) {
+            Ok(sprites) => {
+                if !sprites.is_empty() {
+                    // Get BMP name without path and extension for directory name and sprite lookup
+                    let bmp_base = Path::new(&sheet)
+                        .file_stem()
+                        .and_then(|s| s.to_str())
+                        .unwrap_or(sheet)
+                        .to_uppercase();
+
+                    let sprite_dir = base_dir.join(&bmp_base);
+                    fs::create_dir_all(&sprite_dir)
+                        .unwrap_or_else(|err| fail(format!("Failed to create directory for {}: {}", bmp_base, err)));
+
+                    // Save each sprite as an image file in appropriate directory
+                    for (name, sprite_img) in &sprites {
+                        if sprite_img.width() == 0 || sprite_img.height() == 0 {
+                            say(format!("Skipping sprite {} because it has no size", name));
+                            continue;
                         }
+
+                        let output_path = sprite_dir.join(format!("{}.{}", name, options.format.extension()));
+                        options
+                            .format
+                            .save(sprite_img, &output_path)
+                            .unwrap_or_else(|err| fail(format!("Failed to save sprite {}: {}", name, err)));
                     }
-                    Err(err) => {
-                        eprintln!("Error extracting sprites from {}: {}", sheet, err);
-                    }
+
+                    say(format!("Extracted {} sprites from {}", sprites.len(), sheet));
+                    extracted.push(format!("{}: {}", json_string(sheet), sprites.len()));
+                } else {
+                    say(format!("No sprites found in {}", sheet));
                 }
             }
+            Err(err) => {
+                warn(format!("Error extracting sprites from {}: {}", sheet, err));
+            }
+        }
+    }
+
+    // preserve any files that are not sprite sheets, including GENEX.BMP
+    for (name, content) in contents {
+        // remove any path from the name
+        let name = Path::new(name).file_name().unwrap().to_str().unwrap();
+        if !sprite_sheets.iter().any(|sheet| sheet.eq_ignore_ascii_case(name)) && options.keeps_file(name) {
+            let output_path = base_dir.join(name);
+            fs::write(&output_path, content).unwrap_or_else(|err| fail(format!("Failed to save {}: {}", name, err)));
+            say(format!("Saved {} to {}", name, output_path.display()));
+            saved.push(name.to_string());
+        }
+    }
+
+    record("output", json_string(&base_dir.to_string_lossy()));
+    record("sprites", format!("{{{}}}", extracted.join(", ")));
+    record("files", json_strings(&saved));
+}
+
+/// Parses the value following a command-line flag, exiting with an error if it is missing or invalid
+fn flag_value<T: FromStr>(flag: &str, value: Option<&String>) -> T {
+    let Some(value) = value else {
+        fail(format!("Error: {} needs a value", flag));
+    };
+    value.parse().unwrap_or_else(|_| {
+        fail(format!("Error: Invalid value for {}: {}", flag, value));
+    })
+}
+
+/// Parses a --scale flag, which is bounded so a typo cannot ask for an image too large to hold in memory
+fn scale_value(flag: &str, value: Option<&String>) -> u32 {
+    let scale = flag_value(flag, value);
+    if !(1..=MAX_SCALE).contains(&scale) {
+        fail(format!("Error: {} must be between 1 and {}", flag, MAX_SCALE));
+    }
+    scale
+}
+
+/// Stacks windows from a comma-separated list such as "main,eq,pl" top to bottom
+fn windows_layout(windows: &str, shade: bool) -> Layout {
+    let window_defs = SpriteWindowManager::new();
+    let mut layout = Layout::new();
+    let mut y = 0;
+    for name in windows.split(',').map(str::trim) {
+        let window_type = match (name.to_lowercase().as_str(), shade) {
+            ("main", false) => WindowType::Main,
+            ("main", true) => WindowType::MainShade,
+            ("eq", false) => WindowType::Equalizer,
+            ("eq", true) => WindowType::EqualizerShade,
+            ("pl", false) => WindowType::Playlist,
+            ("pl", true) => WindowType::PlaylistShade,
+            _ => {
+                fail(format!("Error: Unknown window: {} (expected main, eq or pl)", name));
+            }
+        };
+        layout = layout.with_window(window_type, 0, y);
+        y += window_defs.window_size(window_type).1;
+    }
+    layout
+}
+
+fn screenshot(args: &[String]) {
+    if args.len() < 3 {
+        usage_error(&args[0], "Error: No skin specified for screenshot");
+    }
+
+    let wsz_path = &args[2];
+    let mut screenshot_path = "screenshot.png".to_string();
+    let mut windows = "main,eq,pl".to_string();
+    let mut shade = false;
+    let mut options = RenderOptions::default();
+
+    let mut flags = args[3..].iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "--output" => screenshot_path = flag_value(flag, flags.next()),
+            "--scale" => options.scale = scale_value(flag, flags.next()),
+            "--windows" => windows = flag_value(flag, flags.next()),
+            "--shade" => shade = true,
+            "--demo" => options.player = PlayerState::demo(),
+            "--playing" => options.player.status = PlaybackStatus::Playing,
+            "--paused" => options.player.status = PlaybackStatus::Paused,
+            "--stopped" => options.player.status = PlaybackStatus::Stopped,
+            "--title" => options.player.title = flag_value(flag, flags.next()),
+            "--time" => options.player.time = Some(flag_value(flag, flags.next())),
+            "--volume" => options.player.volume = Some(flag_value(flag, flags.next())),
+            "--balance" => options.player.balance = Some(flag_value(flag, flags.next())),
+            "--kbps" => options.player.kbps = Some(flag_value(flag, flags.next())),
+            "--khz" => options.player.khz = Some(flag_value(flag, flags.next())),
+            "--mono" => options.player.channels = Some(Channels::Mono),
+            "--stereo" => options.player.channels = Some(Channels::Stereo),
+            _ => {
+                usage_error(&args[0], format!("Error: Unknown screenshot option: {}", flag));
+            }
+        }
+    }
+    options.layout = windows_layout(&windows, shade);
+
+    let wsz = Wsz::from_file_path(wsz_path).unwrap_or_else(|err| {
+        fail(format!("Error loading skin: {}", err));
+    });
+    let screenshot = wsz.render_screenshot_with_options(&options).unwrap_or_else(|err| {
+        fail(format!("Error rendering screenshot: {}", err));
+    });
+    if let Err(err) = screenshot.save(&screenshot_path) {
+        fail(format!("Error saving screenshot: {}", err));
+    }
+
+    say(format!("Created screenshot at {}", screenshot_path));
+    record("output", json_string(&screenshot_path));
+    record("width", screenshot.width().to_string());
+    record("height", screenshot.height().to_string());
+}
+
+fn preview(args: &[String]) {
+    if args.len() < 3 {
+        usage_error(&args[0], "Error: No skin specified for preview");
+    }
 
-            // preserve any non-bmp files
-            for (name, content) in &contents {
-                // remove any path from the name
-                let name = Path::new(name).file_name().unwrap().to_str().unwrap();
-                let lower_name = name.to_lowercase();
-                if !lower_name.ends_with(".bmp") {
-                    let output_path = PathBuf::from(&base_dir).join(&name);
-                    fs::write(&output_path, content).expect(&format!("Failed to save {}", name));
-                    println!("Saved {} to {}", name, output_path.display());
+    let max_columns = match args.get(3) {
+        Some(columns) => match columns.parse::<u32>() {
+            Ok(columns) => Some(columns),
+            Err(_) => {
+                fail(format!("Error: Invalid column count: {}", columns));
+            }
+        },
+        None => None,
+    };
+
+    let wsz = Wsz::from_file_path(&args[2]).unwrap_or_else(|err| {
+        fail(format!("Error loading skin: {}", err));
+    });
+    let options = wsz::render::RenderOptions {
+        player: wsz::render::PlayerState::demo(),
+        ..Default::default()
+    };
+    match wsz.render_ansi(&options, max_columns) {
+        Ok(preview) if is_json() => record("preview", json_string(&preview)),
+        Ok(preview) => print!("{}", preview),
+        Err(err) => {
+            fail(format!("Error rendering preview: {}", err));
+        }
+    }
+}
+
+/// Loads a skin, exiting with an error if it cannot be loaded
+fn load_skin(path: &str) -> Wsz {
+    Wsz::from_file_path(path).unwrap_or_else(|err| {
+        fail(format!("Error loading skin {}: {}", path, err));
+    })
+}
+
+fn diff_skins(args: &[String]) {
+    if args.len() < 4 {
+        usage_error(&args[0], "Error: Two skins are needed to compare");
+    }
+
+    let mut image_dir = None;
+    let mut flags = args[4..].iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "--image-diff" => image_dir = Some(flag_value::<PathBuf>(flag, flags.next())),
+            _ => {
+                usage_error(&args[0], format!("Error: Unknown diff option: {}", flag));
+            }
+        }
+    }
+
+    let old = load_skin(&args[2]);
+    let new = load_skin(&args[3]);
+    let skin_diff = old.diff(&new);
+    record("same", skin_diff.is_empty().to_string());
+    if skin_diff.is_empty() {
+        say("Skins are the same");
+        return;
+    }
+    if is_json() {
+        let changes: Vec<String> = skin_diff.to_string().lines().map(str::to_string).collect();
+        record("changes", json_strings(&changes));
+    } else {
+        print!("{}", skin_diff);
+    }
+
+    if let Some(image_dir) = image_dir {
+        fs::create_dir_all(&image_dir).unwrap_or_else(|err| {
+            fail(format!("Error creating {}: {}", image_dir.display(), err));
+        });
+        let mut written = 0;
+        for change in &skin_diff.sprites {
+            // added and removed sprites have nothing to compare against
+            let (Some(old_sprite), Some(new_sprite)) = (old.get_sprite(&change.name), new.get_sprite(&change.name))
+            else {
+                continue;
+            };
+            let output_path = image_dir.join(format!("{}.png", change.name));
+            if let Err(err) = difference_image(old_sprite, new_sprite).save(&output_path) {
+                fail(format!("Error saving {}: {}", output_path.display(), err));
+            }
+            written += 1;
+        }
+        say(format!(
+            "Wrote {} difference image(s) to {}",
+            written,
+            image_dir.display()
+        ));
+        record("image_diff", json_string(&image_dir.to_string_lossy()));
+    }
+}
+
+fn atlas(args: &[String]) {
+    if args.len() < 3 {
+        usage_error(&args[0], "Error: No skin specified for atlas");
+    }
+
+    let wsz_path = &args[2];
+    let mut atlas_path = format!("{}_atlas.png", get_filename_without_extension(wsz_path));
+    let mut coords_path: Option<String> = None;
+    let mut options = AtlasOptions::default();
+
+    let mut flags = args[3..].iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "-o" | "--output" => atlas_path = flag_value(flag, flags.next()),
+            "--coords" => coords_path = Some(flag_value(flag, flags.next())),
+            "--padding" => options.padding = flag_value(flag, flags.next()),
+            "--power-of-two" => options.power_of_two = true,
+            "--max-width" => options.max_width = flag_value(flag, flags.next()),
+            _ => {
+                usage_error(&args[0], format!("Error: Unknown atlas option: {}", flag));
+            }
+        }
+    }
+
+    let atlas = load_skin(wsz_path).atlas(&options);
+    if let Err(err) = atlas.image.save(&atlas_path) {
+        fail(format!("Error saving atlas: {}", err));
+    }
+    say(format!(
+        "Packed {} sprites into {} ({}x{})",
+        atlas.entries.len(),
+        atlas_path,
+        atlas.image.width(),
+        atlas.image.height()
+    ));
+    record("output", json_string(&atlas_path));
+    record("atlas", atlas.to_json());
+
+    if let Some(coords_path) = coords_path {
+        if let Err(err) = fs::write(&coords_path, atlas.to_json()) {
+            fail(format!("Error saving coordinates: {}", err));
+        }
+        say(format!("Wrote coordinates to {}", coords_path));
+        record("coords", json_string(&coords_path));
+    }
+}
+
+fn export_sheets(args: &[String]) {
+    if args.len() < 3 {
+        usage_error(&args[0], "Error: No skin specified for sheet export");
+    }
+
+    let wsz_path = &args[2];
+    let mut output_dir = PathBuf::from(format!("{}_sheets", get_filename_without_extension(wsz_path)));
+    let mut flags = args[3..].iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "-o" | "--output" => output_dir = flag_value(flag, flags.next()),
+            _ => {
+                usage_error(&args[0], format!("Error: Unknown sheets option: {}", flag));
+            }
+        }
+    }
+
+    let wsz = load_skin(wsz_path);
+    fs::create_dir_all(&output_dir).unwrap_or_else(|err| {
+        fail(format!("Error creating {}: {}", output_dir.display(), err));
+    });
+
+    let mut written = Vec::new();
+    for sheet in SpriteManager::sprite_sheet_names() {
+        let image = match wsz.get_sheet_image(&sheet) {
+            Ok(Some(image)) => image,
+            Ok(None) => continue,
+            Err(err) => {
+                warn(format!("Error decoding {}: {}", sheet, err));
+                continue;
+            }
+        };
+        let output_path = output_dir.join(format!("{}.png", get_filename_without_extension(&sheet)));
+        if let Err(err) = image.save(&output_path) {
+            fail(format!("Error saving {}: {}", output_path.display(), err));
+        }
+        say(format!("Saved {} to {}", sheet, output_path.display()));
+        written.push(output_path.to_string_lossy().to_string());
+    }
+    say(format!("Exported {} sprite sheet(s)", written.len()));
+    record("sheets", json_strings(&written));
+}
+
+fn export_cursors(args: &[String]) {
+    if args.len() < 3 {
+        usage_error(&args[0], "Error: No skin specified for cursor export");
+    }
+
+    let wsz_path = &args[2];
+    let mut output_dir = PathBuf::from(format!("{}_cursors", get_filename_without_extension(wsz_path)));
+    let mut flags = args[3..].iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "-o" | "--output" => output_dir = flag_value(flag, flags.next()),
+            _ => {
+                usage_error(&args[0], format!("Error: Unknown cursors option: {}", flag));
+            }
+        }
+    }
+
+    let wsz = load_skin(wsz_path);
+    fs::create_dir_all(&output_dir).unwrap_or_else(|err| {
+        fail(format!("Error creating {}: {}", output_dir.display(), err));
+    });
+
+    let mut exported = Vec::new();
+    for (file, cursor) in decode_cursor_files(wsz.raw_files()) {
+        let cursor = match cursor {
+            Ok(cursor) => cursor,
+            Err(err) => {
+                warn(format!("Error decoding {}: {}", file, err));
+                continue;
+            }
+        };
+        let output_path = output_dir.join(format!("{}.png", get_filename_without_extension(&file)));
+        if let Err(err) = cursor.image.save(&output_path) {
+            fail(format!("Error saving {}: {}", output_path.display(), err));
+        }
+        say(format!("Saved {} to {}", file, output_path.display()));
+        exported.push((file, cursor));
+    }
+
+    let json_path = output_dir.join("cursors.json");
+    if let Err(err) = fs::write(&json_path, cursors_json(&exported)) {
+        fail(format!("Error writing {}: {}", json_path.display(), err));
+    }
+    say(format!(
+        "Exported {} cursor(s), hotspots in {}",
+        exported.len(),
+        json_path.display()
+    ));
+    record("output", json_string(&output_dir.to_string_lossy()));
+    record("cursors", cursors_json(&exported));
+}
+
+/// Ways a skin can be stored, inferred from a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SkinFormat {
+    /// A .wsz (or .zip) classic skin
+    Wsz,
+    /// A folder of sprite PNGs as written by extract
+    Directory,
+    /// A .wal Winamp 3 or 5 modern skin
+    Wal,
+}
+
+impl SkinFormat {
+    fn of(path: &Path) -> Self {
+        if path.is_dir() {
+            return SkinFormat::Directory;
+        }
+        let extension = path.extension().and_then(|s| s.to_str()).map(str::to_lowercase);
+        match extension.as_deref() {
+            Some("wsz") | Some("zip") => SkinFormat::Wsz,
+            Some("wal") => SkinFormat::Wal,
+            _ => SkinFormat::Directory,
+        }
+    }
+}
+
+fn convert(args: &[String]) {
+    if args.len() < 4 {
+        usage_error(&args[0], "Error: Convert needs an input and an output");
+    }
+
+    let input = Path::new(&args[2]);
+    let output = Path::new(&args[3]);
+    if !input.exists() {
+        fail(format!("Error: {} does not exist", input.display()));
+    }
+
+    match (SkinFormat::of(input), SkinFormat::of(output)) {
+        (SkinFormat::Wal, _) | (_, SkinFormat::Wal) => {
+            // modern skins are XML and PNG layouts with no sprite sheets to convert to or from
+            fail("Error: .wal modern skins cannot be converted to or from classic skins");
+        }
+        (SkinFormat::Wsz, SkinFormat::Wsz) => {
+            // loading and saving normalizes the sheets and text files
+            if let Err(err) = load_skin(&args[2]).save(output) {
+                fail(format!("Error saving {}: {}", output.display(), err));
+            }
+        }
+        (SkinFormat::Wsz, SkinFormat::Directory) => match unpack_wsz(input) {
+            Ok(contents) => extract_archive(&contents, output, &ExtractOptions::default()),
+            Err(err) => {
+                fail(format!("Error unpacking WSZ file: {}", err));
+            }
+        },
+        (SkinFormat::Directory, SkinFormat::Wsz) => match pack_wsz_dir_with_warnings(input, output) {
+            Ok(warnings) => warnings.into_iter().for_each(warn),
+            Err(err) => {
+                fail(format!("Error packing WSZ file: {}", err));
+            }
+        },
+        (SkinFormat::Directory, SkinFormat::Directory) => {
+            fail(format!(
+                "Error: Both {} and {} are directories",
+                input.display(),
+                output.display()
+            ));
+        }
+    }
+
+    say(format!("Converted {} to {}", input.display(), output.display()));
+    record("output", json_string(&output.to_string_lossy()));
+}
+
+fn gallery(args: &[String]) {
+    if args.len() < 3 {
+        usage_error(&args[0], "Error: No folder of skins specified for gallery");
+    }
+
+    let mut output_dir = PathBuf::from("site");
+    let mut options = GalleryOptions::default();
+    let mut flags = args[3..].iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "-o" | "--output" => output_dir = flag_value(flag, flags.next()),
+            "--title" => options.title = flag_value(flag, flags.next()),
+            _ => {
+                usage_error(&args[0], format!("Error: Unknown gallery option: {}", flag));
+            }
+        }
+    }
+
+    match export_gallery(&args[2], &output_dir, &options) {
+        Ok(summary) => {
+            for (path, err) in &summary.failed {
+                warn(format!("Skipped {}: {}", path.display(), err));
+            }
+            say(format!(
+                "Wrote a gallery of {} skin(s) to {}",
+                summary.exported,
+                output_dir.join("index.html").display()
+            ));
+            let failed: Vec<String> = summary
+                .failed
+                .iter()
+                .map(|(path, err)| {
+                    format!(
+                        "{{\"path\": {}, \"error\": {}}}",
+                        json_string(&path.to_string_lossy()),
+                        json_string(&err.to_string())
+                    )
+                })
+                .collect();
+            record("output", json_string(&output_dir.to_string_lossy()));
+            record("exported", summary.exported.to_string());
+            record("failed", format!("[{}]", failed.join(", ")));
+        }
+        Err(err) => {
+            fail(format!("Error writing gallery: {}", err));
+        }
+    }
+}
+
+fn thumbs(args: &[String]) {
+    if args.len() < 3 {
+        usage_error(&args[0], "Error: No folder of skins specified for thumbs");
+    }
+
+    let mut output_dir = PathBuf::from("thumbs");
+    let mut options = ThumbnailFolderOptions::default();
+    let mut flags = args[3..].iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "-o" | "--output" => output_dir = flag_value(flag, flags.next()),
+            "--size" => {
+                let size: String = flag_value(flag, flags.next());
+                let parsed = size
+                    .split_once('x')
+                    .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)));
+                match parsed {
+                    Some((width, height)) if width > 0 && height > 0 => {
+                        options.width = width;
+                        options.height = height;
+                    }
+                    _ => fail(format!(
+                        "Error: Invalid size for --size: {}, expected e.g. 137x80",
+                        size
+                    )),
                 }
             }
+            "--threads" => options.threads = flag_value(flag, flags.next()),
+            _ => {
+                usage_error(&args[0], format!("Error: Unknown thumbs option: {}", flag));
+            }
+        }
+    }
+
+    match export_thumbnails(&args[2], &output_dir, &options) {
+        Ok(summary) => {
+            for (path, err) in &summary.failed {
+                warn(format!("Skipped {}: {}", path.display(), err));
+            }
+            for (path, fingerprint) in &summary.thumbnails {
+                say(format!("{} -> {}.png", path.display(), fingerprint));
+            }
+            say(format!(
+                "Wrote thumbnails of {} skin(s) to {}",
+                summary.thumbnails.len(),
+                output_dir.display()
+            ));
+            let thumbnails: Vec<String> = summary
+                .thumbnails
+                .iter()
+                .map(|(path, fingerprint)| {
+                    format!(
+                        "{{\"path\": {}, \"thumbnail\": {}}}",
+                        json_string(&path.to_string_lossy()),
+                        json_string(&format!("{}.png", fingerprint))
+                    )
+                })
+                .collect();
+            let failed: Vec<String> = summary
+                .failed
+                .iter()
+                .map(|(path, err)| {
+                    format!(
+                        "{{\"path\": {}, \"error\": {}}}",
+                        json_string(&path.to_string_lossy()),
+                        json_string(&err.to_string())
+                    )
+                })
+                .collect();
+            record("output", json_string(&output_dir.to_string_lossy()));
+            record("thumbnails", format!("[{}]", thumbnails.join(", ")));
+            record("failed", format!("[{}]", failed.join(", ")));
         }
         Err(err) => {
-            eprintln!("Error unpacking WSZ file: {}", err);
-            process::exit(1);
+            fail(format!("Error writing thumbnails: {}", err));
         }
     }
 }
 
-fn screenshot(args: &Vec<String>) {
+fn render_text(args: &[String]) {
+    if args.len() < 4 {
+        usage_error(&args[0], "Error: render-text needs a skin and the text to render");
+    }
+
+    let text = &args[3];
+    let mut output_path = "text.png".to_string();
+    let mut scale = 1;
+    let mut flags = args[4..].iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "-o" | "--output" => output_path = flag_value(flag, flags.next()),
+            "--scale" => scale = scale_value(flag, flags.next()),
+            _ => {
+                usage_error(&args[0], format!("Error: Unknown render-text option: {}", flag));
+            }
+        }
+    }
+
+    let wsz = load_skin(&args[2]);
+    let missing = text::missing_characters(wsz.get_sprites(), text);
+    let missing: Vec<String> = missing.into_iter().map(String::from).collect();
+    if !missing.is_empty() {
+        say(format!(
+            "The skin's font has no {:?}, drawn as spaces",
+            missing.concat()
+        ));
+    }
+    record("missing", json_strings(&missing));
+
+    let image = scale_image(text::render_text(wsz.get_sprites(), text), scale).unwrap_or_else(|err| {
+        fail(format!("Error rendering text: {}", err));
+    });
+    if let Err(err) = image.save(&output_path) {
+        fail(format!("Error saving {}: {}", output_path, err));
+    }
+    say(format!(
+        "Rendered {}x{} text to {}",
+        image.width(),
+        image.height(),
+        output_path
+    ));
+    record("output", json_string(&output_path));
+}
+
+fn palette(args: &[String]) {
+    if args.len() < 3 {
+        usage_error(&args[0], "Error: No skin specified for palette");
+    }
+
+    let mut output_path = None;
+    let mut flags = args[3..].iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "-o" | "--output" => output_path = Some(flag_value::<String>(flag, flags.next())),
+            _ => {
+                usage_error(&args[0], format!("Error: Unknown palette option: {}", flag));
+            }
+        }
+    }
+
+    let wsz = load_skin(&args[2]);
+    let swatches = wsz.color_swatches();
+    record("palette", swatches_json(&swatches));
+
+    // with --json the chart is only drawn when asked for, as the colors are what scripts want
+    let output_path = match output_path {
+        Some(path) => path,
+        None if is_json() => return,
+        None => "palette.png".to_string(),
+    };
+    let image = render_swatches(wsz.get_sprites(), &swatches);
+    if let Err(err) = image.save(&output_path) {
+        fail(format!("Error saving {}: {}", output_path, err));
+    }
+    say(format!("Drew {} colors to {}", swatches.len(), output_path));
+    record("output", json_string(&output_path));
+}
+
+/// Lists the files in a folder and its subfolders with their modification times and sizes, leaving out `ignore`
+fn dir_snapshot(dir: &Path, ignore: &[PathBuf], snapshot: &mut Vec<(PathBuf, Option<SystemTime>, u64)>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            dir_snapshot(&path, ignore, snapshot);
+        } else if !ignore.iter().any(|ignored| ignored == &path) {
+            snapshot.push((path, metadata.modified().ok(), metadata.len()));
+        }
+    }
+}
+
+/// Resolves a path that may not exist yet through its parent folder
+fn absolute_path(path: &Path) -> PathBuf {
+    let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty());
+    let file_name = path.file_name().unwrap_or_default();
+    fs::canonicalize(parent.unwrap_or(Path::new(".")))
+        .map(|parent| parent.join(file_name))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Packs a skin folder to a .wsz and renders its screenshot
+fn rebuild(dir: &Path, skin_path: &Path, preview_path: &Path) -> wsz::error::Result<()> {
+    let (data, warnings) = pack_wsz_dir_bytes(dir)?;
+    warnings.into_iter().for_each(warn);
+    let screenshot = Wsz::from_bytes(&data)?.render_screenshot()?;
+    fs::write(skin_path, data)?;
+    screenshot.save(preview_path)?;
+    Ok(())
+}
+
+fn watch(args: &[String]) {
+    if args.len() < 3 {
+        usage_error(&args[0], "Error: No directory specified to watch");
+    }
+    if is_json() {
+        fail("Error: watch runs until it is stopped, so it cannot print a JSON document");
+    }
+
+    let dir = PathBuf::from(&args[2]);
+    let mut preview_path = PathBuf::from("preview.png");
+    let mut skin_path = None;
+    let mut interval = 500;
+    let mut flags = args[3..].iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "-o" | "--output" => preview_path = flag_value(flag, flags.next()),
+            "--skin" => skin_path = Some(flag_value::<PathBuf>(flag, flags.next())),
+            "--interval" => interval = flag_value(flag, flags.next()),
+            _ => {
+                usage_error(&args[0], format!("Error: Unknown watch option: {}", flag));
+            }
+        }
+    }
+    if !dir.is_dir() {
+        fail(format!("Error: {} is not a directory", dir.display()));
+    }
+    let skin_path = skin_path.unwrap_or_else(|| {
+        let wsz_name = format!("{}.wsz", dir.file_name().and_then(|s| s.to_str()).unwrap_or("skin"));
+        dir.with_file_name(wsz_name)
+    });
+
+    // the outputs may be inside the folder, and writing them must not count as a change
+    let ignore = [absolute_path(&preview_path), absolute_path(&skin_path)];
+    let dir = absolute_path(&dir);
+    if ignore[0].starts_with(&dir) {
+        warn(format!(
+            "Warning: {} is inside {} and will be packed into the skin",
+            preview_path.display(),
+            dir.display()
+        ));
+    }
+
+    say(format!("Watching {} for changes, press Ctrl+C to stop", dir.display()));
+    let mut last = None;
+    loop {
+        let mut snapshot = Vec::new();
+        dir_snapshot(&dir, &ignore, &mut snapshot);
+        snapshot.sort();
+        if last.as_ref() != Some(&snapshot) {
+            match rebuild(&dir, &skin_path, &preview_path) {
+                Ok(()) => say(format!(
+                    "Packed {} and updated {}",
+                    skin_path.display(),
+                    preview_path.display()
+                )),
+                Err(err) => warn(format!("Error rebuilding the skin: {}", err)),
+            }
+            last = Some(snapshot);
+        }
+        thread::sleep(Duration::from_millis(interval));
+    }
+}
+
+/// How long `serve` waits for a request to be sent or its answer to be read before dropping the connection
+const SERVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Page served by `serve`, which reloads the images whenever /version changes
+const SERVE_PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{title} - wsz preview</title>
+<style>
+body { font-family: sans-serif; background: #222; color: #ddd; margin: 2em; }
+img { image-rendering: pixelated; display: block; margin-bottom: 1em; }
+#error { color: #f88; white-space: pre-wrap; }
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<div id="error"></div>
+<h2>Screenshot</h2>
+<img id="screenshot" src="screenshot.png" alt="Screenshot" width="550">
+<h2>Sprite atlas</h2>
+<img id="atlas" src="atlas.png" alt="Sprite atlas"> <a href="atlas.json">Coordinates</a>
+<script>
+let version = null;
+async function poll() {
+  try {
+    const state = await (await fetch("version", { cache: "no-store" })).json();
+    if (state.version !== version) {
+      version = state.version;
+      for (const id of ["screenshot", "atlas"]) {
+        document.getElementById(id).src = id + ".png?v=" + version;
+      }
+    }
+    document.getElementById("error").textContent = state.error || "";
+  } catch (err) {
+    document.getElementById("error").textContent = "The server stopped";
+  }
+  setTimeout(poll, 1000);
+}
+poll();
+</script>
+</body>
+</html>
+"#;
+
+/// What `serve` shows for the current version of the skin
+#[derive(Default)]
+struct ServedSkin {
+    /// Files the skin was last rendered from, to tell when it changes, or None before the first render
+    snapshot: Option<Vec<(PathBuf, Option<SystemTime>, u64)>>,
+    /// Increased every time the skin is rendered
+    version: u64,
+    screenshot: Vec<u8>,
+    atlas: Vec<u8>,
+    atlas_json: String,
+    /// Why the latest change could not be rendered. The last good render is still served.
+    error: Option<String>,
+}
+
+impl ServedSkin {
+    /// Renders the skin again if any of its files changed
+    fn refresh(&mut self, source: &Path) {
+        let mut snapshot = Vec::new();
+        if source.is_dir() {
+            dir_snapshot(source, &[], &mut snapshot);
+        } else if let Ok(metadata) = fs::metadata(source) {
+            snapshot.push((source.to_path_buf(), metadata.modified().ok(), metadata.len()));
+        }
+        snapshot.sort();
+        if self.snapshot.as_ref() == Some(&snapshot) {
+            return;
+        }
+        self.snapshot = Some(snapshot);
+
+        match render_served_skin(source) {
+            Ok((screenshot, atlas, atlas_json)) => {
+                self.screenshot = screenshot;
+                self.atlas = atlas;
+                self.atlas_json = atlas_json;
+                self.error = None;
+                self.version += 1;
+                say(format!("Rendered version {}", self.version));
+            }
+            Err(err) => {
+                warn(format!("Error rendering {}: {}", source.display(), err));
+                self.error = Some(err.to_string());
+            }
+        }
+    }
+}
+
+/// Loads a skin file or packs a skin folder, and renders its screenshot and atlas as PNGs and the atlas's JSON
+fn render_served_skin(source: &Path) -> wsz::error::Result<(Vec<u8>, Vec<u8>, String)> {
+    let skin = if source.is_dir() {
+        let (data, warnings) = pack_wsz_dir_bytes(source)?;
+        warnings.into_iter().for_each(warn);
+        Wsz::from_bytes(&data)?
+    } else {
+        Wsz::from_file_path(source)?
+    };
+    let atlas = skin.atlas(&AtlasOptions::default());
+    Ok((
+        png_bytes(skin.render_screenshot()?)?,
+        png_bytes(atlas.image.clone())?,
+        atlas.to_json(),
+    ))
+}
+
+/// Encodes an image as PNG
+fn png_bytes(image: SpriteImage) -> image::ImageResult<Vec<u8>> {
+    let mut data = Vec::new();
+    DynamicImage::ImageRgba8(image).write_to(&mut Cursor::new(&mut data), image::ImageFormat::Png)?;
+    Ok(data)
+}
+
+/// Answers one HTTP request
+fn serve_request(mut stream: TcpStream, source: &Path, skin: &Mutex<ServedSkin>) -> io::Result<()> {
+    // browsers open connections they may never send on, which must not hold the thread forever
+    stream.set_read_timeout(Some(SERVE_TIMEOUT))?;
+    stream.set_write_timeout(Some(SERVE_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // the headers are not needed, but must be read before answering
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let target = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let path = target.split('?').next().unwrap_or("/");
+    let mut skin = skin.lock().unwrap_or_else(PoisonError::into_inner);
+    skin.refresh(source);
+    let title = source
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let version = format!(
+        "{{\"version\": {}, \"error\": {}}}",
+        skin.version,
+        skin.error.as_deref().map_or("null".to_string(), json_string)
+    );
+    let (status, content_type, body): (&str, &str, Vec<u8>) = match path {
+        "/" | "/index.html" => (
+            "200 OK",
+            "text/html; charset=utf-8",
+            SERVE_PAGE.replace("{title}", &html_text(&title)).into_bytes(),
+        ),
+        "/version" => ("200 OK", "application/json", version.into_bytes()),
+        "/screenshot.png" => ("200 OK", "image/png", skin.screenshot.clone()),
+        "/atlas.png" => ("200 OK", "image/png", skin.atlas.clone()),
+        "/atlas.json" => ("200 OK", "application/json", skin.atlas_json.clone().into_bytes()),
+        _ => ("404 Not Found", "text/plain", b"Not found".to_vec()),
+    };
+    drop(skin);
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(&body)?;
+    stream.flush()
+}
+
+/// Escapes text for use in HTML content
+fn html_text(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn serve(args: &[String]) {
+    if args.len() < 3 {
+        usage_error(&args[0], "Error: No skin or directory specified to serve");
+    }
+    if is_json() {
+        fail("Error: serve runs until it is stopped, so it cannot print a JSON document");
+    }
+
+    let source = PathBuf::from(&args[2]);
+    let mut port: u16 = 8080;
+    let mut flags = args[3..].iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "--port" => port = flag_value(flag, flags.next()),
+            _ => {
+                usage_error(&args[0], format!("Error: Unknown serve option: {}", flag));
+            }
+        }
+    }
+    if !source.exists() {
+        fail(format!("Error: {} does not exist", source.display()));
+    }
+
+    let listener = TcpListener::bind(("127.0.0.1", port)).unwrap_or_else(|err| {
+        fail(format!("Error listening on port {}: {}", port, err));
+    });
+    let mut skin = ServedSkin::default();
+    skin.refresh(&source);
+    let skin = Mutex::new(skin);
+    say(format!(
+        "Serving {} at http://127.0.0.1:{}/, press Ctrl+C to stop",
+        source.display(),
+        port
+    ));
+    // each connection gets its own thread, so a slow or idle one does not hold up the page
+    thread::scope(|scope| {
+        for stream in listener.incoming() {
+            let (source, skin) = (&source, &skin);
+            scope.spawn(move || {
+                let result = stream.and_then(|stream| serve_request(stream, source, skin));
+                match result {
+                    Err(err) if matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {}
+                    Err(err) => warn(format!("Error answering a request: {}", err)),
+                    Ok(()) => {}
+                }
+            });
+        }
+    });
+}
+
+fn repair(args: &[String]) {
+    if args.len() < 3 {
+        usage_error(&args[0], "Error: No skin specified to repair");
+    }
+
+    let wsz_path = &args[2];
+    let mut output_path = PathBuf::from(format!("{}_fixed.wsz", get_filename_without_extension(wsz_path)));
+    let mut base_path: Option<String> = None;
+    let mut options = RepairOptions::default();
+    let mut flags = args[3..].iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "-o" | "--output" => output_path = flag_value(flag, flags.next()),
+            "--base" => base_path = Some(flag_value(flag, flags.next())),
+            "--keep-names" => options.normalize_names = false,
+            "--keep-bitmaps" => options.reencode_bitmaps = false,
+            "--no-default-text" => options.default_text_files = false,
+            _ => {
+                usage_error(&args[0], format!("Error: Unknown repair option: {}", flag));
+            }
+        }
+    }
+
+    let base = base_path.as_deref().map(load_skin);
+    options.base = base.as_ref();
+    let mut wsz = load_skin(wsz_path);
+    let log = wsz.repair(&options).unwrap_or_else(|err| {
+        fail(format!("Error repairing {}: {}", wsz_path, err));
+    });
+
+    let actions: Vec<String> = log.actions().iter().map(|action| action.to_string()).collect();
+    for action in &actions {
+        say(format!("Fixed: {}", action));
+    }
+    if base.is_none() {
+        for issue in wsz.validate().issues() {
+            let sheet_issue = matches!(
+                issue.kind,
+                ValidationIssueKind::MissingSheet { .. } | ValidationIssueKind::UnreadableSheet { .. }
+            );
+            if sheet_issue && issue.severity >= Severity::Warning {
+                warn(format!(
+                    "Not fixed: {} (use --base to fill in sprite sheets)",
+                    issue.kind
+                ));
+            }
+        }
+    }
+    record("fixes", json_strings(&actions));
+
+    if let Err(err) = wsz.save(&output_path) {
+        fail(format!("Error saving {}: {}", output_path.display(), err));
+    }
+    say(format!(
+        "Made {} fix(es) and saved the skin to {}",
+        actions.len(),
+        output_path.display()
+    ));
+    record("output", json_string(&output_path.to_string_lossy()));
+}
+
+fn recolor(args: &[String]) {
+    if args.len() < 3 {
+        usage_error(&args[0], "Error: No skin specified to recolor");
+    }
+
+    let wsz_path = &args[2];
+    let mut output_path = PathBuf::from(format!("{}_recolored.wsz", get_filename_without_extension(wsz_path)));
+    let mut options = RecolorOptions::default();
+    let mut flags = args[3..].iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "-o" | "--output" => output_path = flag_value(flag, flags.next()),
+            "--hue-shift" => options.hue_shift = flag_value(flag, flags.next()),
+            "--saturation" => options.saturation = flag_value(flag, flags.next()),
+            "--brightness" => options.brightness = flag_value(flag, flags.next()),
+            _ => {
+                usage_error(&args[0], format!("Error: Unknown recolor option: {}", flag));
+            }
+        }
+    }
+    if options.saturation < 0.0 || options.brightness < 0.0 {
+        fail("Error: --saturation and --brightness cannot be negative");
+    }
+    if options.is_identity() {
+        warn("Warning: No color change given, use --hue-shift, --saturation or --brightness");
+    }
+
+    let mut wsz = load_skin(wsz_path);
+    if let Err(err) = wsz.recolor(&options) {
+        fail(format!("Error recoloring {}: {}", wsz_path, err));
+    }
+    if let Err(err) = wsz.save(&output_path) {
+        fail(format!("Error saving {}: {}", output_path.display(), err));
+    }
+    say(format!("Saved the recolored skin to {}", output_path.display()));
+    record("output", json_string(&output_path.to_string_lossy()));
+}
+
+fn lint(args: &[String]) {
+    if args.len() < 3 {
+        usage_error(&args[0], "Error: No skin or skin folder specified to lint");
+    }
+
+    let source = &args[2];
+    let mut max_warnings: Option<usize> = None;
+    let mut flags = args[3..].iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "--max-warnings" => max_warnings = Some(flag_value(flag, flags.next())),
+            _ => {
+                usage_error(&args[0], format!("Error: Unknown lint option: {}", flag));
+            }
+        }
+    }
+
+    let wsz = if Path::new(source).is_dir() {
+        pack_wsz_dir_bytes(source)
+            .and_then(|(data, warnings)| {
+                warnings.into_iter().for_each(warn);
+                Wsz::from_bytes(&data)
+            })
+            .unwrap_or_else(|err| {
+                fail(format!("Error loading skin folder {}: {}", source, err));
+            })
+    } else {
+        load_skin(source)
+    };
+
+    let report = wsz.lint();
+    for finding in report.findings() {
+        say(finding.to_string());
+    }
+    let errors = report.count(Severity::Error);
+    let warnings = report.count(Severity::Warning);
+    if report.is_empty() {
+        say("No problems found");
+    } else {
+        say(format!(
+            "{} error(s), {} warning(s), {} info(s)",
+            errors,
+            warnings,
+            report.count(Severity::Info)
+        ));
+    }
+    record("lint", report.to_json());
+
+    if report.has_errors() {
+        fail(format!("Error: {} has {} error(s)", source, errors));
+    }
+    if let Some(max_warnings) = max_warnings {
+        if warnings > max_warnings {
+            fail(format!(
+                "Error: {} has {} warning(s), more than --max-warnings {}",
+                source, warnings, max_warnings
+            ));
+        }
+    }
+}
+
+fn new_skin(args: &[String]) {
+    if args.len() < 3 {
+        usage_error(&args[0], "Error: No folder specified for the new skin");
+    }
+
+    let dir = Path::new(&args[2]);
+    let mut base_path: Option<String> = None;
+    let mut options = ScaffoldOptions {
+        name: dir
+            .file_name()
+            .map_or_else(|| args[2].clone(), |name| name.to_string_lossy().to_string()),
+        ..Default::default()
+    };
+    let mut flags = args[3..].iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "--base" => base_path = Some(flag_value(flag, flags.next())),
+            "--name" => options.name = flag_value(flag, flags.next()),
+            _ => {
+                usage_error(&args[0], format!("Error: Unknown new option: {}", flag));
+            }
+        }
+    }
+
+    let base = base_path.as_deref().map(load_skin);
+    options.base = base.as_ref();
+    let summary = create_skin_dir(dir, &options).unwrap_or_else(|err| {
+        fail(format!("Error creating {}: {}", dir.display(), err));
+    });
+
+    if base.is_some() {
+        say(format!(
+            "Created {} with {} sprites, {} from the base skin",
+            dir.display(),
+            summary.sprites,
+            summary.from_base
+        ));
+    } else {
+        say(format!(
+            "Created {} with {} blank sprites",
+            dir.display(),
+            summary.sprites
+        ));
+    }
+    for file in &summary.files {
+        say(format!("Wrote {}", dir.join(file).display()));
+    }
+    say(format!(
+        "Draw the sprites, then pack the skin with --pack {}",
+        dir.display()
+    ));
+    record("output", json_string(&dir.to_string_lossy()));
+    record("sprites", summary.sprites.to_string());
+    record("files", json_strings(&summary.files));
+}
+
+fn list_entries(args: &[String]) {
+    if args.len() < 3 {
+        usage_error(&args[0], "Error: No skin specified to list");
+    }
+
     let wsz_path = &args[2];
-    let screenshot_path = "screenshot.png";
+    let mut long = false;
+    for flag in &args[3..] {
+        match flag.as_str() {
+            "-l" | "--long" => long = true,
+            _ => {
+                usage_error(&args[0], format!("Error: Unknown ls option: {}", flag));
+            }
+        }
+    }
 
-    let wsz = Wsz::from_file_path(wsz_path).unwrap();
-    let screenshot = wsz.render_screenshot().unwrap();
-    screenshot.save(screenshot_path).unwrap();
+    let listing = list_wsz(wsz_path).unwrap_or_else(|err| {
+        fail(format!("Error reading {}: {}", wsz_path, err));
+    });
 
-    println!("Created screenshot at {}", screenshot_path);
+    let mut header = format!("{:<6}  {:>9}  {:>10}  {:<8}", "type", "size", "compressed", "crc32");
+    if long {
+        header.push_str(&format!("  {:<10}  {:<19}", "method", "modified"));
+    }
+    say(format!("{}  name", header));
+    for entry in &listing {
+        let mut line = format!(
+            "{:<6}  {:>9}  {:>10}  {:08x}",
+            entry.kind.to_string(),
+            entry.size,
+            entry.compressed_size,
+            entry.crc32
+        );
+        if long {
+            let modified = entry.modified.map_or("-".to_string(), |modified| modified.to_string());
+            line.push_str(&format!("  {:<10}  {:<19}", entry.compression, modified));
+        }
+        say(format!("{}  {}", line, entry.name));
+    }
+    let size: u64 = listing.iter().map(|entry| entry.size).sum();
+    let compressed_size: u64 = listing.iter().map(|entry| entry.compressed_size).sum();
+    say(format!(
+        "{} file(s), {} bytes, {} bytes compressed",
+        listing.len(),
+        size,
+        compressed_size
+    ));
+    record("listing", listing_json(&listing));
 }
 
 fn main() {
     // Get command-line arguments
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+
+    // --json can be given anywhere on the command line and applies to every command
+    if args[1..].iter().any(|arg| arg == "--json") {
+        args.retain(|arg| arg != "--json");
+        *json_output() = Some(JsonOutput::default());
+    }
 
     // Check if a path was provided
     if args.len() < 2 {
-        print_usage(&args[0]);
-        process::exit(1);
+        usage_error(&args[0], "Error: No command specified");
     }
 
-    if args[1] == "--pack" {
+    // commands can be given with or without a leading "--"
+    let command = args[1].strip_prefix("--").unwrap_or(&args[1]);
+    if let Some(output) = json_output().as_mut() {
+        output.command = command.to_string();
+    }
+    if command == "pack" {
         pack_sprites(&args);
-    } else if args[1] == "--screenshot" {
+    } else if command == "screenshot" {
         screenshot(&args);
-    } else if args[1] == "--extract" {
+    } else if command == "preview" {
+        preview(&args);
+    } else if command == "extract" {
         extract_sprites(&args);
+    } else if command == "diff" {
+        diff_skins(&args);
+    } else if command == "atlas" {
+        atlas(&args);
+    } else if command == "sheets" {
+        export_sheets(&args);
+    } else if command == "cursors" {
+        export_cursors(&args);
+    } else if command == "convert" {
+        convert(&args);
+    } else if command == "gallery" {
+        gallery(&args);
+    } else if command == "render-text" {
+        render_text(&args);
+    } else if command == "palette" {
+        palette(&args);
+    } else if command == "watch" {
+        watch(&args);
+    } else if command == "serve" {
+        serve(&args);
+    } else if command == "repair" {
+        repair(&args);
+    } else if command == "recolor" {
+        recolor(&args);
+    } else if command == "lint" {
+        lint(&args);
+    } else if command == "new" {
+        new_skin(&args);
+    } else if command == "ls" {
+        list_entries(&args);
+    } else if command == "thumbs" {
+        thumbs(&args);
     } else {
-        eprintln!("Invalid command: {}", args[1]);
-        print_usage(&args[0]);
-        process::exit(1);
+        usage_error(&args[0], format!("Invalid command: {}", args[1]));
+    }
+
+    let output = json_output().take();
+    if let Some(output) = output {
+        println!("{}", output.to_json(None));
     }
 }
@@ -0,0 +1,112 @@
+//! Options controlling what is loaded from a skin
+//!
+//! Loading a whole skin decodes every sprite sheet and cursor. Callers that only need some of it, or that load
+//! skins they do not trust, can narrow the work and bound the memory it takes with WszOptions.
+
+use std::io::Cursor;
+
+use crate::error::{Result, WszError};
+use crate::sprites::SpriteManager;
+use crate::text::ParseMode;
+
+/// Limits on the size of a skin, to protect against malicious or broken archives
+///
+/// None means no limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ResourceLimits {
+    /// Largest size of the archive itself, in bytes
+    pub max_archive_size: Option<u64>,
+    /// Most files the archive may have
+    pub max_files: Option<usize>,
+    /// Largest size of any one file once uncompressed, in bytes
+    pub max_file_size: Option<u64>,
+    /// Largest size of all files together once uncompressed, in bytes
+    pub max_total_size: Option<u64>,
+    /// Most pixels any sprite sheet or other image may have
+    pub max_image_pixels: Option<u64>,
+}
+
+impl ResourceLimits {
+    /// Limits far above what real skins need, for loading skins from untrusted sources
+    pub fn untrusted() -> Self {
+        Self {
+            max_archive_size: Some(32 * 1024 * 1024),
+            max_files: Some(1024),
+            max_file_size: Some(16 * 1024 * 1024),
+            max_total_size: Some(64 * 1024 * 1024),
+            max_image_pixels: Some(4096 * 4096),
+        }
+    }
+
+    /// Checks an image's dimensions against `max_image_pixels` without decoding it
+    ///
+    /// Images whose format cannot be recognized pass, and fail when they are decoded instead.
+    pub(crate) fn check_image(&self, name: &str, data: &[u8]) -> Result<()> {
+        let Some(max_pixels) = self.max_image_pixels else {
+            return Ok(());
+        };
+        let size = image::ImageReader::new(Cursor::new(data))
+            .with_guessed_format()
+            .ok()
+            .and_then(|reader| reader.into_dimensions().ok());
+        match size {
+            Some((width, height)) if width as u64 * height as u64 > max_pixels => Err(WszError::LimitExceeded(
+                format!("{} is {}x{}, more than {} pixels", name, width, height, max_pixels),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Options controlling what `Wsz::from_*_with_options` loads
+#[derive(Debug, Clone)]
+pub struct WszOptions {
+    /// Sprite sheets to decode, e.g. "MAIN.BMP", or None for all of them. Sprites from other sheets are missing,
+    /// and their sheets are saved unchanged.
+    pub sheets: Option<Vec<String>>,
+    /// Whether to decode cursors
+    pub decode_cursors: bool,
+    /// Whether to keep every file of the archive. When false only the bitmaps and text configuration files Winamp
+    /// reads are kept, so `Wsz::raw_file` and `Wsz::raw_files` return nothing and the skin cannot be saved.
+    pub keep_raw_files: bool,
+    /// How to handle lines in text files that cannot be parsed
+    pub parse_mode: ParseMode,
+    /// Limits on the size of the skin
+    pub limits: ResourceLimits,
+}
+
+impl WszOptions {
+    fn new() -> Self {
+        Self {
+            sheets: None,
+            decode_cursors: true,
+            keep_raw_files: true,
+            parse_mode: ParseMode::Lenient,
+            limits: ResourceLimits::default(),
+        }
+    }
+
+    /// Whether a sprite sheet should be decoded
+    pub(crate) fn loads_sheet(&self, sheet_name: &str) -> bool {
+        self.sheets.as_ref().map_or(true, |sheets| {
+            sheets.iter().any(|sheet| sheet.eq_ignore_ascii_case(sheet_name))
+        })
+    }
+
+    /// Checks that every sheet asked for is a known sprite sheet
+    pub(crate) fn check_sheets(&self) -> Result<()> {
+        let known = SpriteManager::sprite_sheet_names();
+        for sheet in self.sheets.iter().flatten() {
+            if !known.iter().any(|name| name.eq_ignore_ascii_case(sheet)) {
+                return Err(WszError::ArgumentError(format!("{} not a known sprite sheet", sheet)));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for WszOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
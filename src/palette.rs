@@ -0,0 +1,236 @@
+//! Dominant colors of a skin, for describing and searching collections of skins
+//!
+//! Colors are counted over the default screenshot, so they weigh what Winamp shows rather than every state of every
+//! button on the sprite sheets. Similar colors are grouped by keeping the top four bits of each channel.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use image::Rgb;
+
+use crate::sprites::WindowImage;
+
+/// Families colors are grouped into for searching, e.g. "skins dominated by blue"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ColorFamily {
+    /// Very dark colors
+    Black,
+    /// Unsaturated colors between black and white
+    Gray,
+    /// Very light, unsaturated colors
+    White,
+    /// Reds
+    Red,
+    /// Oranges
+    Orange,
+    /// Dark oranges and yellows
+    Brown,
+    /// Yellows
+    Yellow,
+    /// Greens
+    Green,
+    /// Cyans and teals
+    Cyan,
+    /// Blues
+    Blue,
+    /// Purples and violets
+    Purple,
+    /// Pinks and magentas
+    Pink,
+}
+
+impl ColorFamily {
+    /// Every family
+    pub const ALL: [ColorFamily; 12] = [
+        Self::Black,
+        Self::Gray,
+        Self::White,
+        Self::Red,
+        Self::Orange,
+        Self::Brown,
+        Self::Yellow,
+        Self::Green,
+        Self::Cyan,
+        Self::Blue,
+        Self::Purple,
+        Self::Pink,
+    ];
+
+    /// Finds the family of a color
+    ///
+    /// # Arguments
+    ///
+    /// * `color` - The color to classify
+    ///
+    /// # Returns
+    ///
+    /// The family the color's hue, saturation and lightness put it in
+    pub fn of(color: Rgb<u8>) -> Self {
+        let [r, g, b] = color.0.map(|channel| channel as f32 / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let lightness = (max + min) / 2.0;
+        let chroma = max - min;
+        let saturation = if chroma == 0.0 {
+            0.0
+        } else {
+            chroma / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+
+        if lightness < 0.1 {
+            return Self::Black;
+        }
+        if lightness > 0.92 {
+            return Self::White;
+        }
+        if saturation < 0.15 || chroma < 0.08 {
+            return match lightness {
+                lightness if lightness < 0.18 => Self::Black,
+                lightness if lightness > 0.85 => Self::White,
+                _ => Self::Gray,
+            };
+        }
+
+        let hue = if max == r {
+            60.0 * ((g - b) / chroma).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / chroma + 2.0)
+        } else {
+            60.0 * ((r - g) / chroma + 4.0)
+        };
+        match hue {
+            hue if !(15.0..335.0).contains(&hue) => Self::Red,
+            hue if hue < 70.0 && lightness < 0.35 => Self::Brown,
+            hue if hue < 45.0 => Self::Orange,
+            hue if hue < 70.0 => Self::Yellow,
+            hue if hue < 160.0 => Self::Green,
+            hue if hue < 200.0 => Self::Cyan,
+            hue if hue < 260.0 => Self::Blue,
+            hue if hue < 290.0 => Self::Purple,
+            _ => Self::Pink,
+        }
+    }
+
+    /// Whether the family is black, gray or white rather than a hue
+    pub fn is_neutral(&self) -> bool {
+        matches!(self, Self::Black | Self::Gray | Self::White)
+    }
+}
+
+impl fmt::Display for ColorFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Black => "black",
+            Self::Gray => "gray",
+            Self::White => "white",
+            Self::Red => "red",
+            Self::Orange => "orange",
+            Self::Brown => "brown",
+            Self::Yellow => "yellow",
+            Self::Green => "green",
+            Self::Cyan => "cyan",
+            Self::Blue => "blue",
+            Self::Purple => "purple",
+            Self::Pink => "pink",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One of a skin's dominant colors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaletteColor {
+    /// Average of the similar colors grouped together
+    pub color: Rgb<u8>,
+    /// Share of the skin's visible pixels with this color, from 0.0 to 1.0
+    pub share: f32,
+}
+
+impl PaletteColor {
+    /// Get the family of the color
+    pub fn family(&self) -> ColorFamily {
+        ColorFamily::of(self.color)
+    }
+}
+
+/// Adds up the shares of a palette's colors by family
+///
+/// # Arguments
+///
+/// * `palette` - Colors from `Wsz::palette`
+///
+/// # Returns
+///
+/// Each family in the palette with its total share, largest first
+pub fn family_shares(palette: &[PaletteColor]) -> Vec<(ColorFamily, f32)> {
+    let mut shares: HashMap<ColorFamily, f32> = HashMap::new();
+    for color in palette {
+        *shares.entry(color.family()).or_default() += color.share;
+    }
+    let mut shares: Vec<(ColorFamily, f32)> = shares.into_iter().collect();
+    shares.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    shares
+}
+
+/// Finds the hue that covers the most of a palette, ignoring black, gray and white, which most skins are mostly
+/// made of
+///
+/// # Arguments
+///
+/// * `palette` - Colors from `Wsz::palette`
+///
+/// # Returns
+///
+/// The family, or None if less than a tenth of the palette's pixels have a hue
+pub fn dominant_hue(palette: &[PaletteColor]) -> Option<ColorFamily> {
+    let shares = family_shares(palette);
+    let colored: f32 = shares
+        .iter()
+        .filter(|(family, _)| !family.is_neutral())
+        .map(|(_, share)| share)
+        .sum();
+    if colored < 0.1 {
+        return None;
+    }
+    shares
+        .into_iter()
+        .find(|(family, _)| !family.is_neutral())
+        .map(|(family, _)| family)
+}
+
+/// Similar colors counted together
+#[derive(Debug, Default)]
+struct ColorGroup {
+    sums: [u64; 3],
+    pixels: u64,
+}
+
+/// Finds the most common colors of an image, ignoring transparent pixels
+pub(crate) fn palette(image: &WindowImage, count: usize) -> Vec<PaletteColor> {
+    let mut groups: HashMap<[u8; 3], ColorGroup> = HashMap::new();
+    let mut total = 0u64;
+    for pixel in image.pixels() {
+        let [r, g, b, a] = pixel.0;
+        if a < 128 {
+            continue;
+        }
+        let group = groups.entry([r >> 4, g >> 4, b >> 4]).or_default();
+        group.sums[0] += r as u64;
+        group.sums[1] += g as u64;
+        group.sums[2] += b as u64;
+        group.pixels += 1;
+        total += 1;
+    }
+
+    let mut groups: Vec<([u8; 3], ColorGroup)> = groups.into_iter().collect();
+    // ties are broken by the group so the palette is the same every time
+    groups.sort_by(|a, b| b.1.pixels.cmp(&a.1.pixels).then(a.0.cmp(&b.0)));
+    groups
+        .into_iter()
+        .take(count)
+        .map(|(_, group)| PaletteColor {
+            color: Rgb(group.sums.map(|sum| (sum / group.pixels) as u8)),
+            share: group.pixels as f32 / total as f32,
+        })
+        .collect()
+}
@@ -0,0 +1,161 @@
+//! Perceptual hashes for finding skins that look the same
+//!
+//! Unlike `Wsz::fingerprint`, which changes with any pixel, a perceptual hash changes little when a skin is re-saved
+//! with a different color depth, dithered or lightly edited. The hash is the usual DCT hash: the rendered main
+//! window is shrunk to 32x32 grayscale, and each bit says whether one of the 64 lowest frequencies is above their
+//! median.
+
+use std::fmt;
+
+use image::imageops::{self, FilterType};
+use image::GrayImage;
+
+use crate::error::Result;
+use crate::render::layout::{Layout, LayoutPreset};
+use crate::render::RenderOptions;
+use crate::sprites::WindowImage;
+use crate::Wsz;
+
+/// Size of the image the DCT is taken of
+const SAMPLE_SIZE: usize = 32;
+/// Size of the block of lowest frequencies that makes up the hash
+const HASH_SIZE: usize = 8;
+
+/// A 64 bit perceptual hash of a skin's main window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PerceptualHash(pub u64);
+
+impl PerceptualHash {
+    /// Count the bits that differ between two hashes
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The hash to compare with
+    ///
+    /// # Returns
+    ///
+    /// The Hamming distance, from 0 (the same) to 64. Copies of a skin are usually within 10.
+    pub fn distance(&self, other: &PerceptualHash) -> u32 {
+        (self.0 ^ other.0).count_ones()
+    }
+}
+
+impl fmt::Display for PerceptualHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// Groups hashes that are within a distance of each other
+///
+/// Groups are chained, so two hashes further apart than `max_distance` share a group if other hashes link them.
+///
+/// # Arguments
+///
+/// * `hashes` - The hashes to group, e.g. from `Wsz::phash`
+/// * `max_distance` - Largest distance between hashes that are linked
+///
+/// # Returns
+///
+/// Groups of indices into `hashes`, each sorted, ordered by their first index. Every index is in exactly one group.
+pub fn cluster(hashes: &[PerceptualHash], max_distance: u32) -> Vec<Vec<usize>> {
+    // union-find over the indices, pointing each at the lowest index of its group
+    let mut parents: Vec<usize> = (0..hashes.len()).collect();
+    fn root(parents: &mut [usize], mut index: usize) -> usize {
+        while parents[index] != index {
+            parents[index] = parents[parents[index]];
+            index = parents[index];
+        }
+        index
+    }
+    for a in 0..hashes.len() {
+        for b in a + 1..hashes.len() {
+            if hashes[a].distance(&hashes[b]) <= max_distance {
+                let (root_a, root_b) = (root(&mut parents, a), root(&mut parents, b));
+                parents[root_a.max(root_b)] = root_a.min(root_b);
+            }
+        }
+    }
+
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of_root = vec![usize::MAX; hashes.len()];
+    for index in 0..hashes.len() {
+        let root = root(&mut parents, index);
+        if group_of_root[root] == usize::MAX {
+            group_of_root[root] = groups.len();
+            groups.push(Vec::new());
+        }
+        groups[group_of_root[root]].push(index);
+    }
+    groups
+}
+
+/// Groups skins whose main windows look alike
+///
+/// # Arguments
+///
+/// * `skins` - The skins to group
+/// * `max_distance` - Largest distance between hashes that are linked, as in `cluster`
+///
+/// # Returns
+///
+/// Groups of indices into `skins`, as `cluster` gives, or an error if a skin cannot be rendered
+pub fn cluster_skins(skins: &[&Wsz], max_distance: u32) -> Result<Vec<Vec<usize>>> {
+    let hashes = skins.iter().map(|skin| skin.phash()).collect::<Result<Vec<_>>>()?;
+    Ok(cluster(&hashes, max_distance))
+}
+
+/// Hashes a skin's main window in its default state
+pub(crate) fn phash(skin: &Wsz) -> Result<PerceptualHash> {
+    let options = RenderOptions {
+        layout: Layout::preset(LayoutPreset::MainOnly),
+        ..Default::default()
+    };
+    Ok(hash_image(&skin.render_screenshot_with_options(&options)?))
+}
+
+/// Hashes an image, drawn over black
+fn hash_image(image: &WindowImage) -> PerceptualHash {
+    let gray = GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b, a] = image.get_pixel(x, y).0;
+        let luma = (299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000;
+        image::Luma([(luma * a as u32 / 255) as u8])
+    });
+    let sample = imageops::resize(&gray, SAMPLE_SIZE as u32, SAMPLE_SIZE as u32, FilterType::Triangle);
+    let pixels: Vec<f64> = sample.pixels().map(|pixel| pixel.0[0] as f64).collect();
+
+    // DCT-II of the rows then the columns, keeping only the lowest frequencies
+    let cosines: Vec<Vec<f64>> = (0..HASH_SIZE)
+        .map(|frequency| {
+            (0..SAMPLE_SIZE)
+                .map(|x| {
+                    (std::f64::consts::PI * frequency as f64 * (2 * x + 1) as f64 / (2 * SAMPLE_SIZE) as f64).cos()
+                })
+                .collect()
+        })
+        .collect();
+    let mut rows = vec![[0.0; HASH_SIZE]; SAMPLE_SIZE];
+    for (y, row) in rows.iter_mut().enumerate() {
+        for (u, value) in row.iter_mut().enumerate() {
+            *value = (0..SAMPLE_SIZE)
+                .map(|x| pixels[y * SAMPLE_SIZE + x] * cosines[u][x])
+                .sum();
+        }
+    }
+    let mut coefficients = Vec::with_capacity(HASH_SIZE * HASH_SIZE);
+    for cosine in &cosines {
+        coefficients.extend((0..HASH_SIZE).map(|u| rows.iter().zip(cosine).map(|(row, c)| row[u] * c).sum::<f64>()));
+    }
+
+    // the first coefficient is the average brightness, which would skew the median
+    let mut sorted = coefficients[1..].to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let median = sorted[sorted.len() / 2];
+    PerceptualHash(
+        coefficients
+            .iter()
+            .enumerate()
+            .filter(|(_, coefficient)| **coefficient > median)
+            .fold(0, |hash, (bit, _)| hash | 1 << bit),
+    )
+}
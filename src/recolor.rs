@@ -0,0 +1,166 @@
+//! Shifting the colors of a whole skin
+//!
+//! `Wsz::recolor` moves every sprite's hue, saturation and lightness together with the visualization, playlist
+//! and GENEX.BMP colors, so a recolored skin stays consistent with itself, e.g. a blue skin turned green keeps a
+//! green spectrum analyzer.
+
+use image::{Rgb, Rgba};
+
+use crate::error::Result;
+use crate::sprites::genex::GenExColors;
+use crate::sprites::{self, SpriteImage};
+use crate::text::viscolor::VisColors;
+use crate::Wsz;
+
+/// Options controlling how `Wsz::recolor` changes colors
+#[derive(Debug, Clone)]
+pub struct RecolorOptions {
+    /// Degrees to turn every hue by, e.g. 120 turns red to green and blue to red
+    pub hue_shift: f32,
+    /// Factor to multiply saturation by. 0 makes the skin gray and 1 leaves saturation unchanged.
+    pub saturation: f32,
+    /// Factor to multiply lightness by. 1 leaves lightness unchanged.
+    pub brightness: f32,
+}
+
+impl RecolorOptions {
+    fn new() -> Self {
+        Self {
+            hue_shift: 0.0,
+            saturation: 1.0,
+            brightness: 1.0,
+        }
+    }
+
+    /// Whether the options change no color
+    pub fn is_identity(&self) -> bool {
+        self.hue_shift.rem_euclid(360.0) == 0.0 && self.saturation == 1.0 && self.brightness == 1.0
+    }
+}
+
+impl Default for RecolorOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Recolors one color
+///
+/// # Arguments
+///
+/// * `color` - The color
+/// * `options` - How to change it
+///
+/// # Returns
+///
+/// The new color
+pub fn recolor_color(color: Rgb<u8>, options: &RecolorOptions) -> Rgb<u8> {
+    let (hue, saturation, lightness) = rgb_to_hsl(color);
+    hsl_to_rgb(
+        (hue + options.hue_shift).rem_euclid(360.0),
+        (saturation * options.saturation).clamp(0.0, 1.0),
+        (lightness * options.brightness).clamp(0.0, 1.0),
+    )
+}
+
+/// Recolors an image in place. Alpha is left untouched.
+///
+/// # Arguments
+///
+/// * `image` - Image to recolor
+/// * `options` - How to change its colors
+pub fn recolor_image(image: &mut SpriteImage, options: &RecolorOptions) {
+    for pixel in image.pixels_mut() {
+        let [r, g, b, a] = pixel.0;
+        let [r, g, b] = recolor_color(Rgb([r, g, b]), options).0;
+        *pixel = Rgba([r, g, b, a]);
+    }
+}
+
+/// Recolors the sprites, viscolor.txt, pledit.txt and GENEX.BMP of a skin
+///
+/// Sprites of sheets the skin was loaded without are left unchanged. Visualization and playlist colors the skin does
+/// not set are recolored from Winamp's defaults, so the skin sets them afterwards.
+pub(crate) fn recolor(skin: &mut Wsz, options: &RecolorOptions) -> Result<()> {
+    if options.is_identity() {
+        return Ok(());
+    }
+
+    for sprite in skin.sprites.values_mut() {
+        recolor_image(sprite, options);
+    }
+
+    // colors the skin leaves to Winamp's defaults are recolored too, or they would stay Winamp's green
+    let default_vis_colors = VisColors::winamp_default();
+    for index in 0..default_vis_colors.len() {
+        if let Some(color) = skin.vis_colors.get(index).or(default_vis_colors.get(index)) {
+            skin.vis_colors.set(index, recolor_color(color, options))?;
+        }
+    }
+
+    let pledit = &mut skin.pledit;
+    pledit.normal = Some(pledit.effective_normal());
+    pledit.current = Some(pledit.effective_current());
+    pledit.normal_bg = Some(pledit.effective_normal_bg());
+    pledit.selected_bg = Some(pledit.effective_selected_bg());
+    for color in [
+        &mut pledit.normal,
+        &mut pledit.current,
+        &mut pledit.normal_bg,
+        &mut pledit.selected_bg,
+        &mut pledit.mb_fg,
+        &mut pledit.mb_bg,
+    ] {
+        *color = color.map(|color| recolor_color(color, options));
+    }
+
+    // GENEX.BMP is not a sprite sheet, so it is recolored as a whole
+    if let Some(key) = sprites::find_sprite_sheet(&skin.files, "GENEX.BMP").cloned() {
+        if let Ok(image) = image::load_from_memory(&skin.files[&key]) {
+            let mut image = image.to_rgba8();
+            recolor_image(&mut image, options);
+            skin.genex_colors = GenExColors::from_image(&image).ok();
+            skin.files
+                .insert(key, crate::encode_bmp(&image::DynamicImage::ImageRgba8(image))?);
+        }
+    }
+    Ok(())
+}
+
+/// Converts a color to hue in degrees, saturation and lightness
+fn rgb_to_hsl(color: Rgb<u8>) -> (f32, f32, f32) {
+    let [r, g, b] = color.0.map(|channel| channel as f32 / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let lightness = (max + min) / 2.0;
+    let chroma = max - min;
+    if chroma == 0.0 {
+        return (0.0, 0.0, lightness);
+    }
+
+    let saturation = chroma / (1.0 - (2.0 * lightness - 1.0).abs());
+    let hue = if max == r {
+        60.0 * ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / chroma + 2.0)
+    } else {
+        60.0 * ((r - g) / chroma + 4.0)
+    };
+    (hue, saturation, lightness)
+}
+
+/// Converts hue in degrees, saturation and lightness to a color
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> Rgb<u8> {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let x = chroma * (1.0 - ((hue / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let (r, g, b) = match hue {
+        hue if hue < 60.0 => (chroma, x, 0.0),
+        hue if hue < 120.0 => (x, chroma, 0.0),
+        hue if hue < 180.0 => (0.0, chroma, x),
+        hue if hue < 240.0 => (0.0, x, chroma),
+        hue if hue < 300.0 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    Rgb([r, g, b].map(|channel| ((channel + m) * 255.0).round().clamp(0.0, 255.0) as u8))
+}
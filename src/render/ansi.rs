@@ -0,0 +1,68 @@
+//! Terminal preview of a rendered skin
+//!
+//! Each character cell shows two pixels stacked vertically using the upper half block character, with the top
+//! pixel as the foreground color and the bottom pixel as the background color. Requires a terminal with 24-bit
+//! color support.
+
+use std::fmt::Write;
+
+use image::imageops::{self, FilterType};
+use image::Rgba;
+
+use crate::sprites::WindowImage;
+
+/// Pixels with less alpha than this are treated as transparent and show the terminal's own background
+const ALPHA_THRESHOLD: u8 = 128;
+
+/// Converts an image to ANSI truecolor text
+///
+/// # Arguments
+///
+/// * `image` - Image to convert
+/// * `max_columns` - Width limit in terminal columns. Wider images are downscaled to fit, keeping the aspect ratio.
+///
+/// # Returns
+///
+/// Lines of ANSI escaped text, each ending with a color reset and a newline
+pub fn image_to_ansi(image: &WindowImage, max_columns: Option<u32>) -> String {
+    let resized;
+    let image = match max_columns {
+        Some(columns) if columns > 0 && image.width() > columns => {
+            let height = (image.height() as u64 * columns as u64 / image.width() as u64).max(1) as u32;
+            resized = imageops::resize(image, columns, height, FilterType::Triangle);
+            &resized
+        }
+        _ => image,
+    };
+
+    let visible = |pixel: &Rgba<u8>| pixel.0[3] >= ALPHA_THRESHOLD;
+
+    let mut ansi = String::new();
+    for y in (0..image.height()).step_by(2) {
+        for x in 0..image.width() {
+            let top = image.get_pixel(x, y);
+            let bottom = (y + 1 < image.height()).then(|| image.get_pixel(x, y + 1));
+            // writing to a String cannot fail
+            let _ = match (visible(top), bottom.filter(|pixel| visible(pixel))) {
+                (true, Some(bottom)) => write!(
+                    ansi,
+                    "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m\u{2580}",
+                    top.0[0], top.0[1], top.0[2], bottom.0[0], bottom.0[1], bottom.0[2]
+                ),
+                (true, None) => write!(
+                    ansi,
+                    "\x1b[49m\x1b[38;2;{};{};{}m\u{2580}",
+                    top.0[0], top.0[1], top.0[2]
+                ),
+                (false, Some(bottom)) => write!(
+                    ansi,
+                    "\x1b[49m\x1b[38;2;{};{};{}m\u{2584}",
+                    bottom.0[0], bottom.0[1], bottom.0[2]
+                ),
+                (false, None) => write!(ansi, "\x1b[0m "),
+            };
+        }
+        ansi.push_str("\x1b[0m\n");
+    }
+    ansi
+}
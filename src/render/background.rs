@@ -0,0 +1,135 @@
+//! Colors drawn behind the sprites of a composite
+
+use image::Rgba;
+
+use crate::render::target::RenderTarget;
+use crate::sprites::WindowType;
+
+/// Fully transparent color
+pub const TRANSPARENT: Rgba<u8> = Rgba([0, 0, 0, 0]);
+
+/// A solid rectangle drawn behind a window's sprites
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackgroundFill {
+    /// Window the rectangle is drawn in
+    pub window_type: WindowType,
+    /// X position of the left edge, relative to the window
+    pub x: u32,
+    /// Y position of the top edge, relative to the window
+    pub y: u32,
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// Fill color
+    pub color: Rgba<u8>,
+}
+
+/// Colors drawn behind the sprites of a composite
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Background {
+    /// Color of the canvas outside of the windows
+    pub canvas: Rgba<u8>,
+    /// Color behind the sprites of windows that have no color of their own
+    pub window: Rgba<u8>,
+    /// Colors behind the sprites of particular windows
+    pub windows: Vec<(WindowType, Rgba<u8>)>,
+    /// Rectangles drawn over the window colors, behind the sprites. Later fills are drawn on top.
+    pub fills: Vec<BackgroundFill>,
+}
+
+impl Background {
+    /// Creates a fully transparent background
+    pub fn new() -> Self {
+        Self {
+            canvas: TRANSPARENT,
+            window: TRANSPARENT,
+            windows: Vec::new(),
+            fills: Vec::new(),
+        }
+    }
+
+    /// Creates a background with the same color everywhere
+    pub fn solid(color: Rgba<u8>) -> Self {
+        Self {
+            canvas: color,
+            window: color,
+            ..Self::new()
+        }
+    }
+
+    /// Sets the color behind the sprites of a window, replacing any earlier color for it
+    pub fn with_window_color(mut self, window_type: WindowType, color: Rgba<u8>) -> Self {
+        self.windows.retain(|(existing, _)| *existing != window_type);
+        self.windows.push((window_type, color));
+        self
+    }
+
+    /// Adds a rectangle drawn behind a window's sprites
+    pub fn with_fill(mut self, fill: BackgroundFill) -> Self {
+        self.fills.push(fill);
+        self
+    }
+
+    /// Returns the color behind the sprites of a window, not counting fills
+    pub fn window_color(&self, window_type: WindowType) -> Rgba<u8> {
+        self.windows
+            .iter()
+            .find(|(existing, _)| *existing == window_type)
+            .map_or(self.window, |(_, color)| *color)
+    }
+
+    /// Returns the background color at a point in a window
+    ///
+    /// # Arguments
+    ///
+    /// * `window_type` - Window the point is in
+    /// * `x` - X position relative to the window
+    /// * `y` - Y position relative to the window
+    pub fn color_at(&self, window_type: WindowType, x: u32, y: u32) -> Rgba<u8> {
+        self.fills
+            .iter()
+            .rev()
+            .find(|fill| {
+                fill.window_type == window_type
+                    && x >= fill.x
+                    && x < fill.x + fill.width
+                    && y >= fill.y
+                    && y < fill.y + fill.height
+            })
+            .map_or_else(|| self.window_color(window_type), |fill| fill.color)
+    }
+
+    /// Draws a window's background onto a render target
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - Surface to draw onto
+    /// * `window_type` - Window to draw the background of
+    /// * `x` - X position of the window's left edge on the target
+    /// * `y` - Y position of the window's top edge on the target
+    /// * `width` - Width of the window
+    /// * `height` - Height of the window
+    pub fn draw<T: RenderTarget + ?Sized>(
+        &self,
+        target: &mut T,
+        window_type: WindowType,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) {
+        target.fill_rect(x, y, width, height, self.window_color(window_type));
+        for fill in self.fills.iter().filter(|fill| fill.window_type == window_type) {
+            let fill_width = fill.width.min(width.saturating_sub(fill.x));
+            let fill_height = fill.height.min(height.saturating_sub(fill.y));
+            target.fill_rect(x + fill.x, y + fill.y, fill_width, fill_height, fill.color);
+        }
+    }
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Self::new()
+    }
+}
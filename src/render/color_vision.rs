@@ -0,0 +1,88 @@
+//! Color vision deficiency simulation, to check how a skin looks to colorblind users
+//!
+//! Uses the matrices of Machado, Oliveira and Fernandes (2009) at full severity, applied in linear RGB.
+
+use std::sync::OnceLock;
+
+use crate::sprites::WindowImage;
+
+/// A type of color vision deficiency to simulate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorVisionDeficiency {
+    /// No red cones (red-green colorblindness)
+    Protanopia,
+    /// No green cones (red-green colorblindness, the most common kind)
+    Deuteranopia,
+    /// No blue cones (blue-yellow colorblindness)
+    Tritanopia,
+}
+
+impl ColorVisionDeficiency {
+    /// Matrix mapping linear RGB to how it is seen
+    fn matrix(&self) -> [[f32; 3]; 3] {
+        match self {
+            ColorVisionDeficiency::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            ColorVisionDeficiency::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            ColorVisionDeficiency::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+}
+
+/// Converts an sRGB channel to linear light, for every channel value
+fn to_linear() -> &'static [f32; 256] {
+    static TABLE: OnceLock<[f32; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        std::array::from_fn(|value| {
+            let value = value as f32 / 255.0;
+            if value <= 0.04045 {
+                value / 12.92
+            } else {
+                ((value + 0.055) / 1.055).powf(2.4)
+            }
+        })
+    })
+}
+
+/// Converts a linear light channel back to sRGB
+fn to_srgb(value: f32) -> u8 {
+    let value = value.clamp(0.0, 1.0);
+    let value = if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+    (value * 255.0).round() as u8
+}
+
+/// Recolors an image as it would be seen with a color vision deficiency
+///
+/// # Arguments
+///
+/// * `image` - Image to recolor in place. Alpha is left untouched.
+/// * `deficiency` - Deficiency to simulate
+pub fn simulate(image: &mut WindowImage, deficiency: ColorVisionDeficiency) {
+    let matrix = deficiency.matrix();
+    let linear = to_linear();
+    // skins use few distinct colors, so convert each one once
+    let mut cache = std::collections::HashMap::new();
+    for pixel in image.pixels_mut() {
+        let [r, g, b, a] = pixel.0;
+        let [r, g, b] = *cache.entry([r, g, b]).or_insert_with(|| {
+            let rgb = [linear[r as usize], linear[g as usize], linear[b as usize]];
+            matrix.map(|row| to_srgb(row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2]))
+        });
+        pixel.0 = [r, g, b, a];
+    }
+}
@@ -0,0 +1,287 @@
+//! Incremental re-rendering for interactive viewers
+//!
+//! Keeps the previous frame and the sprites it was drawn from, and on each new frame only redraws the areas
+//! where a sprite moved, changed image or appeared/disappeared.
+
+use std::collections::HashMap;
+
+use image::{imageops, ImageBuffer};
+
+use crate::error::Result;
+use crate::render::background::Background;
+use crate::render::layout::Layout;
+use crate::render::target::blend_over;
+use crate::sprites::{SpriteImage, SpriteWindowManager, WindowImage, WindowType};
+
+/// A changed area of the frame
+#[derive(Debug, Clone)]
+pub struct DirtyRect {
+    /// X position of the left edge
+    pub x: u32,
+    /// Y position of the top edge
+    pub y: u32,
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// New pixels of the area
+    pub pixels: WindowImage,
+}
+
+/// A sprite as it was placed in a frame
+#[derive(Debug, Clone, PartialEq)]
+struct PlacedSprite {
+    x: u32,
+    y: u32,
+    image: SpriteImage,
+}
+
+/// A rectangle in frame coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rect {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Rect {
+    fn intersects(&self, other: &Rect) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+
+    fn union(&self, other: &Rect) -> Rect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Rect {
+            x,
+            y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y,
+        }
+    }
+
+    fn intersection(&self, other: &Rect) -> Option<Rect> {
+        if !self.intersects(other) {
+            return None;
+        }
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        Some(Rect {
+            x,
+            y,
+            width: (self.x + self.width).min(other.x + other.width) - x,
+            height: (self.y + self.height).min(other.y + other.height) - y,
+        })
+    }
+}
+
+/// Renderer that tracks the previous frame and returns only the areas that changed
+#[derive(Debug, Clone)]
+pub struct IncrementalRenderer {
+    frame: WindowImage,
+    layout: Layout,
+    background: Background,
+    placed: HashMap<String, PlacedSprite>,
+}
+
+impl IncrementalRenderer {
+    /// Creates a renderer with no previous frame, so its first render redraws everything
+    ///
+    /// # Returns
+    ///
+    /// A new IncrementalRenderer
+    pub fn new() -> Self {
+        Self {
+            frame: ImageBuffer::new(0, 0),
+            layout: Layout::new(),
+            background: Background::new(),
+            placed: HashMap::new(),
+        }
+    }
+
+    /// Returns the most recently rendered frame
+    pub fn frame(&self) -> &WindowImage {
+        &self.frame
+    }
+
+    /// Forgets the previous frame so the next render redraws everything
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Renders a new frame, redrawing only what changed since the previous one
+    ///
+    /// The first frame, and any frame where the layout, window sizes or background colors changed,
+    /// is returned as a single rectangle covering the whole frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `window_defs` - Window manager describing the new frame
+    /// * `sprites` - A map of sprite names to sprite images
+    ///
+    /// # Returns
+    ///
+    /// The changed areas and their new pixels, or an empty list if nothing changed
+    pub fn render(
+        &mut self,
+        window_defs: &SpriteWindowManager,
+        sprites: &HashMap<String, SpriteImage>,
+    ) -> Result<Vec<DirtyRect>> {
+        let (width, height) = window_defs.layout_size();
+
+        let mut placed = HashMap::new();
+        for placement in &window_defs.layout().windows {
+            for (sprite_def, sprite) in window_defs.window_sprites(placement.window_type, sprites) {
+                placed.insert(
+                    sprite_def.name.clone(),
+                    PlacedSprite {
+                        x: placement.x + sprite_def.x,
+                        y: placement.y + sprite_def.y,
+                        image: sprite.clone(),
+                    },
+                );
+            }
+        }
+
+        if self.frame.dimensions() != (width, height)
+            || self.layout != *window_defs.layout()
+            || self.background != *window_defs.background()
+        {
+            self.frame = window_defs.draw_all_sprites(sprites)?;
+            self.layout = window_defs.layout().clone();
+            self.background = window_defs.background().clone();
+            self.placed = placed;
+            return Ok(vec![DirtyRect {
+                x: 0,
+                y: 0,
+                width,
+                height,
+                pixels: self.frame.clone(),
+            }]);
+        }
+
+        let mut dirty: Vec<Rect> = Vec::new();
+        for (name, sprite) in &placed {
+            match self.placed.get(name) {
+                Some(previous) if previous == sprite => {}
+                Some(previous) => {
+                    dirty.push(Self::sprite_rect(previous));
+                    dirty.push(Self::sprite_rect(sprite));
+                }
+                None => dirty.push(Self::sprite_rect(sprite)),
+            }
+        }
+        for (name, previous) in &self.placed {
+            if !placed.contains_key(name) {
+                dirty.push(Self::sprite_rect(previous));
+            }
+        }
+        let dirty = Self::merge_rects(dirty);
+
+        for rect in &dirty {
+            self.redraw(window_defs, sprites, rect);
+        }
+        self.placed = placed;
+
+        Ok(dirty
+            .into_iter()
+            .map(|rect| DirtyRect {
+                x: rect.x,
+                y: rect.y,
+                width: rect.width,
+                height: rect.height,
+                pixels: imageops::crop_imm(&self.frame, rect.x, rect.y, rect.width, rect.height).to_image(),
+            })
+            .collect())
+    }
+
+    /// Returns the area a window covers in the frame, if it fits in the frame
+    fn window_rect(&self, window_defs: &SpriteWindowManager, window_type: WindowType) -> Option<Rect> {
+        let (x, y) = window_defs.layout().position(window_type)?;
+        let (width, height) = window_defs.window_size(window_type);
+        if x + width > self.frame.width() || y + height > self.frame.height() {
+            return None;
+        }
+        Some(Rect { x, y, width, height })
+    }
+
+    fn sprite_rect(sprite: &PlacedSprite) -> Rect {
+        Rect {
+            x: sprite.x,
+            y: sprite.y,
+            width: sprite.image.width(),
+            height: sprite.image.height(),
+        }
+    }
+
+    /// Merges overlapping rectangles until none overlap
+    fn merge_rects(mut rects: Vec<Rect>) -> Vec<Rect> {
+        rects.retain(|rect| rect.width > 0 && rect.height > 0);
+        let mut merged = true;
+        while merged {
+            merged = false;
+            'outer: for i in 0..rects.len() {
+                for j in i + 1..rects.len() {
+                    if rects[i].intersects(&rects[j]) {
+                        rects[i] = rects[i].union(&rects[j]);
+                        rects.remove(j);
+                        merged = true;
+                        break 'outer;
+                    }
+                }
+            }
+        }
+        rects.sort_by_key(|rect| (rect.y, rect.x));
+        rects
+    }
+
+    /// Redraws one area of the frame from scratch, drawing only the parts of sprites inside it
+    fn redraw(&mut self, window_defs: &SpriteWindowManager, sprites: &HashMap<String, SpriteImage>, rect: &Rect) {
+        for placement in &window_defs.layout().windows {
+            let Some(window_rect) = self.window_rect(window_defs, placement.window_type) else {
+                continue;
+            };
+            let Some(area) = window_rect.intersection(rect) else {
+                continue;
+            };
+            for y in area.y..area.y + area.height {
+                for x in area.x..area.x + area.width {
+                    let color = self
+                        .background
+                        .color_at(placement.window_type, x - placement.x, y - placement.y);
+                    self.frame.put_pixel(x, y, color);
+                }
+            }
+            for (sprite_def, sprite) in window_defs.window_sprites(placement.window_type, sprites) {
+                let sprite_rect = Rect {
+                    x: placement.x + sprite_def.x,
+                    y: placement.y + sprite_def.y,
+                    width: sprite.width(),
+                    height: sprite.height(),
+                };
+                let Some(visible) = sprite_rect.intersection(&area) else {
+                    continue;
+                };
+                for y in visible.y..visible.y + visible.height {
+                    for x in visible.x..visible.x + visible.width {
+                        if x < self.frame.width() && y < self.frame.height() {
+                            let pixel = *sprite.get_pixel(x - sprite_rect.x, y - sprite_rect.y);
+                            let below = *self.frame.get_pixel(x, y);
+                            self.frame.put_pixel(x, y, blend_over(below, pixel));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for IncrementalRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,71 @@
+//! JSON export of the windows and elements of a composite
+//!
+//! Lets HTML or canvas front-ends place clickable overlays on top of a rendered screenshot. Rectangles are in
+//! screenshot pixels, so they line up with a render made with the same options.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::sprites::{SpriteImage, SpriteWindowManager};
+use crate::util::{json_array, json_string};
+
+/// Describes the windows and elements of a composite as JSON
+///
+/// The document has the composite's `width` and `height`, a `windows` array with each window's `window` type and
+/// rectangle, and an `elements` array with each drawn window sprite's `name`, `sprite`, `window`, `layer` and
+/// rectangle. Elements are listed in draw order, so later elements are on top.
+///
+/// # Arguments
+///
+/// * `window_defs` - Window manager with the layout and sprites to describe
+/// * `sprites` - A map of sprite names to sprite images. Elements with no image are left out, as they are not drawn.
+/// * `scale` - Scale factor the screenshot was rendered at. 0 and 1 keep the skin's pixel size.
+///
+/// # Returns
+///
+/// The JSON document
+pub fn element_bounds_json(
+    window_defs: &SpriteWindowManager,
+    sprites: &HashMap<String, SpriteImage>,
+    scale: u32,
+) -> String {
+    let scale = scale.max(1);
+    let (width, height) = window_defs.layout_size();
+
+    let mut windows = Vec::new();
+    let mut elements = Vec::new();
+    for placement in &window_defs.layout().windows {
+        let window = json_string(&format!("{:?}", placement.window_type));
+        let (window_width, window_height) = window_defs.window_size(placement.window_type);
+        windows.push(format!(
+            r#"    {{"window": {}, "x": {}, "y": {}, "width": {}, "height": {}}}"#,
+            window,
+            placement.x * scale,
+            placement.y * scale,
+            window_width * scale,
+            window_height * scale
+        ));
+        for (sprite_def, _) in window_defs.window_sprites(placement.window_type, sprites) {
+            elements.push(format!(
+                r#"    {{"name": {}, "sprite": {}, "window": {}, "layer": {}, "x": {}, "y": {}, "width": {}, "height": {}}}"#,
+                json_string(&sprite_def.name),
+                json_string(&sprite_def.sprite_name),
+                window,
+                sprite_def.layer,
+                (placement.x + sprite_def.x) * scale,
+                (placement.y + sprite_def.y) * scale,
+                sprite_def.width * scale,
+                sprite_def.height * scale
+            ));
+        }
+    }
+
+    let mut json = String::new();
+    let _ = writeln!(json, "{{");
+    let _ = writeln!(json, r#"  "width": {},"#, width * scale);
+    let _ = writeln!(json, r#"  "height": {},"#, height * scale);
+    let _ = writeln!(json, r#"  "windows": {},"#, json_array(&windows));
+    let _ = writeln!(json, r#"  "elements": {}"#, json_array(&elements));
+    let _ = writeln!(json, "}}");
+    json
+}
@@ -0,0 +1,182 @@
+//! Placement of windows in a composite screenshot
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::WszError;
+use crate::sprites::WindowType;
+
+/// Position of a window in a composite screenshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowPlacement {
+    /// Window to draw
+    pub window_type: WindowType,
+    /// X position of the window's left edge
+    pub x: u32,
+    /// Y position of the window's top edge
+    pub y: u32,
+}
+
+/// Named window arrangements commonly used for skin galleries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LayoutPreset {
+    /// Main window, equalizer below it and the playlist below that
+    #[default]
+    Classic,
+    /// Main window with the equalizer to its right
+    SideBySide,
+    /// Main window only
+    MainOnly,
+    /// Main window, equalizer and playlist in shade mode, stacked
+    ShadeStack,
+}
+
+impl LayoutPreset {
+    /// All presets, in the order they are listed to users
+    pub const ALL: [LayoutPreset; 4] = [
+        LayoutPreset::Classic,
+        LayoutPreset::SideBySide,
+        LayoutPreset::MainOnly,
+        LayoutPreset::ShadeStack,
+    ];
+
+    /// Returns the name of the preset, as accepted by `from_str`
+    pub fn name(&self) -> &'static str {
+        match self {
+            LayoutPreset::Classic => "classic",
+            LayoutPreset::SideBySide => "side-by-side",
+            LayoutPreset::MainOnly => "main-only",
+            LayoutPreset::ShadeStack => "shade-stack",
+        }
+    }
+}
+
+impl fmt::Display for LayoutPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for LayoutPreset {
+    type Err = WszError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| WszError::ArgumentError(format!("Unknown layout preset: '{}'", s)))
+    }
+}
+
+/// Which windows appear in a composite screenshot and where.
+///
+/// Windows are drawn in order, so later windows are stacked on top of earlier ones where they overlap.
+/// The composite is sized to fit every placed window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    /// Windows to draw, bottom-most first
+    pub windows: Vec<WindowPlacement>,
+}
+
+impl Layout {
+    /// Creates an empty layout with no windows
+    pub fn new() -> Self {
+        Self { windows: Vec::new() }
+    }
+
+    /// The classic layout: main window, equalizer below it and the playlist below that
+    pub fn classic() -> Self {
+        Self::new()
+            .with_window(WindowType::Main, 0, 0)
+            .with_window(WindowType::Equalizer, 0, 116)
+            .with_window(WindowType::Playlist, 0, 232)
+    }
+
+    /// Creates the layout of a named preset
+    ///
+    /// # Arguments
+    ///
+    /// * `preset` - Preset to arrange the windows like
+    pub fn preset(preset: LayoutPreset) -> Self {
+        match preset {
+            LayoutPreset::Classic => Self::classic(),
+            LayoutPreset::SideBySide => {
+                Self::new()
+                    .with_window(WindowType::Main, 0, 0)
+                    .with_window(WindowType::Equalizer, 275, 0)
+            }
+            LayoutPreset::MainOnly => Self::new().with_window(WindowType::Main, 0, 0),
+            LayoutPreset::ShadeStack => Self::new()
+                .with_window(WindowType::MainShade, 0, 0)
+                .with_window(WindowType::EqualizerShade, 0, 14)
+                .with_window(WindowType::PlaylistShade, 0, 28),
+        }
+    }
+
+    /// Places a window, replacing any earlier placement of the same window
+    ///
+    /// # Arguments
+    ///
+    /// * `window_type` - Window to place
+    /// * `x` - X position of the window's left edge
+    /// * `y` - Y position of the window's top edge
+    ///
+    /// # Returns
+    ///
+    /// The layout with the window placed on top of the others
+    pub fn with_window(mut self, window_type: WindowType, x: u32, y: u32) -> Self {
+        self.windows.retain(|w| w.window_type != window_type);
+        self.windows.push(WindowPlacement { window_type, x, y });
+        self
+    }
+
+    /// Removes a window from the layout
+    ///
+    /// # Arguments
+    ///
+    /// * `window_type` - Window to hide
+    ///
+    /// # Returns
+    ///
+    /// The layout without the window
+    pub fn without_window(mut self, window_type: WindowType) -> Self {
+        self.windows.retain(|w| w.window_type != window_type);
+        self
+    }
+
+    /// Moves all windows up and left so the topmost and leftmost window edges are at 0,
+    /// removing the empty space above and to the left of the windows
+    ///
+    /// # Returns
+    ///
+    /// The layout with the same relative window positions
+    pub fn cropped(mut self) -> Self {
+        let min_x = self.windows.iter().map(|w| w.x).min().unwrap_or(0);
+        let min_y = self.windows.iter().map(|w| w.y).min().unwrap_or(0);
+        for window in &mut self.windows {
+            window.x -= min_x;
+            window.y -= min_y;
+        }
+        self
+    }
+
+    /// Returns the position of a window, if it is part of the layout
+    pub fn position(&self, window_type: WindowType) -> Option<(u32, u32)> {
+        self.windows
+            .iter()
+            .find(|w| w.window_type == window_type)
+            .map(|w| (w.x, w.y))
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
+impl From<LayoutPreset> for Layout {
+    fn from(preset: LayoutPreset) -> Self {
+        Self::preset(preset)
+    }
+}
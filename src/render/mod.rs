@@ -0,0 +1,504 @@
+//! Options for rendering screenshots of a skin
+
+#[cfg(feature = "animation")]
+pub mod animation;
+pub mod ansi;
+pub mod background;
+pub mod color_vision;
+pub mod incremental;
+pub mod json;
+pub mod layout;
+pub mod montage;
+pub mod ora;
+pub mod svg;
+pub mod swatches;
+pub mod target;
+pub mod text;
+pub mod thumbnail;
+pub mod vis;
+
+use std::collections::HashMap;
+
+use image::imageops::{self, FilterType};
+use image::{Rgb, Rgba};
+
+use crate::error::{Result, WszError};
+use crate::sprites::{SpriteImage, SpriteWindowDefinition, SpriteWindowManager, WindowImage, WindowType};
+use crate::text::pledit::PleditSettings;
+use crate::text::viscolor::VisColors;
+use background::{Background, BackgroundFill};
+use layout::Layout;
+
+/// Position of the title marquee in the main window
+const MARQUEE_X: u32 = 111;
+const MARQUEE_Y: u32 = 27;
+/// Width of the title marquee in the main window, e.g. for `TEXT_METRICS.ellipsize`
+pub const MARQUEE_WIDTH: u32 = 154;
+/// Position of the spectrum analyzer in the main window
+const SPECTRUM_X: u32 = 24;
+const SPECTRUM_Y: u32 = 43;
+/// Position of the work indicator in the main window
+const WORK_INDICATOR_X: u32 = 24;
+const WORK_INDICATOR_Y: u32 = 28;
+/// Left edges of the four time display digits (minutes, minutes, seconds, seconds) in the main window
+const TIME_DIGIT_XS: [u32; 4] = [48, 60, 78, 90];
+const TIME_DIGIT_Y: u32 = 26;
+const TIME_DIGIT_WIDTH: u32 = 9;
+const TIME_DIGIT_HEIGHT: u32 = 13;
+/// Position of the bitrate display in the main window
+const KBPS_X: u32 = 111;
+const KBPS_Y: u32 = 43;
+/// Position of the sample rate display in the main window
+const KHZ_X: u32 = 156;
+const KHZ_Y: u32 = 43;
+/// Position and width of the volume slider in the main window
+const VOLUME_X: u32 = 107;
+const VOLUME_WIDTH: u32 = 68;
+/// Position and width of the balance slider in the main window
+const BALANCE_X: u32 = 177;
+const BALANCE_WIDTH: u32 = 38;
+/// Vertical position and width of the volume and balance slider thumbs
+const SLIDER_THUMB_Y: u32 = 58;
+const SLIDER_THUMB_WIDTH: u32 = 14;
+/// Index of the last background of the volume and balance sliders, which is drawn at full volume or full pan
+const SLIDER_LAST_BACKGROUND: u32 = 27;
+
+/// Menus on the bottom bar of the playlist window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaylistMenu {
+    /// ADD menu (url, dir, file)
+    Add,
+    /// REM menu (all, crop, selected, misc)
+    Remove,
+    /// SEL menu (invert, zero, all)
+    Select,
+    /// MISC menu (sort, info, options)
+    Misc,
+    /// LIST OPTS menu (new, save, load)
+    List,
+}
+
+/// Transport buttons at the bottom of the main window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransportButton {
+    /// Previous track
+    Previous,
+    /// Play
+    Play,
+    /// Pause
+    Pause,
+    /// Stop
+    Stop,
+    /// Next track
+    Next,
+    /// Eject (open file)
+    Eject,
+}
+
+/// State of a clutterbar button
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClutterState {
+    /// Not enabled
+    #[default]
+    Off,
+    /// Enabled (e.g. always on top is turned on)
+    On,
+    /// Held down by the mouse
+    Pressed,
+}
+
+/// States of the O/A/I/D/V clutterbar buttons on the left of the main window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Clutterbar {
+    /// O: options menu
+    pub options: ClutterState,
+    /// A: always on top
+    pub always_on_top: ClutterState,
+    /// I: file info
+    pub file_info: ClutterState,
+    /// D: double size
+    pub double_size: ClutterState,
+    /// V: visualization menu
+    pub visualization: ClutterState,
+}
+
+/// State of a toggle button in the equalizer window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EqToggle {
+    /// Lit, i.e. the option is turned on
+    pub lit: bool,
+    /// Held down by the mouse
+    pub pressed: bool,
+}
+
+/// States of the ON and AUTO buttons of the equalizer window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EqButtons {
+    /// ON: equalizer enabled
+    pub on: EqToggle,
+    /// AUTO: load presets automatically
+    pub auto: EqToggle,
+}
+
+impl EqButtons {
+    fn new() -> Self {
+        Self {
+            on: EqToggle {
+                lit: true,
+                pressed: false,
+            },
+            auto: EqToggle::default(),
+        }
+    }
+}
+
+impl Default for EqButtons {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Playback status shown by the indicator next to the time display
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlaybackStatus {
+    /// Stopped (square indicator)
+    #[default]
+    Stopped,
+    /// Playing (triangle indicator, with the work indicator next to it)
+    Playing,
+    /// Paused (two bars indicator)
+    Paused,
+}
+
+/// Channel mode shown by the mono/stereo indicator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channels {
+    /// Mono lit
+    Mono,
+    /// Stereo lit
+    Stereo,
+}
+
+/// What the player is doing, as shown in the main window
+#[derive(Debug, Clone)]
+pub struct PlayerState {
+    /// Playback status
+    pub status: PlaybackStatus,
+    /// Title shown in the marquee. Nothing is drawn when empty.
+    pub title: String,
+    /// How many characters the marquee has scrolled. Only used when the title is too long to fit.
+    pub marquee_offset: usize,
+    /// State of the work indicator (lit when true). When None, the indicator is drawn unlit while playing
+    /// and left undrawn otherwise, like Winamp does.
+    pub working: Option<bool>,
+    /// Heights (0-16) of the 19 spectrum analyzer bars, or None to leave the visualizer undrawn
+    pub spectrum: Option<Vec<u8>>,
+    /// Elapsed time in seconds shown in the time display, or None to leave it blank. Shown as MM:SS up to 99:59.
+    pub time: Option<u32>,
+    /// Bitrate in kbps, or None to leave it blank
+    pub kbps: Option<u32>,
+    /// Sample rate in kHz, or None to leave it blank
+    pub khz: Option<u32>,
+    /// Lit mono/stereo indicator, or None for neither
+    pub channels: Option<Channels>,
+    /// Volume from 0 to 100, setting the volume slider's thumb and background. None leaves the slider as drawn by
+    /// default, at about 78%.
+    pub volume: Option<u8>,
+    /// Balance from -100 (left) to 100 (right), setting the balance slider's thumb and background. None leaves the
+    /// slider centered.
+    pub balance: Option<i8>,
+}
+
+impl PlayerState {
+    fn new() -> Self {
+        Self {
+            status: PlaybackStatus::Stopped,
+            title: String::new(),
+            marquee_offset: 0,
+            working: None,
+            spectrum: None,
+            time: None,
+            kbps: None,
+            khz: None,
+            channels: None,
+            volume: None,
+            balance: None,
+        }
+    }
+
+    /// The classic Winamp 2 demo: DJ Mike Llama's intro playing at 0:04, like the skin previews everyone knows
+    pub fn demo() -> Self {
+        Self {
+            status: PlaybackStatus::Playing,
+            title: "1. DJ Mike Llama - Llama Whippin' Intro".to_string(),
+            time: Some(4),
+            kbps: Some(56),
+            khz: Some(22),
+            channels: Some(Channels::Stereo),
+            ..Self::new()
+        }
+    }
+}
+
+impl Default for PlayerState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Options controlling what state the skin is rendered in
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Position of the playlist scrollbar thumb, from 0.0 (top) to 1.0 (bottom)
+    pub playlist_scroll: f32,
+    /// Playlist menu to draw opened, if any
+    pub playlist_menu: Option<PlaylistMenu>,
+    /// Size of the playlist window, if it should be resized from the default 275x203.
+    /// Rounded down to the nearest size Winamp allows.
+    pub playlist_size: Option<(u32, u32)>,
+    /// Size of the minibrowser window, if it should be resized from the default 275x116.
+    /// Rounded down to the nearest size Winamp allows. The minibrowser is only drawn if it is in the layout.
+    pub minibrowser_size: Option<(u32, u32)>,
+    /// Integer scale factor of the output. Scaling uses nearest-neighbor sampling so the pixel art stays crisp.
+    pub scale: u32,
+    /// Contents of the main window displays
+    pub player: PlayerState,
+    /// Which windows to draw and where. Use `LayoutPreset::*.into()` for a common arrangement.
+    pub layout: Layout,
+    /// Crop the output to the windows in the layout, dropping empty space above and to the left of them.
+    /// The output is always sized to fit the windows on the right and bottom.
+    pub auto_crop: bool,
+    /// States of the clutterbar buttons
+    pub clutterbar: Clutterbar,
+    /// Transport buttons to draw in their pressed state
+    pub pressed_buttons: Vec<TransportButton>,
+    /// States of the equalizer window's ON and AUTO buttons. By default ON is lit and AUTO is not.
+    pub eq_buttons: EqButtons,
+    /// Colors drawn behind the sprites. If not set, the canvas is transparent, the visualizer area uses the
+    /// viscolor.txt background, the playlist uses the pledit.txt background and other windows are transparent.
+    pub background: Option<background::Background>,
+    /// Color vision deficiency to simulate in the output, if any
+    pub color_vision: Option<color_vision::ColorVisionDeficiency>,
+}
+
+impl RenderOptions {
+    fn new() -> Self {
+        Self {
+            playlist_scroll: 0.0,
+            playlist_menu: None,
+            playlist_size: None,
+            minibrowser_size: None,
+            scale: 1,
+            player: PlayerState::default(),
+            layout: Layout::classic(),
+            auto_crop: false,
+            clutterbar: Clutterbar::default(),
+            pressed_buttons: Vec::new(),
+            eq_buttons: EqButtons::default(),
+            background: None,
+            color_vision: None,
+        }
+    }
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A window, or a single layer of a window, rendered separately from the rest of the layout
+#[derive(Debug, Clone)]
+pub struct RenderedLayer {
+    /// Window the image belongs to
+    pub window_type: WindowType,
+    /// Layer index, or None if all of the window's layers are flattened into the image
+    pub layer: Option<u32>,
+    /// Horizontal offset of the window within the layout
+    pub x: u32,
+    /// Vertical offset of the window within the layout
+    pub y: u32,
+    /// Image of the window or layer. Layers above the first have a transparent background.
+    pub image: WindowImage,
+}
+
+/// Most pixels a scaled image may have, 1 GiB of RGBA, so a large scale factor cannot exhaust memory
+pub const MAX_SCALED_PIXELS: u64 = 16384 * 16384;
+
+/// Scales an image by an integer factor using nearest-neighbor sampling
+///
+/// # Arguments
+///
+/// * `image` - Image to scale
+/// * `scale` - Scale factor. 0 and 1 leave the image untouched.
+///
+/// # Returns
+///
+/// The scaled image, or an error if it would have more than `MAX_SCALED_PIXELS` pixels
+pub fn scale_image(image: WindowImage, scale: u32) -> Result<WindowImage> {
+    if scale <= 1 {
+        return Ok(image);
+    }
+    let too_large = || {
+        WszError::ArgumentError(format!(
+            "{}x{} image scaled by {} is too large",
+            image.width(),
+            image.height(),
+            scale
+        ))
+    };
+    let width = image.width().checked_mul(scale).ok_or_else(too_large)?;
+    let height = image.height().checked_mul(scale).ok_or_else(too_large)?;
+    if width as u64 * height as u64 > MAX_SCALED_PIXELS {
+        return Err(too_large());
+    }
+    Ok(imageops::resize(&image, width, height, FilterType::Nearest))
+}
+
+/// Returns the skin's own background colors: the viscolor.txt background behind the visualizer, the pledit.txt
+/// background (or Winamp's default) behind the playlist, and transparent everywhere else
+pub(crate) fn skin_background(vis_colors: &VisColors, pledit: &PleditSettings) -> Background {
+    let opaque = |color: Rgb<u8>| Rgba([color.0[0], color.0[1], color.0[2], 255]);
+    Background::new()
+        .with_window_color(WindowType::Playlist, opaque(pledit.effective_normal_bg()))
+        .with_fill(BackgroundFill {
+            window_type: WindowType::Main,
+            x: SPECTRUM_X,
+            y: SPECTRUM_Y,
+            width: vis::SPECTRUM_WIDTH,
+            height: vis::SPECTRUM_HEIGHT,
+            color: opaque(vis_colors.bg_color().unwrap_or(Rgb([0, 0, 0]))),
+        })
+}
+
+/// Adds the sprites generated from the player state (marquee text, visualizer, etc.) to the window manager
+pub(crate) fn add_player_sprites(
+    window_defs: &mut SpriteWindowManager,
+    sprites: &HashMap<String, SpriteImage>,
+    vis_colors: &VisColors,
+    player: &PlayerState,
+) {
+    if !player.title.is_empty() {
+        let marquee = text::render_marquee(sprites, &player.title, player.marquee_offset, MARQUEE_WIDTH);
+        add_generated_window_sprite(window_defs, "MAIN_MARQUEE", marquee, MARQUEE_X, MARQUEE_Y);
+    }
+
+    if let Some(bars) = &player.spectrum {
+        let spectrum = vis::render_spectrum(vis_colors, bars);
+        add_generated_window_sprite(window_defs, "MAIN_SPECTRUM", spectrum, SPECTRUM_X, SPECTRUM_Y);
+    }
+
+    if let Some(time) = player.time {
+        // Winamp uses NUMS_EX.BMP when the skin has it
+        let suffix = if sprites.contains_key("DIGIT_0_EX") { "_EX" } else { "" };
+        let minutes = (time / 60).min(99);
+        let seconds = if time / 60 > 99 { 59 } else { time % 60 };
+        let digits = [minutes / 10, minutes % 10, seconds / 10, seconds % 10];
+        for (i, (digit, x)) in digits.iter().zip(TIME_DIGIT_XS).enumerate() {
+            let name = format!("MAIN_TIME_DIGIT_{}", i);
+            window_defs.add_window_sprite(
+                &name,
+                &SpriteWindowDefinition {
+                    name: name.clone(),
+                    sprite_name: format!("DIGIT_{}{}", digit, suffix),
+                    window_type: WindowType::Main,
+                    layer: 2,
+                    x,
+                    y: TIME_DIGIT_Y,
+                    width: TIME_DIGIT_WIDTH,
+                    height: TIME_DIGIT_HEIGHT,
+                },
+            );
+        }
+    }
+
+    if let Some(kbps) = player.kbps {
+        let kbps = text::render_text(sprites, &format!("{:>3}", kbps.min(999)));
+        add_generated_window_sprite(window_defs, "MAIN_KBPS", kbps, KBPS_X, KBPS_Y);
+    }
+
+    if let Some(khz) = player.khz {
+        let khz = text::render_text(sprites, &format!("{:>2}", khz.min(99)));
+        add_generated_window_sprite(window_defs, "MAIN_KHZ", khz, KHZ_X, KHZ_Y);
+    }
+
+    match player.channels {
+        Some(Channels::Mono) => window_defs.set_sprite_name("MAIN_MONO", "MAIN_MONO_ACTIVE"),
+        Some(Channels::Stereo) => window_defs.set_sprite_name("MAIN_STEREO", "MAIN_STEREO_ACTIVE"),
+        None => {}
+    }
+
+    if let Some(volume) = player.volume {
+        let volume = volume.min(100) as u32;
+        let background = (volume * SLIDER_LAST_BACKGROUND + 50) / 100;
+        window_defs.set_sprite_name(
+            "MAIN_VOLUME_BACKGROUND",
+            &format!("MAIN_VOLUME_BACKGROUND_{}", background),
+        );
+        let thumb_x = VOLUME_X + volume * (VOLUME_WIDTH - SLIDER_THUMB_WIDTH) / 100;
+        window_defs.set_sprite_position("MAIN_VOLUME_THUMB", thumb_x, SLIDER_THUMB_Y);
+    }
+
+    if let Some(balance) = player.balance {
+        let balance = balance.clamp(-100, 100) as i32;
+        // the background only shows how far the balance is from the center, not which side
+        let background = (balance.unsigned_abs() * SLIDER_LAST_BACKGROUND + 50) / 100;
+        window_defs.set_sprite_name(
+            "MAIN_BALANCE_BACKGROUND",
+            &format!("MAIN_BALANCE_BACKGROUND_{}", background),
+        );
+        let travel = (BALANCE_WIDTH - SLIDER_THUMB_WIDTH) as i32;
+        let thumb_x = BALANCE_X as i32 + (balance + 100) * travel / 200;
+        window_defs.set_sprite_position("MAIN_BALANCE_THUMB", thumb_x as u32, SLIDER_THUMB_Y);
+    }
+
+    window_defs.set_sprite_name(
+        "MAIN_STOPPED_INDICATOR",
+        match player.status {
+            PlaybackStatus::Stopped => "MAIN_STOPPED_INDICATOR",
+            PlaybackStatus::Playing => "MAIN_PLAYING_INDICATOR",
+            PlaybackStatus::Paused => "MAIN_PAUSED_INDICATOR",
+        },
+    );
+
+    let working = match player.status {
+        PlaybackStatus::Playing => Some(player.working.unwrap_or(false)),
+        _ => player.working,
+    };
+    if let Some(working) = working {
+        let sprite_name = if working {
+            "MAIN_WORKING_INDICATOR"
+        } else {
+            "MAIN_NOT_WORKING_INDICATOR"
+        };
+        window_defs.add_window_sprite(
+            "MAIN_WORK_INDICATOR",
+            &SpriteWindowDefinition {
+                name: "MAIN_WORK_INDICATOR".to_string(),
+                sprite_name: sprite_name.to_string(),
+                window_type: WindowType::Main,
+                layer: 2,
+                x: WORK_INDICATOR_X,
+                y: WORK_INDICATOR_Y,
+                width: 3,
+                height: 9,
+            },
+        );
+    }
+}
+
+/// Adds a generated image to the main window as a top-layer window sprite of the same name
+fn add_generated_window_sprite(window_defs: &mut SpriteWindowManager, name: &str, image: SpriteImage, x: u32, y: u32) {
+    let definition = SpriteWindowDefinition {
+        name: name.to_string(),
+        sprite_name: name.to_string(),
+        window_type: WindowType::Main,
+        layer: 2,
+        x,
+        y,
+        width: image.width(),
+        height: image.height(),
+    };
+    window_defs.set_generated_sprite(name, image);
+    window_defs.add_window_sprite(name, &definition);
+}
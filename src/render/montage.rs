@@ -0,0 +1,124 @@
+//! Overview sheets of many skins arranged in a grid
+//!
+//! Each skin is rendered as a thumbnail in its own tile, optionally with a label underneath drawn in the skin's
+//! own TEXT.BMP font.
+
+use std::path::Path;
+
+use image::{imageops, ImageBuffer, Rgba};
+
+use super::text::{render_text, TEXT_METRICS};
+use super::thumbnail::ThumbnailOptions;
+use crate::error::Result;
+use crate::sprites::WindowImage;
+use crate::Wsz;
+
+/// Options for rendering a montage
+#[derive(Debug, Clone)]
+pub struct MontageOptions {
+    /// Number of tiles per row
+    pub columns: u32,
+    /// Width of each skin's thumbnail
+    pub tile_width: u32,
+    /// Height of each skin's thumbnail, not counting the label
+    pub tile_height: u32,
+    /// Space around and between tiles in pixels
+    pub padding: u32,
+    /// Color of the sheet behind the tiles
+    pub background: Rgba<u8>,
+    /// Draw each skin's label under its thumbnail
+    pub labels: bool,
+    /// How each skin is rendered and fitted into its tile
+    pub thumbnail: ThumbnailOptions,
+}
+
+impl MontageOptions {
+    fn new() -> Self {
+        Self {
+            columns: 4,
+            tile_width: 138,
+            tile_height: 174,
+            padding: 8,
+            background: Rgba([32, 32, 32, 255]),
+            labels: true,
+            thumbnail: ThumbnailOptions::default(),
+        }
+    }
+}
+
+impl Default for MontageOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Renders skins into a grid, left to right and then top to bottom
+///
+/// # Arguments
+///
+/// * `skins` - Skins to render, each with the label to draw under it
+/// * `options` - Montage options
+///
+/// # Returns
+///
+/// The montage image. An empty list of skins gives an image of just the padding.
+pub fn render_montage(skins: &[(&Wsz, &str)], options: &MontageOptions) -> Result<WindowImage> {
+    let columns = options.columns.max(1);
+    let rows = (skins.len() as u32).div_ceil(columns);
+    let label_height = if options.labels {
+        TEXT_METRICS.char_height + options.padding
+    } else {
+        0
+    };
+    let cell_width = options.tile_width + options.padding;
+    let cell_height = options.tile_height + label_height + options.padding;
+
+    let mut montage = ImageBuffer::from_pixel(
+        columns.min(skins.len().max(1) as u32) * cell_width + options.padding,
+        rows * cell_height + options.padding,
+        options.background,
+    );
+
+    for (i, (wsz, label)) in skins.iter().enumerate() {
+        let x = options.padding + (i as u32 % columns) * cell_width;
+        let y = options.padding + (i as u32 / columns) * cell_height;
+
+        let thumbnail =
+            wsz.render_thumbnail_with_options(options.tile_width, options.tile_height, &options.thumbnail)?;
+        imageops::overlay(&mut montage, &thumbnail, x as i64, y as i64);
+
+        if options.labels {
+            let text = render_text(wsz.get_sprites(), &TEXT_METRICS.ellipsize(label, options.tile_width));
+            let text_x = x + (options.tile_width - text.width()) / 2;
+            let text_y = y + options.tile_height + options.padding / 2;
+            imageops::overlay(&mut montage, &text, text_x as i64, text_y as i64);
+        }
+    }
+
+    Ok(montage)
+}
+
+/// Loads skins from files and renders them into a grid, labelled with their file names
+///
+/// # Arguments
+///
+/// * `paths` - Paths to the Winamp skin files
+/// * `options` - Montage options
+///
+/// # Returns
+///
+/// The montage image, or the first error loading or rendering a skin
+pub fn render_montage_from_paths<P: AsRef<Path>>(paths: &[P], options: &MontageOptions) -> Result<WindowImage> {
+    let mut skins = Vec::with_capacity(paths.len());
+    for path in paths {
+        let path = path.as_ref();
+        let label = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        skins.push((Wsz::from_file_path(path)?, label));
+    }
+
+    let skins: Vec<(&Wsz, &str)> = skins.iter().map(|(wsz, label)| (wsz, label.as_str())).collect();
+    render_montage(&skins, options)
+}
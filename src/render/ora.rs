@@ -0,0 +1,132 @@
+//! OpenRaster (.ora) export of a rendered composite
+//!
+//! Each window becomes a group of layers, with one layer per window sprite above the window's background, so the
+//! composite can be opened in Krita, GIMP or MyPaint with its elements still separated.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{Cursor, Write};
+
+use image::imageops::FilterType;
+use image::{ImageBuffer, ImageFormat};
+
+use super::thumbnail::{make_thumbnail, ThumbnailFit};
+use crate::error::Result;
+use crate::sprites::{SpriteImage, SpriteWindowManager, WindowImage};
+
+/// Largest width or height of the thumbnail stored in the file, as required by the OpenRaster spec
+const THUMBNAIL_SIZE: u32 = 256;
+
+fn encode_png(image: &WindowImage) -> Result<Vec<u8>> {
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+/// Renders the composite as an OpenRaster document
+///
+/// # Arguments
+///
+/// * `window_defs` - Window manager with the layout and sprites to draw
+/// * `sprites` - A map of sprite names to sprite images
+///
+/// # Returns
+///
+/// The .ora file contents
+pub fn render_ora(window_defs: &SpriteWindowManager, sprites: &HashMap<String, SpriteImage>) -> Result<Vec<u8>> {
+    let (width, height) = window_defs.layout_size();
+    let background = window_defs.background();
+    let merged = window_defs.draw_all_sprites(sprites)?;
+
+    let mut data = Vec::new();
+    let mut zip = zip::ZipWriter::new(Cursor::new(&mut data));
+    let stored = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    let deflated = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    // The mimetype must be the first entry and uncompressed so the format can be sniffed
+    zip.start_file("mimetype", stored)?;
+    zip.write_all(b"image/openraster")?;
+
+    // Stacks list their children from top to bottom, the reverse of draw order. Layer positions are absolute.
+    let mut windows = Vec::new();
+    for placement in &window_defs.layout().windows {
+        let (window_width, window_height) = window_defs.window_size(placement.window_type);
+        let mut stack = String::new();
+        // Writing to a String cannot fail
+        let _ = writeln!(stack, r#"  <stack name="{:?}">"#, placement.window_type);
+        for (sprite_def, sprite) in window_defs
+            .window_sprites(placement.window_type, sprites)
+            .into_iter()
+            .rev()
+        {
+            let src = format!("data/{:?}/{}.png", placement.window_type, sprite_def.name);
+            let _ = writeln!(
+                stack,
+                r#"    <layer name="{}" src="{}" x="{}" y="{}"/>"#,
+                sprite_def.name,
+                src,
+                placement.x + sprite_def.x,
+                placement.y + sprite_def.y
+            );
+            zip.start_file(src, stored)?;
+            zip.write_all(&encode_png(sprite)?)?;
+        }
+
+        let src = format!("data/{:?}/background.png", placement.window_type);
+        let mut window_background = ImageBuffer::new(window_width, window_height);
+        background.draw(
+            &mut window_background,
+            placement.window_type,
+            0,
+            0,
+            window_width,
+            window_height,
+        );
+        let _ = writeln!(
+            stack,
+            r#"    <layer name="Background" src="{}" x="{}" y="{}"/>"#,
+            src, placement.x, placement.y
+        );
+        let _ = writeln!(stack, "  </stack>");
+        zip.start_file(src, stored)?;
+        zip.write_all(&encode_png(&window_background)?)?;
+        windows.push(stack);
+    }
+
+    let canvas = ImageBuffer::from_pixel(width, height, background.canvas);
+    zip.start_file("data/canvas.png", stored)?;
+    zip.write_all(&encode_png(&canvas)?)?;
+
+    let mut stack_xml = String::new();
+    let _ = writeln!(stack_xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(stack_xml, r#"<image version="0.0.3" w="{}" h="{}">"#, width, height);
+    let _ = writeln!(stack_xml, "<stack>");
+    for stack in windows.iter().rev() {
+        stack_xml.push_str(stack);
+    }
+    let _ = writeln!(
+        stack_xml,
+        r#"  <layer name="Canvas" src="data/canvas.png" x="0" y="0"/>"#
+    );
+    let _ = writeln!(stack_xml, "</stack>");
+    let _ = writeln!(stack_xml, "</image>");
+    zip.start_file("stack.xml", deflated)?;
+    zip.write_all(stack_xml.as_bytes())?;
+
+    zip.start_file("mergedimage.png", stored)?;
+    zip.write_all(&encode_png(&merged)?)?;
+
+    let scale = (THUMBNAIL_SIZE as f32 / width.max(height).max(1) as f32).min(1.0);
+    let thumbnail = make_thumbnail(
+        &merged,
+        ((width as f32 * scale) as u32).max(1),
+        ((height as f32 * scale) as u32).max(1),
+        ThumbnailFit::Stretch,
+        FilterType::Triangle,
+    );
+    zip.start_file("Thumbnails/thumbnail.png", stored)?;
+    zip.write_all(&encode_png(&thumbnail)?)?;
+
+    zip.finish()?;
+    Ok(data)
+}
@@ -0,0 +1,172 @@
+//! SVG export of a rendered skin
+//!
+//! Each sprite is embedded as a PNG `<image>` at its window position, so browsers can scale the preview
+//! without blurring the pixel art. Windows with a region in region.txt are clipped to it.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::io::Cursor;
+
+use image::{ImageFormat, Rgba};
+
+use crate::error::Result;
+use crate::sprites::{SpriteImage, SpriteWindowManager, WindowType};
+use crate::text::region::Regions;
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes bytes as standard padded base64
+fn base64_encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let bits = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Encodes an image as a PNG data URI
+fn png_data_uri(image: &SpriteImage) -> Result<String> {
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(format!("data:image/png;base64,{}", base64_encode(&png)))
+}
+
+/// Returns the id used for a window's clip path
+fn clip_path_id(window_type: WindowType) -> &'static str {
+    match window_type {
+        WindowType::Main => "region-main",
+        WindowType::Equalizer => "region-equalizer",
+        WindowType::Playlist => "region-playlist",
+        WindowType::General => "region-general",
+        WindowType::Minibrowser => "region-minibrowser",
+        WindowType::MainShade => "region-main-shade",
+        WindowType::EqualizerShade => "region-equalizer-shade",
+        WindowType::PlaylistShade => "region-playlist-shade",
+    }
+}
+
+/// Writes a filled rectangle, skipping fully transparent ones
+fn write_rect(svg: &mut String, x: u32, y: u32, width: u32, height: u32, color: Rgba<u8>) {
+    if color.0[3] == 0 {
+        return;
+    }
+    let _ = write!(
+        svg,
+        r##"    <rect x="{}" y="{}" width="{}" height="{}" fill="#{:02x}{:02x}{:02x}""##,
+        x, y, width, height, color.0[0], color.0[1], color.0[2]
+    );
+    if color.0[3] < 255 {
+        let _ = write!(svg, r#" fill-opacity="{:.3}""#, color.0[3] as f32 / 255.0);
+    }
+    svg.push_str("/>\n");
+}
+
+/// Renders the windows of a window manager as an SVG document
+///
+/// # Arguments
+///
+/// * `window_defs` - Window manager with the layout and sprites to draw
+/// * `sprites` - A map of sprite names to sprite images
+/// * `regions` - Transparent regions to clip windows to
+/// * `scale` - Scale factor of the document's displayed size. 0 and 1 keep the skin's pixel size.
+///
+/// # Returns
+///
+/// The SVG document
+pub fn render_svg(
+    window_defs: &SpriteWindowManager,
+    sprites: &HashMap<String, SpriteImage>,
+    regions: &Regions,
+    scale: u32,
+) -> Result<String> {
+    let (width, height) = window_defs.layout_size();
+    let scale = scale.max(1);
+    let background = window_defs.background();
+
+    let mut svg = String::new();
+    // Writing to a String cannot fail
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" shape-rendering="crispEdges">"#,
+        width * scale,
+        height * scale,
+        width,
+        height
+    );
+
+    let mut clip_paths = String::new();
+    for placement in &window_defs.layout().windows {
+        if let Some(polygons) = regions.for_window(placement.window_type) {
+            let _ = writeln!(
+                clip_paths,
+                r#"    <clipPath id="{}">"#,
+                clip_path_id(placement.window_type)
+            );
+            for polygon in polygons {
+                let points = polygon
+                    .iter()
+                    .map(|(x, y)| format!("{},{}", x, y))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let _ = writeln!(clip_paths, r#"      <polygon points="{}"/>"#, points);
+            }
+            let _ = writeln!(clip_paths, "    </clipPath>");
+        }
+    }
+    if !clip_paths.is_empty() {
+        let _ = writeln!(svg, "  <defs>\n{}  </defs>", clip_paths);
+    }
+
+    write_rect(&mut svg, 0, 0, width, height, background.canvas);
+    for placement in &window_defs.layout().windows {
+        let (window_width, window_height) = window_defs.window_size(placement.window_type);
+        let clip = match regions.for_window(placement.window_type) {
+            Some(_) => format!(r#" clip-path="url(#{})""#, clip_path_id(placement.window_type)),
+            None => String::new(),
+        };
+        let _ = writeln!(
+            svg,
+            r#"  <g id="{:?}" transform="translate({},{})"{}>"#,
+            placement.window_type, placement.x, placement.y, clip
+        );
+        write_rect(
+            &mut svg,
+            0,
+            0,
+            window_width,
+            window_height,
+            background.window_color(placement.window_type),
+        );
+        for fill in background
+            .fills
+            .iter()
+            .filter(|fill| fill.window_type == placement.window_type)
+        {
+            write_rect(&mut svg, fill.x, fill.y, fill.width, fill.height, fill.color);
+        }
+        for (sprite_def, sprite) in window_defs.window_sprites(placement.window_type, sprites) {
+            let _ = writeln!(
+                svg,
+                r#"    <image id="{}" x="{}" y="{}" width="{}" height="{}" style="image-rendering:pixelated" href="{}"/>"#,
+                sprite_def.name,
+                sprite_def.x,
+                sprite_def.y,
+                sprite.width(),
+                sprite.height(),
+                png_data_uri(sprite)?
+            );
+        }
+        let _ = writeln!(svg, "  </g>");
+    }
+    svg.push_str("</svg>\n");
+
+    Ok(svg)
+}
@@ -0,0 +1,232 @@
+//! Swatches of the colors a skin sets in viscolor.txt and pledit.txt
+//!
+//! Color schemes are easier to compare side by side than by reading the text files. Swatches are labeled with the
+//! skin's own TEXT.BMP font, so the chart is drawn entirely from the skin.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use image::{imageops, Rgb, Rgba};
+
+use crate::render::text::{render_text, TEXT_METRICS};
+use crate::sprites::{SpriteImage, WindowImage};
+use crate::text::pledit::PleditSettings;
+use crate::text::viscolor::{VisColors, VIS_COLOR_COUNT};
+use crate::util::{json_array, json_string};
+
+/// Space around the chart and between its columns
+const MARGIN: u32 = 6;
+/// Size of each color sample
+const SAMPLE_WIDTH: u32 = 24;
+const SAMPLE_HEIGHT: u32 = 8;
+/// Distance from the top of one swatch to the next
+const ROW_HEIGHT: u32 = 10;
+/// Gap between a sample and its label
+const LABEL_GAP: u32 = 4;
+/// Background of the chart when the font has no space character to take it from
+const DEFAULT_BACKGROUND: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+/// One color of a skin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Swatch {
+    /// File the color is set in, "viscolor.txt" or "pledit.txt"
+    pub file: &'static str,
+    /// Line number in viscolor.txt, from 0, or the key in pledit.txt, e.g. "NormalBG"
+    pub key: String,
+    /// What the color is used for, e.g. "spectrum 3"
+    pub name: String,
+    /// The color Winamp uses
+    pub color: Rgb<u8>,
+    /// Whether the skin does not set the color, so Winamp's default is used. An unset minibrowser color is the
+    /// matching playlist color.
+    pub is_default: bool,
+}
+
+/// Lists the visualization and playlist colors of a skin, in file order
+///
+/// # Arguments
+///
+/// * `vis_colors` - Visualization colors
+/// * `pledit` - Playlist editor settings
+///
+/// # Returns
+///
+/// The 24 viscolor.txt colors followed by the pledit.txt colors
+pub fn color_swatches(vis_colors: &VisColors, pledit: &PleditSettings) -> Vec<Swatch> {
+    let defaults = VisColors::winamp_default();
+    let mut swatches: Vec<Swatch> = (0..VIS_COLOR_COUNT)
+        .map(|index| {
+            let name = match index {
+                0 => "background".to_string(),
+                1 => "background dots".to_string(),
+                2..=17 => format!("spectrum {}", 17 - index),
+                18..=22 => format!("oscilloscope {}", index - 17),
+                _ => "peak dots".to_string(),
+            };
+            let color = vis_colors.get(index);
+            Swatch {
+                file: "viscolor.txt",
+                key: index.to_string(),
+                name,
+                color: color.or(defaults.get(index)).unwrap_or(Rgb([0, 0, 0])),
+                is_default: color.is_none(),
+            }
+        })
+        .collect();
+
+    // the minibrowser falls back to the playlist's colors
+    let pledit_colors = [
+        ("Normal", "playlist text", pledit.normal, pledit.effective_normal()),
+        (
+            "Current",
+            "current track text",
+            pledit.current,
+            pledit.effective_current(),
+        ),
+        (
+            "NormalBG",
+            "playlist background",
+            pledit.normal_bg,
+            pledit.effective_normal_bg(),
+        ),
+        (
+            "SelectedBG",
+            "selection background",
+            pledit.selected_bg,
+            pledit.effective_selected_bg(),
+        ),
+        ("mbFG", "minibrowser text", pledit.mb_fg, pledit.effective_normal()),
+        (
+            "mbBG",
+            "minibrowser background",
+            pledit.mb_bg,
+            pledit.effective_normal_bg(),
+        ),
+    ];
+    for (key, name, color, effective) in pledit_colors {
+        swatches.push(Swatch {
+            file: "pledit.txt",
+            key: key.to_string(),
+            name: name.to_string(),
+            color: color.unwrap_or(effective),
+            is_default: color.is_none(),
+        });
+    }
+    swatches
+}
+
+/// Formats a color as #rrggbb
+fn hex(color: Rgb<u8>) -> String {
+    let [r, g, b] = color.0;
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Draws a chart of swatches, with a column per file
+///
+/// Each swatch is labeled with its name and color in the skin's TEXT.BMP font, and colors the skin does not set
+/// are marked "default".
+///
+/// # Arguments
+///
+/// * `sprites` - A map of sprite names to sprite images, for the font
+/// * `swatches` - Swatches to draw, as `color_swatches` gives
+///
+/// # Returns
+///
+/// The chart
+pub fn render_swatches(sprites: &HashMap<String, SpriteImage>, swatches: &[Swatch]) -> WindowImage {
+    let mut columns: Vec<(&str, Vec<&Swatch>)> = Vec::new();
+    for swatch in swatches {
+        match columns.iter_mut().find(|(file, _)| *file == swatch.file) {
+            Some((_, column)) => column.push(swatch),
+            None => columns.push((swatch.file, vec![swatch])),
+        }
+    }
+
+    let label = |swatch: &Swatch| {
+        let default = if swatch.is_default { " default" } else { "" };
+        format!("{} {}{}", swatch.name, hex(swatch.color), default)
+    };
+    let widths: Vec<u32> = columns
+        .iter()
+        .map(|(file, column)| {
+            let labels = column.iter().map(|swatch| TEXT_METRICS.measure(&label(swatch)));
+            labels
+                .map(|width| SAMPLE_WIDTH + LABEL_GAP + width)
+                .chain([TEXT_METRICS.measure(file)])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let rows = columns.iter().map(|(_, column)| column.len()).max().unwrap_or(0) as u32;
+    let width = MARGIN + widths.iter().map(|width| width + MARGIN).sum::<u32>();
+    let height = MARGIN + ROW_HEIGHT * (rows + 1) + MARGIN;
+
+    // the font's space is the background its glyphs are drawn on
+    let background = sprites
+        .get("CHARACTER_32")
+        .and_then(|space| space.get_pixel_checked(0, 0).copied())
+        .unwrap_or(DEFAULT_BACKGROUND);
+    let mut chart = WindowImage::from_pixel(width, height, background);
+
+    let text_offset = (ROW_HEIGHT - TEXT_METRICS.char_height) as i64 / 2;
+    let mut x = MARGIN;
+    for ((file, column), column_width) in columns.iter().zip(widths) {
+        let heading = render_text(sprites, &file.to_uppercase());
+        imageops::replace(&mut chart, &heading, x as i64, (MARGIN as i64) + text_offset);
+        for (row, swatch) in column.iter().enumerate() {
+            let y = MARGIN + ROW_HEIGHT * (row as u32 + 1);
+            let [r, g, b] = swatch.color.0;
+            let sample = WindowImage::from_pixel(SAMPLE_WIDTH, SAMPLE_HEIGHT, Rgba([r, g, b, 255]));
+            imageops::replace(
+                &mut chart,
+                &sample,
+                x as i64,
+                (y + (ROW_HEIGHT - SAMPLE_HEIGHT) / 2) as i64,
+            );
+            let text = render_text(sprites, &label(swatch));
+            imageops::replace(
+                &mut chart,
+                &text,
+                (x + SAMPLE_WIDTH + LABEL_GAP) as i64,
+                y as i64 + text_offset,
+            );
+        }
+        x += column_width + MARGIN;
+    }
+    chart
+}
+
+/// Describes swatches as JSON
+///
+/// The document has a `swatches` array with each swatch's `file`, `key`, `name`, `color` as "#rrggbb" and whether
+/// it is Winamp's `default`.
+///
+/// # Arguments
+///
+/// * `swatches` - Swatches to describe
+///
+/// # Returns
+///
+/// The JSON document
+pub fn swatches_json(swatches: &[Swatch]) -> String {
+    let items: Vec<String> = swatches
+        .iter()
+        .map(|swatch| {
+            format!(
+                r#"    {{"file": {}, "key": {}, "name": {}, "color": "{}", "default": {}}}"#,
+                json_string(swatch.file),
+                json_string(&swatch.key),
+                json_string(&swatch.name),
+                hex(swatch.color),
+                swatch.is_default
+            )
+        })
+        .collect();
+
+    let mut json = String::new();
+    let _ = writeln!(json, "{{");
+    let _ = writeln!(json, r#"  "swatches": {}"#, json_array(&items));
+    let _ = writeln!(json, "}}");
+    json
+}
@@ -0,0 +1,109 @@
+//! Surfaces that skins can be drawn onto
+//!
+//! The window manager draws through the `RenderTarget` trait, so a GUI can draw straight into its own
+//! framebuffer or texture instead of rendering to an image and copying the pixels again.
+
+use image::Rgba;
+
+use crate::sprites::{SpriteImage, WindowImage};
+
+/// A surface that windows are drawn onto
+///
+/// Only `size`, `get_pixel` and `set_pixel` are required. Backends that can blit or blend faster should
+/// override `fill_rect` and `put_sprite`. Callers never draw outside of `size`.
+pub trait RenderTarget {
+    /// Returns the (width, height) of the surface in pixels
+    fn size(&self) -> (u32, u32);
+
+    /// Returns the color of a single pixel
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - X position (from left)
+    /// * `y` - Y position (from top)
+    fn get_pixel(&self, x: u32, y: u32) -> Rgba<u8>;
+
+    /// Sets a single pixel, replacing its color
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - X position (from left)
+    /// * `y` - Y position (from top)
+    /// * `color` - Color of the pixel
+    fn set_pixel(&mut self, x: u32, y: u32, color: Rgba<u8>);
+
+    /// Fills a rectangle with a single color, replacing the pixels under it
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - X position of the left edge
+    /// * `y` - Y position of the top edge
+    /// * `width` - Width in pixels
+    /// * `height` - Height in pixels
+    /// * `color` - Fill color
+    fn fill_rect(&mut self, x: u32, y: u32, width: u32, height: u32, color: Rgba<u8>) {
+        for dst_y in y..y + height {
+            for dst_x in x..x + width {
+                self.set_pixel(dst_x, dst_y, color);
+            }
+        }
+    }
+
+    /// Composites a sprite onto the surface with source-over alpha blending.
+    ///
+    /// Fully transparent sprite pixels leave the surface untouched and opaque ones replace it.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - X position of the sprite's left edge
+    /// * `y` - Y position of the sprite's top edge
+    /// * `sprite` - Sprite image to draw
+    fn put_sprite(&mut self, x: u32, y: u32, sprite: &SpriteImage) {
+        for (src_x, src_y, pixel) in sprite.enumerate_pixels() {
+            match pixel.0[3] {
+                0 => {}
+                255 => self.set_pixel(x + src_x, y + src_y, *pixel),
+                _ => {
+                    let below = self.get_pixel(x + src_x, y + src_y);
+                    self.set_pixel(x + src_x, y + src_y, blend_over(below, *pixel));
+                }
+            }
+        }
+    }
+}
+
+impl RenderTarget for WindowImage {
+    fn size(&self) -> (u32, u32) {
+        self.dimensions()
+    }
+
+    fn get_pixel(&self, x: u32, y: u32) -> Rgba<u8> {
+        *image::ImageBuffer::get_pixel(self, x, y)
+    }
+
+    fn set_pixel(&mut self, x: u32, y: u32, color: Rgba<u8>) {
+        self.put_pixel(x, y, color);
+    }
+}
+
+/// Blends a color over another with the source-over operator
+///
+/// # Arguments
+///
+/// * `below` - Color underneath
+/// * `above` - Color drawn on top
+///
+/// # Returns
+///
+/// The composited color
+pub fn blend_over(below: Rgba<u8>, above: Rgba<u8>) -> Rgba<u8> {
+    let above_alpha = above.0[3] as u32;
+    let below_alpha = below.0[3] as u32 * (255 - above_alpha) / 255;
+    let alpha = above_alpha + below_alpha;
+    if alpha == 0 {
+        return Rgba([0, 0, 0, 0]);
+    }
+    let channel =
+        |i: usize| ((above.0[i] as u32 * above_alpha + below.0[i] as u32 * below_alpha + alpha / 2) / alpha) as u8;
+    Rgba([channel(0), channel(1), channel(2), alpha as u8])
+}
@@ -0,0 +1,182 @@
+//! Rendering text with the skin's TEXT.BMP font
+
+use std::collections::HashMap;
+
+use image::{GenericImage, ImageBuffer};
+
+use crate::sprites::SpriteImage;
+
+/// Width of a TEXT.BMP character
+pub const CHAR_WIDTH: u32 = 5;
+/// Height of a TEXT.BMP character
+pub const CHAR_HEIGHT: u32 = 6;
+
+/// Horizontal gap between TEXT.BMP characters. The glyphs include their own spacing, so there is none.
+pub const CHAR_SPACING: u32 = 0;
+
+/// Separator Winamp puts between repetitions of a scrolling title
+const MARQUEE_SEPARATOR: &str = "  ***  ";
+/// What Winamp puts at the end of a title that is cut off
+const ELLIPSIS: &str = "...";
+
+/// Layout metrics of a fixed width bitmap font
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextMetrics {
+    /// Width of a character in pixels
+    pub char_width: u32,
+    /// Height of a character in pixels
+    pub char_height: u32,
+    /// Gap between characters in pixels
+    pub spacing: u32,
+}
+
+/// Metrics of the TEXT.BMP font
+pub const TEXT_METRICS: TextMetrics = TextMetrics {
+    char_width: CHAR_WIDTH,
+    char_height: CHAR_HEIGHT,
+    spacing: CHAR_SPACING,
+};
+
+impl TextMetrics {
+    /// Measures the width of a string in pixels
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Text to measure
+    ///
+    /// # Returns
+    ///
+    /// Width of the rendered text, without spacing after the last character
+    pub fn measure(&self, text: &str) -> u32 {
+        let num_chars = text.chars().count() as u32;
+        if num_chars == 0 {
+            return 0;
+        }
+        num_chars * self.char_width + (num_chars - 1) * self.spacing
+    }
+
+    /// Returns how many characters fit in a width
+    pub fn max_chars(&self, width: u32) -> usize {
+        ((width + self.spacing) / (self.char_width + self.spacing)) as usize
+    }
+
+    /// Cuts a string off with "..." so it fits in a width, like Winamp does when title scrolling is off
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Text to fit
+    /// * `width` - Available width in pixels
+    ///
+    /// # Returns
+    ///
+    /// The text unchanged if it fits, otherwise as many leading characters as fit followed by "..."
+    pub fn ellipsize(&self, text: &str, width: u32) -> String {
+        if self.measure(text) <= width {
+            return text.to_string();
+        }
+        let max_chars = self.max_chars(width);
+        let ellipsis_chars = ELLIPSIS.chars().count();
+        if max_chars <= ellipsis_chars {
+            return ELLIPSIS.chars().take(max_chars).collect();
+        }
+        let kept: String = text.chars().take(max_chars - ellipsis_chars).collect();
+        format!("{}{}", kept.trim_end(), ELLIPSIS)
+    }
+}
+
+/// Returns the TEXT.BMP sprite for a character, falling back to lowercase and then to a space
+fn character_sprite(sprites: &HashMap<String, SpriteImage>, ch: char) -> Option<&SpriteImage> {
+    let lookup = |c: char| sprites.get(&format!("CHARACTER_{}", c as u32));
+    lookup(ch)
+        .or_else(|| ch.to_lowercase().next().and_then(lookup))
+        .or_else(|| lookup(' '))
+}
+
+/// Renders a string with the skin's TEXT.BMP font
+///
+/// Characters the font does not have are drawn as spaces.
+///
+/// # Arguments
+///
+/// * `sprites` - A map of sprite names to sprite images
+/// * `text` - Text to render
+///
+/// # Returns
+///
+/// An image 5 pixels wide per character and 6 pixels tall
+pub fn render_text(sprites: &HashMap<String, SpriteImage>, text: &str) -> SpriteImage {
+    let mut image = ImageBuffer::new(TEXT_METRICS.measure(text), TEXT_METRICS.char_height);
+
+    for (i, ch) in text.chars().enumerate() {
+        if let Some(sprite) = character_sprite(sprites, ch) {
+            // sprites cut off at the edge of the sheet may be smaller than a character
+            let x = i as u32 * (TEXT_METRICS.char_width + TEXT_METRICS.spacing);
+            let _ = image.copy_from(sprite, x, 0);
+        }
+    }
+
+    image
+}
+
+/// Finds the characters of a string the skin's TEXT.BMP font does not have, which `render_text` draws as spaces
+///
+/// # Arguments
+///
+/// * `sprites` - A map of sprite names to sprite images
+/// * `text` - Text to check
+///
+/// # Returns
+///
+/// Each missing character once, in the order they first appear
+pub fn missing_characters(sprites: &HashMap<String, SpriteImage>, text: &str) -> Vec<char> {
+    let mut missing = Vec::new();
+    for ch in text.chars() {
+        if ch == ' ' || missing.contains(&ch) {
+            continue;
+        }
+        let lookup = |c: char| sprites.contains_key(&format!("CHARACTER_{}", c as u32));
+        if !lookup(ch) && !ch.to_lowercase().next().is_some_and(lookup) {
+            missing.push(ch);
+        }
+    }
+    missing
+}
+
+/// Renders the scrolling title marquee
+///
+/// Titles which fit are padded with spaces. Longer titles are repeated with a separator, like Winamp does, and
+/// scrolled by `offset` characters.
+///
+/// # Arguments
+///
+/// * `sprites` - A map of sprite names to sprite images
+/// * `title` - Title to show
+/// * `offset` - Number of characters the marquee has scrolled
+/// * `width` - Width of the marquee in pixels
+///
+/// # Returns
+///
+/// An image `width` pixels wide and 6 pixels tall
+pub fn render_marquee(sprites: &HashMap<String, SpriteImage>, title: &str, offset: usize, width: u32) -> SpriteImage {
+    let visible_chars = width.div_ceil(CHAR_WIDTH) as usize;
+    let title_chars: Vec<char> = title.chars().collect();
+
+    let text: String = if TEXT_METRICS.measure(title) <= width {
+        title_chars
+            .into_iter()
+            .chain(std::iter::repeat(' '))
+            .take(visible_chars)
+            .collect()
+    } else {
+        let looped: Vec<char> = title_chars.into_iter().chain(MARQUEE_SEPARATOR.chars()).collect();
+        looped
+            .iter()
+            .cycle()
+            .skip(offset % looped.len())
+            .take(visible_chars)
+            .collect()
+    };
+
+    let rendered = render_text(sprites, &text);
+    image::imageops::crop_imm(&rendered, 0, 0, width.min(rendered.width()), CHAR_HEIGHT).to_image()
+}
@@ -0,0 +1,113 @@
+//! Thumbnails of rendered skins for gallery pages
+
+use image::imageops::{self, FilterType};
+use image::{ImageBuffer, Rgba};
+
+use crate::render::RenderOptions;
+use crate::sprites::WindowImage;
+
+/// How a render is fitted into the thumbnail's box when the aspect ratios differ
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThumbnailFit {
+    /// Scale to fit inside the box and pad the rest with a color, centering the render
+    Letterbox(Rgba<u8>),
+    /// Scale to fit inside the box. The thumbnail may be smaller than the box on one side.
+    Contain,
+    /// Scale to cover the box and crop the overflow, keeping the center
+    Cover,
+    /// Stretch to exactly the box, ignoring the aspect ratio
+    Stretch,
+}
+
+/// Options controlling how thumbnails are rendered
+#[derive(Debug, Clone)]
+pub struct ThumbnailOptions {
+    /// How to fit the render into the box
+    pub fit: ThumbnailFit,
+    /// Resampling filter used to resize the render
+    pub filter: FilterType,
+    /// Options for the full size render that is downscaled. `scale` is ignored.
+    pub render: RenderOptions,
+}
+
+impl ThumbnailOptions {
+    fn new() -> Self {
+        Self {
+            fit: ThumbnailFit::Letterbox(Rgba([0, 0, 0, 0])),
+            filter: FilterType::Lanczos3,
+            render: RenderOptions::default(),
+        }
+    }
+}
+
+impl Default for ThumbnailOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Resizes a render into a thumbnail
+///
+/// # Arguments
+///
+/// * `image` - Full size render
+/// * `width` - Width of the thumbnail's box
+/// * `height` - Height of the thumbnail's box
+/// * `fit` - How to fit the render into the box
+/// * `filter` - Resampling filter
+///
+/// # Returns
+///
+/// The thumbnail
+pub fn make_thumbnail(
+    image: &WindowImage,
+    width: u32,
+    height: u32,
+    fit: ThumbnailFit,
+    filter: FilterType,
+) -> WindowImage {
+    if image.width() == 0 || image.height() == 0 || width == 0 || height == 0 {
+        return ImageBuffer::new(width, height);
+    }
+
+    let scale_x = width as f64 / image.width() as f64;
+    let scale_y = height as f64 / image.height() as f64;
+    let scaled_size = |scale: f64| {
+        (
+            ((image.width() as f64 * scale).round() as u32).max(1),
+            ((image.height() as f64 * scale).round() as u32).max(1),
+        )
+    };
+
+    match fit {
+        ThumbnailFit::Stretch => imageops::resize(image, width, height, filter),
+        ThumbnailFit::Contain => {
+            let (scaled_width, scaled_height) = scaled_size(scale_x.min(scale_y));
+            imageops::resize(image, scaled_width.min(width), scaled_height.min(height), filter)
+        }
+        ThumbnailFit::Letterbox(color) => {
+            let (scaled_width, scaled_height) = scaled_size(scale_x.min(scale_y));
+            let scaled = imageops::resize(image, scaled_width.min(width), scaled_height.min(height), filter);
+            let mut thumbnail = ImageBuffer::from_pixel(width, height, color);
+            imageops::overlay(
+                &mut thumbnail,
+                &scaled,
+                ((width - scaled.width()) / 2) as i64,
+                ((height - scaled.height()) / 2) as i64,
+            );
+            thumbnail
+        }
+        ThumbnailFit::Cover => {
+            let (scaled_width, scaled_height) = scaled_size(scale_x.max(scale_y));
+            let scaled = imageops::resize(image, scaled_width.max(width), scaled_height.max(height), filter);
+            imageops::crop_imm(
+                &scaled,
+                (scaled.width() - width) / 2,
+                (scaled.height() - height) / 2,
+                width,
+                height,
+            )
+            .to_image()
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! Rendering the visualizer with the skin's viscolor.txt colors
+
+use image::{ImageBuffer, Rgb, Rgba};
+
+use crate::sprites::SpriteImage;
+use crate::text::viscolor::VisColors;
+
+/// Width of the main window visualizer
+pub const SPECTRUM_WIDTH: u32 = 76;
+/// Height of the main window visualizer
+pub const SPECTRUM_HEIGHT: u32 = 16;
+/// Number of bars in the spectrum analyzer
+pub const SPECTRUM_BARS: usize = 19;
+/// Width of a single spectrum analyzer bar (bars are separated by a 1 pixel gap)
+const BAR_WIDTH: u32 = 3;
+
+fn opaque(color: Rgb<u8>) -> Rgba<u8> {
+    Rgba([color.0[0], color.0[1], color.0[2], 255])
+}
+
+/// Renders the spectrum analyzer
+///
+/// Each row of a bar is drawn with the viscolor for that height, so taller bars reach further up the gradient.
+///
+/// # Arguments
+///
+/// * `vis_colors` - Colors from viscolor.txt
+/// * `bars` - Heights (0-16) of up to 19 bars, from left to right
+///
+/// # Returns
+///
+/// A 76x16 image of the visualizer
+pub fn render_spectrum(vis_colors: &VisColors, bars: &[u8]) -> SpriteImage {
+    let bg = opaque(vis_colors.bg_color().unwrap_or(Rgb([0, 0, 0])));
+    let mut image = ImageBuffer::from_pixel(SPECTRUM_WIDTH, SPECTRUM_HEIGHT, bg);
+
+    // background dot grid
+    if let Some(dots) = vis_colors.bg_dots_color() {
+        for y in (1..SPECTRUM_HEIGHT).step_by(2) {
+            for x in (1..SPECTRUM_WIDTH).step_by(2) {
+                image.put_pixel(x, y, opaque(dots));
+            }
+        }
+    }
+
+    for (i, &height) in bars.iter().take(SPECTRUM_BARS).enumerate() {
+        let height = (height as u32).min(SPECTRUM_HEIGHT);
+        for row in 0..height {
+            let color = match vis_colors.vis_color(row as usize) {
+                Some(color) => opaque(color),
+                None => continue,
+            };
+            let y = SPECTRUM_HEIGHT - 1 - row;
+            for x in 0..BAR_WIDTH {
+                image.put_pixel(i as u32 * (BAR_WIDTH + 1) + x, y, color);
+            }
+        }
+    }
+
+    image
+}
@@ -0,0 +1,360 @@
+//! Fixes for common problems that stop skins from loading or displaying correctly
+//!
+//! `Wsz::repair` changes the skin in place and returns a RepairLog of what it did, so callers can show it to the
+//! skin's author or decide not to save.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Cursor;
+
+use crate::cursor::CursorRole;
+use crate::error::Result;
+use crate::sprites::bmp::{BitmapInfo, BmpCompression};
+use crate::sprites::{self, SpriteManager, OTHER_BITMAPS, TEXT_FILES};
+use crate::text::pledit::PleditSettings;
+use crate::text::viscolor::{VisColors, VIS_COLOR_COUNT};
+use crate::Wsz;
+
+/// Options controlling what `Wsz::repair` fixes
+#[derive(Debug, Clone)]
+pub struct RepairOptions<'a> {
+    /// Skin to copy missing and unreadable sprite sheets and missing sprites from, such as Winamp's base skin.
+    /// Nothing is filled in when None.
+    pub base: Option<&'a Wsz>,
+    /// Write viscolor.txt and pledit.txt with Winamp's defaults when they are missing, and add Winamp's default
+    /// colors to the end of a viscolor.txt with fewer than 24 colors
+    pub default_text_files: bool,
+    /// Rename sprite sheets, cursors and text files to the case Winamp's base skin uses, e.g. "main.bmp" to
+    /// "MAIN.BMP", for players on case-sensitive file systems
+    pub normalize_names: bool,
+    /// Re-encode bitmaps that are not plain uncompressed BMPs, e.g. PNGs named .bmp, RLE or bitfield compressed,
+    /// top-down, or 16 or 32 bit BMPs, as 24 bit BMPs
+    pub reencode_bitmaps: bool,
+}
+
+impl RepairOptions<'_> {
+    fn new() -> Self {
+        Self {
+            base: None,
+            default_text_files: true,
+            normalize_names: true,
+            reencode_bitmaps: true,
+        }
+    }
+}
+
+impl Default for RepairOptions<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A change `Wsz::repair` made
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepairAction {
+    /// A file was renamed to the case Winamp's base skin uses
+    RenamedFile {
+        /// Path of the file before
+        from: String,
+        /// Path of the file after
+        to: String,
+    },
+    /// A bitmap was re-encoded as a 24 bit BMP
+    ReencodedBitmap {
+        /// Path of the file
+        file: String,
+    },
+    /// A missing sprite sheet was copied from the base skin
+    AddedSheet {
+        /// Name of the sheet, e.g. "EQ_EX.BMP"
+        sheet: String,
+    },
+    /// A sprite sheet that could not be decoded was replaced with the base skin's
+    ReplacedSheet {
+        /// Name of the sheet
+        sheet: String,
+    },
+    /// A sprite missing from its sheet was copied from the base skin
+    AddedSprite {
+        /// Name of the sprite
+        sprite: String,
+    },
+    /// A missing text configuration file was written with Winamp's defaults
+    AddedTextFile {
+        /// Name of the file, e.g. "pledit.txt"
+        file: String,
+    },
+    /// Winamp's default colors were added to the end of a viscolor.txt that was too short
+    CompletedVisColors {
+        /// Number of colors added
+        added: usize,
+    },
+}
+
+impl fmt::Display for RepairAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepairAction::RenamedFile { from, to } => write!(f, "renamed {} to {}", from, to),
+            RepairAction::ReencodedBitmap { file } => write!(f, "re-encoded {} as a 24 bit BMP", file),
+            RepairAction::AddedSheet { sheet } => write!(f, "added {} from the base skin", sheet),
+            RepairAction::ReplacedSheet { sheet } => {
+                write!(f, "replaced unreadable {} with the base skin's", sheet)
+            }
+            RepairAction::AddedSprite { sprite } => write!(f, "added sprite {} from the base skin", sprite),
+            RepairAction::AddedTextFile { file } => write!(f, "added {} with Winamp's defaults", file),
+            RepairAction::CompletedVisColors { added } => {
+                write!(f, "added {} default color(s) to viscolor.txt", added)
+            }
+        }
+    }
+}
+
+/// Everything `Wsz::repair` changed, in the order it was done
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairLog {
+    actions: Vec<RepairAction>,
+}
+
+impl RepairLog {
+    /// Get every change
+    pub fn actions(&self) -> &[RepairAction] {
+        &self.actions
+    }
+
+    /// Whether nothing was changed
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+}
+
+impl fmt::Display for RepairLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for action in &self.actions {
+            writeln!(f, "{}", action)?;
+        }
+        Ok(())
+    }
+}
+
+/// Repairs a skin in place
+pub(crate) fn repair(skin: &mut Wsz, options: &RepairOptions) -> Result<RepairLog> {
+    let mut log = RepairLog::default();
+    if options.normalize_names {
+        normalize_names(skin, &mut log);
+    }
+    if options.reencode_bitmaps {
+        reencode_bitmaps(skin, &mut log)?;
+    }
+    if let Some(base) = options.base {
+        fill_from_base(skin, base, &mut log);
+    }
+    if options.default_text_files {
+        default_text_files(skin, &mut log);
+    }
+
+    if !log.is_empty() {
+        let file_name = skin.metadata.file_name.take();
+        let compressed_size = skin.metadata.compressed_size;
+        skin.metadata = crate::text::readme::SkinMetadata::from_skin_archive(&skin.files);
+        skin.metadata.file_name = file_name;
+        skin.metadata.compressed_size = compressed_size;
+    }
+    Ok(log)
+}
+
+/// Returns the file name part of a path in the archive
+fn base_name(path: &str) -> &str {
+    path.split('/').next_back().unwrap()
+}
+
+/// Returns the directory the skin's files are in, with a trailing slash, or "" for the top of the archive
+fn skin_dir(skin: &Wsz) -> String {
+    sprites::find_sprite_sheet(&skin.files, "MAIN.BMP")
+        .and_then(|key| key.rsplit_once('/'))
+        .map_or(String::new(), |(dir, _)| format!("{}/", dir))
+}
+
+fn normalize_names(skin: &mut Wsz, log: &mut RepairLog) {
+    let sheets = SpriteManager::sprite_sheet_names();
+    let known: Vec<&str> = sheets
+        .iter()
+        .map(String::as_str)
+        .chain(OTHER_BITMAPS)
+        .chain(TEXT_FILES)
+        .chain(CursorRole::ALL.iter().map(|role| role.file_name()))
+        .collect();
+
+    let mut keys: Vec<String> = skin.files.keys().cloned().collect();
+    keys.sort();
+    for key in keys {
+        let name = base_name(&key);
+        let Some(canonical) = known.iter().find(|known| known.eq_ignore_ascii_case(name)) else {
+            continue;
+        };
+        if name == *canonical {
+            continue;
+        }
+        let renamed = format!("{}{}", &key[..key.len() - name.len()], canonical);
+        // leave duplicates alone rather than lose one of them
+        if skin.files.contains_key(&renamed) {
+            continue;
+        }
+        let data = skin.files.remove(&key).unwrap();
+        skin.files.insert(renamed.clone(), data);
+        // keep the file's place and timestamp in the archive
+        for entry in skin.provenance.entries.iter_mut().filter(|entry| entry.name == key) {
+            entry.name = renamed.clone();
+        }
+        log.actions.push(RepairAction::RenamedFile { from: key, to: renamed });
+    }
+}
+
+/// Whether a bitmap decodes but is stored in a way some players cannot read
+fn needs_reencoding(data: &[u8]) -> bool {
+    let Ok(reader) = image::ImageReader::new(Cursor::new(data)).with_guessed_format() else {
+        return false;
+    };
+    if reader.format() != Some(image::ImageFormat::Bmp) {
+        return reader.format().is_some();
+    }
+    // OS/2 headers have no compression or top-down rows, so they read as plain
+    BitmapInfo::from_bytes(data).is_some_and(|info| {
+        info.top_down || info.compression != BmpCompression::None || info.bit_depth == 16 || info.bit_depth == 32
+    })
+}
+
+fn reencode_bitmaps(skin: &mut Wsz, log: &mut RepairLog) -> Result<()> {
+    let sheets = SpriteManager::sprite_sheet_names();
+    let mut keys: Vec<String> = skin
+        .files
+        .keys()
+        .filter(|key| {
+            let name = base_name(key);
+            sheets
+                .iter()
+                .map(String::as_str)
+                .chain(OTHER_BITMAPS)
+                .any(|bitmap| bitmap.eq_ignore_ascii_case(name))
+        })
+        .cloned()
+        .collect();
+    keys.sort();
+    for key in keys {
+        if !needs_reencoding(&skin.files[&key]) {
+            continue;
+        }
+        let Ok(image) = image::load_from_memory(&skin.files[&key]) else {
+            continue;
+        };
+        let name = base_name(&key).to_uppercase();
+        let decodes_sprites = sheets.contains(&name) && !skin.unloaded_sheets.contains(&name);
+        let sprite_manager = SpriteManager::new();
+        let old_sprites = if decodes_sprites {
+            sprite_manager
+                .extract_sprite_sheet_from_archive(&skin.files, &name)
+                .unwrap_or_default()
+        } else {
+            HashMap::new()
+        };
+        skin.files.insert(key.clone(), crate::encode_bmp(&image)?);
+
+        // decode the sprites again, since the new file has no alpha channel, keeping sprites set since loading
+        if decodes_sprites {
+            let sheet_sprites = sprite_manager.extract_sprite_sheet_from_archive(&skin.files, &name)?;
+            for (sprite_name, sprite) in sheet_sprites {
+                if skin.sprites.get(&sprite_name) == old_sprites.get(&sprite_name) {
+                    skin.sprites.insert(sprite_name, sprite);
+                }
+            }
+        }
+        log.actions.push(RepairAction::ReencodedBitmap { file: key });
+    }
+    Ok(())
+}
+
+fn fill_from_base(skin: &mut Wsz, base: &Wsz, log: &mut RepairLog) {
+    let sprite_manager = SpriteManager::new();
+    let dir = skin_dir(skin);
+    for sheet in SpriteManager::sprite_sheet_names() {
+        // sheets left out by WszOptions were never decoded, so they may not be missing at all
+        if skin.unloaded_sheets.contains(&sheet) {
+            continue;
+        }
+        let sheet_names: Vec<&String> = sprite_manager
+            .get_sprite_definitions()
+            .values()
+            .filter(|def| def.sprite_sheet == sheet)
+            .map(|def| &def.name)
+            .collect();
+        let has_sprites = sheet_names.iter().any(|name| skin.sprites.contains_key(*name));
+        let key = sprites::find_sprite_sheet(&skin.files, &sheet).cloned();
+
+        // sprites set without a sheet are drawn onto a new one when the skin is saved, so only sheets with no
+        // sprites at all are missing or unreadable
+        if !has_sprites {
+            let Some(base_key) = sprites::find_sprite_sheet(&base.files, &sheet) else {
+                continue;
+            };
+            let base_data = base.files[base_key].clone();
+            let action = match &key {
+                Some(key) => {
+                    skin.files.insert(key.clone(), base_data);
+                    RepairAction::ReplacedSheet { sheet: sheet.clone() }
+                }
+                None => {
+                    skin.files.insert(format!("{}{}", dir, sheet), base_data);
+                    RepairAction::AddedSheet { sheet: sheet.clone() }
+                }
+            };
+            for name in &sheet_names {
+                if let Some(sprite) = base.sprites.get(*name) {
+                    skin.sprites.insert((*name).clone(), sprite.clone());
+                }
+            }
+            log.actions.push(action);
+            continue;
+        }
+
+        let mut missing: Vec<&String> = sheet_names
+            .into_iter()
+            .filter(|name| !skin.sprites.contains_key(*name) && base.sprites.contains_key(*name))
+            .collect();
+        missing.sort();
+        for name in missing {
+            skin.sprites.insert(name.clone(), base.sprites[name].clone());
+            log.actions.push(RepairAction::AddedSprite { sprite: name.clone() });
+        }
+    }
+}
+
+fn default_text_files(skin: &mut Wsz, log: &mut RepairLog) {
+    let dir = skin_dir(skin);
+    let has_file = |skin: &Wsz, file: &str| skin.files.keys().any(|key| base_name(key).eq_ignore_ascii_case(file));
+
+    if !has_file(skin, "viscolor.txt") {
+        skin.vis_colors = VisColors::winamp_default();
+        skin.files
+            .insert(format!("{}viscolor.txt", dir), skin.vis_colors.to_txt().into_bytes());
+        log.actions.push(RepairAction::AddedTextFile {
+            file: "viscolor.txt".to_string(),
+        });
+    } else if skin.vis_colors.len() < VIS_COLOR_COUNT {
+        let defaults = VisColors::winamp_default();
+        let added = VIS_COLOR_COUNT - skin.vis_colors.len();
+        for index in skin.vis_colors.len()..VIS_COLOR_COUNT {
+            // default colors are always in range
+            let _ = skin.vis_colors.set(index, defaults.get(index).unwrap());
+        }
+        // the file itself is rewritten when the skin is saved
+        log.actions.push(RepairAction::CompletedVisColors { added });
+    }
+
+    if !has_file(skin, "pledit.txt") {
+        skin.pledit = PleditSettings::winamp_default();
+        skin.files
+            .insert(format!("{}pledit.txt", dir), skin.pledit.to_txt().into_bytes());
+        log.actions.push(RepairAction::AddedTextFile {
+            file: "pledit.txt".to_string(),
+        });
+    }
+}
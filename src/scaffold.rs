@@ -0,0 +1,162 @@
+//! Starting points for new skins
+//!
+//! `create_skin_dir` writes a folder laid out the way the `extract` command leaves a skin, with a correctly sized
+//! image for every sprite, so a new skin can be drawn sprite by sprite and packed with `pack_wsz_dir` at any point.
+
+use std::fs;
+use std::path::Path;
+
+use image::{imageops, ImageFormat, Rgba};
+
+use crate::error::{Result, WszError};
+use crate::sprites::{self, SpriteImage, SpriteManager, OTHER_BITMAPS, TEXT_FILES};
+use crate::text::pledit::PleditSettings;
+use crate::text::viscolor::VisColors;
+use crate::Wsz;
+
+/// region.txt for a new skin. Every region is commented out, so the windows stay rectangular until one is drawn.
+const REGION_TEMPLATE: &str = "; Window shapes. Points are x,y pixel positions, listed around each polygon in order.
+; NumPoints lists how many points each polygon has, and PointList lists all of the points.
+; Remove the ; in front of a section and its keys to use it.
+;
+;[Normal]
+;NumPoints=4
+;PointList=0,0, 275,0, 275,116, 0,116
+;
+;[WindowShade]
+;NumPoints=4
+;PointList=0,0, 275,0, 275,14, 0,14
+;
+;[Equalizer]
+;NumPoints=4
+;PointList=0,0, 275,0, 275,116, 0,116
+;
+;[EqualizerWS]
+;NumPoints=4
+;PointList=0,0, 275,0, 275,14, 0,14
+";
+
+/// Options controlling what `create_skin_dir` writes
+#[derive(Debug, Clone)]
+pub struct ScaffoldOptions<'a> {
+    /// Name of the skin, written to readme.txt
+    pub name: String,
+    /// Skin to start from. Its sprites, GENEX.BMP and text files are copied, and sprites it does not have are left
+    /// blank. Every sprite is blank when None.
+    pub base: Option<&'a Wsz>,
+    /// Color blank sprites are filled with
+    pub blank: Rgba<u8>,
+}
+
+impl ScaffoldOptions<'_> {
+    fn new() -> Self {
+        Self {
+            name: "Untitled".to_string(),
+            base: None,
+            blank: Rgba([0, 0, 0, 255]),
+        }
+    }
+}
+
+impl Default for ScaffoldOptions<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What `create_skin_dir` wrote
+#[derive(Debug, Clone, Default)]
+pub struct ScaffoldSummary {
+    /// Number of sprite images written
+    pub sprites: usize,
+    /// Number of those that were copied from the base skin
+    pub from_base: usize,
+    /// Files written at the top of the folder, e.g. "pledit.txt"
+    pub files: Vec<String>,
+}
+
+/// Creates a folder for a new skin
+///
+/// Each sprite sheet gets a folder of PNGs named after its sprites, e.g. MAIN/MAIN_PLAY_BUTTON.png, at the size
+/// Winamp draws them. Without a base skin NUMS_EX.BMP is left out, since Winamp would draw its blank digits instead
+/// of NUMBERS.BMP's. viscolor.txt and pledit.txt have Winamp's default colors, region.txt describes the format with
+/// every region commented out, and readme.txt is a stub for the author to fill in.
+///
+/// # Arguments
+///
+/// * `dir` - Folder to create. It may exist, but must be empty.
+/// * `options` - What to start the skin from
+///
+/// # Returns
+///
+/// What was written, or an error if the folder is not empty or cannot be written
+pub fn create_skin_dir<P: AsRef<Path>>(dir: P, options: &ScaffoldOptions) -> Result<ScaffoldSummary> {
+    let dir = dir.as_ref();
+    if dir.exists() && fs::read_dir(dir)?.next().is_some() {
+        return Err(WszError::ArgumentError(format!(
+            "{} already exists and is not empty",
+            dir.display()
+        )));
+    }
+    fs::create_dir_all(dir)?;
+
+    let mut summary = ScaffoldSummary::default();
+    let sprite_manager = SpriteManager::new();
+    let definitions = sprite_manager.get_sprite_definitions();
+    for sheet in SpriteManager::sprite_sheet_names() {
+        let mut sheet_defs = definitions
+            .values()
+            .filter(|def| def.sprite_sheet == sheet && def.width > 0 && def.height > 0)
+            .collect::<Vec<_>>();
+        sheet_defs.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let base_sprites = options.base.map(Wsz::get_sprites);
+        let base_has_sheet = base_sprites.is_some_and(|base| sheet_defs.iter().any(|def| base.contains_key(&def.name)));
+        if sheet == "NUMS_EX.BMP" && !base_has_sheet {
+            continue;
+        }
+
+        let sheet_dir = dir.join(sheet.trim_end_matches(".BMP"));
+        fs::create_dir_all(&sheet_dir)?;
+        for def in sheet_defs {
+            let mut sprite = SpriteImage::from_pixel(def.width, def.height, options.blank);
+            if let Some(base) = base_sprites.and_then(|base| base.get(&def.name)) {
+                // sprites of the wrong size are cropped or padded, so the folder packs into a valid skin
+                imageops::replace(&mut sprite, base, 0, 0);
+                summary.from_base += 1;
+            }
+            sprite.save_with_format(sheet_dir.join(format!("{}.png", def.name)), ImageFormat::Png)?;
+            summary.sprites += 1;
+        }
+    }
+
+    let mut files: Vec<(String, Vec<u8>)> = Vec::new();
+    for file in OTHER_BITMAPS.iter().chain(TEXT_FILES.iter()) {
+        let base_file = options
+            .base
+            .and_then(|base| sprites::find_sprite_sheet(&base.files, file).map(|key| base.files[key].clone()));
+        let content = match (base_file, *file) {
+            (Some(content), _) => content,
+            (None, "viscolor.txt") => VisColors::winamp_default().to_txt().into_bytes(),
+            (None, "pledit.txt") => PleditSettings::winamp_default().to_txt().into_bytes(),
+            (None, "region.txt") => REGION_TEMPLATE.as_bytes().to_vec(),
+            (None, _) => continue,
+        };
+        files.push((file.to_string(), content));
+    }
+    files.push(("readme.txt".to_string(), readme(&options.name).into_bytes()));
+
+    for (file, content) in files {
+        fs::write(dir.join(&file), content)?;
+        summary.files.push(file);
+    }
+    Ok(summary)
+}
+
+/// Writes the readme stub of a new skin
+fn readme(name: &str) -> String {
+    format!(
+        "Name: {}\r\nAuthor: \r\nEmail: \r\nWebsite: \r\nDate: \r\n\r\nDescription:\r\n\r\n",
+        name
+    )
+}
@@ -0,0 +1,165 @@
+//! Packing a skin's sprites into a single texture atlas
+//!
+//! Game engines and web front-ends draw skins faster from one texture than from hundreds of small images. The atlas
+//! is packed in shelves, tallest sprites first, and its coordinates can be written as JSON for loading alongside it.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::sprites::SpriteImage;
+use crate::util::{json_array, json_string};
+
+/// Options controlling how `Wsz::atlas` packs sprites
+#[derive(Debug, Clone)]
+pub struct AtlasOptions {
+    /// Transparent pixels left around every sprite, so texture filtering does not pick up neighboring sprites
+    pub padding: u32,
+    /// Round the atlas's width and height up to powers of two, which older GPUs and some engines require
+    pub power_of_two: bool,
+    /// Width of the shelves sprites are packed into. The atlas is wider if a sprite does not fit.
+    pub max_width: u32,
+}
+
+impl AtlasOptions {
+    fn new() -> Self {
+        Self {
+            padding: 0,
+            power_of_two: false,
+            max_width: 512,
+        }
+    }
+}
+
+impl Default for AtlasOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Where a sprite is in an atlas
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AtlasEntry {
+    /// Name of the sprite
+    pub name: String,
+    /// X position of the sprite's left edge
+    pub x: u32,
+    /// Y position of the sprite's top edge
+    pub y: u32,
+    /// Width of the sprite
+    pub width: u32,
+    /// Height of the sprite
+    pub height: u32,
+}
+
+/// Sprites packed into one image
+#[derive(Debug, Clone)]
+pub struct Atlas {
+    /// The packed image, transparent between sprites
+    pub image: SpriteImage,
+    /// Position of each sprite, sorted by name
+    pub entries: Vec<AtlasEntry>,
+}
+
+impl Atlas {
+    /// Get the position of a sprite
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the sprite
+    ///
+    /// # Returns
+    ///
+    /// The sprite's entry, or None if it is not in the atlas
+    pub fn get_entry(&self, name: &str) -> Option<&AtlasEntry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    /// Describes the atlas as JSON
+    ///
+    /// The document has the atlas's `width` and `height` and a `sprites` array with each sprite's `name` and
+    /// rectangle, sorted by name.
+    ///
+    /// # Returns
+    ///
+    /// The JSON document
+    pub fn to_json(&self) -> String {
+        let sprites: Vec<String> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    r#"    {{"name": {}, "x": {}, "y": {}, "width": {}, "height": {}}}"#,
+                    json_string(&entry.name),
+                    entry.x,
+                    entry.y,
+                    entry.width,
+                    entry.height
+                )
+            })
+            .collect();
+
+        let mut json = String::new();
+        let _ = writeln!(json, "{{");
+        let _ = writeln!(json, r#"  "width": {},"#, self.image.width());
+        let _ = writeln!(json, r#"  "height": {},"#, self.image.height());
+        let _ = writeln!(json, r#"  "sprites": {}"#, json_array(&sprites));
+        let _ = writeln!(json, "}}");
+        json
+    }
+}
+
+/// Packs sprites into an atlas
+///
+/// # Arguments
+///
+/// * `sprites` - A map of sprite names to sprite images. Sprites with no pixels are left out.
+/// * `options` - Packing options
+///
+/// # Returns
+///
+/// The atlas
+pub fn build_atlas(sprites: &HashMap<String, SpriteImage>, options: &AtlasOptions) -> Atlas {
+    let padding = options.padding;
+    let mut order: Vec<(&String, &SpriteImage)> = sprites
+        .iter()
+        .filter(|(_, sprite)| sprite.width() > 0 && sprite.height() > 0)
+        .collect();
+    // tallest first so each shelf wastes little space, then by name so the layout is the same every time
+    order.sort_by(|(a_name, a), (b_name, b)| b.height().cmp(&a.height()).then(a_name.cmp(b_name)));
+
+    let widest = order.iter().map(|(_, sprite)| sprite.width()).max().unwrap_or(0);
+    let shelf_width = options.max_width.max(widest + 2 * padding);
+
+    let mut entries = Vec::with_capacity(order.len());
+    let (mut x, mut y, mut shelf_height) = (padding, padding, 0);
+    let (mut width, mut height) = (0, 0);
+    for (name, sprite) in &order {
+        if x + sprite.width() + padding > shelf_width {
+            x = padding;
+            y += shelf_height + padding;
+            shelf_height = 0;
+        }
+        entries.push(AtlasEntry {
+            name: name.to_string(),
+            x,
+            y,
+            width: sprite.width(),
+            height: sprite.height(),
+        });
+        width = width.max(x + sprite.width() + padding);
+        height = height.max(y + sprite.height() + padding);
+        x += sprite.width() + padding;
+        shelf_height = shelf_height.max(sprite.height());
+    }
+    if options.power_of_two {
+        width = width.max(1).next_power_of_two();
+        height = height.max(1).next_power_of_two();
+    }
+
+    let mut image = SpriteImage::new(width, height);
+    for entry in &entries {
+        image::imageops::replace(&mut image, &sprites[&entry.name], entry.x as i64, entry.y as i64);
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Atlas { image, entries }
+}
@@ -0,0 +1,141 @@
+//! How a sprite sheet's BMP file is stored
+//!
+//! Sprites are decoded to RGBA, which loses what the skin's author saved: many skins from the 1990s are 8 bit
+//! BMPs drawn with the 216 color web palette or a palette of their own. BitmapInfo reads the file's header and
+//! color table without decoding the pixels.
+
+use image::Rgb;
+
+use crate::archive::WszArchive;
+use crate::sprites::find_sprite_sheet;
+
+/// Size of the file header before the DIB header
+const FILE_HEADER_SIZE: usize = 14;
+/// Size of the DIB header used by OS/2 and Windows 2.x bitmaps, which has 16 bit sizes and 3 byte palette entries
+const CORE_HEADER_SIZE: u32 = 12;
+/// Size of the smallest Windows DIB header with a compression field
+const INFO_HEADER_SIZE: u32 = 40;
+
+/// How the pixels of a BMP are compressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BmpCompression {
+    /// Uncompressed, which every player can read
+    None,
+    /// Run-length encoded 8 bit pixels
+    Rle8,
+    /// Run-length encoded 4 bit pixels
+    Rle4,
+    /// 16 or 32 bit pixels with channel masks
+    Bitfields,
+    /// Any other method, such as JPEG or PNG data in a BMP, with its number from the header
+    Other(u32),
+}
+
+impl BmpCompression {
+    fn from_header(compression: u32) -> Self {
+        match compression {
+            0 => BmpCompression::None,
+            1 => BmpCompression::Rle8,
+            2 => BmpCompression::Rle4,
+            3 => BmpCompression::Bitfields,
+            other => BmpCompression::Other(other),
+        }
+    }
+}
+
+/// Details of a BMP file read from its header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitmapInfo {
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// Bits per pixel: 1, 4 or 8 for indexed bitmaps, 16, 24 or 32 otherwise
+    pub bit_depth: u16,
+    /// How the pixels are compressed
+    pub compression: BmpCompression,
+    /// Whether the rows are stored from the top down rather than the usual bottom up
+    pub top_down: bool,
+    /// The color table of an indexed bitmap, in index order, or None for 16, 24 and 32 bit bitmaps. Some bitmaps
+    /// have fewer colors than their bit depth allows.
+    pub palette: Option<Vec<Rgb<u8>>>,
+}
+
+impl BitmapInfo {
+    /// Read the header and color table of a BMP file
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Contents of the file
+    ///
+    /// # Returns
+    ///
+    /// The details, or None if the data is not a BMP, e.g. a PNG saved with a .bmp name
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if !data.starts_with(b"BM") {
+            return None;
+        }
+        let read_u16 = |offset: usize| data.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+        let read_u32 = |offset: usize| {
+            data.get(offset..offset + 4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        };
+
+        let header_size = read_u32(FILE_HEADER_SIZE)?;
+        let (width, height, bit_depth, compression, colors_used, entry_size) = if header_size == CORE_HEADER_SIZE {
+            let width = read_u16(18)? as i32;
+            let height = read_u16(20)? as i32;
+            (width, height, read_u16(24)?, 0, 0, 3)
+        } else if header_size >= INFO_HEADER_SIZE {
+            let width = read_u32(18)? as i32;
+            let height = read_u32(22)? as i32;
+            (width, height, read_u16(28)?, read_u32(30)?, read_u32(46)?, 4)
+        } else {
+            return None;
+        };
+
+        let palette = (bit_depth <= 8).then(|| {
+            let max_colors = 1usize << bit_depth;
+            let count = match colors_used as usize {
+                0 => max_colors,
+                count => count.min(max_colors),
+            };
+            let start = FILE_HEADER_SIZE + header_size as usize;
+            (0..count)
+                .map_while(|index| {
+                    let offset = start + index * entry_size;
+                    // entries are stored blue first
+                    data.get(offset..offset + 3).map(|bgr| Rgb([bgr[2], bgr[1], bgr[0]]))
+                })
+                .collect()
+        });
+
+        Some(Self {
+            width: width.unsigned_abs(),
+            height: height.unsigned_abs(),
+            bit_depth,
+            compression: BmpCompression::from_header(compression),
+            top_down: height < 0,
+            palette,
+        })
+    }
+
+    /// Whether the pixels are indexes into a palette
+    pub fn is_indexed(&self) -> bool {
+        self.palette.is_some()
+    }
+}
+
+/// Reads how a sprite sheet is stored in the archive contents
+///
+/// # Arguments
+///
+/// * `archive` - WSZ archive
+/// * `sprite_sheet_name` - Name of the sprite sheet, e.g. "MAIN.BMP", found as `find_sprite_sheet` does
+///
+/// # Returns
+///
+/// The sheet's details, or None if the skin does not have the sheet or it is not a BMP
+pub fn sprite_sheet_info(archive: &WszArchive, sprite_sheet_name: &str) -> Option<BitmapInfo> {
+    find_sprite_sheet(archive, sprite_sheet_name).and_then(|key| BitmapInfo::from_bytes(&archive[key]))
+}
@@ -1,4 +1,5 @@
 use super::{SpriteDefinition, SpriteWindowDefinition, WindowType};
+use crate::render::TransportButton;
 
 pub fn cbuttons_sprites() -> Vec<SpriteDefinition> {
     vec![
@@ -165,3 +166,15 @@ pub fn cbuttons_window_sprites() -> Vec<SpriteWindowDefinition> {
         },
     ]
 }
+
+/// Returns the window sprite name of a transport button and the name of its pressed sprite
+pub fn cbuttons_pressed_sprite(button: TransportButton) -> (&'static str, &'static str) {
+    match button {
+        TransportButton::Previous => ("MAIN_PREVIOUS_BUTTON", "MAIN_PREVIOUS_BUTTON_SELECTED"),
+        TransportButton::Play => ("MAIN_PLAY_BUTTON", "MAIN_PLAY_BUTTON_SELECTED"),
+        TransportButton::Pause => ("MAIN_PAUSE_BUTTON", "MAIN_PAUSE_BUTTON_SELECTED"),
+        TransportButton::Stop => ("MAIN_STOP_BUTTON", "MAIN_STOP_BUTTON_SELECTED"),
+        TransportButton::Next => ("MAIN_NEXT_BUTTON", "MAIN_NEXT_BUTTON_SELECTED"),
+        TransportButton::Eject => ("MAIN_EJECT_BUTTON", "MAIN_EJECT_BUTTON_SELECTED"),
+    }
+}
@@ -0,0 +1,285 @@
+//! Sprites grouped by the element of a window they draw
+//!
+//! Sprite names follow Webamp's, where a "_SELECTED" suffix usually means the mouse is pressed on a button but means
+//! the window has focus for a title bar, and "_ACTIVE" means a toggle button is on. `element_sprite_names` hides
+//! this, so UI builders can ask for the play button's pressed state without knowing it is
+//! "MAIN_PLAY_BUTTON_SELECTED".
+
+use std::collections::HashMap;
+
+use crate::sprites::{SpriteImage, WindowType};
+
+/// A part of a window that is drawn with one or more sprites
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Element {
+    /// Title bar, which is active while the window has focus. In shade mode the whole window is its title bar.
+    TitleBar,
+    /// Background of the whole window
+    Background,
+    /// Options menu button at the left of the title bar
+    OptionsButton,
+    /// Minimize button
+    MinimizeButton,
+    /// Shade mode button, which is active in shade mode
+    ShadeButton,
+    /// Close button
+    CloseButton,
+    /// Previous track button
+    PreviousButton,
+    /// Play button
+    PlayButton,
+    /// Pause button
+    PauseButton,
+    /// Stop button
+    StopButton,
+    /// Next track button
+    NextButton,
+    /// Open file button
+    EjectButton,
+    /// Shuffle toggle, active when shuffle is on
+    ShuffleButton,
+    /// Repeat toggle, active when repeat is on
+    RepeatButton,
+    /// Equalizer window toggle, active when the equalizer is shown
+    EqualizerButton,
+    /// Playlist window toggle, active when the playlist is shown
+    PlaylistButton,
+    /// Thumb of the volume slider
+    VolumeThumb,
+    /// Thumb of the balance slider
+    BalanceThumb,
+    /// Thumb of the seek bar
+    PositionThumb,
+    /// Mono indicator, active for mono tracks
+    MonoIndicator,
+    /// Stereo indicator, active for stereo tracks
+    StereoIndicator,
+    /// Equalizer on toggle
+    OnButton,
+    /// Equalizer auto toggle
+    AutoButton,
+    /// Equalizer presets button
+    PresetsButton,
+    /// Thumb of the equalizer's band and preamp sliders
+    SliderThumb,
+    /// Playlist scroll bar handle
+    ScrollHandle,
+}
+
+/// A state an element is drawn in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ElementState {
+    /// Not pressed, and off for toggles
+    Normal,
+    /// Pressed with the mouse
+    Pressed,
+    /// On, for toggles and indicators, or focused, for title bars
+    Active,
+    /// Pressed while on
+    ActivePressed,
+}
+
+impl ElementState {
+    /// Every state
+    pub const ALL: [ElementState; 4] = [
+        ElementState::Normal,
+        ElementState::Pressed,
+        ElementState::Active,
+        ElementState::ActivePressed,
+    ];
+}
+
+/// Names of the sprites an element is drawn with in each state. States the element does not have are None.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ElementSpriteNames {
+    /// Sprite drawn normally
+    pub normal: Option<String>,
+    /// Sprite drawn while pressed
+    pub pressed: Option<String>,
+    /// Sprite drawn while on or focused
+    pub active: Option<String>,
+    /// Sprite drawn while pressed and on
+    pub active_pressed: Option<String>,
+}
+
+impl ElementSpriteNames {
+    /// Get the name of the sprite for a state
+    pub fn get(&self, state: ElementState) -> Option<&str> {
+        match state {
+            ElementState::Normal => self.normal.as_deref(),
+            ElementState::Pressed => self.pressed.as_deref(),
+            ElementState::Active => self.active.as_deref(),
+            ElementState::ActivePressed => self.active_pressed.as_deref(),
+        }
+    }
+
+    /// Sprites with no states
+    fn single(name: &str) -> Self {
+        Self {
+            normal: Some(name.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Buttons and slider thumbs with a "_SELECTED" sprite while pressed
+    fn button(name: &str) -> Self {
+        Self {
+            normal: Some(name.to_string()),
+            pressed: Some(format!("{}_SELECTED", name)),
+            ..Default::default()
+        }
+    }
+
+    /// Toggle buttons, with "_ACTIVE" sprites while on
+    fn toggle(name: &str) -> Self {
+        Self {
+            normal: Some(name.to_string()),
+            pressed: Some(format!("{}_SELECTED", name)),
+            active: Some(format!("{}_ACTIVE", name)),
+            active_pressed: Some(format!("{}_ACTIVE_SELECTED", name)),
+        }
+    }
+
+    /// Indicators, with an "_ACTIVE" sprite while on
+    fn indicator(name: &str) -> Self {
+        Self {
+            normal: Some(name.to_string()),
+            active: Some(format!("{}_ACTIVE", name)),
+            ..Default::default()
+        }
+    }
+
+    /// Title bars, with a "_SELECTED" sprite while the window has focus
+    fn title_bar(name: &str) -> Self {
+        Self {
+            normal: Some(name.to_string()),
+            active: Some(format!("{}_SELECTED", name)),
+            ..Default::default()
+        }
+    }
+
+    /// Buttons drawn as part of the window until pressed
+    fn pressed_only(name: &str) -> Self {
+        Self {
+            pressed: Some(name.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Finds the sprites an element of a window is drawn with
+///
+/// # Arguments
+///
+/// * `window` - The window the element is in
+/// * `element` - The element
+///
+/// # Returns
+///
+/// The sprite names for each state, or None if the window has no such element
+pub fn element_sprite_names(window: WindowType, element: Element) -> Option<ElementSpriteNames> {
+    use Element::*;
+    use ElementSpriteNames as Names;
+
+    let names = match (window, element) {
+        (WindowType::Main, TitleBar) => Names::title_bar("MAIN_TITLE_BAR"),
+        (WindowType::Main, Background) => Names::single("MAIN_WINDOW_BACKGROUND"),
+        (WindowType::Main, OptionsButton) => Names::button("MAIN_OPTIONS_BUTTON"),
+        (WindowType::Main, MinimizeButton) => Names::button("MAIN_MINIMIZE_BUTTON"),
+        (WindowType::Main | WindowType::MainShade, ShadeButton) => Names::toggle("MAIN_SHADE_BUTTON"),
+        (WindowType::Main | WindowType::MainShade, CloseButton) => Names::button("MAIN_CLOSE_BUTTON"),
+        (WindowType::Main, PreviousButton) => Names::button("MAIN_PREVIOUS_BUTTON"),
+        (WindowType::Main, PlayButton) => Names::button("MAIN_PLAY_BUTTON"),
+        (WindowType::Main, PauseButton) => Names::button("MAIN_PAUSE_BUTTON"),
+        (WindowType::Main, StopButton) => Names::button("MAIN_STOP_BUTTON"),
+        (WindowType::Main, NextButton) => Names::button("MAIN_NEXT_BUTTON"),
+        (WindowType::Main, EjectButton) => Names::button("MAIN_EJECT_BUTTON"),
+        (WindowType::Main, ShuffleButton) => Names::toggle("MAIN_SHUFFLE_BUTTON"),
+        (WindowType::Main, RepeatButton) => Names::toggle("MAIN_REPEAT_BUTTON"),
+        (WindowType::Main, EqualizerButton) => Names::toggle("MAIN_EQ_BUTTON"),
+        (WindowType::Main, PlaylistButton) => Names::toggle("MAIN_PLAYLIST_BUTTON"),
+        (WindowType::Main, VolumeThumb) => Names::button("MAIN_VOLUME_THUMB"),
+        (WindowType::Main, BalanceThumb) => Names::button("MAIN_BALANCE_THUMB"),
+        (WindowType::Main, PositionThumb) => Names::button("MAIN_POSITION_SLIDER_THUMB"),
+        (WindowType::Main, MonoIndicator) => Names::indicator("MAIN_MONO"),
+        (WindowType::Main, StereoIndicator) => Names::indicator("MAIN_STEREO"),
+
+        (WindowType::MainShade, TitleBar) => Names::title_bar("MAIN_SHADE_BACKGROUND"),
+        (WindowType::MainShade, PositionThumb) => Names::single("MAIN_SHADE_POSITION_THUMB"),
+
+        (WindowType::Equalizer, TitleBar) => Names::title_bar("EQ_TITLE_BAR"),
+        (WindowType::Equalizer, Background) => Names::single("EQ_WINDOW_BACKGROUND"),
+        (WindowType::Equalizer, CloseButton) => Names::button("EQ_CLOSE_BUTTON"),
+        (WindowType::Equalizer, OnButton) => Names::toggle("EQ_ON_BUTTON"),
+        (WindowType::Equalizer, AutoButton) => Names::toggle("EQ_AUTO_BUTTON"),
+        (WindowType::Equalizer, PresetsButton) => Names::button("EQ_PRESETS_BUTTON"),
+        (WindowType::Equalizer, SliderThumb) => Names::button("EQ_SLIDER_THUMB"),
+
+        (WindowType::EqualizerShade, TitleBar) => Names::title_bar("EQ_SHADE_BACKGROUND"),
+        // the shade close button's pressed sprite is named "_ACTIVE"
+        (WindowType::EqualizerShade, CloseButton) => Names {
+            normal: Some("EQ_SHADE_CLOSE_BUTTON".to_string()),
+            pressed: Some("EQ_SHADE_CLOSE_BUTTON_ACTIVE".to_string()),
+            ..Default::default()
+        },
+
+        (WindowType::Playlist, TitleBar) => Names::title_bar("PLAYLIST_TITLE_BAR"),
+        (WindowType::Playlist, CloseButton) => Names::pressed_only("PLAYLIST_CLOSE_SELECTED"),
+        (WindowType::Playlist, ScrollHandle) => Names::button("PLAYLIST_SCROLL_HANDLE"),
+
+        (WindowType::PlaylistShade, TitleBar) => Names::single("PLAYLIST_SHADE_BACKGROUND"),
+
+        (WindowType::General, TitleBar) => Names::title_bar("GEN_TOP_CENTER_FILL"),
+        (WindowType::General, CloseButton) => Names::pressed_only("GEN_CLOSE_SELECTED"),
+
+        (WindowType::Minibrowser, TitleBar) => Names::title_bar("MB_TITLE_BAR"),
+        _ => return None,
+    };
+    Some(names)
+}
+
+/// The sprites of one element of a window, grouped by state
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementSprites<'a> {
+    /// Names of the sprites, including those the skin does not have
+    pub names: ElementSpriteNames,
+    /// Sprite drawn normally
+    pub normal: Option<&'a SpriteImage>,
+    /// Sprite drawn while pressed
+    pub pressed: Option<&'a SpriteImage>,
+    /// Sprite drawn while on or focused
+    pub active: Option<&'a SpriteImage>,
+    /// Sprite drawn while pressed and on
+    pub active_pressed: Option<&'a SpriteImage>,
+}
+
+impl<'a> ElementSprites<'a> {
+    pub(crate) fn new(names: ElementSpriteNames, sprites: &'a HashMap<String, SpriteImage>) -> Self {
+        let get = |state| names.get(state).and_then(|name| sprites.get(name));
+        Self {
+            normal: get(ElementState::Normal),
+            pressed: get(ElementState::Pressed),
+            active: get(ElementState::Active),
+            active_pressed: get(ElementState::ActivePressed),
+            names,
+        }
+    }
+
+    /// Get the sprite for a state
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state to draw
+    ///
+    /// # Returns
+    ///
+    /// The sprite, or None if the element has no such state or the skin does not have its sprite
+    pub fn get(&self, state: ElementState) -> Option<&'a SpriteImage> {
+        match state {
+            ElementState::Normal => self.normal,
+            ElementState::Pressed => self.pressed,
+            ElementState::Active => self.active,
+            ElementState::ActivePressed => self.active_pressed,
+        }
+    }
+}
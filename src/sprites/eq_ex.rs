@@ -1,4 +1,4 @@
-use super::SpriteDefinition;
+use super::{SpriteDefinition, SpriteWindowDefinition, WindowType};
 
 pub fn eq_ex_sprites() -> Vec<SpriteDefinition> {
     vec![
@@ -100,3 +100,39 @@ pub fn eq_ex_sprites() -> Vec<SpriteDefinition> {
         },
     ]
 }
+
+/// Returns the window sprites of the equalizer window in shade mode
+pub fn eq_ex_window_sprites() -> Vec<SpriteWindowDefinition> {
+    vec![
+        SpriteWindowDefinition {
+            name: "EQ_SHADE_BACKGROUND".to_string(),
+            sprite_name: "EQ_SHADE_BACKGROUND_SELECTED".to_string(),
+            window_type: WindowType::EqualizerShade,
+            layer: 0,
+            x: 0,
+            y: 0,
+            width: 275,
+            height: 14,
+        },
+        SpriteWindowDefinition {
+            name: "EQ_SHADE_UNSHADE_BUTTON".to_string(),
+            sprite_name: "EQ_MAXIMIZE_BUTTON_ACTIVE".to_string(),
+            window_type: WindowType::EqualizerShade,
+            layer: 2,
+            x: 254,
+            y: 3,
+            width: 9,
+            height: 9,
+        },
+        SpriteWindowDefinition {
+            name: "EQ_SHADE_CLOSE_BUTTON".to_string(),
+            sprite_name: "EQ_SHADE_CLOSE_BUTTON".to_string(),
+            window_type: WindowType::EqualizerShade,
+            layer: 2,
+            x: 264,
+            y: 3,
+            width: 9,
+            height: 9,
+        },
+    ]
+}
@@ -1,4 +1,5 @@
 use super::{SpriteDefinition, SpriteWindowDefinition, WindowType};
+use crate::render::{EqButtons, EqToggle};
 
 pub fn eqmain_sprites() -> Vec<SpriteDefinition> {
     vec![
@@ -699,3 +700,21 @@ pub fn eqmain_window_sprites() -> Vec<SpriteWindowDefinition> {
         },
     ]
 }
+
+/// Returns the sprite name for an equalizer toggle button in a given state
+fn eqmain_toggle_sprite(name: &str, toggle: EqToggle) -> String {
+    match (toggle.lit, toggle.pressed) {
+        (false, false) => name.to_string(),
+        (false, true) => format!("{}_SELECTED", name),
+        (true, false) => format!("{}_ACTIVE", name),
+        (true, true) => format!("{}_ACTIVE_SELECTED", name),
+    }
+}
+
+/// Returns the window sprite names of the ON and AUTO buttons paired with the sprites to draw for their states
+pub fn eqmain_button_sprites(buttons: &EqButtons) -> [(&'static str, String); 2] {
+    [
+        ("EQ_ON_BUTTON", eqmain_toggle_sprite("EQ_ON_BUTTON", buttons.on)),
+        ("EQ_AUTO_BUTTON", eqmain_toggle_sprite("EQ_AUTO_BUTTON", buttons.auto)),
+    ]
+}
@@ -1,4 +1,4 @@
-use super::SpriteDefinition;
+use super::{SpriteDefinition, SpriteWindowDefinition, WindowType};
 
 pub fn gen_sprites() -> Vec<SpriteDefinition> {
     vec![
@@ -580,3 +580,242 @@ pub fn gen_sprites() -> Vec<SpriteDefinition> {
         },
     ]
 }
+
+/// Smallest width of a general purpose window
+pub const GEN_MIN_WIDTH: u32 = 275;
+/// Smallest height of a general purpose window
+pub const GEN_MIN_HEIGHT: u32 = 116;
+/// General purpose windows are resized in steps of this many pixels horizontally
+pub const GEN_WIDTH_STEP: u32 = 25;
+/// General purpose windows are resized in steps of this many pixels vertically
+pub const GEN_HEIGHT_STEP: u32 = 29;
+
+/// Height of the title bar
+const GEN_TOP_HEIGHT: u32 = 20;
+/// Height of the bottom bar
+const GEN_BOTTOM_HEIGHT: u32 = 14;
+/// Width of the title bar pieces
+const GEN_TOP_TILE_WIDTH: u32 = 25;
+/// Width of the bottom corners
+const GEN_BOTTOM_CORNER_WIDTH: u32 = 125;
+/// Width of the left border
+const GEN_LEFT_WIDTH: u32 = 11;
+/// Width of the right border
+const GEN_RIGHT_WIDTH: u32 = 8;
+/// Height of the border pieces just above the bottom bar
+const GEN_MIDDLE_BOTTOM_HEIGHT: u32 = 24;
+/// Distance from the top of the window to the top of the title letters
+const GEN_TITLE_Y: u32 = 4;
+/// Width of a character with no letter in GEN.BMP, such as a space
+const GEN_SPACE_WIDTH: u32 = 5;
+
+/// Snaps a requested general purpose window size to the nearest size Winamp allows (rounding down)
+///
+/// Like the playlist, general purpose windows grow in 25x29 pixel steps starting from 275x116.
+pub fn gen_snap_size(width: u32, height: u32) -> (u32, u32) {
+    let width = width.max(GEN_MIN_WIDTH);
+    let height = height.max(GEN_MIN_HEIGHT);
+    (
+        width - (width - GEN_MIN_WIDTH) % GEN_WIDTH_STEP,
+        height - (height - GEN_MIN_HEIGHT) % GEN_HEIGHT_STEP,
+    )
+}
+
+/// Returns the sprite name and width of a title character.
+///
+/// GEN.BMP only has the letters A-Z. Other characters are drawn as blank space.
+fn gen_char(c: char) -> (Option<String>, u32) {
+    let name = format!("GEN_CHAR_{}_SELECTED", c.to_ascii_uppercase());
+    match gen_sprites()
+        .into_iter()
+        .find(|def| c.is_ascii_alphabetic() && def.name == name)
+    {
+        Some(def) => (Some(name), def.width),
+        None => (None, GEN_SPACE_WIDTH),
+    }
+}
+
+/// Returns the window sprites for a general purpose window of the given size with a title.
+///
+/// The size should already be snapped with `gen_snap_size`. Titles too long for the window are cut off.
+pub fn gen_window_sprites(width: u32, height: u32, title: &str) -> Vec<SpriteWindowDefinition> {
+    let mut sprites = Vec::new();
+
+    let tile =
+        |name: String, sprite_name: &str, layer: u32, x: u32, y: u32, width: u32, height: u32| SpriteWindowDefinition {
+            name,
+            sprite_name: sprite_name.to_string(),
+            window_type: WindowType::General,
+            layer,
+            x,
+            y,
+            width,
+            height,
+        };
+
+    // title bar: the title sits on center tiles, with fill tiles splitting the rest of the space evenly
+    let fill_tiles = (width - 4 * GEN_TOP_TILE_WIDTH) / GEN_TOP_TILE_WIDTH;
+    let mut letters = Vec::new();
+    let mut title_width = 0;
+    for c in title.chars() {
+        let (sprite_name, char_width) = gen_char(c);
+        if title_width + char_width > fill_tiles * GEN_TOP_TILE_WIDTH {
+            break;
+        }
+        letters.push((sprite_name, title_width, char_width));
+        title_width += char_width;
+    }
+    let title_tiles = title_width.div_ceil(GEN_TOP_TILE_WIDTH);
+    let left_tiles = (fill_tiles - title_tiles) / 2;
+    let right_tiles = fill_tiles - title_tiles - left_tiles;
+
+    let mut x = 0;
+    let push_top = |sprites: &mut Vec<SpriteWindowDefinition>, name: String, sprite_name: &str, x: &mut u32| {
+        sprites.push(tile(name, sprite_name, 0, *x, 0, GEN_TOP_TILE_WIDTH, GEN_TOP_HEIGHT));
+        *x += GEN_TOP_TILE_WIDTH;
+    };
+    push_top(
+        &mut sprites,
+        "GEN_TOP_LEFT".to_string(),
+        "GEN_TOP_LEFT_SELECTED",
+        &mut x,
+    );
+    for i in 0..left_tiles {
+        push_top(
+            &mut sprites,
+            format!("GEN_TOP_LEFT_FILL_{}", i),
+            "GEN_TOP_LEFT_RIGHT_FILL_SELECTED",
+            &mut x,
+        );
+    }
+    push_top(
+        &mut sprites,
+        "GEN_TOP_LEFT_END".to_string(),
+        "GEN_TOP_LEFT_END_SELECTED",
+        &mut x,
+    );
+    let title_x = x + (title_tiles * GEN_TOP_TILE_WIDTH - title_width) / 2;
+    for i in 0..title_tiles {
+        push_top(
+            &mut sprites,
+            format!("GEN_TOP_CENTER_FILL_{}", i),
+            "GEN_TOP_CENTER_FILL_SELECTED",
+            &mut x,
+        );
+    }
+    push_top(
+        &mut sprites,
+        "GEN_TOP_RIGHT_END".to_string(),
+        "GEN_TOP_RIGHT_END_SELECTED",
+        &mut x,
+    );
+    for i in 0..right_tiles {
+        push_top(
+            &mut sprites,
+            format!("GEN_TOP_RIGHT_FILL_{}", i),
+            "GEN_TOP_LEFT_RIGHT_FILL_SELECTED",
+            &mut x,
+        );
+    }
+    push_top(
+        &mut sprites,
+        "GEN_TOP_RIGHT".to_string(),
+        "GEN_TOP_RIGHT_SELECTED",
+        &mut x,
+    );
+
+    for (i, (sprite_name, offset, char_width)) in letters.into_iter().enumerate() {
+        if let Some(sprite_name) = sprite_name {
+            sprites.push(tile(
+                format!("GEN_TITLE_CHAR_{}", i),
+                &sprite_name,
+                1,
+                title_x + offset,
+                GEN_TITLE_Y,
+                char_width,
+                7,
+            ));
+        }
+    }
+
+    // sides: full tiles, then a shorter piece that meets the bottom bar
+    let bottom_y = height - GEN_BOTTOM_HEIGHT;
+    let middle_bottom_y = bottom_y - GEN_MIDDLE_BOTTOM_HEIGHT;
+    for (i, y) in (GEN_TOP_HEIGHT..middle_bottom_y)
+        .step_by(GEN_HEIGHT_STEP as usize)
+        .enumerate()
+    {
+        sprites.push(tile(
+            format!("GEN_MIDDLE_LEFT_{}", i),
+            "GEN_MIDDLE_LEFT",
+            0,
+            0,
+            y,
+            GEN_LEFT_WIDTH,
+            GEN_HEIGHT_STEP,
+        ));
+        sprites.push(tile(
+            format!("GEN_MIDDLE_RIGHT_{}", i),
+            "GEN_MIDDLE_RIGHT",
+            0,
+            width - GEN_RIGHT_WIDTH,
+            y,
+            GEN_RIGHT_WIDTH,
+            GEN_HEIGHT_STEP,
+        ));
+    }
+    sprites.push(tile(
+        "GEN_MIDDLE_LEFT_BOTTOM".to_string(),
+        "GEN_MIDDLE_LEFT_BOTTOM",
+        0,
+        0,
+        middle_bottom_y,
+        GEN_LEFT_WIDTH,
+        GEN_MIDDLE_BOTTOM_HEIGHT,
+    ));
+    sprites.push(tile(
+        "GEN_MIDDLE_RIGHT_BOTTOM".to_string(),
+        "GEN_MIDDLE_RIGHT_BOTTOM",
+        0,
+        width - GEN_RIGHT_WIDTH,
+        middle_bottom_y,
+        GEN_RIGHT_WIDTH,
+        GEN_MIDDLE_BOTTOM_HEIGHT,
+    ));
+
+    // bottom bar: corners with fill tiles in between
+    sprites.push(tile(
+        "GEN_BOTTOM_LEFT".to_string(),
+        "GEN_BOTTOM_LEFT",
+        0,
+        0,
+        bottom_y,
+        GEN_BOTTOM_CORNER_WIDTH,
+        GEN_BOTTOM_HEIGHT,
+    ));
+    for (i, x) in (GEN_BOTTOM_CORNER_WIDTH..width - GEN_BOTTOM_CORNER_WIDTH)
+        .step_by(GEN_WIDTH_STEP as usize)
+        .enumerate()
+    {
+        sprites.push(tile(
+            format!("GEN_BOTTOM_FILL_{}", i),
+            "GEN_BOTTOM_FILL",
+            0,
+            x,
+            bottom_y,
+            GEN_WIDTH_STEP,
+            GEN_BOTTOM_HEIGHT,
+        ));
+    }
+    sprites.push(tile(
+        "GEN_BOTTOM_RIGHT".to_string(),
+        "GEN_BOTTOM_RIGHT",
+        0,
+        width - GEN_BOTTOM_CORNER_WIDTH,
+        bottom_y,
+        GEN_BOTTOM_CORNER_WIDTH,
+        GEN_BOTTOM_HEIGHT,
+    ));
+
+    sprites
+}
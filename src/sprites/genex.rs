@@ -0,0 +1,214 @@
+//! Colors encoded in GENEX.BMP
+//!
+//! Besides its button and scrollbar sprites, GENEX.BMP has a strip of single pixels along its top row, one every
+//! other pixel starting at x=48. Winamp reads the colors of its media library and other plugin windows from them.
+
+use std::io::Cursor;
+use std::path::Path;
+
+use image::{GenericImageView, Rgb};
+
+use crate::archive::WszArchive;
+use crate::error::{Result, WszError};
+
+/// Number of colors in the strip
+pub const GENEX_COLOR_COUNT: usize = 22;
+
+/// X position of the first color
+const GENEX_COLOR_X: u32 = 48;
+
+/// Distance between colors
+const GENEX_COLOR_STEP: u32 = 2;
+
+pub const GENEX_ITEM_BACKGROUND: usize = 0;
+pub const GENEX_ITEM_FOREGROUND: usize = 1;
+pub const GENEX_WINDOW_BACKGROUND: usize = 2;
+pub const GENEX_BUTTON_TEXT: usize = 3;
+pub const GENEX_WINDOW_TEXT: usize = 4;
+pub const GENEX_DIVIDER: usize = 5;
+pub const GENEX_PLAYLIST_SELECTION: usize = 6;
+pub const GENEX_LIST_HEADER_BACKGROUND: usize = 7;
+pub const GENEX_LIST_HEADER_TEXT: usize = 8;
+pub const GENEX_LIST_HEADER_FRAME_TOP_LEFT: usize = 9;
+pub const GENEX_LIST_HEADER_FRAME_BOTTOM_RIGHT: usize = 10;
+pub const GENEX_LIST_HEADER_FRAME_PRESSED: usize = 11;
+pub const GENEX_LIST_HEADER_DEAD_AREA: usize = 12;
+pub const GENEX_SCROLLBAR_ONE: usize = 13;
+pub const GENEX_SCROLLBAR_TWO: usize = 14;
+pub const GENEX_PRESSED_SCROLLBAR_ONE: usize = 15;
+pub const GENEX_PRESSED_SCROLLBAR_TWO: usize = 16;
+pub const GENEX_SCROLLBAR_DEAD_AREA: usize = 17;
+pub const GENEX_LIST_TEXT_HIGHLIGHTED: usize = 18;
+pub const GENEX_LIST_TEXT_HIGHLIGHTED_BACKGROUND: usize = 19;
+pub const GENEX_LIST_TEXT_SELECTED: usize = 20;
+pub const GENEX_LIST_TEXT_SELECTED_BACKGROUND: usize = 21;
+
+/// The UI colors from GENEX.BMP
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenExColors {
+    // colors with indices given by the GENEX_* constants
+    colors: [Rgb<u8>; GENEX_COLOR_COUNT],
+}
+
+impl GenExColors {
+    /// Find GENEX.BMP in the archive contents, at any path, and read its colors
+    pub fn from_archive(archive: &WszArchive) -> Result<Self> {
+        let data = archive
+            .iter()
+            .find(|(name, _)| {
+                Path::new(name)
+                    .file_name()
+                    .and_then(|s| s.to_str())
+                    .is_some_and(|s| s.eq_ignore_ascii_case("GENEX.BMP"))
+            })
+            .map(|(_, data)| data)
+            .ok_or(WszError::NotFound("GENEX.BMP".to_string()))?;
+
+        let image = image::ImageReader::new(Cursor::new(data))
+            .with_guessed_format()?
+            .decode()?;
+        Self::from_image(&image)
+    }
+
+    /// Read the colors from a decoded GENEX.BMP
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - The GENEX.BMP image
+    ///
+    /// # Returns
+    ///
+    /// The colors, or an error if the image is too narrow to hold them all
+    pub fn from_image<I: GenericImageView<Pixel = image::Rgba<u8>>>(image: &I) -> Result<Self> {
+        let min_width = GENEX_COLOR_X + (GENEX_COLOR_COUNT as u32 - 1) * GENEX_COLOR_STEP + 1;
+        if image.width() < min_width || image.height() == 0 {
+            return Err(WszError::ArgumentError(format!(
+                "GENEX.BMP is {}x{}, too small to hold its colors",
+                image.width(),
+                image.height()
+            )));
+        }
+
+        let mut colors = [Rgb([0, 0, 0]); GENEX_COLOR_COUNT];
+        for (i, color) in colors.iter_mut().enumerate() {
+            let pixel = image.get_pixel(GENEX_COLOR_X + i as u32 * GENEX_COLOR_STEP, 0);
+            *color = Rgb([pixel.0[0], pixel.0[1], pixel.0[2]]);
+        }
+        Ok(Self { colors })
+    }
+
+    /// Get a color by index
+    pub fn get(&self, index: usize) -> Option<Rgb<u8>> {
+        self.colors.get(index).copied()
+    }
+
+    /// Background of list items
+    pub fn item_background(&self) -> Rgb<u8> {
+        self.colors[GENEX_ITEM_BACKGROUND]
+    }
+
+    /// Text of list items
+    pub fn item_foreground(&self) -> Rgb<u8> {
+        self.colors[GENEX_ITEM_FOREGROUND]
+    }
+
+    /// Background of windows
+    pub fn window_background(&self) -> Rgb<u8> {
+        self.colors[GENEX_WINDOW_BACKGROUND]
+    }
+
+    /// Text on buttons
+    pub fn button_text(&self) -> Rgb<u8> {
+        self.colors[GENEX_BUTTON_TEXT]
+    }
+
+    /// Text on windows, outside of lists
+    pub fn window_text(&self) -> Rgb<u8> {
+        self.colors[GENEX_WINDOW_TEXT]
+    }
+
+    /// Dividers between panes
+    pub fn divider(&self) -> Rgb<u8> {
+        self.colors[GENEX_DIVIDER]
+    }
+
+    /// Selected playlist entries
+    pub fn playlist_selection(&self) -> Rgb<u8> {
+        self.colors[GENEX_PLAYLIST_SELECTION]
+    }
+
+    /// Background of list column headers
+    pub fn list_header_background(&self) -> Rgb<u8> {
+        self.colors[GENEX_LIST_HEADER_BACKGROUND]
+    }
+
+    /// Text of list column headers
+    pub fn list_header_text(&self) -> Rgb<u8> {
+        self.colors[GENEX_LIST_HEADER_TEXT]
+    }
+
+    /// Top and left edges of list column header frames
+    pub fn list_header_frame_top_left(&self) -> Rgb<u8> {
+        self.colors[GENEX_LIST_HEADER_FRAME_TOP_LEFT]
+    }
+
+    /// Bottom and right edges of list column header frames
+    pub fn list_header_frame_bottom_right(&self) -> Rgb<u8> {
+        self.colors[GENEX_LIST_HEADER_FRAME_BOTTOM_RIGHT]
+    }
+
+    /// Frame of a pressed list column header
+    pub fn list_header_frame_pressed(&self) -> Rgb<u8> {
+        self.colors[GENEX_LIST_HEADER_FRAME_PRESSED]
+    }
+
+    /// Header area to the right of the last column
+    pub fn list_header_dead_area(&self) -> Rgb<u8> {
+        self.colors[GENEX_LIST_HEADER_DEAD_AREA]
+    }
+
+    /// First scrollbar color
+    pub fn scrollbar_one(&self) -> Rgb<u8> {
+        self.colors[GENEX_SCROLLBAR_ONE]
+    }
+
+    /// Second scrollbar color
+    pub fn scrollbar_two(&self) -> Rgb<u8> {
+        self.colors[GENEX_SCROLLBAR_TWO]
+    }
+
+    /// First scrollbar color while pressed
+    pub fn pressed_scrollbar_one(&self) -> Rgb<u8> {
+        self.colors[GENEX_PRESSED_SCROLLBAR_ONE]
+    }
+
+    /// Second scrollbar color while pressed
+    pub fn pressed_scrollbar_two(&self) -> Rgb<u8> {
+        self.colors[GENEX_PRESSED_SCROLLBAR_TWO]
+    }
+
+    /// Corner where a horizontal and vertical scrollbar meet
+    pub fn scrollbar_dead_area(&self) -> Rgb<u8> {
+        self.colors[GENEX_SCROLLBAR_DEAD_AREA]
+    }
+
+    /// Text of highlighted list items
+    pub fn list_text_highlighted(&self) -> Rgb<u8> {
+        self.colors[GENEX_LIST_TEXT_HIGHLIGHTED]
+    }
+
+    /// Background of highlighted list items
+    pub fn list_text_highlighted_background(&self) -> Rgb<u8> {
+        self.colors[GENEX_LIST_TEXT_HIGHLIGHTED_BACKGROUND]
+    }
+
+    /// Text of selected list items
+    pub fn list_text_selected(&self) -> Rgb<u8> {
+        self.colors[GENEX_LIST_TEXT_SELECTED]
+    }
+
+    /// Background of selected list items
+    pub fn list_text_selected_background(&self) -> Rgb<u8> {
+        self.colors[GENEX_LIST_TEXT_SELECTED_BACKGROUND]
+    }
+}
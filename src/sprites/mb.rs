@@ -0,0 +1,255 @@
+use super::{SpriteDefinition, SpriteWindowDefinition, WindowType};
+
+pub fn mb_sprites() -> Vec<SpriteDefinition> {
+    vec![
+        SpriteDefinition {
+            name: "MB_TOP_LEFT_CORNER_SELECTED".to_string(),
+            sprite_sheet: "MB.BMP".to_string(),
+            x: 0,
+            y: 0,
+            width: 25,
+            height: 20,
+        },
+        SpriteDefinition {
+            name: "MB_TITLE_BAR_SELECTED".to_string(),
+            sprite_sheet: "MB.BMP".to_string(),
+            x: 26,
+            y: 0,
+            width: 100,
+            height: 20,
+        },
+        SpriteDefinition {
+            name: "MB_TOP_TILE_SELECTED".to_string(),
+            sprite_sheet: "MB.BMP".to_string(),
+            x: 127,
+            y: 0,
+            width: 25,
+            height: 20,
+        },
+        SpriteDefinition {
+            name: "MB_TOP_RIGHT_CORNER_SELECTED".to_string(),
+            sprite_sheet: "MB.BMP".to_string(),
+            x: 153,
+            y: 0,
+            width: 25,
+            height: 20,
+        },
+        SpriteDefinition {
+            name: "MB_TOP_LEFT_CORNER".to_string(),
+            sprite_sheet: "MB.BMP".to_string(),
+            x: 0,
+            y: 21,
+            width: 25,
+            height: 20,
+        },
+        SpriteDefinition {
+            name: "MB_TITLE_BAR".to_string(),
+            sprite_sheet: "MB.BMP".to_string(),
+            x: 26,
+            y: 21,
+            width: 100,
+            height: 20,
+        },
+        SpriteDefinition {
+            name: "MB_TOP_TILE".to_string(),
+            sprite_sheet: "MB.BMP".to_string(),
+            x: 127,
+            y: 21,
+            width: 25,
+            height: 20,
+        },
+        SpriteDefinition {
+            name: "MB_TOP_RIGHT_CORNER".to_string(),
+            sprite_sheet: "MB.BMP".to_string(),
+            x: 153,
+            y: 21,
+            width: 25,
+            height: 20,
+        },
+        SpriteDefinition {
+            name: "MB_LEFT_TILE".to_string(),
+            sprite_sheet: "MB.BMP".to_string(),
+            x: 127,
+            y: 42,
+            width: 11,
+            height: 29,
+        },
+        SpriteDefinition {
+            name: "MB_RIGHT_TILE".to_string(),
+            sprite_sheet: "MB.BMP".to_string(),
+            x: 139,
+            y: 42,
+            width: 8,
+            height: 29,
+        },
+        SpriteDefinition {
+            name: "MB_BOTTOM_LEFT_CORNER".to_string(),
+            sprite_sheet: "MB.BMP".to_string(),
+            x: 0,
+            y: 42,
+            width: 125,
+            height: 14,
+        },
+        SpriteDefinition {
+            name: "MB_BOTTOM_RIGHT_CORNER".to_string(),
+            sprite_sheet: "MB.BMP".to_string(),
+            x: 0,
+            y: 57,
+            width: 125,
+            height: 14,
+        },
+        SpriteDefinition {
+            name: "MB_BOTTOM_TILE".to_string(),
+            sprite_sheet: "MB.BMP".to_string(),
+            x: 127,
+            y: 72,
+            width: 25,
+            height: 14,
+        },
+    ]
+}
+
+/// Smallest width of the minibrowser window
+pub const MINIBROWSER_MIN_WIDTH: u32 = 275;
+/// Smallest height of the minibrowser window
+pub const MINIBROWSER_MIN_HEIGHT: u32 = 116;
+/// The minibrowser is resized in steps of this many pixels horizontally
+pub const MINIBROWSER_WIDTH_STEP: u32 = 25;
+/// The minibrowser is resized in steps of this many pixels vertically
+pub const MINIBROWSER_HEIGHT_STEP: u32 = 29;
+
+/// Height of the title bar
+const MINIBROWSER_TOP_HEIGHT: u32 = 20;
+/// Height of the bottom bar
+const MINIBROWSER_BOTTOM_HEIGHT: u32 = 14;
+
+/// Snaps a requested minibrowser size to the nearest size Winamp allows (rounding down)
+///
+/// The minibrowser grows in 25x29 pixel steps starting from 275x116.
+pub fn mb_snap_size(width: u32, height: u32) -> (u32, u32) {
+    let width = width.max(MINIBROWSER_MIN_WIDTH);
+    let height = height.max(MINIBROWSER_MIN_HEIGHT);
+    (
+        width - (width - MINIBROWSER_MIN_WIDTH) % MINIBROWSER_WIDTH_STEP,
+        height - (height - MINIBROWSER_MIN_HEIGHT) % MINIBROWSER_HEIGHT_STEP,
+    )
+}
+
+/// Returns the window sprites for a minibrowser window of the given size.
+///
+/// The size should already be snapped with `mb_snap_size`.
+pub fn mb_window_sprites(width: u32, height: u32) -> Vec<SpriteWindowDefinition> {
+    let mut sprites = Vec::new();
+
+    let tile =
+        |name: String, sprite_name: &str, layer: u32, x: u32, y: u32, width: u32, height: u32| SpriteWindowDefinition {
+            name,
+            sprite_name: sprite_name.to_string(),
+            window_type: WindowType::Minibrowser,
+            layer,
+            x,
+            y,
+            width,
+            height,
+        };
+
+    // title bar: corners with tiles in between and the title centered on top
+    sprites.push(tile(
+        "MB_TOP_LEFT_CORNER".to_string(),
+        "MB_TOP_LEFT_CORNER_SELECTED",
+        1,
+        0,
+        0,
+        25,
+        MINIBROWSER_TOP_HEIGHT,
+    ));
+    for (i, x) in (25..width - 25).step_by(25).enumerate() {
+        sprites.push(tile(
+            format!("MB_TOP_TILE_{}", i),
+            "MB_TOP_TILE_SELECTED",
+            1,
+            x,
+            0,
+            25,
+            MINIBROWSER_TOP_HEIGHT,
+        ));
+    }
+    // n.b. this overlaps the neighbouring tiles. this is intentional.
+    sprites.push(tile(
+        "MB_TITLE_BAR".to_string(),
+        "MB_TITLE_BAR_SELECTED",
+        2,
+        (width - 100) / 2,
+        0,
+        100,
+        MINIBROWSER_TOP_HEIGHT,
+    ));
+    sprites.push(tile(
+        "MB_TOP_RIGHT_CORNER".to_string(),
+        "MB_TOP_RIGHT_CORNER_SELECTED",
+        1,
+        width - 25,
+        0,
+        25,
+        MINIBROWSER_TOP_HEIGHT,
+    ));
+
+    // sides. the last tiles run under the bottom bar, which is drawn on top of them.
+    let bottom_y = height - MINIBROWSER_BOTTOM_HEIGHT;
+    for (i, y) in (MINIBROWSER_TOP_HEIGHT..bottom_y)
+        .step_by(MINIBROWSER_HEIGHT_STEP as usize)
+        .enumerate()
+    {
+        sprites.push(tile(
+            format!("MB_LEFT_TILE_{}", i),
+            "MB_LEFT_TILE",
+            1,
+            0,
+            y,
+            11,
+            MINIBROWSER_HEIGHT_STEP,
+        ));
+        sprites.push(tile(
+            format!("MB_RIGHT_TILE_{}", i),
+            "MB_RIGHT_TILE",
+            1,
+            width - 8,
+            y,
+            8,
+            MINIBROWSER_HEIGHT_STEP,
+        ));
+    }
+
+    // bottom bar: corners with tiles in between. the right corner holds the navigation buttons.
+    sprites.push(tile(
+        "MB_BOTTOM_LEFT_CORNER".to_string(),
+        "MB_BOTTOM_LEFT_CORNER",
+        2,
+        0,
+        bottom_y,
+        125,
+        MINIBROWSER_BOTTOM_HEIGHT,
+    ));
+    for (i, x) in (125..width - 125).step_by(25).enumerate() {
+        sprites.push(tile(
+            format!("MB_BOTTOM_TILE_{}", i),
+            "MB_BOTTOM_TILE",
+            2,
+            x,
+            bottom_y,
+            25,
+            MINIBROWSER_BOTTOM_HEIGHT,
+        ));
+    }
+    sprites.push(tile(
+        "MB_BOTTOM_RIGHT_CORNER".to_string(),
+        "MB_BOTTOM_RIGHT_CORNER",
+        2,
+        width - 125,
+        bottom_y,
+        125,
+        MINIBROWSER_BOTTOM_HEIGHT,
+    ));
+
+    sprites
+}
@@ -1,11 +1,16 @@
 //! Sprite definitions for Winamp skin elements
 
+pub mod atlas;
 mod balance;
+pub mod bmp;
 mod cbuttons;
+pub mod element;
 mod eq_ex;
 mod eqmain;
 mod gen_;
+pub mod genex;
 mod main;
+mod mb;
 mod monoster;
 mod numbers;
 mod nums_ex;
@@ -19,6 +24,11 @@ mod volume;
 
 use crate::archive::WszArchive;
 use crate::error::{Result, WszError};
+use crate::render::background::Background;
+use crate::render::layout::Layout;
+use crate::render::target::RenderTarget;
+use crate::render::{RenderOptions, RenderedLayer};
+use crate::text::region::{self, Regions};
 
 use std::collections::HashMap;
 use std::io::{self, Cursor};
@@ -26,7 +36,7 @@ use std::path::Path;
 
 use image::{DynamicImage, GenericImage, GenericImageView, ImageBuffer, Rgb, Rgba, RgbaImage};
 
-const SPRITE_SHEETS: [&str; 16] = [
+const SPRITE_SHEETS: [&str; 17] = [
     "BALANCE.BMP",
     "CBUTTONS.BMP",
     "MAIN.BMP",
@@ -43,13 +53,21 @@ const SPRITE_SHEETS: [&str; 16] = [
     "TITLEBAR.BMP",
     "VOLUME.BMP",
     "GEN.BMP",
+    "MB.BMP",
 ];
 
+/// Bitmaps Winamp reads that are not sprite sheets
+pub(crate) const OTHER_BITMAPS: [&str; 1] = ["GENEX.BMP"];
+/// Bitmaps Winamp 5 reads that this crate does not draw
+pub(crate) const EXTRA_BITMAPS: [&str; 1] = ["VIDEO.BMP"];
+/// Names of the text configuration files, as Winamp's base skin spells them
+pub(crate) const TEXT_FILES: [&str; 3] = ["viscolor.txt", "pledit.txt", "region.txt"];
+
 /// The standard background color used in Winamp skin BMPs (#00C5FF)
 const WINAMP_BG_COLOR: Rgba<u8> = Rgba([0, 198, 255, 255]);
 
 /// Represents a sprite within a sprite sheet
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SpriteDefinition {
     /// Name of the sprite
     pub name: String,
@@ -73,6 +91,12 @@ pub struct SpriteManager {
     definitions: HashMap<String, SpriteDefinition>,
 }
 
+impl Default for SpriteManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SpriteManager {
     /// Creates a new SpriteManager
     ///
@@ -103,6 +127,7 @@ impl SpriteManager {
         titlebar::titlebar_sprites().iter().for_each(&mut ins_fn);
         volume::volume_sprites().iter().for_each(&mut ins_fn);
         gen_::gen_sprites().iter().for_each(&mut ins_fn);
+        mb::mb_sprites().iter().for_each(&mut ins_fn);
 
         Self { definitions }
     }
@@ -148,31 +173,12 @@ impl SpriteManager {
             )));
         }
 
-        // First, try to find the BMP file directly (case insensitive)
-        let key = archive
-            .keys()
-            .find(|k| k.to_uppercase() == sprite_sheet_name.to_uppercase());
-
-        // If not found, look for it in any subdirectory
-        let key = match key {
-            Some(k) => k,
-            None => {
-                // Look for any file ending with this filename (in any directory)
-                archive
-                    .keys()
-                    .find(|k| {
-                        let path = Path::new(k);
-                        let file_name = path.file_name().and_then(|s| s.to_str());
-                        file_name.map_or(false, |s| s.to_uppercase() == sprite_sheet_name.to_uppercase())
-                    })
-                    .ok_or_else(|| {
-                        io::Error::new(
-                            io::ErrorKind::NotFound,
-                            format!("{} not found in skin (at any path)", sprite_sheet_name),
-                        )
-                    })?
-            }
-        };
+        let key = find_sprite_sheet(archive, sprite_sheet_name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} not found in skin (at any path)", sprite_sheet_name),
+            )
+        })?;
 
         let data = &archive[key];
         let cursor = Cursor::new(data);
@@ -249,6 +255,48 @@ impl SpriteManager {
         Ok(image)
     }
 
+    /// Draws sprites over an existing sprite sheet, keeping the parts of the sheet no sprite covers
+    ///
+    /// The sheet is grown, filled with the Winamp background color, if a sprite does not fit on it.
+    ///
+    /// # Arguments
+    ///
+    /// * `sprite_sheet` - Existing sprite sheet
+    /// * `sprites` - HashMap of sprite names to sprite images
+    /// * `sprite_sheet_name` - Name of the sprite sheet
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the updated sprite sheet
+    pub fn update_sprite_sheet(
+        &self,
+        sprite_sheet: &DynamicImage,
+        sprites: &HashMap<String, SpriteImage>,
+        sprite_sheet_name: &str,
+    ) -> Result<DynamicImage> {
+        let (mut width, mut height) = sprite_sheet.dimensions();
+        for def in self.definitions.values() {
+            if def.sprite_sheet == sprite_sheet_name {
+                if let Some(sprite_img) = sprites.get(&def.name) {
+                    width = width.max(def.x + sprite_img.width());
+                    height = height.max(def.y + sprite_img.height());
+                }
+            }
+        }
+
+        let mut image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(width, height, WINAMP_BG_COLOR));
+        image.copy_from(&sprite_sheet.to_rgba8(), 0, 0)?;
+        for def in self.definitions.values() {
+            if def.sprite_sheet == sprite_sheet_name {
+                if let Some(sprite_img) = sprites.get(&def.name) {
+                    image.copy_from(sprite_img, def.x, def.y)?;
+                }
+            }
+        }
+
+        Ok(image)
+    }
+
     /// Extracts a specific sprite from a sprite sheet image
     ///
     /// # Arguments
@@ -259,7 +307,7 @@ impl SpriteManager {
     /// # Returns
     ///
     /// A Result containing the extracted sprite as a new image.
-    /// 
+    ///
     /// If the sprite is partially off the sheet, it will be drawn with whatever part overlaps the sheet.
     pub fn extract_sprite(&self, name: &str, sprite_sheet: &DynamicImage) -> Result<SpriteImage> {
         let def = self
@@ -270,8 +318,16 @@ impl SpriteManager {
             return Ok(ImageBuffer::new(0, 0));
         }
 
-        let width = if def.x + def.width > sprite_sheet.width() { sprite_sheet.width() - def.x } else { def.width };
-        let height = if def.y + def.height > sprite_sheet.height() { sprite_sheet.height() - def.y } else { def.height };
+        let width = if def.x + def.width > sprite_sheet.width() {
+            sprite_sheet.width() - def.x
+        } else {
+            def.width
+        };
+        let height = if def.y + def.height > sprite_sheet.height() {
+            sprite_sheet.height() - def.y
+        } else {
+            def.height
+        };
 
         let mut sprite_img = ImageBuffer::new(width, height);
 
@@ -321,15 +377,33 @@ impl SpriteManager {
     }
 }
 
-const WINDOW_HEIGHT: u32 = 435;
-const WINDOW_WIDTH: u32 = 275;
+/// Finds the archive entry for a sprite sheet, at the top level or in any subdirectory, ignoring case
+///
+/// # Arguments
+///
+/// * `archive` - WSZ archive
+/// * `sprite_sheet_name` - Name of the sprite sheet, e.g. "MAIN.BMP"
+///
+/// # Returns
+///
+/// The entry's name, or None if the skin does not have the sheet
+pub fn find_sprite_sheet<'a>(archive: &'a WszArchive, sprite_sheet_name: &str) -> Option<&'a String> {
+    archive
+        .keys()
+        .find(|k| k.eq_ignore_ascii_case(sprite_sheet_name))
+        .or_else(|| {
+            archive.keys().find(|k| {
+                let file_name = Path::new(k).file_name().and_then(|s| s.to_str());
+                file_name.is_some_and(|s| s.eq_ignore_ascii_case(sprite_sheet_name))
+            })
+        })
+}
+
+pub(crate) const WINDOW_WIDTH: u32 = 275;
+pub(crate) const MAIN_WINDOW_HEIGHT: u32 = 116;
+pub(crate) const EQUALIZER_WINDOW_HEIGHT: u32 = 116;
+pub(crate) const SHADE_WINDOW_HEIGHT: u32 = 14;
 const MAX_LAYER: u32 = 3;
-const MAIN_WINDOW_START_Y: u32 = 0;
-const MAIN_WINDOW_START_X: u32 = 0;
-const EQUALIZER_WINDOW_START_Y: u32 = 116;
-const EQUALIZER_WINDOW_START_X: u32 = 0;
-const PLAYLIST_WINDOW_START_Y: u32 = 232;
-const PLAYLIST_WINDOW_START_X: u32 = 0;
 
 pub type WindowImage = ImageBuffer<Rgba<u8>, Vec<u8>>;
 
@@ -342,10 +416,20 @@ pub enum WindowType {
     Equalizer,
     /// Playlist window
     Playlist,
+    /// General purpose window, used by Winamp for plugin windows
+    General,
+    /// Minibrowser window
+    Minibrowser,
+    /// Main window in shade mode
+    MainShade,
+    /// Equalizer window in shade mode
+    EqualizerShade,
+    /// Playlist window in shade mode
+    PlaylistShade,
 }
 
 /// Represents a sprite within a window image   
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SpriteWindowDefinition {
     /// Name of the element
     pub name: String,
@@ -366,17 +450,35 @@ pub struct SpriteWindowDefinition {
 }
 
 /// Rasterizes sprites into an image resembling a Winamp window
+///
+/// Draw order is deterministic, so the same inputs always produce the same pixels: windows are drawn in
+/// layout order, each window's layers from bottom to top, and the sprites within a layer in the order
+/// they were added.
 pub struct SpriteWindowManager {
-    definitions: HashMap<String, SpriteWindowDefinition>,
-    bg_color: Rgb<u8>,
+    /// Window sprites in draw order. Within a layer, later definitions are drawn on top of earlier ones.
+    definitions: Vec<SpriteWindowDefinition>,
+    background: Background,
+    playlist_size: (u32, u32),
+    general_size: (u32, u32),
+    minibrowser_size: (u32, u32),
+    generated: HashMap<String, SpriteImage>,
+    layout: Layout,
+    regions: Regions,
+}
+
+impl Default for SpriteWindowManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SpriteWindowManager {
     pub fn new() -> Self {
-        let mut definitions = HashMap::new();
+        let mut definitions: Vec<SpriteWindowDefinition> = Vec::new();
 
-        let mut ins_fn = |def: &SpriteWindowDefinition| {
-            definitions.insert(def.name.clone(), def.clone());
+        let mut ins_fn = |def: &SpriteWindowDefinition| match definitions.iter_mut().find(|d| d.name == def.name) {
+            Some(existing) => *existing = def.clone(),
+            None => definitions.push(def.clone()),
         };
 
         balance::balance_window_sprites().iter().for_each(&mut ins_fn);
@@ -384,78 +486,468 @@ impl SpriteWindowManager {
         main::main_window_sprites().iter().for_each(&mut ins_fn);
         monoster::monoster_window_sprites().iter().for_each(&mut ins_fn);
         playpaus::playpaus_window_sprites().iter().for_each(&mut ins_fn);
-        pledit::pledit_window_sprites().iter().for_each(&mut ins_fn);
+        pledit::pledit_window_sprites(pledit::PLAYLIST_MIN_WIDTH, pledit::PLAYLIST_DEFAULT_HEIGHT)
+            .iter()
+            .for_each(&mut ins_fn);
+        pledit::pledit_shade_window_sprites(pledit::PLAYLIST_MIN_WIDTH)
+            .iter()
+            .for_each(&mut ins_fn);
         eqmain::eqmain_window_sprites().iter().for_each(&mut ins_fn);
+        eq_ex::eq_ex_window_sprites().iter().for_each(&mut ins_fn);
         posbar::posbar_window_sprites().iter().for_each(&mut ins_fn);
         shufrep::shufrep_window_sprites().iter().for_each(&mut ins_fn);
         titlebar::titlebar_window_sprites().iter().for_each(&mut ins_fn);
+        titlebar::titlebar_shade_window_sprites().iter().for_each(&mut ins_fn);
         volume::volume_window_sprites().iter().for_each(&mut ins_fn);
+        gen_::gen_window_sprites(gen_::GEN_MIN_WIDTH, gen_::GEN_MIN_HEIGHT, "")
+            .iter()
+            .for_each(&mut ins_fn);
+        mb::mb_window_sprites(mb::MINIBROWSER_MIN_WIDTH, mb::MINIBROWSER_MIN_HEIGHT)
+            .iter()
+            .for_each(&mut ins_fn);
 
         Self {
             definitions,
-            bg_color: Rgb([0, 0, 0]),
+            background: Background::new(),
+            playlist_size: (pledit::PLAYLIST_MIN_WIDTH, pledit::PLAYLIST_DEFAULT_HEIGHT),
+            general_size: (gen_::GEN_MIN_WIDTH, gen_::GEN_MIN_HEIGHT),
+            minibrowser_size: (mb::MINIBROWSER_MIN_WIDTH, mb::MINIBROWSER_MIN_HEIGHT),
+            generated: HashMap::new(),
+            layout: Layout::classic(),
+            regions: Regions::default(),
         }
     }
 
-    /// Draws all sprites in a window
+    /// Draws all windows in the layout, stacked in layout order
     pub fn draw_all_sprites(&self, sprites: &HashMap<String, SpriteImage>) -> Result<WindowImage> {
-        let mut window = ImageBuffer::from_pixel(
-            WINDOW_WIDTH,
-            WINDOW_HEIGHT,
-            Rgba([self.bg_color.0[0], self.bg_color.0[1], self.bg_color.0[2], 255]),
-        );
+        let (width, height) = self.layout_size();
+        let mut window = ImageBuffer::from_pixel(width, height, self.background.canvas);
+        self.draw_to(&mut window, sprites)?;
+        Ok(window)
+    }
+
+    /// Draws all windows in the layout onto a render target, stacked in layout order
+    ///
+    /// Only the windows' areas are drawn; the rest of the target is left untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - Surface to draw onto. Must be at least `layout_size()` big.
+    /// * `sprites` - A map of sprite names to sprite images
+    pub fn draw_to<T: RenderTarget + ?Sized>(
+        &self,
+        target: &mut T,
+        sprites: &HashMap<String, SpriteImage>,
+    ) -> Result<()> {
+        for placement in &self.layout.windows {
+            self.draw_window_to(target, placement.window_type, placement.x, placement.y, sprites)?;
+        }
+        Ok(())
+    }
+
+    /// Draws a single window onto a render target
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - Surface to draw onto
+    /// * `window_type` - Window to draw
+    /// * `x` - X position of the window's left edge on the target
+    /// * `y` - Y position of the window's top edge on the target
+    /// * `sprites` - A map of sprite names to sprite images
+    pub fn draw_window_to<T: RenderTarget + ?Sized>(
+        &self,
+        target: &mut T,
+        window_type: WindowType,
+        x: u32,
+        y: u32,
+        sprites: &HashMap<String, SpriteImage>,
+    ) -> Result<()> {
+        let (width, height) = self.window_size(window_type);
+        let (target_width, target_height) = target.size();
+        if x + width > target_width || y + height > target_height {
+            return Err(WszError::ArgumentError(format!(
+                "Window {:?} does not fit in the render target",
+                window_type
+            )));
+        }
+        self.background.draw(target, window_type, x, y, width, height);
+        for (sprite_def, sprite) in self.window_sprites(window_type, sprites) {
+            Self::draw_sprite_at(target, sprite, sprite_def, x, y)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the size of the image needed to fit every window in the layout
+    ///
+    /// # Returns
+    ///
+    /// The (width, height) of the composite image
+    pub fn layout_size(&self) -> (u32, u32) {
+        self.layout.windows.iter().fold((0, 0), |(width, height), placement| {
+            let (window_width, window_height) = self.window_size(placement.window_type);
+            (
+                width.max(placement.x + window_width),
+                height.max(placement.y + window_height),
+            )
+        })
+    }
+
+    /// Draws the sprites of a single window into an image of just that window
+    ///
+    /// # Arguments
+    ///
+    /// * `window_type` - Window to draw
+    /// * `sprites` - A map of sprite names to sprite images
+    ///
+    /// # Returns
+    ///
+    /// An image the size of the window
+    pub fn draw_window(&self, window_type: WindowType, sprites: &HashMap<String, SpriteImage>) -> Result<WindowImage> {
+        let (width, height) = self.window_size(window_type);
+        let mut window = ImageBuffer::new(width, height);
+        self.draw_window_to(&mut window, window_type, 0, 0, sprites)?;
+        Ok(window)
+    }
+
+    /// Draws each window in the layout as a separate image, optionally split into one image per layer
+    ///
+    /// # Arguments
+    ///
+    /// * `sprites` - A map of sprite names to sprite images
+    /// * `split_layers` - Whether to return one transparent image per layer instead of one image per window
+    ///
+    /// # Returns
+    ///
+    /// The rendered windows in layout order, bottom-most first
+    pub fn draw_layers(
+        &self,
+        sprites: &HashMap<String, SpriteImage>,
+        split_layers: bool,
+    ) -> Result<Vec<RenderedLayer>> {
+        let mut layers = Vec::new();
+        for placement in &self.layout.windows {
+            if !split_layers {
+                layers.push(RenderedLayer {
+                    window_type: placement.window_type,
+                    layer: None,
+                    x: placement.x,
+                    y: placement.y,
+                    image: self.draw_window(placement.window_type, sprites)?,
+                });
+                continue;
+            }
+            let (width, height) = self.window_size(placement.window_type);
+            for layer in 0..MAX_LAYER {
+                let mut image = ImageBuffer::new(width, height);
+                if layer == 0 {
+                    self.background
+                        .draw(&mut image, placement.window_type, 0, 0, width, height);
+                }
+                for (sprite_def, sprite) in self.window_sprites(placement.window_type, sprites) {
+                    if sprite_def.layer == layer {
+                        Self::draw_sprite_at(&mut image, sprite, sprite_def, 0, 0)?;
+                    }
+                }
+                layers.push(RenderedLayer {
+                    window_type: placement.window_type,
+                    layer: Some(layer),
+                    x: placement.x,
+                    y: placement.y,
+                    image,
+                });
+            }
+        }
+        Ok(layers)
+    }
+
+    /// Lists the sprites drawn in a window, in draw order
+    ///
+    /// # Arguments
+    ///
+    /// * `window_type` - Window to list the sprites of
+    /// * `sprites` - A map of sprite names to sprite images
+    ///
+    /// # Returns
+    ///
+    /// Each window sprite definition paired with the image drawn for it. Definitions with no image are skipped.
+    pub fn window_sprites<'a>(
+        &'a self,
+        window_type: WindowType,
+        sprites: &'a HashMap<String, SpriteImage>,
+    ) -> Vec<(&'a SpriteWindowDefinition, &'a SpriteImage)> {
+        let mut window_sprites = Vec::new();
         for layer in 0..MAX_LAYER {
-            for sprite_def in self.definitions.values() {
-                if sprite_def.layer == layer {
-                    if let Some(sprite) = sprites.get(sprite_def.sprite_name.as_str()) {
-                        self.draw_sprite(&mut window, sprite, &sprite_def.name)?;
+            for sprite_def in &self.definitions {
+                if sprite_def.layer == layer && sprite_def.window_type == window_type {
+                    if let Some(sprite) = self.lookup_sprite(sprites, &sprite_def.sprite_name) {
+                        window_sprites.push((sprite_def, sprite));
                     }
                 }
             }
         }
-        Ok(window)
+        window_sprites
+    }
+
+    /// Returns which windows are drawn and where
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// Finds the element drawn at a point in a window
+    ///
+    /// Points outside the window's region are transparent and never hit an element.
+    ///
+    /// # Arguments
+    ///
+    /// * `window_type` - Window the point is in
+    /// * `x` - X position relative to the window
+    /// * `y` - Y position relative to the window
+    ///
+    /// # Returns
+    ///
+    /// The top-most window sprite covering the point, or None if the point hits no element
+    pub fn element_at(&self, window_type: WindowType, x: u32, y: u32) -> Option<&SpriteWindowDefinition> {
+        let (width, height) = self.window_size(window_type);
+        if x >= width || y >= height {
+            return None;
+        }
+        if let Some(polygons) = self.regions.for_window(window_type) {
+            if !region::polygons_contain(polygons, x, y) {
+                return None;
+            }
+        }
+
+        (0..MAX_LAYER).rev().find_map(|layer| {
+            self.definitions.iter().rev().find(|def| {
+                def.window_type == window_type
+                    && def.layer == layer
+                    && x >= def.x
+                    && x < def.x + def.width
+                    && y >= def.y
+                    && y < def.y + def.height
+            })
+        })
+    }
+
+    /// Returns the colors drawn behind the sprites
+    pub fn background(&self) -> &Background {
+        &self.background
+    }
+
+    /// Finds a sprite by name, preferring generated sprites over the skin's sprites
+    fn lookup_sprite<'a>(
+        &'a self,
+        sprites: &'a HashMap<String, SpriteImage>,
+        sprite_name: &str,
+    ) -> Option<&'a SpriteImage> {
+        self.generated.get(sprite_name).or_else(|| sprites.get(sprite_name))
     }
 
     /// Draws a sprite in a window
     pub fn draw_sprite(&self, window: &mut WindowImage, sprite: &SpriteImage, window_sprite_name: &str) -> Result<()> {
         let sprite_def = self
             .definitions
-            .get(window_sprite_name)
+            .iter()
+            .find(|def| def.name == window_sprite_name)
             .ok_or(WszError::ArgumentError(format!(
                 "Sprite {} not found",
                 window_sprite_name
             )))?;
 
-        let (start_x, start_y) = match sprite_def.window_type {
-            WindowType::Main => (MAIN_WINDOW_START_X, MAIN_WINDOW_START_Y),
-            WindowType::Equalizer => (EQUALIZER_WINDOW_START_X, EQUALIZER_WINDOW_START_Y),
-            WindowType::Playlist => (PLAYLIST_WINDOW_START_X, PLAYLIST_WINDOW_START_Y),
-        };
+        let (start_x, start_y) = self
+            .layout
+            .position(sprite_def.window_type)
+            .ok_or(WszError::ArgumentError(format!(
+                "Window {:?} is not part of the layout",
+                sprite_def.window_type
+            )))?;
+
+        Self::draw_sprite_at(window, sprite, sprite_def, start_x, start_y)
+    }
 
-        let start_x = start_x + sprite_def.x;
-        let start_y = start_y + sprite_def.y;
+    /// Draws a sprite at its window position, offset by the window's position on the target
+    fn draw_sprite_at<T: RenderTarget + ?Sized>(
+        target: &mut T,
+        sprite: &SpriteImage,
+        sprite_def: &SpriteWindowDefinition,
+        window_x: u32,
+        window_y: u32,
+    ) -> Result<()> {
+        let start_x = window_x + sprite_def.x;
+        let start_y = window_y + sprite_def.y;
+        let (width, height) = target.size();
 
-        if start_x + sprite_def.width > window.width() || start_y + sprite_def.height > window.height() {
+        if start_x + sprite_def.width > width
+            || start_y + sprite_def.height > height
+            || start_x + sprite.width() > width
+            || start_y + sprite.height() > height
+        {
             return Err(WszError::ArgumentError("Sprite is out of bounds".to_string()));
         }
 
-        for (src_x, src_y, pixel) in sprite.enumerate_pixels() {
-            let dst_x = start_x + src_x;
-            let dst_y = start_y + src_y;
+        target.put_sprite(start_x, start_y, sprite);
 
-            window.put_pixel(dst_x, dst_y, *pixel);
+        Ok(())
+    }
+
+    /// Returns the size of a window in pixels
+    ///
+    /// # Arguments
+    ///
+    /// * `window_type` - Window to get the size of
+    ///
+    /// # Returns
+    ///
+    /// The (width, height) of the window
+    pub fn window_size(&self, window_type: WindowType) -> (u32, u32) {
+        match window_type {
+            WindowType::Main => (WINDOW_WIDTH, MAIN_WINDOW_HEIGHT),
+            WindowType::Equalizer => (WINDOW_WIDTH, EQUALIZER_WINDOW_HEIGHT),
+            WindowType::Playlist => self.playlist_size,
+            WindowType::General => self.general_size,
+            WindowType::Minibrowser => self.minibrowser_size,
+            WindowType::MainShade | WindowType::EqualizerShade => (WINDOW_WIDTH, SHADE_WINDOW_HEIGHT),
+            WindowType::PlaylistShade => (self.playlist_size.0, SHADE_WINDOW_HEIGHT),
         }
+    }
 
-        Ok(())
+    /// Sets which windows are drawn and where
+    ///
+    /// # Arguments
+    ///
+    /// * `layout` - Window layout
+    pub fn set_layout(&mut self, layout: &Layout) {
+        self.layout = layout.clone();
     }
 
-    /// Sets the background color of the window
+    /// Resizes the playlist window, rebuilding its tiled frame like Winamp does. The shaded playlist is resized
+    /// to the same width.
+    ///
+    /// Winamp only allows the playlist to grow in 25x29 pixel steps from a minimum of 275x116, so the size
+    /// is rounded down to the nearest allowed size.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Requested width of the playlist window
+    /// * `height` - Requested height of the playlist window
+    pub fn set_playlist_size(&mut self, width: u32, height: u32) {
+        let (width, height) = pledit::pledit_snap_size(width, height);
+        self.playlist_size = (width, height);
+
+        self.definitions
+            .retain(|def| def.window_type != WindowType::Playlist && def.window_type != WindowType::PlaylistShade);
+        self.definitions.extend(pledit::pledit_window_sprites(width, height));
+        self.definitions.extend(pledit::pledit_shade_window_sprites(width));
+    }
+
+    /// Sets the size and title of the general purpose window, rebuilding its tiled frame.
+    ///
+    /// The size is rounded down to the nearest size Winamp allows, in 25x29 pixel steps from 275x116.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Requested width of the window
+    /// * `height` - Requested height of the window
+    /// * `title` - Title drawn in the title bar. Only the letters A-Z can be drawn.
+    pub fn set_general_window(&mut self, width: u32, height: u32, title: &str) {
+        let (width, height) = gen_::gen_snap_size(width, height);
+        self.general_size = (width, height);
+
+        self.definitions.retain(|def| def.window_type != WindowType::General);
+        self.definitions.extend(gen_::gen_window_sprites(width, height, title));
+    }
+
+    /// Resizes the minibrowser window, rebuilding its tiled frame.
+    ///
+    /// The size is rounded down to the nearest size Winamp allows, in 25x29 pixel steps from 275x116.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Requested width of the minibrowser window
+    /// * `height` - Requested height of the minibrowser window
+    pub fn set_minibrowser_size(&mut self, width: u32, height: u32) {
+        let (width, height) = mb::mb_snap_size(width, height);
+        self.minibrowser_size = (width, height);
+
+        self.definitions
+            .retain(|def| def.window_type != WindowType::Minibrowser);
+        self.definitions.extend(mb::mb_window_sprites(width, height));
+    }
+
+    /// Sets an opaque background color for the canvas and every window
     ///
     /// # Arguments
     ///
     /// * `color` - Background color
     pub fn set_bg_color(&mut self, color: Rgb<u8>) {
-        self.bg_color = color;
+        self.background = Background::solid(Rgba([color.0[0], color.0[1], color.0[2], 255]));
+    }
+
+    /// Sets the colors drawn behind the sprites
+    ///
+    /// # Arguments
+    ///
+    /// * `background` - Canvas, window and fill colors
+    pub fn set_background(&mut self, background: &Background) {
+        self.background = background.clone();
+    }
+
+    /// Sets the transparent regions used for hit testing
+    ///
+    /// # Arguments
+    ///
+    /// * `regions` - Regions of the skin (region.txt)
+    pub fn set_regions(&mut self, regions: &Regions) {
+        self.regions = regions.clone();
+    }
+
+    /// Applies render options to the window sprites (e.g. scroll positions and opened menus)
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Options to render with
+    pub fn apply_render_options(&mut self, options: &RenderOptions) {
+        if options.auto_crop {
+            self.set_layout(&options.layout.clone().cropped());
+        } else {
+            self.set_layout(&options.layout);
+        }
+        if let Some(background) = &options.background {
+            self.set_background(background);
+        }
+
+        if let Some((width, height)) = options.playlist_size {
+            self.set_playlist_size(width, height);
+        }
+        if let Some((width, height)) = options.minibrowser_size {
+            self.set_minibrowser_size(width, height);
+        }
+
+        let (playlist_width, playlist_height) = self.playlist_size;
+        self.set_sprite_position(
+            "PLAYLIST_SCROLL_HANDLE",
+            playlist_width - 15,
+            pledit::pledit_scroll_handle_y(options.playlist_scroll, playlist_height),
+        );
+
+        for button in &options.pressed_buttons {
+            let (name, pressed_sprite) = cbuttons::cbuttons_pressed_sprite(*button);
+            self.set_sprite_name(name, pressed_sprite);
+        }
+
+        for (name, sprite_name) in eqmain::eqmain_button_sprites(&options.eq_buttons) {
+            self.set_sprite_name(name, &sprite_name);
+        }
+
+        for def in titlebar::clutterbar_window_sprites(&options.clutterbar) {
+            self.add_window_sprite(&def.name, &def);
+        }
+
+        if let Some(menu) = options.playlist_menu {
+            for def in pledit::pledit_menu_window_sprites(menu, playlist_width, playlist_height) {
+                self.add_window_sprite(&def.name, &def);
+            }
+        }
     }
 
     /// Sets the position of a window sprite
@@ -466,7 +958,7 @@ impl SpriteWindowManager {
     /// * `x` - X position
     /// * `y` - Y position
     pub fn set_sprite_position(&mut self, name: &str, x: u32, y: u32) {
-        if let Some(sprite_def) = self.definitions.get_mut(name) {
+        if let Some(sprite_def) = self.definition_mut(name) {
             sprite_def.x = x;
             sprite_def.y = y;
         }
@@ -479,19 +971,41 @@ impl SpriteWindowManager {
     /// * `name` - Name of the window sprite
     /// * `new_name` - New sprite sheet name of the sprite
     pub fn set_sprite_name(&mut self, name: &str, new_name: &str) {
-        if let Some(sprite_def) = self.definitions.get_mut(name) {
+        if let Some(sprite_def) = self.definition_mut(name) {
             sprite_def.sprite_name = new_name.to_string();
         }
     }
 
+    /// Sets a generated sprite (e.g. rendered text) which window sprites can draw by name.
+    ///
+    /// Generated sprites take precedence over the skin's sprites of the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Sprite name
+    /// * `image` - Sprite image
+    pub fn set_generated_sprite(&mut self, name: &str, image: SpriteImage) {
+        self.generated.insert(name.to_string(), image);
+    }
+
     /// Adds a window sprite to the sprite manager
     ///
+    /// New sprites are drawn on top of the existing sprites in the same layer. Replacing an existing
+    /// sprite keeps its place in the draw order.
+    ///
     /// # Arguments
     ///
     /// * `name` - Name of the window sprite
     /// * `definition` - SpriteWindowDefinition
     pub fn add_window_sprite(&mut self, name: &str, definition: &SpriteWindowDefinition) {
-        self.definitions.insert(name.to_string(), definition.clone());
+        let definition = SpriteWindowDefinition {
+            name: name.to_string(),
+            ..definition.clone()
+        };
+        match self.definition_mut(name) {
+            Some(existing) => *existing = definition,
+            None => self.definitions.push(definition),
+        }
     }
 
     /// Removes a window sprite from the sprite manager
@@ -500,6 +1014,11 @@ impl SpriteWindowManager {
     ///
     /// * `name` - Name of the window sprite
     pub fn remove_window_sprite(&mut self, name: &str) {
-        self.definitions.remove(name);
+        self.definitions.retain(|def| def.name != name);
+    }
+
+    /// Finds a window sprite by name
+    fn definition_mut(&mut self, name: &str) -> Option<&mut SpriteWindowDefinition> {
+        self.definitions.iter_mut().find(|def| def.name == name)
     }
 }
@@ -1,4 +1,5 @@
 use super::{SpriteDefinition, SpriteWindowDefinition, WindowType};
+use crate::render::PlaylistMenu;
 
 pub fn pledit_sprites() -> Vec<SpriteDefinition> {
     vec![
@@ -485,228 +486,296 @@ pub fn pledit_sprites() -> Vec<SpriteDefinition> {
     ]
 }
 
-pub fn pledit_window_sprites() -> Vec<SpriteWindowDefinition> {
-    vec![
-        SpriteWindowDefinition {
-            name: "PLAYLIST_TOP_LEFT_CORNER".to_string(),
-            sprite_name: "PLAYLIST_TOP_LEFT_CORNER".to_string(),
-            window_type: WindowType::Playlist,
-            layer: 1,
-            x: 0,
-            y: 0,
-            width: 25,
-            height: 20,
-        },
-        SpriteWindowDefinition {
-            name: "PLAYLIST_TOP_TILE_0".to_string(),
-            sprite_name: "PLAYLIST_TOP_TILE".to_string(),
-            window_type: WindowType::Playlist,
-            layer: 1,
-            x: 25,
-            y: 0,
-            width: 25,
-            height: 20,
-        },
-        SpriteWindowDefinition {
-            name: "PLAYLIST_TOP_TILE_1".to_string(),
-            sprite_name: "PLAYLIST_TOP_TILE".to_string(),
-            window_type: WindowType::Playlist,
-            layer: 1,
-            x: 50,
-            y: 0,
-            width: 25,
-            height: 20,
-        },
-        SpriteWindowDefinition {
-            name: "PLAYLIST_TOP_TILE_2".to_string(),
-            sprite_name: "PLAYLIST_TOP_TILE".to_string(),
-            window_type: WindowType::Playlist,
-            layer: 1,
-            x: 75,
-            y: 0,
-            width: 25,
-            height: 20,
-        },
-        // n.b. this overlaps the previous and next tiles. this is intentional.
-        SpriteWindowDefinition {
-            name: "PLAYLIST_TITLE_BAR".to_string(),
-            sprite_name: "PLAYLIST_TITLE_BAR".to_string(),
-            window_type: WindowType::Playlist,
-            layer: 2,
-            x: 87,
-            y: 0,
-            width: 100,
-            height: 20,
-        },
-        SpriteWindowDefinition {
-            name: "PLAYLIST_TOP_TILE_3".to_string(),
-            sprite_name: "PLAYLIST_TOP_TILE".to_string(),
-            window_type: WindowType::Playlist,
-            layer: 1,
-            x: 175,
-            y: 0,
-            width: 25,
-            height: 20,
-        },
-        SpriteWindowDefinition {
-            name: "PLAYLIST_TOP_TILE_4".to_string(),
-            sprite_name: "PLAYLIST_TOP_TILE".to_string(),
-            window_type: WindowType::Playlist,
-            layer: 1,
-            x: 200,
-            y: 0,
-            width: 25,
-            height: 20,
-        },
-        SpriteWindowDefinition {
-            name: "PLAYLIST_TOP_TILE_5".to_string(),
-            sprite_name: "PLAYLIST_TOP_TILE".to_string(),
-            window_type: WindowType::Playlist,
-            layer: 1,
-            x: 225,
-            y: 0,
-            width: 25,
-            height: 20,
-        },
-        SpriteWindowDefinition {
-            name: "PLAYLIST_TOP_RIGHT_CORNER".to_string(),
-            sprite_name: "PLAYLIST_TOP_RIGHT_CORNER".to_string(),
-            window_type: WindowType::Playlist,
-            layer: 1,
-            x: 250,
-            y: 0,
-            width: 25,
-            height: 20,
-        },
-        SpriteWindowDefinition {
-            name: "PLAYLIST_LEFT_TILE_0".to_string(),
-            sprite_name: "PLAYLIST_LEFT_TILE".to_string(),
-            window_type: WindowType::Playlist,
-            layer: 1,
-            x: 0,
-            y: 20,
-            width: 12,
-            height: 29,
-        },
-        SpriteWindowDefinition {
-            name: "PLAYLIST_RIGHT_TILE_0".to_string(),
-            sprite_name: "PLAYLIST_RIGHT_TILE".to_string(),
+/// Minimum (and default) width of the playlist window
+pub const PLAYLIST_MIN_WIDTH: u32 = 275;
+/// Minimum height of the playlist window
+pub const PLAYLIST_MIN_HEIGHT: u32 = 116;
+/// Default height of the playlist window
+pub const PLAYLIST_DEFAULT_HEIGHT: u32 = 203;
+/// The playlist window grows horizontally in steps of this many pixels
+pub const PLAYLIST_WIDTH_STEP: u32 = 25;
+/// The playlist window grows vertically in steps of this many pixels
+pub const PLAYLIST_HEIGHT_STEP: u32 = 29;
+
+/// Height of the playlist title bar
+const PLAYLIST_TOP_HEIGHT: u32 = 20;
+/// Height of the playlist bottom bar
+const PLAYLIST_BOTTOM_HEIGHT: u32 = 38;
+/// Height of the scrollbar thumb
+const PLAYLIST_SCROLL_HANDLE_HEIGHT: u32 = 18;
+/// Distance between the bottom of the menu buttons and the bottom of the window
+const PLAYLIST_MENU_BOTTOM_OFFSET: u32 = 12;
+/// Height of a single playlist menu item
+const PLAYLIST_MENU_ITEM_HEIGHT: u32 = 18;
+/// The visualizer in the bottom bar is only shown when the window is at least this wide
+const PLAYLIST_VISUALIZER_MIN_WIDTH: u32 = 350;
+
+/// Snaps a requested playlist size to the nearest size Winamp allows (rounding down)
+///
+/// Winamp resizes the playlist in 25x29 pixel steps starting from 275x116.
+pub fn pledit_snap_size(width: u32, height: u32) -> (u32, u32) {
+    let width = width.max(PLAYLIST_MIN_WIDTH);
+    let height = height.max(PLAYLIST_MIN_HEIGHT);
+    (
+        width - (width - PLAYLIST_MIN_WIDTH) % PLAYLIST_WIDTH_STEP,
+        height - (height - PLAYLIST_MIN_HEIGHT) % PLAYLIST_HEIGHT_STEP,
+    )
+}
+
+/// Returns the window sprites for a playlist window of the given size.
+///
+/// The size should already be snapped with `pledit_snap_size`.
+pub fn pledit_window_sprites(width: u32, height: u32) -> Vec<SpriteWindowDefinition> {
+    let mut sprites = Vec::new();
+
+    let tile =
+        |name: String, sprite_name: &str, layer: u32, x: u32, y: u32, width: u32, height: u32| SpriteWindowDefinition {
+            name,
+            sprite_name: sprite_name.to_string(),
             window_type: WindowType::Playlist,
-            layer: 1,
-            x: 255,
-            y: 20,
-            width: 20,
-            height: 29,
-        },
-        SpriteWindowDefinition {
-            name: "PLAYLIST_SCROLL_HANDLE".to_string(),
-            sprite_name: "PLAYLIST_SCROLL_HANDLE".to_string(),
+            layer,
+            x,
+            y,
+            width,
+            height,
+        };
+
+    // title bar: corners with tiles in between and the title centered on top
+    sprites.push(tile(
+        "PLAYLIST_TOP_LEFT_CORNER".to_string(),
+        "PLAYLIST_TOP_LEFT_CORNER",
+        1,
+        0,
+        0,
+        25,
+        PLAYLIST_TOP_HEIGHT,
+    ));
+    for (i, x) in (25..width - 25).step_by(25).enumerate() {
+        sprites.push(tile(
+            format!("PLAYLIST_TOP_TILE_{}", i),
+            "PLAYLIST_TOP_TILE",
+            1,
+            x,
+            0,
+            25,
+            PLAYLIST_TOP_HEIGHT,
+        ));
+    }
+    // n.b. this overlaps the neighbouring tiles. this is intentional.
+    sprites.push(tile(
+        "PLAYLIST_TITLE_BAR".to_string(),
+        "PLAYLIST_TITLE_BAR",
+        2,
+        (width - 100) / 2,
+        0,
+        100,
+        PLAYLIST_TOP_HEIGHT,
+    ));
+    sprites.push(tile(
+        "PLAYLIST_TOP_RIGHT_CORNER".to_string(),
+        "PLAYLIST_TOP_RIGHT_CORNER",
+        1,
+        width - 25,
+        0,
+        25,
+        PLAYLIST_TOP_HEIGHT,
+    ));
+
+    // sides
+    let bottom_y = height - PLAYLIST_BOTTOM_HEIGHT;
+    for (i, y) in (PLAYLIST_TOP_HEIGHT..bottom_y)
+        .step_by(PLAYLIST_HEIGHT_STEP as usize)
+        .enumerate()
+    {
+        sprites.push(tile(
+            format!("PLAYLIST_LEFT_TILE_{}", i),
+            "PLAYLIST_LEFT_TILE",
+            1,
+            0,
+            y,
+            12,
+            PLAYLIST_HEIGHT_STEP,
+        ));
+        sprites.push(tile(
+            format!("PLAYLIST_RIGHT_TILE_{}", i),
+            "PLAYLIST_RIGHT_TILE",
+            1,
+            width - 20,
+            y,
+            20,
+            PLAYLIST_HEIGHT_STEP,
+        ));
+    }
+
+    sprites.push(tile(
+        "PLAYLIST_SCROLL_HANDLE".to_string(),
+        "PLAYLIST_SCROLL_HANDLE",
+        2,
+        width - 15,
+        PLAYLIST_TOP_HEIGHT,
+        8,
+        PLAYLIST_SCROLL_HANDLE_HEIGHT,
+    ));
+
+    // bottom bar: corners with tiles in between, and the visualizer when there is room for it
+    sprites.push(tile(
+        "PLAYLIST_BOTTOM_LEFT_CORNER".to_string(),
+        "PLAYLIST_BOTTOM_LEFT_CORNER",
+        1,
+        0,
+        bottom_y,
+        125,
+        PLAYLIST_BOTTOM_HEIGHT,
+    ));
+    for (i, x) in (125..width - 150).step_by(25).enumerate() {
+        sprites.push(tile(
+            format!("PLAYLIST_BOTTOM_TILE_{}", i),
+            "PLAYLIST_BOTTOM_TILE",
+            1,
+            x,
+            bottom_y,
+            25,
+            PLAYLIST_BOTTOM_HEIGHT,
+        ));
+    }
+    if width >= PLAYLIST_VISUALIZER_MIN_WIDTH {
+        sprites.push(tile(
+            "PLAYLIST_VISUALIZER_BACKGROUND".to_string(),
+            "PLAYLIST_VISUALIZER_BACKGROUND",
+            2,
+            width - 225,
+            bottom_y,
+            75,
+            PLAYLIST_BOTTOM_HEIGHT,
+        ));
+    }
+    sprites.push(tile(
+        "PLAYLIST_BOTTOM_RIGHT_CORNER".to_string(),
+        "PLAYLIST_BOTTOM_RIGHT_CORNER",
+        1,
+        width - 150,
+        bottom_y,
+        150,
+        PLAYLIST_BOTTOM_HEIGHT,
+    ));
+
+    sprites
+}
+
+/// Returns the window sprites of the playlist window in shade mode
+///
+/// # Arguments
+///
+/// * `width` - Width of the window, as returned by `pledit_snap_size`
+pub fn pledit_shade_window_sprites(width: u32) -> Vec<SpriteWindowDefinition> {
+    let piece = |name: String, sprite_name: &str, x: u32, width: u32| SpriteWindowDefinition {
+        name,
+        sprite_name: sprite_name.to_string(),
+        window_type: WindowType::PlaylistShade,
+        layer: 1,
+        x,
+        y: 0,
+        width,
+        height: 14,
+    };
+
+    let mut sprites = vec![piece(
+        "PLAYLIST_SHADE_LEFT".to_string(),
+        "PLAYLIST_SHADE_BACKGROUND_LEFT",
+        0,
+        25,
+    )];
+    for (i, x) in (25..width - 50).step_by(25).enumerate() {
+        sprites.push(piece(
+            format!("PLAYLIST_SHADE_TILE_{}", i),
+            "PLAYLIST_SHADE_BACKGROUND",
+            x,
+            25,
+        ));
+    }
+    sprites.push(piece(
+        "PLAYLIST_SHADE_RIGHT".to_string(),
+        "PLAYLIST_SHADE_BACKGROUND_RIGHT_SELECTED",
+        width - 50,
+        50,
+    ));
+    sprites
+}
+
+/// Returns the y position of the scrollbar thumb for a scroll fraction between 0.0 and 1.0
+pub fn pledit_scroll_handle_y(scroll: f32, height: u32) -> u32 {
+    let travel = height - PLAYLIST_TOP_HEIGHT - PLAYLIST_BOTTOM_HEIGHT - PLAYLIST_SCROLL_HANDLE_HEIGHT;
+    PLAYLIST_TOP_HEIGHT + (scroll.clamp(0.0, 1.0) * travel as f32).round() as u32
+}
+
+/// Returns the window sprites for an opened playlist menu.
+///
+/// Menus open upwards from their button on the bottom bar, with the menu bar drawn to the left of the items.
+pub fn pledit_menu_window_sprites(menu: PlaylistMenu, width: u32, height: u32) -> Vec<SpriteWindowDefinition> {
+    let (button_x, bar, items): (u32, &str, &[&str]) = match menu {
+        PlaylistMenu::Add => (
+            14,
+            "PLAYLIST_ADD_MENU_BAR",
+            &["PLAYLIST_ADD_URL", "PLAYLIST_ADD_DIR", "PLAYLIST_ADD_FILE"],
+        ),
+        PlaylistMenu::Remove => (
+            43,
+            "PLAYLIST_REMOVE_MENU_BAR",
+            &[
+                "PLAYLIST_REMOVE_ALL",
+                "PLAYLIST_CROP",
+                "PLAYLIST_REMOVE_SELECTED",
+                "PLAYLIST_REMOVE_MISC",
+            ],
+        ),
+        PlaylistMenu::Select => (
+            72,
+            "PLAYLIST_SELECT_MENU_BAR",
+            &[
+                "PLAYLIST_INVERT_SELECTION",
+                "PLAYLIST_SELECT_ZERO",
+                "PLAYLIST_SELECT_ALL",
+            ],
+        ),
+        PlaylistMenu::Misc => (
+            101,
+            "PLAYLIST_MISC_MENU_BAR",
+            &["PLAYLIST_SORT_LIST", "PLAYLIST_FILE_INFO", "PLAYLIST_MISC_OPTIONS"],
+        ),
+        // the list button is anchored to the right edge of the window
+        PlaylistMenu::List => (
+            width - 44,
+            "PLAYLIST_LIST_BAR",
+            &["PLAYLIST_NEW_LIST", "PLAYLIST_SAVE_LIST", "PLAYLIST_LOAD_LIST"],
+        ),
+    };
+
+    let menu_height = items.len() as u32 * PLAYLIST_MENU_ITEM_HEIGHT;
+    let menu_top = height - PLAYLIST_MENU_BOTTOM_OFFSET - menu_height;
+
+    let mut sprites = vec![SpriteWindowDefinition {
+        name: bar.to_string(),
+        sprite_name: bar.to_string(),
+        window_type: WindowType::Playlist,
+        layer: 2,
+        x: button_x - 3,
+        y: menu_top,
+        width: 3,
+        height: menu_height,
+    }];
+
+    for (i, item) in items.iter().enumerate() {
+        sprites.push(SpriteWindowDefinition {
+            name: item.to_string(),
+            sprite_name: item.to_string(),
             window_type: WindowType::Playlist,
             layer: 2,
-            x: 260,
-            y: 20,
-            width: 8,
-            height: 18,
-        },
-        SpriteWindowDefinition {
-            name: "PLAYLIST_LEFT_TILE_1".to_string(),
-            sprite_name: "PLAYLIST_LEFT_TILE".to_string(),
-            window_type: WindowType::Playlist,
-            layer: 1,
-            x: 0,
-            y: 49,
-            width: 12,
-            height: 29,
-        },
-        SpriteWindowDefinition {
-            name: "PLAYLIST_RIGHT_TILE_1".to_string(),
-            sprite_name: "PLAYLIST_RIGHT_TILE".to_string(),
-            window_type: WindowType::Playlist,
-            layer: 1,
-            x: 255,
-            y: 49,
-            width: 20,
-            height: 29,
-        },
-        SpriteWindowDefinition {
-            name: "PLAYLIST_LEFT_TILE_2".to_string(),
-            sprite_name: "PLAYLIST_LEFT_TILE".to_string(),
-            window_type: WindowType::Playlist,
-            layer: 1,
-            x: 0,
-            y: 78,
-            width: 12,
-            height: 29,
-        },
-        SpriteWindowDefinition {
-            name: "PLAYLIST_RIGHT_TILE_2".to_string(),
-            sprite_name: "PLAYLIST_RIGHT_TILE".to_string(),
-            window_type: WindowType::Playlist,
-            layer: 1,
-            x: 255,
-            y: 78,
-            width: 20,
-            height: 29,
-        },
-        SpriteWindowDefinition {
-            name: "PLAYLIST_LEFT_TILE_3".to_string(),
-            sprite_name: "PLAYLIST_LEFT_TILE".to_string(),
-            window_type: WindowType::Playlist,
-            layer: 1,
-            x: 0,
-            y: 107,
-            width: 12,
-            height: 29,
-        },
-        SpriteWindowDefinition {
-            name: "PLAYLIST_RIGHT_TILE_3".to_string(),
-            sprite_name: "PLAYLIST_RIGHT_TILE".to_string(),
-            window_type: WindowType::Playlist,
-            layer: 1,
-            x: 255,
-            y: 107,
-            width: 20,
-            height: 29,
-        },
-        SpriteWindowDefinition {
-            name: "PLAYLIST_LEFT_TILE_4".to_string(),
-            sprite_name: "PLAYLIST_LEFT_TILE".to_string(),
-            window_type: WindowType::Playlist,
-            layer: 1,
-            x: 0,
-            y: 136,
-            width: 12,
-            height: 29,
-        },
-        SpriteWindowDefinition {
-            name: "PLAYLIST_RIGHT_TILE_4".to_string(),
-            sprite_name: "PLAYLIST_RIGHT_TILE".to_string(),
-            window_type: WindowType::Playlist,
-            layer: 1,
-            x: 255,
-            y: 136,
-            width: 20,
-            height: 29,
-        },
-        SpriteWindowDefinition {
-            name: "PLAYLIST_BOTTOM_LEFT_CORNER".to_string(),
-            sprite_name: "PLAYLIST_BOTTOM_LEFT_CORNER".to_string(),
-            window_type: WindowType::Playlist,
-            layer: 1,
-            x: 0,
-            y: 165,
-            width: 125,
-            height: 38,
-        },
-        SpriteWindowDefinition {
-            name: "PLAYLIST_BOTTOM_RIGHT_CORNER".to_string(),
-            sprite_name: "PLAYLIST_BOTTOM_RIGHT_CORNER".to_string(),
-            window_type: WindowType::Playlist,
-            layer: 1,
-            x: 125,
-            y: 165,
-            width: 150,
-            height: 38,
-        },
-    ]
+            x: button_x,
+            y: menu_top + i as u32 * PLAYLIST_MENU_ITEM_HEIGHT,
+            width: 22,
+            height: PLAYLIST_MENU_ITEM_HEIGHT,
+        });
+    }
+
+    sprites
 }
@@ -1,4 +1,5 @@
 use super::{SpriteDefinition, SpriteWindowDefinition, WindowType};
+use crate::render::{ClutterState, Clutterbar};
 
 pub fn titlebar_sprites() -> Vec<SpriteDefinition> {
     vec![
@@ -285,3 +286,63 @@ pub fn titlebar_window_sprites() -> Vec<SpriteWindowDefinition> {
         },
     ]
 }
+
+/// Returns the window sprites of the main window in shade mode
+pub fn titlebar_shade_window_sprites() -> Vec<SpriteWindowDefinition> {
+    let button = |name: &str, sprite_name: &str, x: u32| SpriteWindowDefinition {
+        name: name.to_string(),
+        sprite_name: sprite_name.to_string(),
+        window_type: WindowType::MainShade,
+        layer: 2,
+        x,
+        y: 3,
+        width: 9,
+        height: 9,
+    };
+
+    vec![
+        SpriteWindowDefinition {
+            name: "MAIN_SHADE_BACKGROUND".to_string(),
+            sprite_name: "MAIN_SHADE_BACKGROUND_SELECTED".to_string(),
+            window_type: WindowType::MainShade,
+            layer: 0,
+            x: 0,
+            y: 0,
+            width: 275,
+            height: 14,
+        },
+        button("MAIN_SHADE_OPTIONS_BUTTON", "MAIN_OPTIONS_BUTTON", 6),
+        button("MAIN_SHADE_MINIMIZE_BUTTON", "MAIN_MINIMIZE_BUTTON", 244),
+        button("MAIN_SHADE_UNSHADE_BUTTON", "MAIN_SHADE_BUTTON_ACTIVE", 254),
+        button("MAIN_SHADE_CLOSE_BUTTON", "MAIN_CLOSE_BUTTON", 264),
+    ]
+}
+
+/// Returns the window sprites for the clutterbar buttons which are on or pressed.
+///
+/// Skins only have one "down" image per button, which Winamp shows both while the button is held and while its
+/// option is enabled. Buttons which are off show the clutterbar background.
+pub fn clutterbar_window_sprites(clutterbar: &Clutterbar) -> Vec<SpriteWindowDefinition> {
+    let buttons = [
+        ("O", clutterbar.options, 3, 8),
+        ("A", clutterbar.always_on_top, 11, 7),
+        ("I", clutterbar.file_info, 18, 7),
+        ("D", clutterbar.double_size, 25, 8),
+        ("V", clutterbar.visualization, 33, 7),
+    ];
+
+    buttons
+        .iter()
+        .filter(|(_, state, _, _)| *state != ClutterState::Off)
+        .map(|(letter, _, y, height)| SpriteWindowDefinition {
+            name: format!("MAIN_CLUTTER_BAR_BUTTON_{}", letter),
+            sprite_name: format!("MAIN_CLUTTER_BAR_BUTTON_{}_SELECTED", letter),
+            window_type: WindowType::Main,
+            layer: 2,
+            x: 10,
+            y: 22 + y,
+            width: 8,
+            height: *height,
+        })
+        .collect()
+}
@@ -0,0 +1,166 @@
+//! Removing everything Winamp does not need from a skin, for bundling skins in apps with size budgets
+//!
+//! `Wsz::strip` keeps the sprite sheets, GENEX.BMP, VIDEO.BMP and the text configuration files, removes the kinds of
+//! files chosen in StripOptions and can re-encode bitmaps in the smallest BMP format that keeps every pixel.
+
+use std::io::Cursor;
+
+use image::codecs::bmp::BmpEncoder;
+use image::{ExtendedColorType, RgbImage};
+
+use crate::archive::FileKind;
+use crate::error::Result;
+use crate::Wsz;
+
+/// Options controlling what `Wsz::strip` removes
+#[derive(Debug, Clone)]
+pub struct StripOptions {
+    /// Remove readmes and other documents, i.e. text files that are not viscolor.txt, pledit.txt or region.txt
+    pub readmes: bool,
+    /// Remove screenshots and other images that are not sprite sheets, GENEX.BMP, VIDEO.BMP or AVS.BMP
+    pub images: bool,
+    /// Remove cursors, so Winamp uses its own
+    pub cursors: bool,
+    /// Remove AVS presets and AVS.BMP
+    pub avs: bool,
+    /// Remove any other file Winamp does not read, e.g. Thumbs.db or files from other players
+    pub other_files: bool,
+    /// Re-encode the sprite sheets and other bitmaps that are kept as 8 bit BMPs when they have 256 colors or fewer,
+    /// and as 24 bit BMPs otherwise, when that is smaller. Every pixel is kept, so bitmaps with transparent pixels
+    /// are left as they are.
+    pub recompress_sheets: bool,
+}
+
+impl StripOptions {
+    fn new() -> Self {
+        Self {
+            readmes: true,
+            images: true,
+            cursors: true,
+            avs: true,
+            other_files: true,
+            recompress_sheets: true,
+        }
+    }
+
+    /// Whether files of a kind are removed
+    fn removes(&self, kind: FileKind) -> bool {
+        match kind {
+            FileKind::Bitmap | FileKind::Config => false,
+            FileKind::Document => self.readmes,
+            FileKind::Image => self.images,
+            FileKind::Cursor => self.cursors,
+            FileKind::Avs => self.avs,
+            FileKind::Settings | FileKind::Other => self.other_files,
+        }
+    }
+}
+
+impl Default for StripOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What `Wsz::strip` changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StripSummary {
+    /// Paths of the files removed, in name order
+    pub removed: Vec<String>,
+    /// Paths of the bitmaps re-encoded, in name order
+    pub recompressed: Vec<String>,
+    /// Total size of the skin's files before, uncompressed
+    pub size_before: u64,
+    /// Total size of the skin's files after, uncompressed
+    pub size_after: u64,
+}
+
+/// Strips a skin in place
+pub(crate) fn strip(skin: &mut Wsz, options: &StripOptions) -> Result<StripSummary> {
+    let size = |skin: &Wsz| skin.files.values().map(|data| data.len() as u64).sum();
+    let mut summary = StripSummary {
+        size_before: size(skin),
+        ..Default::default()
+    };
+
+    let mut keys: Vec<String> = skin.files.keys().cloned().collect();
+    keys.sort();
+    for key in keys {
+        let kind = FileKind::of(&key);
+        if options.removes(kind) {
+            skin.files.remove(&key);
+            summary.removed.push(key);
+            if kind == FileKind::Cursor {
+                skin.cursors.clear();
+            }
+        } else if kind == FileKind::Bitmap && options.recompress_sheets && key.to_lowercase().ends_with(".bmp") {
+            if let Some(data) = smallest_bmp(&skin.files[&key])? {
+                skin.files.insert(key.clone(), data);
+                summary.recompressed.push(key);
+            }
+        }
+    }
+    let removed = &summary.removed;
+    skin.provenance.entries.retain(|entry| !removed.contains(&entry.name));
+
+    if !summary.removed.is_empty() || !summary.recompressed.is_empty() {
+        let file_name = skin.metadata.file_name.take();
+        let compressed_size = skin.metadata.compressed_size;
+        skin.metadata = crate::text::readme::SkinMetadata::from_skin_archive(&skin.files);
+        skin.metadata.file_name = file_name;
+        skin.metadata.compressed_size = compressed_size;
+    }
+    summary.size_after = size(skin);
+    Ok(summary)
+}
+
+/// Encodes a bitmap as an 8 bit BMP if it has few enough colors, or a 24 bit BMP otherwise
+///
+/// Returns None if the bitmap cannot be decoded, has transparent pixels the re-encoded bitmap could not keep, or is
+/// already no larger.
+fn smallest_bmp(data: &[u8]) -> Result<Option<Vec<u8>>> {
+    let Ok(image) = image::load_from_memory(data) else {
+        return Ok(None);
+    };
+    if image.color().has_alpha() && image.to_rgba8().pixels().any(|pixel| pixel.0[3] != 255) {
+        return Ok(None);
+    }
+    let image = image.to_rgb8();
+    let encoded = match indexed(&image) {
+        Some((palette, indices)) => {
+            let mut bmp = Vec::new();
+            BmpEncoder::new(&mut Cursor::new(&mut bmp)).encode_with_palette(
+                &indices,
+                image.width(),
+                image.height(),
+                ExtendedColorType::L8,
+                Some(&palette),
+            )?;
+            bmp
+        }
+        None => crate::encode_bmp(&image::DynamicImage::ImageRgb8(image))?,
+    };
+    Ok((encoded.len() < data.len()).then_some(encoded))
+}
+
+/// Splits an image into a palette and an index for each pixel, or None if it has more than 256 colors
+fn indexed(image: &RgbImage) -> Option<(Vec<[u8; 3]>, Vec<u8>)> {
+    let mut palette: Vec<[u8; 3]> = Vec::new();
+    let mut lookup = std::collections::HashMap::new();
+    let mut indices = Vec::with_capacity((image.width() * image.height()) as usize);
+    for pixel in image.pixels() {
+        let index = match lookup.get(&pixel.0) {
+            Some(&index) => index,
+            None => {
+                if palette.len() == 256 {
+                    return None;
+                }
+                palette.push(pixel.0);
+                lookup.insert(pixel.0, (palette.len() - 1) as u8);
+                (palette.len() - 1) as u8
+            }
+        };
+        indices.push(index);
+    }
+    Some((palette, indices))
+}
@@ -0,0 +1,207 @@
+//! All of a skin's text configuration in one place
+//!
+//! SkinConfig reads viscolor.txt, pledit.txt and region.txt together, lets them be edited, and writes them back
+//! into an archive.
+
+use crate::archive::WszArchive;
+use crate::error::{Result, WszError};
+use crate::text::pledit::PleditSettings;
+use crate::text::region::Regions;
+use crate::text::viscolor::VisColors;
+use crate::text::{encoding, ParseMode, ParseWarning};
+
+/// File names the configuration is written to when the archive does not already have the file
+const VISCOLOR_TXT: &str = "viscolor.txt";
+const PLEDIT_TXT: &str = "pledit.txt";
+const REGION_TXT: &str = "region.txt";
+
+/// The text configuration files of a skin
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SkinConfig {
+    vis_colors: VisColors,
+    pledit: PleditSettings,
+    regions: Regions,
+}
+
+impl SkinConfig {
+    /// Find and parse the text configuration files in the archive contents
+    ///
+    /// Files the skin does not have are left empty.
+    pub fn from_archive(archive: &WszArchive) -> Result<Self> {
+        Self::from_archive_with_mode(archive, ParseMode::Strict).map(|(config, _)| config)
+    }
+
+    /// Find and parse the text configuration files in the archive contents, optionally skipping lines that
+    /// cannot be parsed
+    ///
+    /// Unlike `Wsz`, a region.txt that cannot be parsed is an error in strict mode, so that writing the
+    /// configuration back does not drop it.
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - Archive to find the files in
+    /// * `mode` - How to handle lines that cannot be parsed
+    ///
+    /// # Returns
+    ///
+    /// The configuration and the problems skipped in lenient mode
+    pub fn from_archive_with_mode(archive: &WszArchive, mode: ParseMode) -> Result<(Self, Vec<ParseWarning>)> {
+        let mut warnings = Vec::new();
+        let vis_colors = or_default(VisColors::from_archive_with_mode(archive, mode), &mut warnings)?;
+        let pledit = or_default(PleditSettings::from_archive_with_mode(archive, mode), &mut warnings)?;
+        let regions = or_default(Regions::from_archive_with_mode(archive, mode), &mut warnings)?;
+        Ok((
+            Self {
+                vis_colors,
+                pledit,
+                regions,
+            },
+            warnings,
+        ))
+    }
+
+    /// Get the visualization colors (viscolor.txt)
+    pub fn get_vis_colors(&self) -> &VisColors {
+        &self.vis_colors
+    }
+
+    /// Get the visualization colors (viscolor.txt) for editing
+    pub fn get_vis_colors_mut(&mut self) -> &mut VisColors {
+        &mut self.vis_colors
+    }
+
+    /// Replace the visualization colors (viscolor.txt)
+    pub fn set_vis_colors(&mut self, vis_colors: VisColors) {
+        self.vis_colors = vis_colors;
+    }
+
+    /// Get the playlist editor settings (pledit.txt)
+    pub fn get_pledit(&self) -> &PleditSettings {
+        &self.pledit
+    }
+
+    /// Get the playlist editor settings (pledit.txt) for editing
+    pub fn get_pledit_mut(&mut self) -> &mut PleditSettings {
+        &mut self.pledit
+    }
+
+    /// Replace the playlist editor settings (pledit.txt)
+    pub fn set_pledit(&mut self, pledit: PleditSettings) {
+        self.pledit = pledit;
+    }
+
+    /// Get the window regions (region.txt)
+    pub fn get_regions(&self) -> &Regions {
+        &self.regions
+    }
+
+    /// Get the window regions (region.txt) for editing
+    pub fn get_regions_mut(&mut self) -> &mut Regions {
+        &mut self.regions
+    }
+
+    /// Replace the window regions (region.txt)
+    pub fn set_regions(&mut self, regions: Regions) {
+        self.regions = regions;
+    }
+
+    /// Write the configuration into archive contents
+    ///
+    /// Each file replaces the archive's existing copy, keeping its path and encoding, or is added as viscolor.txt,
+    /// pledit.txt or region.txt if the archive has none. Files with nothing to write are removed, so Winamp uses
+    /// its defaults.
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - Archive to write the files into
+    pub fn write_into(&self, archive: &mut WszArchive) {
+        for (file_name, content) in self.files() {
+            write_file(archive, file_name, content);
+        }
+    }
+
+    /// Write only the files that differ from another configuration, leaving the archive's copies of the rest
+    /// untouched
+    ///
+    /// # Arguments
+    ///
+    /// * `original` - Configuration the archive's files were parsed into
+    /// * `archive` - Archive to write the files into
+    pub(crate) fn write_changes_into(&self, original: &SkinConfig, archive: &mut WszArchive) {
+        for ((file_name, content), (_, original)) in self.files().into_iter().zip(original.files()) {
+            if content != original {
+                write_file(archive, file_name, content);
+            }
+        }
+    }
+
+    /// Serializes each file, or None for files with nothing to write
+    pub(crate) fn files(&self) -> [(&'static str, Option<String>); 3] {
+        let regions_empty = [
+            &self.regions.main,
+            &self.regions.main_shade,
+            &self.regions.equalizer,
+            &self.regions.equalizer_shade,
+        ]
+        .iter()
+        .all(|region| region.is_none())
+            && self.regions.custom.is_empty();
+        let pledit = &self.pledit;
+        let pledit_empty = [
+            pledit.normal,
+            pledit.current,
+            pledit.normal_bg,
+            pledit.selected_bg,
+            pledit.mb_fg,
+            pledit.mb_bg,
+        ]
+        .iter()
+        .all(|color| color.is_none())
+            && pledit.font.is_none()
+            && pledit.font_size.is_none()
+            && pledit.custom.is_empty();
+
+        [
+            (
+                VISCOLOR_TXT,
+                (!self.vis_colors.is_empty()).then(|| self.vis_colors.to_txt()),
+            ),
+            (PLEDIT_TXT, (!pledit_empty).then(|| self.pledit.to_txt())),
+            (REGION_TXT, (!regions_empty).then(|| self.regions.to_txt())),
+        ]
+    }
+}
+
+/// Replaces, adds or removes a text file, matching its name in any directory and ignoring case
+fn write_file(archive: &mut WszArchive, file_name: &str, content: Option<String>) {
+    let existing = archive
+        .keys()
+        .find(|name| name.split('/').next_back().unwrap().eq_ignore_ascii_case(file_name))
+        .cloned();
+    match (content, existing) {
+        (Some(content), Some(existing)) => {
+            // Winamp reads these files in the system code page, so keep whatever encoding the skin used
+            let encoding = encoding::detect_encoding(&archive[&existing]);
+            archive.insert(existing, encoding::encode_with(&content, encoding));
+        }
+        (Some(content), None) => {
+            archive.insert(file_name.to_string(), content.into_bytes());
+        }
+        (None, Some(existing)) => {
+            archive.remove(&existing);
+        }
+        (None, None) => {}
+    }
+}
+
+/// Takes a parsed file, collecting its warnings, or the default if the skin does not have it
+fn or_default<T: Default>(parsed: Result<(T, Vec<ParseWarning>)>, warnings: &mut Vec<ParseWarning>) -> Result<T> {
+    match parsed {
+        Ok((value, file_warnings)) => {
+            warnings.extend(file_warnings);
+            Ok(value)
+        }
+        Err(WszError::NotFound(_)) => Ok(T::default()),
+        Err(e) => Err(e),
+    }
+}
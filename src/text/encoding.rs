@@ -0,0 +1,236 @@
+//! Character set detection for skin text files
+//!
+//! Skins were written on many systems, so their text files are not always UTF-8. Files with a byte order mark
+//! are decoded as it says, UTF-16 without a mark is recognized by its zero bytes, and valid UTF-8 is taken as is.
+//! With the `encoding` feature, anything else is identified by `chardetng` and decoded by `encoding_rs`, which
+//! covers Shift-JIS, GBK, Big5, EUC-KR and the Cyrillic and Central European code pages. Without it, anything else
+//! is decoded as Windows-1252, the most common encoding for skins made on Windows.
+
+/// Encoding a text file was decoded from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    /// UTF-8, with or without a byte order mark
+    Utf8,
+    /// UTF-16, little endian
+    Utf16Le,
+    /// UTF-16, big endian
+    Utf16Be,
+    /// Windows-1252 (Western European)
+    Windows1252,
+    /// Another legacy encoding, such as Shift-JIS. Requires the `encoding` feature.
+    #[cfg(feature = "encoding")]
+    Legacy(&'static encoding_rs::Encoding),
+}
+
+/// Characters for bytes 0x80 to 0x9F in Windows-1252. The rest of the range matches Latin-1 and Unicode.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{FFFD}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}', '\u{02C6}',
+    '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{FFFD}', '\u{017D}', '\u{FFFD}', '\u{FFFD}', '\u{2018}',
+    '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}', '\u{02DC}', '\u{2122}', '\u{0161}',
+    '\u{203A}', '\u{0153}', '\u{FFFD}', '\u{017E}', '\u{0178}',
+];
+
+/// Guesses the encoding of a text file
+///
+/// # Arguments
+///
+/// * `data` - Contents of the file
+///
+/// # Returns
+///
+/// The most likely encoding. Without the `encoding` feature, Shift-JIS and other multi-byte legacy encodings are
+/// not recognized, and are decoded as Windows-1252.
+pub fn detect_encoding(data: &[u8]) -> TextEncoding {
+    if data.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        return TextEncoding::Utf8;
+    }
+    if data.starts_with(&[0xFF, 0xFE]) {
+        return TextEncoding::Utf16Le;
+    }
+    if data.starts_with(&[0xFE, 0xFF]) {
+        return TextEncoding::Utf16Be;
+    }
+
+    // ASCII text in UTF-16 has a zero byte in every character, on the even or odd side depending on byte order
+    if data.len() >= 2 {
+        let pairs = data.len() / 2;
+        let even_zeros = data.iter().step_by(2).filter(|&&b| b == 0).count();
+        let odd_zeros = data.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+        if odd_zeros * 2 > pairs && even_zeros * 10 < pairs {
+            return TextEncoding::Utf16Le;
+        }
+        if even_zeros * 2 > pairs && odd_zeros * 10 < pairs {
+            return TextEncoding::Utf16Be;
+        }
+    }
+
+    if std::str::from_utf8(data).is_ok() {
+        TextEncoding::Utf8
+    } else {
+        detect_legacy_encoding(data)
+    }
+}
+
+/// Guesses the legacy encoding of text that is not UTF-8 or UTF-16
+#[cfg(feature = "encoding")]
+fn detect_legacy_encoding(data: &[u8]) -> TextEncoding {
+    let mut detector = chardetng::EncodingDetector::new(chardetng::Iso2022JpDetection::Deny);
+    detector.feed(data, true);
+    match detector.guess(None, chardetng::Utf8Detection::Deny) {
+        encoding if encoding == encoding_rs::WINDOWS_1252 => TextEncoding::Windows1252,
+        encoding => TextEncoding::Legacy(encoding),
+    }
+}
+
+/// Guesses the legacy encoding of text that is not UTF-8 or UTF-16
+#[cfg(not(feature = "encoding"))]
+fn detect_legacy_encoding(_data: &[u8]) -> TextEncoding {
+    TextEncoding::Windows1252
+}
+
+/// Decodes a text file in a particular encoding, dropping any byte order mark
+///
+/// # Arguments
+///
+/// * `data` - Contents of the file
+/// * `encoding` - Encoding to decode from
+///
+/// # Returns
+///
+/// The text. Invalid sequences are replaced with U+FFFD.
+pub fn decode_with(data: &[u8], encoding: TextEncoding) -> String {
+    match encoding {
+        TextEncoding::Utf8 => {
+            String::from_utf8_lossy(data.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(data)).into_owned()
+        }
+        TextEncoding::Utf16Le | TextEncoding::Utf16Be => {
+            let units = data.chunks_exact(2).map(|pair| match encoding {
+                TextEncoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                _ => u16::from_be_bytes([pair[0], pair[1]]),
+            });
+            let text: String = char::decode_utf16(units)
+                .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect();
+            text.strip_prefix('\u{FEFF}').map(str::to_string).unwrap_or(text)
+        }
+        TextEncoding::Windows1252 => data
+            .iter()
+            .map(|&b| match b {
+                0x80..=0x9F => WINDOWS_1252_HIGH[(b - 0x80) as usize],
+                _ => b as char,
+            })
+            .collect(),
+        #[cfg(feature = "encoding")]
+        TextEncoding::Legacy(encoding) => encoding.decode_without_bom_handling(data).0.into_owned(),
+    }
+}
+
+/// Decodes a text file, detecting its encoding
+///
+/// # Arguments
+///
+/// * `data` - Contents of the file
+///
+/// # Returns
+///
+/// The text and the encoding it was decoded from
+pub fn decode_text(data: &[u8]) -> (String, TextEncoding) {
+    let encoding = detect_encoding(data);
+    (decode_with(data, encoding), encoding)
+}
+
+/// Encodes text for writing back to a skin, in the encoding the file was read from
+///
+/// # Arguments
+///
+/// * `text` - Text to encode
+/// * `encoding` - Encoding to write
+///
+/// # Returns
+///
+/// The encoded bytes. UTF-16 starts with a byte order mark, and characters a legacy encoding cannot represent are
+/// written as '?'.
+pub fn encode_with(text: &str, encoding: TextEncoding) -> Vec<u8> {
+    match encoding {
+        TextEncoding::Utf8 => text.as_bytes().to_vec(),
+        TextEncoding::Utf16Le => std::iter::once(0xFEFF)
+            .chain(text.encode_utf16())
+            .flat_map(u16::to_le_bytes)
+            .collect(),
+        TextEncoding::Utf16Be => std::iter::once(0xFEFF)
+            .chain(text.encode_utf16())
+            .flat_map(u16::to_be_bytes)
+            .collect(),
+        TextEncoding::Windows1252 => text
+            .chars()
+            .map(|c| match c as u32 {
+                0x00..=0x7F | 0xA0..=0xFF => c as u8,
+                _ => WINDOWS_1252_HIGH
+                    .iter()
+                    .position(|&high| high == c && high != char::REPLACEMENT_CHARACTER)
+                    .map_or(b'?', |i| 0x80 + i as u8),
+            })
+            .collect(),
+        #[cfg(feature = "encoding")]
+        TextEncoding::Legacy(encoding) => {
+            let mut bytes = Vec::with_capacity(text.len());
+            let mut buffer = [0; 4];
+            for c in text.chars() {
+                let (encoded, _, unmappable) = encoding.encode(c.encode_utf8(&mut buffer));
+                if unmappable {
+                    bytes.push(b'?');
+                } else {
+                    bytes.extend_from_slice(&encoded);
+                }
+            }
+            bytes
+        }
+    }
+}
+
+#[cfg(all(test, feature = "encoding"))]
+mod tests {
+    use super::*;
+    use crate::archive::WszArchive;
+    use crate::text::pledit::PleditSettings;
+    use crate::text::readme::SkinMetadata;
+
+    /// Encodes test text as Shift-JIS
+    fn shift_jis(text: &str) -> Vec<u8> {
+        encoding_rs::SHIFT_JIS.encode(text).0.into_owned()
+    }
+
+    #[test]
+    fn detects_shift_jis() {
+        let data = shift_jis("[Text]\r\nFont=ＭＳ Ｐゴシック\r\n; 日本語のスキンです\r\n");
+        let (text, encoding) = decode_text(&data);
+        assert_eq!(encoding, TextEncoding::Legacy(encoding_rs::SHIFT_JIS));
+        assert!(text.contains("ＭＳ Ｐゴシック"));
+        assert_eq!(encode_with(&text, encoding), data);
+    }
+
+    #[test]
+    fn reads_shift_jis_pledit_font() {
+        let pledit = "[Text]\r\nNormal=#00FF00\r\nFont=ＭＳ Ｐゴシック\r\n; プレイリストの設定\r\n";
+        let archive = WszArchive::from([("pledit.txt".to_string(), shift_jis(pledit))]);
+        let settings = PleditSettings::from_archive(&archive).unwrap();
+        assert_eq!(settings.font.as_deref(), Some("ＭＳ Ｐゴシック"));
+    }
+
+    #[test]
+    fn reads_shift_jis_readme() {
+        let readme = "スキン名: 夕焼けの街\r\nAuthor: 山田太郎\r\n\r\n\
+                      このスキンを使ってくれてありがとう。\r\n";
+        let archive = WszArchive::from([("readme.txt".to_string(), shift_jis(readme))]);
+        let metadata = SkinMetadata::from_archive(&archive).unwrap();
+        assert_eq!(metadata.author.as_deref(), Some("山田太郎"));
+    }
+
+    #[test]
+    fn windows_1252_stays_windows_1252() {
+        let data = b"Skin by Ren\xe9e M\xfcller \x96 caf\xe9 edition\r\n".to_vec();
+        let (text, encoding) = decode_text(&data);
+        assert_eq!(encoding, TextEncoding::Windows1252);
+        assert!(text.contains("Renée Müller – café"));
+    }
+}
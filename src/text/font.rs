@@ -0,0 +1,218 @@
+//! Resolution of pledit.txt font names to font files on this system
+//!
+//! pledit.txt only names a font family, such as "Arial". Renderers that draw the playlist need a font file, so
+//! the family is looked up with fontconfig's `fc-match` where it is installed, and otherwise by searching the
+//! usual font directories for a file named after the family. Requires the `system-fonts` feature.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+
+use crate::text::pledit::PleditSettings;
+
+/// Families tried, in order, when the requested font cannot be found by searching font directories
+const FALLBACK_FAMILIES: [&str; 6] = [
+    "Arial",
+    "Liberation Sans",
+    "DejaVu Sans",
+    "Helvetica",
+    "Verdana",
+    "Tahoma",
+];
+
+/// Extensions of font files that can be used
+const FONT_EXTENSIONS: [&str; 4] = ["ttf", "otf", "ttc", "otc"];
+
+/// Deepest directory level searched below each font directory
+const MAX_SEARCH_DEPTH: usize = 4;
+
+/// A font file found for a font family
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedFont {
+    /// Family that was asked for
+    pub requested: String,
+    /// Family of the font that was found
+    pub family: String,
+    /// Path of the font file
+    pub path: PathBuf,
+    /// Whether the font is a substitute because the requested family is not installed
+    pub fallback: bool,
+}
+
+/// Find a font file for a font family
+///
+/// # Arguments
+///
+/// * `family` - Name of the font family, e.g. "Arial"
+///
+/// # Returns
+///
+/// The font file, which is a substitute if the family is not installed, or None if no usable font was found
+///
+/// Where fontconfig is installed, `fc-match` is run in a new process the first time each family is resolved. The
+/// font directories searched otherwise are only listed on the first call. Both results are kept for the life of the
+/// process, so fonts installed after that are not found.
+pub fn resolve_font(family: &str) -> Option<ResolvedFont> {
+    resolve_with_fontconfig(family).or_else(|| resolve_in_font_dirs(family))
+}
+
+impl PleditSettings {
+    /// Find a font file for the playlist font, or Winamp's default font if the skin does not set one
+    ///
+    /// Requires the `system-fonts` feature.
+    ///
+    /// # Returns
+    ///
+    /// The font file, which is a substitute if the font is not installed, or None if no usable font was found
+    pub fn resolve_font(&self) -> Option<ResolvedFont> {
+        resolve_font(self.effective_font())
+    }
+}
+
+/// Asks fontconfig for the best match, which it always has unless no fonts are installed at all
+fn resolve_with_fontconfig(family: &str) -> Option<ResolvedFont> {
+    static MATCHES: OnceLock<Mutex<HashMap<String, Option<ResolvedFont>>>> = OnceLock::new();
+    let matches = MATCHES.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some(found) = matches.lock().ok()?.get(family) {
+        return found.clone();
+    }
+    let found = run_fc_match(family);
+    if let Ok(mut matches) = matches.lock() {
+        matches.insert(family.to_string(), found.clone());
+    }
+    found
+}
+
+fn run_fc_match(family: &str) -> Option<ResolvedFont> {
+    let output = Command::new("fc-match")
+        .arg("--format=%{family[0]}\n%{file}")
+        .arg(family)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let output = String::from_utf8_lossy(&output.stdout);
+    let (found, path) = output.split_once('\n')?;
+    let path = PathBuf::from(path.trim());
+    if !path.is_file() || !is_font_file(&path) {
+        return None;
+    }
+
+    Some(ResolvedFont {
+        requested: family.to_string(),
+        family: found.trim().to_string(),
+        fallback: normalize(found) != normalize(family),
+        path,
+    })
+}
+
+/// Searches font directories for a file named after the family, then after each fallback family
+fn resolve_in_font_dirs(family: &str) -> Option<ResolvedFont> {
+    let files = installed_font_files();
+    let requested = std::iter::once(family).chain(FALLBACK_FAMILIES);
+    for (i, candidate) in requested.enumerate() {
+        if let Some(path) = find_font_file(files, candidate) {
+            return Some(ResolvedFont {
+                requested: family.to_string(),
+                family: candidate.to_string(),
+                path: path.clone(),
+                fallback: i > 0,
+            });
+        }
+    }
+
+    // any font is better than none
+    files.first().map(|path| ResolvedFont {
+        requested: family.to_string(),
+        family: path.file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+        path: path.clone(),
+        fallback: true,
+    })
+}
+
+/// Picks the file for a family, preferring the regular style, e.g. arial.ttf over arialbd.ttf or Arial-Bold.ttf
+fn find_font_file<'a>(files: &'a [PathBuf], family: &str) -> Option<&'a PathBuf> {
+    let family = normalize(family);
+    let stem = |path: &PathBuf| normalize(&path.file_stem().unwrap_or_default().to_string_lossy());
+    files
+        .iter()
+        .find(|path| {
+            let stem = stem(path);
+            stem == family || stem == format!("{}regular", family)
+        })
+        .or_else(|| files.iter().find(|path| stem(path).starts_with(&family)))
+}
+
+/// Font files in the font directories, which are searched once per process
+fn installed_font_files() -> &'static [PathBuf] {
+    static FILES: OnceLock<Vec<PathBuf>> = OnceLock::new();
+    FILES.get_or_init(|| {
+        let mut files = Vec::new();
+        for dir in font_dirs() {
+            collect_font_files(&dir, 0, &mut files);
+        }
+        files.sort();
+        files
+    })
+}
+
+/// Directories fonts are installed in on Windows, macOS and Linux
+fn font_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(windir) = env::var_os("WINDIR") {
+        dirs.push(PathBuf::from(windir).join("Fonts"));
+    }
+    if let Some(local) = env::var_os("LOCALAPPDATA") {
+        dirs.push(PathBuf::from(local).join("Microsoft").join("Windows").join("Fonts"));
+    }
+    if let Some(home) = env::var_os("HOME") {
+        let home = PathBuf::from(home);
+        dirs.push(home.join("Library").join("Fonts"));
+        dirs.push(home.join(".local").join("share").join("fonts"));
+        dirs.push(home.join(".fonts"));
+    }
+    for dir in [
+        "/Library/Fonts",
+        "/System/Library/Fonts",
+        "/usr/share/fonts",
+        "/usr/local/share/fonts",
+    ] {
+        dirs.push(PathBuf::from(dir));
+    }
+    dirs
+}
+
+fn collect_font_files(dir: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth < MAX_SEARCH_DEPTH {
+                collect_font_files(&path, depth + 1, files);
+            }
+        } else if is_font_file(&path) {
+            files.push(path);
+        }
+    }
+}
+
+fn is_font_file(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| {
+        FONT_EXTENSIONS
+            .iter()
+            .any(|font_ext| ext.eq_ignore_ascii_case(font_ext))
+    })
+}
+
+/// Lowercases a family or file name and drops spaces, dashes and underscores, so "Liberation Sans" matches
+/// LiberationSans-Regular.ttf
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
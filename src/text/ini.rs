@@ -0,0 +1,155 @@
+//! Parser for the INI-style text files used in Winamp skins
+//!
+//! pledit.txt and region.txt are INI files, and skins sometimes ship other INI files for plugins. Files are read
+//! line by line into an `Ini` that keeps every line, including comments and lines that are not valid INI, so
+//! callers can decide what to do with them.
+
+/// Kinds of lines in an INI file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IniLineKind {
+    /// Empty or whitespace-only line
+    Blank,
+    /// Comment starting with ';', without the ';'
+    Comment(String),
+    /// Section header, e.g. "[Text]", with the name trimmed
+    Section(String),
+    /// Key-value pair, with the key and value trimmed
+    Entry {
+        /// Key, as written
+        key: String,
+        /// Value, which may be empty
+        value: String,
+    },
+    /// Line that is none of the above, trimmed
+    Invalid(String),
+}
+
+/// A line of an INI file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IniLine {
+    /// Line number, starting from 1
+    pub number: usize,
+    /// The line as written, without its line ending
+    pub text: String,
+    /// What the line contains
+    pub kind: IniLineKind,
+}
+
+/// A key-value pair along with the section it is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IniEntry<'a> {
+    /// Section the pair is in, or None if it comes before the first section header
+    pub section: Option<&'a str>,
+    /// Key, as written
+    pub key: &'a str,
+    /// Value, which may be empty
+    pub value: &'a str,
+    /// Line number, starting from 1
+    pub line: usize,
+}
+
+/// A parsed INI file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ini {
+    /// Every line of the file, in order
+    pub lines: Vec<IniLine>,
+}
+
+impl Ini {
+    /// Parse INI content
+    ///
+    /// Parsing never fails: lines that are not blank, a comment, a section header or a key-value pair are kept
+    /// as `IniLineKind::Invalid`. A leading byte order mark is ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - Contents of the file
+    ///
+    /// # Returns
+    ///
+    /// The parsed file
+    pub fn parse(content: &str) -> Self {
+        let content = content.strip_prefix('\u{FEFF}').unwrap_or(content);
+        let lines = content
+            .lines()
+            .enumerate()
+            .map(|(i, line)| IniLine {
+                number: i + 1,
+                text: line.to_string(),
+                kind: parse_line(line),
+            })
+            .collect();
+        Self { lines }
+    }
+
+    /// Iterates over the key-value pairs, in file order
+    pub fn entries(&self) -> impl Iterator<Item = IniEntry<'_>> {
+        let mut section = None;
+        self.lines.iter().filter_map(move |line| match &line.kind {
+            IniLineKind::Section(name) => {
+                section = Some(name.as_str());
+                None
+            }
+            IniLineKind::Entry { key, value } => Some(IniEntry {
+                section,
+                key,
+                value,
+                line: line.number,
+            }),
+            _ => None,
+        })
+    }
+
+    /// Names of the sections, in file order, including repeats
+    pub fn sections(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().filter_map(|line| match &line.kind {
+            IniLineKind::Section(name) => Some(name.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Gets the first value of a key, ignoring the case of the section and key like Windows does
+    ///
+    /// # Arguments
+    ///
+    /// * `section` - Section the key is in
+    /// * `key` - Key to look up
+    ///
+    /// # Returns
+    ///
+    /// The value, or None if the file does not have the key in that section
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.entries()
+            .find(|entry| {
+                entry.section.is_some_and(|s| s.eq_ignore_ascii_case(section)) && entry.key.eq_ignore_ascii_case(key)
+            })
+            .map(|entry| entry.value)
+    }
+}
+
+/// Returns the line ending used by content, "\r\n" if it has any and "\n" otherwise
+pub fn line_ending(content: &str) -> &'static str {
+    if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    }
+}
+
+fn parse_line(line: &str) -> IniLineKind {
+    let line = line.trim();
+    if line.is_empty() {
+        IniLineKind::Blank
+    } else if let Some(comment) = line.strip_prefix(';') {
+        IniLineKind::Comment(comment.to_string())
+    } else if line.starts_with('[') && line.ends_with(']') {
+        IniLineKind::Section(line[1..line.len() - 1].trim().to_string())
+    } else if let Some(pos) = line.find('=') {
+        IniLineKind::Entry {
+            key: line[..pos].trim().to_string(),
+            value: line[pos + 1..].trim().to_string(),
+        }
+    } else {
+        IniLineKind::Invalid(line.to_string())
+    }
+}
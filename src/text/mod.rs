@@ -1,5 +1,136 @@
 //! Support for the various text files in Winamp skins
 
+use std::fmt;
+
+use crate::error::{Result, WszError};
+
+pub mod config;
+pub mod encoding;
+#[cfg(feature = "system-fonts")]
+pub mod font;
+pub mod ini;
 pub mod pledit;
+pub mod readme;
 pub mod region;
 pub mod viscolor;
+
+/// How strictly text files are parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    /// Fail on the first line that cannot be parsed
+    #[default]
+    Strict,
+    /// Skip lines that cannot be parsed, reporting each one as a warning
+    Lenient,
+}
+
+/// A problem found in a text file that was skipped by a lenient parse
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWarning {
+    /// Name of the file, e.g. "pledit.txt"
+    pub file: String,
+    /// Line the problem was found on, starting from 1
+    pub line: usize,
+    /// Description of the problem
+    pub message: String,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} line {}: {}", self.file, self.line, self.message)
+    }
+}
+
+/// A line of a text file that a parser skipped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnparsedLine {
+    /// Line number, starting from 1
+    pub line: usize,
+    /// The line as written
+    pub text: String,
+}
+
+/// A section of an INI-style text file that a parser does not use
+///
+/// Its keys are still kept as custom settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownSection {
+    /// Line number of the section header, starting from 1
+    pub line: usize,
+    /// Name of the section, without the brackets
+    pub name: String,
+}
+
+/// The parts of a text file a parser did not understand, for diagnostics
+///
+/// Lines are only skipped in lenient mode, since strict mode fails on them instead, but lines that are ignored
+/// in either mode, such as text in viscolor.txt that is not a color, are always listed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnparsedContent {
+    /// Lines that were skipped, in file order
+    pub lines: Vec<UnparsedLine>,
+    /// Sections that are not used, in file order, including repeats
+    pub sections: Vec<UnknownSection>,
+}
+
+impl UnparsedContent {
+    /// Checks whether the whole file was understood
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty() && self.sections.is_empty()
+    }
+
+    pub(crate) fn push_line(&mut self, line: usize, text: &str) {
+        self.lines.push(UnparsedLine {
+            line,
+            text: text.to_string(),
+        });
+    }
+
+    pub(crate) fn push_section(&mut self, line: usize, name: &str) {
+        self.sections.push(UnknownSection {
+            line,
+            name: name.to_string(),
+        });
+    }
+}
+
+/// Collects the problems found while parsing a file, failing on the first one in strict mode
+pub(crate) struct Diagnostics {
+    mode: ParseMode,
+    file: &'static str,
+    warnings: Vec<ParseWarning>,
+}
+
+impl Diagnostics {
+    pub(crate) fn new(mode: ParseMode, file: &'static str) -> Self {
+        Self {
+            mode,
+            file,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Reports a problem on a line
+    ///
+    /// # Returns
+    ///
+    /// An `InvalidFormat` error in strict mode, so the caller can stop with `?`. In lenient mode the problem is
+    /// recorded as a warning and the caller should skip the line.
+    pub(crate) fn report(&mut self, line: usize, message: String) -> Result<()> {
+        match self.mode {
+            ParseMode::Strict => Err(WszError::InvalidFormat { line, error: message }),
+            ParseMode::Lenient => {
+                self.warnings.push(ParseWarning {
+                    file: self.file.to_string(),
+                    line,
+                    message,
+                });
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn into_warnings(self) -> Vec<ParseWarning> {
+        self.warnings
+    }
+}
@@ -8,6 +8,19 @@ use image::Rgb;
 
 use crate::archive::WszArchive;
 use crate::error::{Result, WszError};
+use crate::text::ini::{self, Ini, IniLineKind};
+use crate::text::{encoding, Diagnostics, ParseMode, ParseWarning, UnparsedContent};
+
+/// Winamp's default normal text color
+pub const WINAMP_DEFAULT_NORMAL: Rgb<u8> = Rgb([0x00, 0xFF, 0x00]);
+/// Winamp's default current text color
+pub const WINAMP_DEFAULT_CURRENT: Rgb<u8> = Rgb([0xFF, 0xFF, 0xFF]);
+/// Winamp's default normal background color
+pub const WINAMP_DEFAULT_NORMAL_BG: Rgb<u8> = Rgb([0x00, 0x00, 0x00]);
+/// Winamp's default selected background color
+pub const WINAMP_DEFAULT_SELECTED_BG: Rgb<u8> = Rgb([0x00, 0x00, 0xC6]);
+/// Winamp's default font name
+pub const WINAMP_DEFAULT_FONT: &str = "Arial";
 
 /// Playlist editor settings (colors and font)
 #[derive(Debug, Clone)]
@@ -22,8 +35,18 @@ pub struct PleditSettings {
     pub selected_bg: Option<Rgb<u8>>,
     /// Font name
     pub font: Option<String>,
+    /// Font size in points (FontSize, also read from "Font Size" and FontHeight)
+    pub font_size: Option<u32>,
+    /// Minibrowser text color (mbFG)
+    pub mb_fg: Option<Rgb<u8>>,
+    /// Minibrowser background color (mbBG)
+    pub mb_bg: Option<Rgb<u8>>,
     /// Additional custom settings not defined in the standard
     pub custom: HashMap<String, String>,
+    // file the settings were parsed from, kept so that to_txt can preserve its formatting
+    source: Option<String>,
+    // parts of the file that were not understood
+    unparsed: UnparsedContent,
 }
 
 impl PleditSettings {
@@ -34,124 +57,380 @@ impl PleditSettings {
             normal_bg: None,
             selected_bg: None,
             font: None,
+            font_size: None,
+            mb_fg: None,
+            mb_bg: None,
             custom: HashMap::new(),
+            source: None,
+            unparsed: UnparsedContent::default(),
         }
     }
 
+    /// Creates the settings of Winamp's base skin
+    pub fn winamp_default() -> Self {
+        Self {
+            normal: Some(WINAMP_DEFAULT_NORMAL),
+            current: Some(WINAMP_DEFAULT_CURRENT),
+            normal_bg: Some(WINAMP_DEFAULT_NORMAL_BG),
+            selected_bg: Some(WINAMP_DEFAULT_SELECTED_BG),
+            font: Some(WINAMP_DEFAULT_FONT.to_string()),
+            font_size: None,
+            mb_fg: None,
+            mb_bg: None,
+            custom: HashMap::new(),
+            source: None,
+            unparsed: UnparsedContent::default(),
+        }
+    }
+
+    /// Lines and sections of the parsed pledit.txt that were not understood
+    ///
+    /// Sections other than [Text] are listed here, though their keys are kept in `custom`.
+    pub fn unparsed(&self) -> &UnparsedContent {
+        &self.unparsed
+    }
+
+    /// Normal text color, or Winamp's default if the skin does not set it
+    pub fn effective_normal(&self) -> Rgb<u8> {
+        self.normal.unwrap_or(WINAMP_DEFAULT_NORMAL)
+    }
+
+    /// Current text color, or Winamp's default if the skin does not set it
+    pub fn effective_current(&self) -> Rgb<u8> {
+        self.current.unwrap_or(WINAMP_DEFAULT_CURRENT)
+    }
+
+    /// Normal background color, or Winamp's default if the skin does not set it
+    pub fn effective_normal_bg(&self) -> Rgb<u8> {
+        self.normal_bg.unwrap_or(WINAMP_DEFAULT_NORMAL_BG)
+    }
+
+    /// Selected background color, or Winamp's default if the skin does not set it
+    pub fn effective_selected_bg(&self) -> Rgb<u8> {
+        self.selected_bg.unwrap_or(WINAMP_DEFAULT_SELECTED_BG)
+    }
+
+    /// Font name, or Winamp's default if the skin does not set it
+    pub fn effective_font(&self) -> &str {
+        self.font.as_deref().unwrap_or(WINAMP_DEFAULT_FONT)
+    }
+
     /// Find pledit.txt in the archive contents
     pub fn from_archive(archive: &WszArchive) -> Result<Self> {
+        Self::from_archive_with_mode(archive, ParseMode::Strict).map(|(settings, _)| settings)
+    }
+
+    /// Find pledit.txt in the archive contents and parse it, optionally skipping lines that cannot be parsed
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - Archive to find pledit.txt in
+    /// * `mode` - How to handle lines that cannot be parsed
+    ///
+    /// # Returns
+    ///
+    /// The settings and the problems skipped in lenient mode
+    pub fn from_archive_with_mode(archive: &WszArchive, mode: ParseMode) -> Result<(Self, Vec<ParseWarning>)> {
         let pledit_txt = archive
             .iter()
-            .find(|(name, _)| name.split('/').last().unwrap().to_lowercase() == "pledit.txt")
+            .find(|(name, _)| name.split('/').next_back().unwrap().to_lowercase() == "pledit.txt")
             .ok_or(WszError::NotFound("pledit.txt".to_string()))?;
 
-        Self::from_string(&String::from_utf8_lossy(pledit_txt.1).to_string())
+        Self::from_string_with_mode(&encoding::decode_text(pledit_txt.1).0, mode)
     }
 
     /// Parse pledit.txt content into settings
     pub fn from_string(content: &str) -> Result<Self> {
-        let mut settings = Self::new();
+        Self::from_string_with_mode(content, ParseMode::Strict).map(|(settings, _)| settings)
+    }
+
+    /// Parse pledit.txt content into settings, optionally skipping lines that cannot be parsed
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - Contents of pledit.txt
+    /// * `mode` - How to handle lines that cannot be parsed
+    ///
+    /// # Returns
+    ///
+    /// The settings and the problems skipped in lenient mode
+    pub fn from_string_with_mode(content: &str, mode: ParseMode) -> Result<(Self, Vec<ParseWarning>)> {
+        let mut settings = Self {
+            source: Some(content.to_string()),
+            ..Self::new()
+        };
+        let mut diagnostics = Diagnostics::new(mode, "pledit.txt");
+        let mut unparsed = UnparsedContent::default();
 
         let mut current_section = String::new();
 
-        for (line_num, line) in content.lines().enumerate() {
-            let line = line.trim();
+        for line in Ini::parse(content).lines {
+            let line_num = line.number;
+            let (key, value) = match &line.kind {
+                // Skip empty lines and comments
+                IniLineKind::Blank | IniLineKind::Comment(_) => continue,
+                IniLineKind::Section(name) => {
+                    if name != "Text" {
+                        unparsed.push_section(line_num, name);
+                    }
+                    current_section = name.clone();
+                    continue;
+                }
+                IniLineKind::Entry { key, value } => (key.as_str(), value.as_str()),
+                IniLineKind::Invalid(text) => {
+                    // Line is not a key-value pair and not a section header
+                    diagnostics.report(line_num, format!("Invalid line format: '{}'", text))?;
+                    unparsed.push_line(line_num, &line.text);
+                    continue;
+                }
+            };
 
-            // Skip empty lines
-            if line.is_empty() {
-                continue;
+            let mut color = || match parse_hex_color(value) {
+                Ok(color) => Ok(Some(color)),
+                Err(error) => {
+                    diagnostics.report(line_num, error)?;
+                    unparsed.push_line(line_num, &line.text);
+                    Ok::<_, WszError>(None)
+                }
+            };
+
+            match current_section.as_str() {
+                "Text" => {
+                    match key.to_lowercase().as_str() {
+                        "normal" => {
+                            settings.normal = color()?.or(settings.normal);
+                        }
+                        "current" => {
+                            settings.current = color()?.or(settings.current);
+                        }
+                        "normalbg" => {
+                            settings.normal_bg = color()?.or(settings.normal_bg);
+                        }
+                        "selectedbg" => {
+                            settings.selected_bg = color()?.or(settings.selected_bg);
+                        }
+                        "font" => {
+                            settings.font = Some(value.to_string());
+                        }
+                        "fontsize" | "font size" | "fontheight" => match value.parse::<u32>() {
+                            Ok(size) => settings.font_size = Some(size),
+                            Err(_) => {
+                                diagnostics.report(line_num, format!("Invalid font size: '{}'", value))?;
+                                unparsed.push_line(line_num, &line.text);
+                            }
+                        },
+                        "mbfg" => {
+                            settings.mb_fg = color()?.or(settings.mb_fg);
+                        }
+                        "mbbg" => {
+                            settings.mb_bg = color()?.or(settings.mb_bg);
+                        }
+                        _ => {
+                            // Store unknown keys as custom settings
+                            settings.custom.insert(key.to_string(), value.to_string());
+                        }
+                    }
+                }
+                // Store settings from other sections as custom settings
+                _ if !current_section.is_empty() => {
+                    let full_key = format!("{}.{}", current_section, key);
+                    settings.custom.insert(full_key, value.to_string());
+                }
+                _ => {
+                    diagnostics.report(line_num, "Key-value pair outside of any section".to_string())?;
+                    unparsed.push_line(line_num, &line.text);
+                }
             }
+        }
+
+        settings.unparsed = unparsed;
+        Ok((settings, diagnostics.into_warnings()))
+    }
+}
+
+impl PleditSettings {
+    /// Serializes the settings as pledit.txt content
+    ///
+    /// If the settings were parsed from a file, that file is updated in place: comments, the order of keys and
+    /// its line endings are kept, values that have not changed are left as written, settings that were removed
+    /// are dropped and new ones are added to the end of their section.
+    ///
+    /// Otherwise, settings that are not set are left out, so Winamp uses its defaults for them. Custom settings
+    /// are written after the standard ones, in their sections, sorted by key.
+    pub fn to_txt(&self) -> String {
+        match &self.source {
+            Some(source) => self.rewrite_txt(source),
+            None => self.format_txt(),
+        }
+    }
 
-            // Skip comments
-            if line.starts_with(';') {
-                continue;
+    /// Lists the settings to write as (section, key, value), standard settings first
+    pub(crate) fn txt_entries(&self) -> Vec<(String, String, String)> {
+        let mut entries = Vec::new();
+        let mut text = |key: &str, value: Option<String>| {
+            if let Some(value) = value {
+                entries.push(("Text".to_string(), key.to_string(), value));
             }
+        };
+        text("Normal", self.normal.map(format_hex_color));
+        text("Current", self.current.map(format_hex_color));
+        text("NormalBG", self.normal_bg.map(format_hex_color));
+        text("SelectedBG", self.selected_bg.map(format_hex_color));
+        text("Font", self.font.clone());
+        text("FontSize", self.font_size.map(|size| size.to_string()));
+        text("mbFG", self.mb_fg.map(format_hex_color));
+        text("mbBG", self.mb_bg.map(format_hex_color));
+
+        // keys in other sections are stored as "Section.key"
+        let mut custom: Vec<(String, String, String)> = self
+            .custom
+            .iter()
+            .map(|(key, value)| match key.split_once('.') {
+                Some((section, key)) => (section.to_string(), key.to_string(), value.clone()),
+                None => ("Text".to_string(), key.clone(), value.clone()),
+            })
+            .collect();
+        custom.sort_by(|a, b| (a.0 != "Text", &a.0, &a.1).cmp(&(b.0 != "Text", &b.0, &b.1)));
+        entries.extend(custom);
+        entries
+    }
 
-            // Check for section headers
-            if line.starts_with('[') && line.ends_with(']') {
-                current_section = line[1..line.len() - 1].to_string();
-                continue;
+    /// Writes the settings in Winamp's layout
+    fn format_txt(&self) -> String {
+        let mut txt = String::from("[Text]\r\n");
+        let mut current_section = "Text".to_string();
+        for (section, key, value) in self.txt_entries() {
+            if section != current_section {
+                txt.push_str(&format!("\r\n[{}]\r\n", section));
+                current_section = section;
             }
+            txt.push_str(&format!("{}={}\r\n", key, value));
+        }
+        txt
+    }
 
-            // Parse key-value pairs
-            if let Some(pos) = line.find('=') {
-                let key = line[..pos].trim();
-                let value = line[pos + 1..].trim();
+    /// Writes the settings into the file they were parsed from
+    fn rewrite_txt(&self, source: &str) -> String {
+        let entries = self.txt_entries();
+        let mut written = vec![false; entries.len()];
+        let mut lines: Vec<String> = Vec::new();
+        // index in lines just after the last line of each section, for adding new keys
+        let mut section_ends: Vec<(String, usize)> = Vec::new();
+        let mut section: Option<String> = None;
 
-                match current_section.as_str() {
-                    "Text" => {
-                        match key.to_lowercase().as_str() {
-                            "normal" => {
-                                settings.normal = Some(parse_hex_color(value)?);
-                            }
-                            "current" => {
-                                settings.current = Some(parse_hex_color(value)?);
-                            }
-                            "normalbg" => {
-                                settings.normal_bg = Some(parse_hex_color(value)?);
-                            }
-                            "selectedbg" => {
-                                settings.selected_bg = Some(parse_hex_color(value)?);
-                            }
-                            "font" => {
-                                settings.font = Some(value.to_string());
-                            }
-                            _ => {
-                                // Store unknown keys as custom settings
-                                settings.custom.insert(key.to_string(), value.to_string());
-                            }
-                        }
-                    }
-                    // Store settings from other sections as custom settings
-                    _ if !current_section.is_empty() => {
-                        let full_key = format!("{}.{}", current_section, key);
-                        settings.custom.insert(full_key, value.to_string());
-                    }
-                    _ => {
-                        return Err(WszError::InvalidFormat {
-                            line: line_num + 1,
-                            error: "Key-value pair outside of any section".to_string(),
-                        });
+        for line in Ini::parse(source).lines {
+            match (&line.kind, &section) {
+                (IniLineKind::Section(name), _) => {
+                    section = Some(name.clone());
+                    lines.push(line.text.clone());
+                }
+                (IniLineKind::Entry { key, value }, Some(section)) => {
+                    let id = entry_id(section, key);
+                    let Some(i) = entries.iter().position(|entry| entry_id(&entry.0, &entry.1) == id) else {
+                        // the setting was removed
+                        continue;
+                    };
+                    written[i] = true;
+                    if normalize_value(&id, value) != entries[i].2 {
+                        lines.push(format!("{}={}", key, entries[i].2));
+                    } else {
+                        lines.push(line.text.clone());
                     }
                 }
-            } else {
-                // Line is not a key-value pair and not a section header
-                return Err(WszError::InvalidFormat {
-                    line: line_num + 1,
-                    error: format!("Invalid line format: '{}'", line),
-                });
+                _ => lines.push(line.text.clone()),
+            }
+
+            if let (Some(section), false) = (&section, line.kind == IniLineKind::Blank) {
+                match section_ends
+                    .iter_mut()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(section))
+                {
+                    Some((_, end)) => *end = lines.len(),
+                    None => section_ends.push((section.clone(), lines.len())),
+                }
+            }
+        }
+
+        // new keys go at the end of their section, or in a new section at the end of the file
+        let mut inserts: Vec<Vec<String>> = vec![Vec::new(); lines.len() + 1];
+        let mut new_sections: Vec<(String, Vec<String>)> = Vec::new();
+        for ((section, key, value), _) in entries.iter().zip(&written).filter(|(_, written)| !**written) {
+            let line = format!("{}={}", key, value);
+            match section_ends.iter().find(|(name, _)| name.eq_ignore_ascii_case(section)) {
+                Some((_, end)) => inserts[*end].push(line),
+                None => match new_sections.iter_mut().find(|(name, _)| name == section) {
+                    Some((_, lines)) => lines.push(line),
+                    None => new_sections.push((section.clone(), vec![line])),
+                },
+            }
+        }
+
+        let mut output: Vec<String> = Vec::new();
+        for (i, inserted) in inserts.into_iter().enumerate() {
+            output.extend(inserted);
+            if let Some(line) = lines.get(i) {
+                output.push(line.clone());
+            }
+        }
+        for (section, lines) in new_sections {
+            if output.last().is_some_and(|line| !line.trim().is_empty()) {
+                output.push(String::new());
             }
+            output.push(format!("[{}]", section));
+            output.extend(lines);
         }
 
-        Ok(settings)
+        let ending = ini::line_ending(source);
+        let mut txt = output.join(ending);
+        if source.ends_with('\n') || source.is_empty() {
+            txt.push_str(ending);
+        }
+        txt
     }
 }
 
-/// Parse a hex color string (e.g. "#9BBBAD" or "9BBBAD")
-fn parse_hex_color(hex: &str) -> Result<Rgb<u8>> {
-    let hex = hex.trim_start_matches('#');
+/// Identifies a setting by its section and key, ignoring case and the aliases of FontSize
+fn entry_id(section: &str, key: &str) -> (String, String) {
+    let section = section.to_lowercase();
+    let key = key.to_lowercase();
+    let key = match (section.as_str(), key.as_str()) {
+        ("text", "font size" | "fontheight") => "fontsize".to_string(),
+        _ => key,
+    };
+    (section, key)
+}
 
-    if hex.len() != 6 {
-        return Err(WszError::InvalidFormat {
-            line: 0,
-            error: format!("Invalid hex color format: '{}'", hex),
-        });
+/// Formats a value as `txt_entries` would, so unchanged values can be recognized however they are written
+fn normalize_value(id: &(String, String), value: &str) -> String {
+    match (id.0.as_str(), id.1.as_str()) {
+        ("text", "normal" | "current" | "normalbg" | "selectedbg" | "mbfg" | "mbbg") => {
+            parse_hex_color(value).map_or(value.to_string(), format_hex_color)
+        }
+        ("text", "fontsize") => value.parse::<u32>().map_or(value.to_string(), |size| size.to_string()),
+        _ => value.to_string(),
     }
+}
 
-    let r = u8::from_str_radix(&hex[0..2], 16).map_err(|_| WszError::InvalidFormat {
-        line: 0,
-        error: format!("Invalid hex value for red: '{}'", &hex[0..2]),
-    })?;
+/// Formats a color as pledit.txt writes it, e.g. "#9BBBAD"
+fn format_hex_color(color: Rgb<u8>) -> String {
+    format!("#{:02X}{:02X}{:02X}", color.0[0], color.0[1], color.0[2])
+}
+
+/// Parse a hex color string (e.g. "#9BBBAD" or "9BBBAD")
+///
+/// # Returns
+///
+/// The color, or a description of the problem
+fn parse_hex_color(hex: &str) -> std::result::Result<Rgb<u8>, String> {
+    let hex = hex.trim_start_matches('#');
 
-    let g = u8::from_str_radix(&hex[2..4], 16).map_err(|_| WszError::InvalidFormat {
-        line: 0,
-        error: format!("Invalid hex value for green: '{}'", &hex[2..4]),
-    })?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(format!("Invalid hex color format: '{}'", hex));
+    }
 
-    let b = u8::from_str_radix(&hex[4..6], 16).map_err(|_| WszError::InvalidFormat {
-        line: 0,
-        error: format!("Invalid hex value for blue: '{}'", &hex[4..6]),
-    })?;
+    let r = u8::from_str_radix(&hex[0..2], 16).map_err(|_| format!("Invalid hex value for red: '{}'", &hex[0..2]))?;
+    let g = u8::from_str_radix(&hex[2..4], 16).map_err(|_| format!("Invalid hex value for green: '{}'", &hex[2..4]))?;
+    let b = u8::from_str_radix(&hex[4..6], 16).map_err(|_| format!("Invalid hex value for blue: '{}'", &hex[4..6]))?;
 
     Ok(Rgb([r, g, b]))
 }
@@ -161,3 +440,51 @@ impl Default for PleditSettings {
         Self::new()
     }
 }
+
+/// Compares the colors, font and custom settings
+impl PartialEq for PleditSettings {
+    fn eq(&self, other: &Self) -> bool {
+        self.normal == other.normal
+            && self.current == other.current
+            && self.normal_bg == other.normal_bg
+            && self.selected_bg == other.selected_bg
+            && self.font == other.font
+            && self.font_size == other.font_size
+            && self.mb_fg == other.mb_fg
+            && self.mb_bg == other.mb_bg
+            && self.custom == other.custom
+    }
+}
+
+impl Eq for PleditSettings {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Wsz;
+
+    const PLEDIT_TXT: &str = "; playlist colors\r\n[Text]\r\nNormal=#00FF00\r\n; the playing song\r\n\
+                              Current=#ffffff\r\nNormalBG=#000000\r\nFont=Arial\r\n";
+
+    #[test]
+    fn unchanged_settings_are_written_as_read() {
+        let pledit = PleditSettings::from_string(PLEDIT_TXT).unwrap();
+        assert_eq!(pledit.to_txt(), PLEDIT_TXT);
+    }
+
+    #[test]
+    fn changed_value_keeps_comments_and_line_endings() {
+        let mut pledit = PleditSettings::from_string(PLEDIT_TXT).unwrap();
+        pledit.normal = Some(Rgb([255, 0, 0]));
+        assert_eq!(pledit.to_txt(), PLEDIT_TXT.replace("Normal=#00FF00", "Normal=#FF0000"));
+    }
+
+    #[test]
+    fn saving_an_unchanged_skin_keeps_the_file() {
+        let mut archive = WszArchive::new();
+        archive.insert("pledit.txt".to_string(), PLEDIT_TXT.as_bytes().to_vec());
+        let packed = Wsz::from_archive(&archive).unwrap().to_bytes().unwrap();
+        let repacked = Wsz::from_bytes(&packed).unwrap().to_archive().unwrap();
+        assert_eq!(repacked["pledit.txt"], PLEDIT_TXT.as_bytes());
+    }
+}
@@ -0,0 +1,299 @@
+//! Heuristic parser for the readme files that come with Winamp skins
+//!
+//! Readmes have no fixed format, so details are picked out of "Key: value" lines, "Skin by Author" lines, and
+//! anything that looks like a URL, email address or date. SkinMetadata also records what else the archive
+//! contains, so catalogs can describe a skin without opening it again.
+
+use crate::archive::WszArchive;
+use crate::error::{Result, WszError};
+use crate::text::encoding;
+
+/// Longest line that is taken as the skin's name when the readme does not label it
+const MAX_NAME_LENGTH: usize = 60;
+
+/// Text files in skins that are configuration rather than documentation
+const CONFIG_FILES: [&str; 3] = ["pledit.txt", "viscolor.txt", "region.txt"];
+
+/// Words in file names that suggest a readme, most likely first
+const README_NAMES: [&str; 7] = ["readme", "read me", "read_me", "credits", "info", "about", "skin"];
+
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+/// What a labelled readme line describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Name,
+    Author,
+    Url,
+    Contact,
+    Date,
+}
+
+impl Field {
+    fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "name" | "skin name" | "skin" | "title" | "skin title" => Some(Self::Name),
+            "author" | "authors" | "artist" | "by" | "created by" | "made by" | "designed by" | "skinned by"
+            | "skin by" | "creator" | "designer" | "skinner" => Some(Self::Author),
+            "url" | "homepage" | "home page" | "website" | "web site" | "web" | "site" => Some(Self::Url),
+            "email" | "e-mail" | "mail" | "contact" => Some(Self::Contact),
+            "date" | "released" | "release date" | "created" | "finished" => Some(Self::Date),
+            _ => None,
+        }
+    }
+}
+
+/// Details about a skin found in its readme and its archive
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SkinMetadata {
+    /// Name of the skin
+    pub name: Option<String>,
+    /// Name of the skin's author
+    pub author: Option<String>,
+    /// Website of the skin or its author
+    pub url: Option<String>,
+    /// Email address of the author
+    pub email: Option<String>,
+    /// Release date, as written in the readme
+    pub date: Option<String>,
+    /// Name of the file the details were read from
+    pub source: Option<String>,
+    /// Name of the .wsz file without its extension, if the skin was loaded from a file
+    pub file_name: Option<String>,
+    /// Number of files in the archive
+    pub file_count: usize,
+    /// Total size of the files in the archive when uncompressed, in bytes
+    pub total_size: u64,
+    /// Size of the .wsz file in bytes, if the skin was loaded from a file or bytes
+    pub compressed_size: Option<u64>,
+    /// Whether the skin styles the equalizer window (EQMAIN.BMP)
+    pub has_equalizer: bool,
+    /// Whether the skin styles the playlist window (PLEDIT.BMP)
+    pub has_playlist: bool,
+    /// Whether the skin has cursors (.cur or .ani files)
+    pub has_cursors: bool,
+    /// Whether the skin has AVS presets or styles the AVS window (.avs files or AVS.BMP)
+    pub has_avs: bool,
+    /// Whether the skin styles the minibrowser window (MB.BMP)
+    pub has_minibrowser: bool,
+    /// Whether the skin styles plugin windows (GENEX.BMP)
+    pub has_genex: bool,
+    /// Whether the skin shapes its windows (region.txt)
+    pub has_regions: bool,
+    /// Whether the archive is a Winamp 3 or 5 modern skin (.wal, with skin.xml) rather than a classic skin
+    pub is_modern: bool,
+}
+
+impl SkinMetadata {
+    /// Find the skin's readme in the archive contents and read details from it
+    ///
+    /// Files named like readmes are preferred over other text files. Configuration files such as pledit.txt are
+    /// never used.
+    pub fn from_archive(archive: &WszArchive) -> Result<Self> {
+        let mut candidates: Vec<(usize, &String, &Vec<u8>)> = archive
+            .iter()
+            .filter_map(|(name, content)| {
+                let file_name = name.split('/').next_back().unwrap().to_lowercase();
+                let is_text = [".txt", ".nfo", ".diz"].iter().any(|ext| file_name.ends_with(ext));
+                if !is_text || CONFIG_FILES.contains(&file_name.as_str()) {
+                    return None;
+                }
+                let rank = README_NAMES
+                    .iter()
+                    .position(|word| file_name.contains(word))
+                    .unwrap_or(README_NAMES.len());
+                Some((rank, name, content))
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)));
+
+        let (_, name, content) = candidates.first().ok_or(WszError::NotFound("readme".to_string()))?;
+        let mut metadata = Self::from_string(&encoding::decode_text(content).0);
+        metadata.source = Some(name.to_string());
+        Ok(metadata)
+    }
+
+    /// Collect details about a skin from its archive contents
+    ///
+    /// The readme's details are included if the skin has one. Details that depend on where the skin was loaded
+    /// from, `file_name` and `compressed_size`, are left for the caller.
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - Contents of the skin
+    ///
+    /// # Returns
+    ///
+    /// The details
+    pub fn from_skin_archive(archive: &WszArchive) -> Self {
+        let mut metadata = Self::from_archive(archive).unwrap_or_default();
+        let file_names: Vec<String> = archive
+            .keys()
+            .map(|name| name.split('/').next_back().unwrap().to_lowercase())
+            .collect();
+        let has_file = |file_name: &str| file_names.iter().any(|name| name == file_name);
+
+        metadata.file_count = archive.len();
+        metadata.total_size = archive.values().map(|content| content.len() as u64).sum();
+        metadata.has_equalizer = has_file("eqmain.bmp");
+        metadata.has_playlist = has_file("pledit.bmp");
+        metadata.has_cursors = file_names
+            .iter()
+            .any(|name| name.ends_with(".cur") || name.ends_with(".ani"));
+        metadata.has_avs = has_file("avs.bmp") || file_names.iter().any(|name| name.ends_with(".avs"));
+        metadata.has_minibrowser = has_file("mb.bmp");
+        metadata.has_genex = has_file("genex.bmp");
+        metadata.has_regions = has_file("region.txt");
+        metadata.is_modern = has_file("skin.xml");
+        metadata
+    }
+
+    /// Get the skin's name, from its readme or else its file name
+    ///
+    /// # Returns
+    ///
+    /// The name, or None if the readme does not give one and the skin was not loaded from a file
+    pub fn skin_name(&self) -> Option<&str> {
+        self.name.as_deref().or(self.file_name.as_deref())
+    }
+
+    /// Read details from readme text
+    ///
+    /// The first value found for each detail is kept.
+    pub fn from_string(content: &str) -> Self {
+        let mut metadata = Self::default();
+        let mut first_line = None;
+
+        for line in content.lines() {
+            let line = line.trim().trim_matches(|c: char| "*-=#~|_+".contains(c)).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if first_line.is_none() {
+                first_line = Some(line);
+            }
+
+            // labelled details, e.g. "Author: Jane Doe"
+            if let Some(pos) = line.find([':', '=']) {
+                let key = line[..pos].trim().to_lowercase();
+                let value = line[pos + 1..].trim();
+                if !value.is_empty() {
+                    match Field::from_key(&key) {
+                        Some(Field::Name) => set_once(&mut metadata.name, value),
+                        Some(Field::Author) => set_once(&mut metadata.author, value),
+                        Some(Field::Url) => set_once(&mut metadata.url, value),
+                        Some(Field::Date) => set_once(&mut metadata.date, value),
+                        Some(Field::Contact) if value.contains('@') => set_once(&mut metadata.email, value),
+                        Some(Field::Contact) => set_once(&mut metadata.url, value),
+                        None => {}
+                    }
+                }
+            }
+
+            // "Some Skin by Jane Doe"
+            if metadata.author.is_none() {
+                if let Some(pos) = find_ignore_ascii_case(line, " by ") {
+                    let author = line[pos + 4..]
+                        .split(['(', '<', '[', ','])
+                        .next()
+                        .unwrap_or_default()
+                        .trim()
+                        .trim_end_matches('.');
+                    if !author.is_empty() && !author.contains(':') {
+                        set_once(&mut metadata.author, author);
+                    }
+                }
+            }
+
+            let words: Vec<&str> = line.split_whitespace().collect();
+            for (i, word) in words.iter().enumerate() {
+                let word = word.trim_matches(|c: char| "()<>[]\"',;".contains(c));
+                let lower = word.to_lowercase();
+                if lower.starts_with("http://") || lower.starts_with("https://") || lower.starts_with("www.") {
+                    set_once(&mut metadata.url, word.trim_end_matches('.'));
+                } else if is_email(word) {
+                    set_once(
+                        &mut metadata.email,
+                        word.trim_start_matches("mailto:").trim_end_matches('.'),
+                    );
+                } else if is_numeric_date(word) {
+                    set_once(&mut metadata.date, word);
+                } else if let Some(year) = words.get(i + 1).filter(|_| MONTHS.contains(&lower.as_str())) {
+                    let year = year.trim_matches(|c: char| !c.is_ascii_digit());
+                    if year.len() == 4 {
+                        set_once(&mut metadata.date, &format!("{} {}", word, year));
+                    }
+                }
+            }
+        }
+
+        // readmes often start with the skin's name, sometimes followed by its author
+        if metadata.name.is_none() {
+            let name = first_line.map(|line| match find_ignore_ascii_case(line, " by ") {
+                Some(pos) => line[..pos].trim(),
+                None => line,
+            });
+            if let Some(name) = name.filter(|name| looks_like_name(name)) {
+                metadata.name = Some(name.to_string());
+            }
+        }
+
+        metadata
+    }
+}
+
+/// Checks that a line is a short title rather than a sentence or a link
+fn looks_like_name(line: &str) -> bool {
+    !line.is_empty()
+        && line.len() <= MAX_NAME_LENGTH
+        && !line.contains("://")
+        && !line.contains(", ")
+        && !line.contains(". ")
+        && !line.ends_with(['.', ':', '!'])
+}
+
+fn set_once(field: &mut Option<String>, value: &str) {
+    if field.is_none() {
+        *field = Some(value.to_string());
+    }
+}
+
+/// Finds the byte offset of an ASCII needle in a string, ignoring ASCII case
+fn find_ignore_ascii_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack.char_indices().map(|(i, _)| i).find(|&i| {
+        haystack[i..]
+            .get(..needle.len())
+            .is_some_and(|s| s.eq_ignore_ascii_case(needle))
+    })
+}
+
+fn is_email(word: &str) -> bool {
+    let word = word.trim_start_matches("mailto:");
+    match word.split_once('@') {
+        Some((user, domain)) => !user.is_empty() && domain.contains('.') && !domain.starts_with('.'),
+        None => false,
+    }
+}
+
+/// Checks for dates like 12/31/2003, 31.12.03 or 2003-12-31
+fn is_numeric_date(word: &str) -> bool {
+    let parts: Vec<&str> = word.split(['/', '.', '-']).collect();
+    parts.len() == 3
+        && parts
+            .iter()
+            .all(|part| (1..=4).contains(&part.len()) && part.chars().all(|c| c.is_ascii_digit()))
+        && parts.iter().any(|part| part.len() >= 2)
+}
@@ -4,42 +4,253 @@
 //!
 //! Each region is a list of vertices which define a polygon. The outside of the polygon is the transparent region.
 
+use std::collections::HashMap;
+use std::fmt;
+
+use image::{ImageBuffer, Luma};
+
 use crate::archive::WszArchive;
 use crate::error::{Result, WszError};
+use crate::sprites::{WindowType, EQUALIZER_WINDOW_HEIGHT, MAIN_WINDOW_HEIGHT, SHADE_WINDOW_HEIGHT, WINDOW_WIDTH};
+use crate::text::ini::{Ini, IniLineKind};
+use crate::text::{encoding, Diagnostics, ParseMode, ParseWarning, UnparsedContent};
 
-enum RegionType {
+/// Windows a region.txt can define a region for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionType {
+    /// Main window ([Normal])
     Main,
+    /// Main window in shade mode ([WindowShade])
     MainShade,
+    /// Equalizer window ([Equalizer])
     Equalizer,
+    /// Equalizer window in shade mode ([EqualizerWS])
     EqualizerShade,
 }
 
 impl RegionType {
-    fn from_str(s: &str) -> Result<Self> {
-        match s {
-            "Normal" => Ok(Self::Main),
-            "WindowShade" => Ok(Self::MainShade),
-            "Equalizer" => Ok(Self::Equalizer),
-            "EqualizerWS" => Ok(Self::EqualizerShade),
-            _ => Err(WszError::InvalidFormat {
-                line: 0,
-                error: format!("Invalid region type: '{}'", s),
-            }),
+    /// Returns the region a region.txt section is for, or None for sections Winamp ignores
+    ///
+    /// Section names are matched ignoring case, spaces, dashes and underscores, and some skins use other names
+    /// for the same sections, such as [Main] or [EqualizerShade], which are accepted too.
+    fn from_section(s: &str) -> Option<Self> {
+        let name: String = s
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '_'))
+            .flat_map(char::to_lowercase)
+            .collect();
+        match name.as_str() {
+            "normal" | "main" | "mainwindow" => Some(Self::Main),
+            "windowshade" | "mainshade" | "mainwindowshade" | "shade" => Some(Self::MainShade),
+            "equalizer" | "equaliser" | "eq" => Some(Self::Equalizer),
+            "equalizerws" | "equalizershade" | "equaliserws" | "equalisershade" | "eqws" | "eqshade" => {
+                Some(Self::EqualizerShade)
+            }
+            _ => None,
+        }
+    }
+
+    /// Name of the region.txt section the region is read from
+    pub fn section_name(self) -> &'static str {
+        match self {
+            Self::Main => "Normal",
+            Self::MainShade => "WindowShade",
+            Self::Equalizer => "Equalizer",
+            Self::EqualizerShade => "EqualizerWS",
+        }
+    }
+
+    /// Size of the window the region shapes, as (width, height)
+    pub fn window_size(self) -> (u32, u32) {
+        match self {
+            Self::Main => (WINDOW_WIDTH, MAIN_WINDOW_HEIGHT),
+            Self::Equalizer => (WINDOW_WIDTH, EQUALIZER_WINDOW_HEIGHT),
+            Self::MainShade | Self::EqualizerShade => (WINDOW_WIDTH, SHADE_WINDOW_HEIGHT),
         }
     }
 }
 
+/// A problem with a region found by `Regions::validate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionIssue {
+    /// Region the problem is in
+    pub region: RegionType,
+    /// What the problem is
+    pub kind: RegionIssueKind,
+}
+
+/// Kinds of problems `Regions::validate` looks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegionIssueKind {
+    /// A polygon has fewer than 3 points, so it covers no area
+    DegeneratePolygon {
+        /// Index of the polygon within the region
+        polygon: usize,
+        /// Number of points in the polygon
+        points: usize,
+    },
+    /// A point lies outside the window. Points read from region.txt are clamped to the window, like Winamp does,
+    /// and reported with their original coordinates.
+    PointOutOfBounds {
+        /// Index of the polygon within the region
+        polygon: usize,
+        /// The point, in window coordinates
+        point: (i64, i64),
+        /// Size of the window, as (width, height)
+        window_size: (u32, u32),
+    },
+    /// The total of NumPoints does not match the number of points in PointList. The region is left out, so
+    /// Winamp would not shape the window.
+    PointCountMismatch {
+        /// Total of the NumPoints counts
+        declared: usize,
+        /// Number of points in PointList
+        listed: usize,
+    },
+}
+
+impl fmt::Display for RegionIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] ", self.region.section_name())?;
+        match &self.kind {
+            RegionIssueKind::DegeneratePolygon { polygon, points } => {
+                write!(f, "polygon {} has {} point(s), at least 3 are needed", polygon, points)
+            }
+            RegionIssueKind::PointOutOfBounds {
+                polygon,
+                point,
+                window_size,
+            } => write!(
+                f,
+                "polygon {} has point ({}, {}) outside the {}x{} window",
+                polygon, point.0, point.1, window_size.0, window_size.1
+            ),
+            RegionIssueKind::PointCountMismatch { declared, listed } => write!(
+                f,
+                "NumPoints adds up to {} points but PointList has {}",
+                declared, listed
+            ),
+        }
+    }
+}
+
+/// Polygons of a region, each a list of (x, y) vertices in window coordinates
+pub type RegionPolygons = Vec<Vec<(u32, u32)>>;
+
+/// A key of a region section, with the line it was read from
+struct RegionKey {
+    line: usize,
+    text: String,
+    value: String,
+}
+
+/// Keys of a region section
 struct Region {
-    pub num_points: Vec<usize>,
-    pub points: Vec<u32>,
+    region_type: RegionType,
+    num_points: Option<RegionKey>,
+    point_list: Option<RegionKey>,
 }
 
 impl Region {
-    fn new() -> Self {
+    fn new(region_type: RegionType) -> Self {
         Self {
-            num_points: Vec::new(),
-            points: Vec::new(),
+            region_type,
+            num_points: None,
+            point_list: None,
+        }
+    }
+
+    /// Builds the region's polygons once the whole file has been read
+    ///
+    /// # Returns
+    ///
+    /// The polygons, or None if the section is missing either key, in which case Winamp does not shape the window
+    fn polygons(
+        &self,
+        diagnostics: &mut Diagnostics,
+        issues: &mut Vec<RegionIssue>,
+        unparsed: &mut UnparsedContent,
+    ) -> Result<Option<RegionPolygons>> {
+        let (Some(num_points_key), Some(point_list_key)) = (&self.num_points, &self.point_list) else {
+            return Ok(None);
+        };
+        let (num_points, point_list) = (&num_points_key.value, &point_list_key.value);
+        let mut skip = |key: &RegionKey, message: String| -> Result<Option<RegionPolygons>> {
+            diagnostics.report(key.line, message)?;
+            unparsed.push_line(key.line, &key.text);
+            Ok(None)
+        };
+
+        let num_points: std::result::Result<Vec<usize>, &str> = num_points
+            .split(',')
+            .map(|s| s.trim().parse::<usize>().map_err(|_| s.trim()))
+            .collect();
+        let num_points = match num_points {
+            Ok(num_points) => num_points,
+            Err(value) => return skip(num_points_key, format!("Invalid NumPoints value: '{}'", value)),
+        };
+        // PointList can be either comma or space separated
+        // some skins have negative coordinates, which are clamped to the window below
+        let points: std::result::Result<Vec<i64>, _> = point_list
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse::<i64>().map_err(|_| s))
+            .collect();
+        let points = match points {
+            Ok(points) => points,
+            Err(value) => return skip(point_list_key, format!("Invalid PointList value: '{}'", value)),
+        };
+
+        if points.len() % 2 != 0 {
+            return skip(point_list_key, "PointList has an odd number of values".to_string());
+        }
+
+        // saturating, so that huge counts are reported as a mismatch rather than overflowing
+        let declared = num_points
+            .iter()
+            .fold(0usize, |total, &count| total.saturating_add(count));
+        if declared != points.len() / 2 {
+            issues.push(RegionIssue {
+                region: self.region_type,
+                kind: RegionIssueKind::PointCountMismatch {
+                    declared,
+                    listed: points.len() / 2,
+                },
+            });
+            return skip(
+                point_list_key,
+                "Number of points does not match number of points in the region".to_string(),
+            );
+        }
+
+        let window_size = self.region_type.window_size();
+        let mut points_list = Vec::new();
+        let mut current_offset = 0;
+        for (polygon, count) in num_points.into_iter().enumerate() {
+            let polygon = (current_offset..current_offset + count)
+                .map(|i| {
+                    let point = (points[i * 2], points[i * 2 + 1]);
+                    let clamped = (
+                        point.0.clamp(0, window_size.0 as i64) as u32,
+                        point.1.clamp(0, window_size.1 as i64) as u32,
+                    );
+                    if (clamped.0 as i64, clamped.1 as i64) != point {
+                        issues.push(RegionIssue {
+                            region: self.region_type,
+                            kind: RegionIssueKind::PointOutOfBounds {
+                                polygon,
+                                point,
+                                window_size,
+                            },
+                        });
+                    }
+                    clamped
+                })
+                .collect();
+            points_list.push(polygon);
+            current_offset += count;
         }
+        Ok(Some(points_list))
     }
 }
 
@@ -54,6 +265,12 @@ pub struct Regions {
     pub equalizer: Option<Vec<Vec<(u32, u32)>>>,
     /// Equalizer window shade region
     pub equalizer_shade: Option<Vec<Vec<(u32, u32)>>>,
+    /// Keys Winamp does not use, including those in other sections, as "Section.key"
+    pub custom: HashMap<String, String>,
+    // problems found while parsing that leave a region out, reported by validate
+    parse_issues: Vec<RegionIssue>,
+    // parts of the file that were not understood
+    unparsed: UnparsedContent,
 }
 
 impl Regions {
@@ -63,139 +280,501 @@ impl Regions {
             main_shade: None,
             equalizer: None,
             equalizer_shade: None,
+            custom: HashMap::new(),
+            parse_issues: Vec::new(),
+            unparsed: UnparsedContent::default(),
         }
     }
 
     /// Find region.txt in the archive contents
     pub fn from_archive(archive: &WszArchive) -> Result<Self> {
+        Self::from_archive_with_mode(archive, ParseMode::Strict).map(|(regions, _)| regions)
+    }
+
+    /// Find region.txt in the archive contents and parse it, optionally skipping lines that cannot be parsed
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - Archive to find region.txt in
+    /// * `mode` - How to handle lines that cannot be parsed
+    ///
+    /// # Returns
+    ///
+    /// The regions and the problems skipped in lenient mode
+    pub fn from_archive_with_mode(archive: &WszArchive, mode: ParseMode) -> Result<(Self, Vec<ParseWarning>)> {
         let region_txt = archive
             .iter()
-            .find(|(name, _)| name.split('/').last().unwrap().to_lowercase() == "region.txt")
+            .find(|(name, _)| name.split('/').next_back().unwrap().to_lowercase() == "region.txt")
             .ok_or(WszError::NotFound("region.txt".to_string()))?;
 
-        Self::from_string(&String::from_utf8_lossy(region_txt.1).to_string())
+        Self::from_string_with_mode(&encoding::decode_text(region_txt.1).0, mode)
     }
 
     /// Parse region.txt content into regions
+    ///
+    /// Like Winamp, sections may list their keys in any order and may be repeated. Repeated sections and keys are
+    /// merged, and the first value of a key is used. Keys and sections Winamp does not use are kept in `custom`.
     pub fn from_string(content: &str) -> Result<Self> {
+        Self::from_string_with_mode(content, ParseMode::Strict).map(|(regions, _)| regions)
+    }
+
+    /// Parse region.txt content into regions, optionally skipping lines that cannot be parsed
+    ///
+    /// In lenient mode, a region whose point counts do not match its point list is left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - Contents of region.txt
+    /// * `mode` - How to handle lines that cannot be parsed
+    ///
+    /// # Returns
+    ///
+    /// The regions and the problems skipped in lenient mode
+    pub fn from_string_with_mode(content: &str, mode: ParseMode) -> Result<(Self, Vec<ParseWarning>)> {
         let mut regions = Self::new();
+        let mut diagnostics = Diagnostics::new(mode, "region.txt");
+        let mut unparsed = UnparsedContent::default();
 
-        let mut current_section = None;
-        let mut current_region = Region::new();
+        // sections are only finalized once the whole file has been read, since they may be repeated
+        let mut sections: Vec<Region> = Vec::new();
+        let mut current_section: Option<String> = None;
 
-        for (line_num, line) in content.lines().enumerate() {
-            let line = line.trim();
+        for line in Ini::parse(content).lines {
+            let line_num = line.number;
+            let (key, value) = match &line.kind {
+                // Skip empty lines and comments
+                IniLineKind::Blank | IniLineKind::Comment(_) => continue,
+                IniLineKind::Section(name) => {
+                    match RegionType::from_section(name) {
+                        Some(region_type) => {
+                            if !sections.iter().any(|section| section.region_type == region_type) {
+                                sections.push(Region::new(region_type));
+                            }
+                        }
+                        None => unparsed.push_section(line_num, name),
+                    }
+                    current_section = Some(name.clone());
+                    continue;
+                }
+                IniLineKind::Entry { key, value } => (key.as_str(), value.as_str()),
+                IniLineKind::Invalid(text) => {
+                    // Line is not a key-value pair and not a section header
+                    diagnostics.report(line_num, format!("Invalid line format: '{}'", text))?;
+                    unparsed.push_line(line_num, &line.text);
+                    continue;
+                }
+            };
 
-            // Skip empty lines
-            if line.is_empty() {
+            let Some(section_name) = &current_section else {
+                diagnostics.report(line_num, "Key-value pair outside of any section".to_string())?;
+                unparsed.push_line(line_num, &line.text);
                 continue;
+            };
+
+            let section = RegionType::from_section(section_name)
+                .and_then(|region_type| sections.iter_mut().find(|section| section.region_type == region_type));
+            let entry = match (section, key.to_lowercase().as_str()) {
+                (Some(section), "numpoints") => &mut section.num_points,
+                (Some(section), "pointlist") => &mut section.point_list,
+                _ => {
+                    // Store unknown keys as custom settings
+                    regions
+                        .custom
+                        .entry(format!("{}.{}", section_name, key))
+                        .or_insert_with(|| value.to_string());
+                    continue;
+                }
+            };
+            if entry.is_none() {
+                *entry = Some(RegionKey {
+                    line: line_num,
+                    text: line.text.clone(),
+                    value: value.to_string(),
+                });
             }
+        }
 
-            // Skip comments
-            if line.starts_with(";") {
-                continue;
+        for section in &sections {
+            let polygons = section.polygons(&mut diagnostics, &mut regions.parse_issues, &mut unparsed)?;
+            match section.region_type {
+                RegionType::Main => regions.main = polygons,
+                RegionType::MainShade => regions.main_shade = polygons,
+                RegionType::Equalizer => regions.equalizer = polygons,
+                RegionType::EqualizerShade => regions.equalizer_shade = polygons,
             }
+        }
 
-            // Check for section headers
-            if line.starts_with('[') && line.ends_with(']') {
-                current_section = Some(RegionType::from_str(line[1..line.len() - 1].to_string().as_str())?);
-                current_region = Region::new();
-                continue;
+        // regions are built after the whole file is read, so their lines come last
+        unparsed.lines.sort_by_key(|line| line.line);
+        regions.unparsed = unparsed;
+        Ok((regions, diagnostics.into_warnings()))
+    }
+}
+
+impl Regions {
+    /// Returns the polygons of a region, if the skin defines it
+    pub fn get(&self, region: RegionType) -> Option<&Vec<Vec<(u32, u32)>>> {
+        match region {
+            RegionType::Main => self.main.as_ref(),
+            RegionType::MainShade => self.main_shade.as_ref(),
+            RegionType::Equalizer => self.equalizer.as_ref(),
+            RegionType::EqualizerShade => self.equalizer_shade.as_ref(),
+        }
+    }
+
+    /// Lines and sections of the parsed region.txt that were not understood
+    ///
+    /// Sections for windows other than the main and equalizer windows are listed here, though their keys are kept
+    /// in `custom`. In lenient mode, the lines of regions that were left out are included.
+    pub fn unparsed(&self) -> &UnparsedContent {
+        &self.unparsed
+    }
+
+    /// Serializes the regions as region.txt content
+    ///
+    /// Defined regions are written in the order Winamp lists them, followed by custom keys grouped by section and
+    /// sorted by key.
+    pub fn to_txt(&self) -> String {
+        let mut txt = String::new();
+        let mut sections: Vec<(&str, Vec<String>)> = Vec::new();
+        for region in [
+            RegionType::Main,
+            RegionType::MainShade,
+            RegionType::Equalizer,
+            RegionType::EqualizerShade,
+        ] {
+            if let Some(polygons) = self.get(region) {
+                let num_points: Vec<String> = polygons.iter().map(|polygon| polygon.len().to_string()).collect();
+                let point_list: Vec<String> = polygons.iter().flatten().map(|(x, y)| format!("{},{}", x, y)).collect();
+                let lines = vec![
+                    format!("NumPoints={}", num_points.join(",")),
+                    format!("PointList={}", point_list.join(", ")),
+                ];
+                sections.push((region.section_name(), lines));
             }
+        }
 
-            // Parse key-value pairs
-            if let Some(pos) = line.find('=') {
-                let key = line[..pos].trim();
-                let value = line[pos + 1..].trim();
-
-                match current_section {
-                    Some(_) => {
-                        match key.to_lowercase().as_str() {
-                            "numpoints" => {
-                                current_region.num_points =
-                                    value.split(',').map(|s| s.trim().parse::<usize>().unwrap()).collect();
-                            }
-                            "pointlist" => {
-                                // PointList can be either comma or space separated
-                                current_region.points = value
-                                    .split(|c: char| c.is_whitespace() || c == ',')
-                                    .filter(|s| !s.is_empty())
-                                    .map(|s| s.trim().parse::<u32>().unwrap())
-                                    .collect();
-                            }
-                            _ => {
-                                // Store unknown keys as custom settings
-                                return Err(WszError::InvalidFormat {
-                                    line: line_num + 1,
-                                    error: format!("Invalid key: '{}'", key),
-                                });
-                            }
-                        }
-                    }
-                    _ => {
-                        return Err(WszError::InvalidFormat {
-                            line: line_num + 1,
-                            error: "Key-value pair outside of any section".to_string(),
-                        });
-                    }
-                }
-            } else {
-                // Line is not a key-value pair and not a section header
-                return Err(WszError::InvalidFormat {
-                    line: line_num + 1,
-                    error: format!("Invalid line format: '{}'", line),
-                });
+        let mut custom: Vec<(&str, &str, &str)> = self
+            .custom
+            .iter()
+            .filter_map(|(key, value)| {
+                let (section, key) = key.split_once('.')?;
+                Some((section, key, value.as_str()))
+            })
+            .collect();
+        custom.sort();
+        for (section, key, value) in custom {
+            let line = format!("{}={}", key, value);
+            match sections.iter_mut().find(|(name, _)| *name == section) {
+                Some((_, lines)) => lines.push(line),
+                None => sections.push((section, vec![line])),
             }
+        }
 
-            if current_section.is_some() && current_region.num_points.len() > 0 && current_region.points.len() > 0 {
-                if current_region.num_points.iter().sum::<usize>() != current_region.points.len() / 2 {
-                    return Err(WszError::InvalidFormat {
-                        line: line_num + 1,
-                        error: "Number of points does not match number of points in the region".to_string(),
+        for (i, (section, lines)) in sections.iter().enumerate() {
+            if i > 0 {
+                txt.push_str("\r\n");
+            }
+            txt.push_str(&format!("[{}]\r\n", section));
+            for line in lines {
+                txt.push_str(line);
+                txt.push_str("\r\n");
+            }
+        }
+        txt
+    }
+
+    /// Checks the regions for polygons that cannot shape a window as intended
+    ///
+    /// Looks for polygons with fewer than 3 points, points outside the window, including those clamped when
+    /// region.txt was read, and sections whose NumPoints do not add up to the points in PointList. Points exactly
+    /// on the edge are allowed, since a polygon covering the whole window ends there.
+    ///
+    /// # Returns
+    ///
+    /// The problems found, or an empty list if there are none
+    pub fn validate(&self) -> Vec<RegionIssue> {
+        let mut issues = self.parse_issues.clone();
+        for region in [
+            RegionType::Main,
+            RegionType::MainShade,
+            RegionType::Equalizer,
+            RegionType::EqualizerShade,
+        ] {
+            let Some(polygons) = self.get(region) else {
+                continue;
+            };
+            let window_size = region.window_size();
+            for (polygon, points) in polygons.iter().enumerate() {
+                if points.len() < 3 {
+                    issues.push(RegionIssue {
+                        region,
+                        kind: RegionIssueKind::DegeneratePolygon {
+                            polygon,
+                            points: points.len(),
+                        },
                     });
                 }
-
-                let mut points_list = Vec::new();
-                let mut current_offset = 0;
-                for i in 0..current_region.num_points.len() {
-                    let mut points = Vec::new();
-                    for j in 0..current_region.num_points[i] {
-                        let index = (current_offset + j) * 2;
-                        points.push((current_region.points[index], current_region.points[index + 1]));
+                for &(x, y) in points {
+                    if x > window_size.0 || y > window_size.1 {
+                        issues.push(RegionIssue {
+                            region,
+                            kind: RegionIssueKind::PointOutOfBounds {
+                                polygon,
+                                point: (x as i64, y as i64),
+                                window_size,
+                            },
+                        });
                     }
-                    points_list.push(points);
-                    current_offset += current_region.num_points[i];
                 }
+            }
+        }
+        issues
+    }
 
-                match current_section {
-                    Some(RegionType::Main) => {
-                        regions.main = Some(points_list);
-                    }
-                    Some(RegionType::MainShade) => {
-                        regions.main_shade = Some(points_list);
-                    }
-                    Some(RegionType::Equalizer) => {
-                        regions.equalizer = Some(points_list);
-                    }
-                    Some(RegionType::EqualizerShade) => {
-                        regions.equalizer_shade = Some(points_list);
-                    }
-                    _ => {
-                        unreachable!()
-                    }
-                }
+    /// Rasterizes a region into an alpha mask
+    ///
+    /// Polygons are filled like a Win32 polygon region with the `ALTERNATE` fill mode: a pixel is inside if its
+    /// center is, which includes the top and left edges of a rectangle but not the bottom and right edges.
+    ///
+    /// # Arguments
+    ///
+    /// * `region` - Region to rasterize
+    /// * `width` - Width of the mask, usually the width of the window
+    /// * `height` - Height of the mask, usually the height of the window
+    ///
+    /// # Returns
+    ///
+    /// A mask that is 255 where the window is visible and 0 where it is transparent. If the skin does not define
+    /// the region the whole window is visible, like in Winamp.
+    pub fn rasterize(&self, region: RegionType, width: u32, height: u32) -> ImageBuffer<Luma<u8>, Vec<u8>> {
+        let polygons = match self.get(region) {
+            Some(polygons) => polygons,
+            None => return ImageBuffer::from_pixel(width, height, Luma([255])),
+        };
+
+        let mut mask = ImageBuffer::new(width, height);
+        let mut crossings = Vec::new();
+        for y in 0..height {
+            let py = y as f64 + 0.5;
+            edge_crossings(polygons, py, &mut crossings);
+            crossings.sort_by(|a, b| a.total_cmp(b));
 
-                current_section = None;
-                current_region = Region::new();
+            // fill the pixels whose centers lie between each pair of crossings
+            for span in crossings.chunks_exact(2) {
+                let start = (span[0] - 0.5).ceil().max(0.0) as u32;
+                let end = ((span[1] - 0.5).ceil().max(0.0) as u32).min(width);
+                for x in start..end {
+                    mask.put_pixel(x, y, Luma([255]));
+                }
             }
         }
+        mask
+    }
+
+    /// Checks whether a point in a window is inside the visible part of a region
+    ///
+    /// Matches `rasterize`: the pixel at the point is tested at its center.
+    ///
+    /// # Arguments
+    ///
+    /// * `region` - Region to test against
+    /// * `x` - X position relative to the window
+    /// * `y` - Y position relative to the window
+    ///
+    /// # Returns
+    ///
+    /// Whether the point is visible. If the skin does not define the region the whole window is visible, so
+    /// any point is, and checking that the point is within the window is up to the caller.
+    pub fn contains(&self, region: RegionType, x: u32, y: u32) -> bool {
+        self.get(region)
+            .map_or(true, |polygons| polygons_contain(polygons, x, y))
+    }
+
+    /// Returns the region polygons of a window, if the skin defines any
+    ///
+    /// # Arguments
+    ///
+    /// * `window_type` - Window to get the region of. Only the main and equalizer windows, normal or shaded, can have regions.
+    pub fn for_window(&self, window_type: WindowType) -> Option<&Vec<Vec<(u32, u32)>>> {
+        match window_type {
+            WindowType::Main => self.main.as_ref(),
+            WindowType::Equalizer => self.equalizer.as_ref(),
+            WindowType::MainShade => self.main_shade.as_ref(),
+            WindowType::EqualizerShade => self.equalizer_shade.as_ref(),
+            WindowType::Playlist | WindowType::PlaylistShade | WindowType::General | WindowType::Minibrowser => None,
+        }
+    }
+}
 
-        Ok(regions)
+/// Finds where the edges of polygons cross a horizontal line
+///
+/// # Arguments
+///
+/// * `polygons` - Polygons in window coordinates
+/// * `y` - Y position of the line
+/// * `crossings` - Cleared, then filled with the X positions of the crossings, unsorted
+fn edge_crossings(polygons: &[Vec<(u32, u32)>], y: f64, crossings: &mut Vec<f64>) {
+    crossings.clear();
+    for polygon in polygons {
+        for (i, &(x1, y1)) in polygon.iter().enumerate() {
+            let (x2, y2) = polygon[(i + 1) % polygon.len()];
+            let (x1, y1, x2, y2) = (x1 as f64, y1 as f64, x2 as f64, y2 as f64);
+            if (y1 > y) != (y2 > y) {
+                crossings.push(x1 + (y - y1) * (x2 - x1) / (y2 - y1));
+            }
+        }
     }
 }
 
+/// Checks whether a pixel is inside a set of polygons
+///
+/// Uses the even-odd rule over the edges of all polygons together, like a Win32 polygon region filled with
+/// `ALTERNATE`. The pixel is tested at its center.
+///
+/// # Arguments
+///
+/// * `polygons` - Polygons in window coordinates
+/// * `x` - X position of the pixel (from left)
+/// * `y` - Y position of the pixel (from top)
+pub(crate) fn polygons_contain(polygons: &[Vec<(u32, u32)>], x: u32, y: u32) -> bool {
+    let mut crossings = Vec::new();
+    edge_crossings(polygons, y as f64 + 0.5, &mut crossings);
+    let px = x as f64 + 0.5;
+    crossings.iter().filter(|&&crossing| px < crossing).count() % 2 == 1
+}
+
 impl Default for Regions {
     fn default() -> Self {
         Self::new()
     }
 }
+
+/// Compares the polygons and custom keys
+impl PartialEq for Regions {
+    fn eq(&self, other: &Self) -> bool {
+        self.main == other.main
+            && self.main_shade == other.main_shade
+            && self.equalizer == other.equalizer
+            && self.equalizer_shade == other.equalizer_shade
+            && self.custom == other.custom
+    }
+}
+
+impl Eq for Regions {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_valid_region() {
+        let regions = Regions::from_string("[Normal]\nNumPoints=4\nPointList=0,0, 275,0, 275,116, 0,116\n").unwrap();
+        assert_eq!(regions.main, Some(vec![vec![(0, 0), (275, 0), (275, 116), (0, 116)]]));
+    }
+
+    #[test]
+    fn section_names_ignore_case_and_aliases() {
+        let content = "[normal]\nNumPoints=3\nPointList=0,0 1,0 1,1\n\
+                       [WINDOWSHADE]\nNumPoints=3\nPointList=0,0 2,0 2,2\n\
+                       [Equalizer Shade]\nNumPoints=3\nPointList=0,0 3,0 3,3\n\
+                       [EQ]\nNumPoints=3\nPointList=0,0 4,0 4,4\n\
+                       [Unknown]\nNumPoints=3\n";
+        let regions = Regions::from_string(content).unwrap();
+        assert_eq!(regions.main, Some(vec![vec![(0, 0), (1, 0), (1, 1)]]));
+        assert_eq!(regions.main_shade, Some(vec![vec![(0, 0), (2, 0), (2, 2)]]));
+        assert_eq!(regions.equalizer_shade, Some(vec![vec![(0, 0), (3, 0), (3, 3)]]));
+        assert_eq!(regions.equalizer, Some(vec![vec![(0, 0), (4, 0), (4, 4)]]));
+        assert_eq!(regions.custom.get("Unknown.NumPoints").map(String::as_str), Some("3"));
+    }
+
+    #[test]
+    fn unparsed_lines_and_sections_are_kept() {
+        let content = "stray\n[Normal]\nNumPoints=3\nPointList=0,0 1,0 x,1\ngarbage\n[Playlist]\nFoo=1\n";
+        let (regions, _) = Regions::from_string_with_mode(content, ParseMode::Lenient).unwrap();
+        let lines: Vec<(usize, &str)> = regions
+            .unparsed()
+            .lines
+            .iter()
+            .map(|line| (line.line, line.text.as_str()))
+            .collect();
+        assert_eq!(lines, vec![(1, "stray"), (4, "PointList=0,0 1,0 x,1"), (5, "garbage")]);
+        assert_eq!(regions.unparsed().sections.len(), 1);
+        assert_eq!(regions.unparsed().sections[0].name, "Playlist");
+        assert_eq!(regions.unparsed().sections[0].line, 6);
+    }
+
+    #[test]
+    fn malformed_num_points_is_an_error() {
+        let content = "[Normal]\nNumPoints=4,x\nPointList=0,0 1,0 1,1 0,1\n";
+        match Regions::from_string(content) {
+            Err(WszError::InvalidFormat { line, error }) => {
+                assert_eq!(line, 2);
+                assert!(error.contains("'x'"), "{}", error);
+            }
+            other => panic!("expected InvalidFormat, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn malformed_point_list_is_an_error() {
+        for value in ["abc", "1.5", "99999999999999999999", "-"] {
+            let content = format!("[Normal]\nNumPoints=3\nPointList=0,0 1,0 {},1\n", value);
+            match Regions::from_string(&content) {
+                Err(WszError::InvalidFormat { line, .. }) => assert_eq!(line, 3),
+                other => panic!("expected InvalidFormat for '{}', got {:?}", value, other),
+            }
+        }
+    }
+
+    #[test]
+    fn out_of_bounds_points_are_clamped() {
+        let content = "[WindowShade]\nNumPoints=4\nPointList=-3,-1 300,0 300,20 0,14\n";
+        let regions = Regions::from_string(content).unwrap();
+        assert_eq!(
+            regions.main_shade,
+            Some(vec![vec![(0, 0), (275, 0), (275, 14), (0, 14)]])
+        );
+
+        let clamped: Vec<(i64, i64)> = regions
+            .validate()
+            .into_iter()
+            .filter_map(|issue| match issue.kind {
+                RegionIssueKind::PointOutOfBounds { point, .. } => Some(point),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(clamped, vec![(-3, -1), (300, 0), (300, 20)]);
+    }
+
+    #[test]
+    fn odd_number_of_values_is_an_error() {
+        let content = "[Normal]\nNumPoints=1\nPointList=0,0,1\n";
+        assert!(matches!(
+            Regions::from_string(content),
+            Err(WszError::InvalidFormat { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn huge_num_points_does_not_overflow() {
+        let content = format!("[Normal]\nNumPoints={},{}\nPointList=0,0\n", usize::MAX, usize::MAX);
+        assert!(matches!(
+            Regions::from_string(&content),
+            Err(WszError::InvalidFormat { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn lenient_mode_skips_malformed_regions() {
+        let content = "[Normal]\nNumPoints=x\nPointList=0,0 1,0 1,1\n\
+                       [Equalizer]\nNumPoints=3\nPointList=0,0 1,0 1,y\n\
+                       [WindowShade]\nNumPoints=3\nPointList=0,0 1,0 1,1\n";
+        let (regions, warnings) = Regions::from_string_with_mode(content, ParseMode::Lenient).unwrap();
+        assert_eq!(regions.main, None);
+        assert_eq!(regions.equalizer, None);
+        assert_eq!(regions.main_shade, Some(vec![vec![(0, 0), (1, 0), (1, 1)]]));
+        let lines: Vec<usize> = warnings.iter().map(|warning| warning.line).collect();
+        assert_eq!(lines, vec![2, 6]);
+    }
+}
@@ -2,6 +2,7 @@
 
 use crate::archive::WszArchive;
 use crate::error::{Result, WszError};
+use crate::text::{encoding, ini, Diagnostics, ParseMode, ParseWarning, UnparsedContent};
 use image::Rgb;
 use std::str::FromStr;
 
@@ -29,77 +30,162 @@ pub const VIS_COLOR_OSC_3: usize = 20;
 pub const VIS_COLOR_OSC_4: usize = 21;
 pub const VIS_COLOR_OSC_5: usize = 22;
 pub const VIS_COLOR_PEAK_DOTS: usize = 23;
+/// Number of colors in a complete viscolor.txt
+pub const VIS_COLOR_COUNT: usize = 24;
+
+/// Colors of Winamp's base skin, in viscolor.txt order
+const WINAMP_DEFAULT_COLORS: [[u8; 3]; VIS_COLOR_COUNT] = [
+    [0, 0, 0],
+    [24, 33, 41],
+    [239, 49, 16],
+    [206, 41, 16],
+    [214, 90, 0],
+    [214, 102, 0],
+    [214, 115, 0],
+    [198, 123, 8],
+    [222, 165, 24],
+    [214, 181, 33],
+    [189, 222, 41],
+    [148, 222, 33],
+    [41, 206, 16],
+    [50, 190, 16],
+    [57, 181, 16],
+    [49, 156, 8],
+    [41, 148, 0],
+    [24, 132, 8],
+    [255, 255, 255],
+    [214, 214, 222],
+    [181, 189, 189],
+    [160, 170, 175],
+    [148, 156, 165],
+    [150, 150, 150],
+];
 
 /// Represents the visualization colors from viscolor.txt
 #[derive(Debug, Clone)]
 pub struct VisColors {
     // colors with indices given by viscolor spec
     colors: Vec<Rgb<u8>>,
+    // file the colors were parsed from, kept so that to_txt can preserve its formatting
+    source: Option<String>,
+    // lines of the file that were not understood
+    unparsed: UnparsedContent,
 }
 
 impl VisColors {
     fn new() -> Self {
-        Self { colors: Vec::new() }
+        Self {
+            colors: Vec::new(),
+            source: None,
+            unparsed: UnparsedContent::default(),
+        }
+    }
+
+    /// Creates the colors of Winamp's base skin
+    pub fn winamp_default() -> Self {
+        Self {
+            colors: WINAMP_DEFAULT_COLORS.iter().map(|color| Rgb(*color)).collect(),
+            source: None,
+            unparsed: UnparsedContent::default(),
+        }
     }
 
     /// Creates a VisColors collection from a WSZ archive
     pub fn from_archive(archive: &WszArchive) -> Result<Self> {
+        Self::from_archive_with_mode(archive, ParseMode::Strict).map(|(colors, _)| colors)
+    }
+
+    /// Creates a VisColors collection from a WSZ archive, optionally skipping lines that cannot be parsed
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - Archive to find viscolor.txt in
+    /// * `mode` - How to handle lines that cannot be parsed
+    ///
+    /// # Returns
+    ///
+    /// The colors and the problems skipped in lenient mode
+    pub fn from_archive_with_mode(archive: &WszArchive, mode: ParseMode) -> Result<(Self, Vec<ParseWarning>)> {
         let viscolor_txt = archive
             .iter()
-            .find(|(name, _)| name.split('/').last().unwrap().to_lowercase() == "viscolor.txt")
+            .find(|(name, _)| name.split('/').next_back().unwrap().to_lowercase() == "viscolor.txt")
             .ok_or(WszError::NotFound("viscolor.txt".to_string()))?;
 
-        Self::from_string(&String::from_utf8_lossy(viscolor_txt.1).to_string())
+        Self::from_string_with_mode(&encoding::decode_text(viscolor_txt.1).0, mode)
     }
 
     /// Creates a VisColors collection from a string
     pub fn from_string(content: &str) -> Result<Self> {
+        Self::from_string_with_mode(content, ParseMode::Strict).map(|(colors, _)| colors)
+    }
+
+    /// Creates a VisColors collection from a string, optionally skipping lines that cannot be parsed
+    ///
+    /// Colors are positional, so in lenient mode a color that cannot be parsed is replaced with Winamp's default
+    /// for its position rather than dropped. Values outside 0 to 255 are clamped in lenient mode, with a warning,
+    /// and are an error in strict mode. Hex values like "0xFF" are accepted in both.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - Contents of viscolor.txt
+    /// * `mode` - How to handle lines that cannot be parsed
+    ///
+    /// # Returns
+    ///
+    /// The colors and the problems skipped in lenient mode
+    pub fn from_string_with_mode(content: &str, mode: ParseMode) -> Result<(Self, Vec<ParseWarning>)> {
         let mut colors = Vec::new();
+        let mut diagnostics = Diagnostics::new(mode, "viscolor.txt");
+        let mut unparsed = UnparsedContent::default();
 
-        for (line_num, line) in content.lines().enumerate() {
+        for (line_num, text) in content.lines().enumerate() {
             // Skip empty lines
-            let line = line.trim();
+            let line = text.trim();
             if line.is_empty() {
                 continue;
             }
 
             // Remove comments
-            let line = match line.split("//").next() {
-                Some(content) => content.trim(),
-                None => continue,
-            };
+            let line = strip_comment(line);
 
+            // text other than comments that is not a color is ignored, like Winamp does
             if !line.contains(",") {
+                if !line.is_empty() {
+                    unparsed.push_line(line_num + 1, text);
+                }
                 continue;
             }
 
-            // Parse RGB values
-            let rgb_parts: Vec<&str> = line.split(',').map(|s| s.trim()).collect();
-            if rgb_parts.len() < 3 {
-                return Err(WszError::InvalidFormat {
-                    line: line_num + 1,
-                    error: "Expected three comma-separated RGB values".to_string(),
-                });
+            match parse_color(line) {
+                Ok((color, clamped)) => {
+                    for message in clamped {
+                        diagnostics.report(line_num + 1, message)?;
+                    }
+                    colors.push(color);
+                }
+                Err(error) => {
+                    diagnostics.report(line_num + 1, error)?;
+                    unparsed.push_line(line_num + 1, text);
+                    if let Some(default) = WINAMP_DEFAULT_COLORS.get(colors.len()) {
+                        colors.push(Rgb(*default));
+                    }
+                }
             }
-
-            // Parse the RGB values
-            let r = u8::from_str(rgb_parts[0]).map_err(|_| WszError::InvalidFormat {
-                line: line_num + 1,
-                error: format!("Invalid color value: '{}'", rgb_parts[0]),
-            })?;
-            let g = u8::from_str(rgb_parts[1]).map_err(|_| WszError::InvalidFormat {
-                line: line_num + 1,
-                error: format!("Invalid color value: '{}'", rgb_parts[1]),
-            })?;
-            let b = u8::from_str(rgb_parts[2]).map_err(|_| WszError::InvalidFormat {
-                line: line_num + 1,
-                error: format!("Invalid color value: '{}'", rgb_parts[2]),
-            })?;
-
-            colors.push(Rgb([r, g, b]));
         }
 
-        Ok(Self { colors })
+        let colors = Self {
+            colors,
+            source: Some(content.to_string()),
+            unparsed,
+        };
+        Ok((colors, diagnostics.into_warnings()))
+    }
+
+    /// Lines of the parsed viscolor.txt that were not understood
+    ///
+    /// In lenient mode, these include colors that could not be parsed and were replaced with Winamp's defaults.
+    pub fn unparsed(&self) -> &UnparsedContent {
+        &self.unparsed
     }
 
     /// Number of colors in the collection
@@ -117,6 +203,88 @@ impl VisColors {
         self.colors.get(index).copied()
     }
 
+    /// Set a color by index
+    ///
+    /// Setting a color past the end of the collection fills the colors in between with Winamp's defaults.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Index of the color, one of the `VIS_COLOR_*` constants
+    /// * `color` - New color
+    ///
+    /// # Returns
+    ///
+    /// An error if the index is not a viscolor.txt color
+    pub fn set(&mut self, index: usize, color: Rgb<u8>) -> Result<()> {
+        if index >= VIS_COLOR_COUNT {
+            return Err(WszError::ArgumentError(format!(
+                "Color index {} is out of range, expected less than {}",
+                index, VIS_COLOR_COUNT
+            )));
+        }
+        while self.colors.len() <= index {
+            self.colors.push(Rgb(WINAMP_DEFAULT_COLORS[self.colors.len()]));
+        }
+        self.colors[index] = color;
+        Ok(())
+    }
+
+    /// Set the visualizer background color
+    pub fn set_bg(&mut self, color: Rgb<u8>) {
+        // always in range
+        let _ = self.set(VIS_COLOR_BG, color);
+    }
+
+    /// Set the color of the dots drawn on the visualizer background
+    pub fn set_bg_dots(&mut self, color: Rgb<u8>) {
+        let _ = self.set(VIS_COLOR_BG_DOTS, color);
+    }
+
+    /// Set the color of the spectrum analyzer's peak dots
+    pub fn set_peak_dots(&mut self, color: Rgb<u8>) {
+        let _ = self.set(VIS_COLOR_PEAK_DOTS, color);
+    }
+
+    /// Set a spectrum analyzer color
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Bar height the color is drawn at, from 0 (bottom) to 15 (top), like `vis_color`
+    /// * `color` - New color
+    ///
+    /// # Returns
+    ///
+    /// An error if the value is greater than 15
+    pub fn set_spec_color(&mut self, value: usize, color: Rgb<u8>) -> Result<()> {
+        if value > 15 {
+            return Err(WszError::ArgumentError(format!(
+                "Spectrum color {} is out of range, expected 0 to 15",
+                value
+            )));
+        }
+        self.set(15 - value + VIS_COLOR_SPEC_15, color)
+    }
+
+    /// Set an oscilloscope color
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Index of the oscilloscope color, from 0 to 4, like `osc_color`
+    /// * `color` - New color
+    ///
+    /// # Returns
+    ///
+    /// An error if the value is greater than 4
+    pub fn set_osc_color(&mut self, value: usize, color: Rgb<u8>) -> Result<()> {
+        if value > 4 {
+            return Err(WszError::ArgumentError(format!(
+                "Oscilloscope color {} is out of range, expected 0 to 4",
+                value
+            )));
+        }
+        self.set(value + VIS_COLOR_OSC_1, color)
+    }
+
     pub fn vis_color(&self, value: usize) -> Option<Rgb<u8>> {
         if value > 15 {
             return None;
@@ -129,7 +297,7 @@ impl VisColors {
             .iter()
             .enumerate()
             .filter(|(i, _)| *i >= VIS_COLOR_SPEC_15 && *i <= VIS_COLOR_SPEC_0)
-            .map(|(_, c)| c.clone())
+            .map(|(_, c)| *c)
             .rev()
             .collect()
     }
@@ -146,7 +314,7 @@ impl VisColors {
             .iter()
             .enumerate()
             .filter(|(i, _)| *i >= VIS_COLOR_OSC_1 && *i <= VIS_COLOR_OSC_5)
-            .map(|(_, c)| c.clone())
+            .map(|(_, c)| *c)
             .collect()
     }
 
@@ -163,8 +331,196 @@ impl VisColors {
     }
 }
 
+impl VisColors {
+    /// Serializes the colors as viscolor.txt content
+    ///
+    /// If the colors were parsed from a file, that file is updated in place: lines of colors that have not
+    /// changed are left as written, changed colors keep their comments, and other lines and the line endings are
+    /// kept. Otherwise each color is written on its own line, followed by a comment naming what it is used for.
+    pub fn to_txt(&self) -> String {
+        let Some(source) = &self.source else {
+            let mut txt = String::new();
+            for (i, color) in self.colors.iter().enumerate() {
+                txt.push_str(&format_color_line(*color, &format!("// {}", color_description(i))));
+                txt.push_str("\r\n");
+            }
+            return txt;
+        };
+
+        let mut lines = Vec::new();
+        let mut index = 0;
+        for line in source.lines() {
+            // lines are matched to colors the same way from_string_with_mode reads them
+            let content = strip_comment(line);
+            if !content.contains(',') {
+                lines.push(line.to_string());
+                continue;
+            }
+            if let Some(&color) = self.colors.get(index) {
+                if parse_color(content).is_ok_and(|(parsed, _)| parsed == color) {
+                    lines.push(line.to_string());
+                } else {
+                    let comment = comment_start(line).map_or("", |pos| &line[pos..]);
+                    lines.push(format_color_line(color, comment));
+                }
+            }
+            index += 1;
+        }
+        for (i, color) in self.colors.iter().enumerate().skip(index) {
+            lines.push(format_color_line(*color, &format!("// {}", color_description(i))));
+        }
+
+        let ending = ini::line_ending(source);
+        let mut txt = lines.join(ending);
+        if source.ends_with('\n') || source.is_empty() {
+            txt.push_str(ending);
+        }
+        txt
+    }
+}
+
+/// Formats a line of viscolor.txt, e.g. "24,33,41, // grid dots"
+fn format_color_line(color: Rgb<u8>, comment: &str) -> String {
+    let [r, g, b] = color.0;
+    match comment {
+        "" => format!("{},{},{},", r, g, b),
+        _ => format!("{},{},{}, {}", r, g, b, comment),
+    }
+}
+
+/// Describes what the color at an index is used for, for comments in viscolor.txt
+fn color_description(index: usize) -> String {
+    match index {
+        VIS_COLOR_BG => "background".to_string(),
+        VIS_COLOR_BG_DOTS => "grid dots".to_string(),
+        VIS_COLOR_SPEC_15 => "top of spectrum".to_string(),
+        VIS_COLOR_SPEC_0 => "bottom of spectrum".to_string(),
+        VIS_COLOR_SPEC_14..=VIS_COLOR_SPEC_1 => format!("spectrum {}", VIS_COLOR_SPEC_0 - index),
+        VIS_COLOR_OSC_1 => "oscilloscope, brightest".to_string(),
+        VIS_COLOR_OSC_2..=VIS_COLOR_OSC_5 => format!("oscilloscope {}", index - VIS_COLOR_OSC_1 + 1),
+        VIS_COLOR_PEAK_DOTS => "analyzer peak dots".to_string(),
+        _ => "unused".to_string(),
+    }
+}
+
+/// Finds where the comment on a line starts. Comments usually start with "//", but some skins use ';'.
+fn comment_start(line: &str) -> Option<usize> {
+    [line.find("//"), line.find(';')].into_iter().flatten().min()
+}
+
+/// Returns the part of a line before any comment, trimmed
+fn strip_comment(line: &str) -> &str {
+    comment_start(line).map_or(line, |pos| &line[..pos]).trim()
+}
+
+/// Parses a line of comma separated RGB values
+///
+/// Values may be decimal or hex with a "0x" prefix, and may be followed by other text, e.g. "16 dark green".
+/// Values outside 0 to 255 are clamped.
+///
+/// # Returns
+///
+/// The color and a description of each value that was clamped, or a description of the problem
+fn parse_color(line: &str) -> std::result::Result<(Rgb<u8>, Vec<String>), String> {
+    let rgb_parts: Vec<&str> = line.split(',').map(|s| s.trim()).collect();
+    if rgb_parts.len() < 3 {
+        return Err("Expected three comma-separated RGB values".to_string());
+    }
+
+    let mut clamped = Vec::new();
+    let mut channel = |part: &str| {
+        let value = part.split_whitespace().next().unwrap_or_default();
+        let parsed = match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+            Some(hex) => i64::from_str_radix(hex, 16),
+            None => i64::from_str(value),
+        };
+        let value = parsed.map_err(|_| format!("Invalid color value: '{}'", part))?;
+        let channel = value.clamp(0, 255);
+        if channel != value {
+            clamped.push(format!("Color value {} is outside 0 to 255", value));
+        }
+        Ok::<u8, String>(channel as u8)
+    };
+    let color = Rgb([channel(rgb_parts[0])?, channel(rgb_parts[1])?, channel(rgb_parts[2])?]);
+    Ok((color, clamped))
+}
+
 impl Default for VisColors {
     fn default() -> Self {
         Self::new()
     }
 }
+
+/// Compares the colors
+impl PartialEq for VisColors {
+    fn eq(&self, other: &Self) -> bool {
+        self.colors == other.colors
+    }
+}
+
+impl Eq for VisColors {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Wsz;
+
+    /// A viscolor.txt with a header comment, CRLF line endings and a comment on each color
+    fn viscolor_txt() -> String {
+        let colors: String = (0..VIS_COLOR_COUNT)
+            .map(|i| format!("{},{},{}, // color {}\r\n", i, i * 2, i * 3, i))
+            .collect();
+        format!("// my skin's colors\r\n{}", colors)
+    }
+
+    #[test]
+    fn unchanged_colors_are_written_as_read() {
+        let source = viscolor_txt();
+        let vis_colors = VisColors::from_string(&source).unwrap();
+        assert_eq!(vis_colors.to_txt(), source);
+    }
+
+    #[test]
+    fn changed_color_keeps_comments_and_line_endings() {
+        let source = viscolor_txt();
+        let mut vis_colors = VisColors::from_string(&source).unwrap();
+        vis_colors.set(1, Rgb([255, 0, 0])).unwrap();
+        assert_eq!(
+            vis_colors.to_txt(),
+            source.replace("1,2,3, // color 1\r\n", "255,0,0, // color 1\r\n")
+        );
+    }
+
+    #[test]
+    fn saving_an_unchanged_skin_keeps_the_file() {
+        let source = viscolor_txt();
+        let mut archive = WszArchive::new();
+        archive.insert("viscolor.txt".to_string(), source.as_bytes().to_vec());
+        let packed = Wsz::from_archive(&archive).unwrap().to_bytes().unwrap();
+        let repacked = Wsz::from_bytes(&packed).unwrap().to_archive().unwrap();
+        assert_eq!(repacked["viscolor.txt"], source.as_bytes());
+    }
+
+    #[test]
+    fn rrggbb_lines_are_left_unparsed() {
+        let (vis_colors, warnings) =
+            VisColors::from_string_with_mode("#ff0000\n0,255,0\n", ParseMode::Lenient).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(vis_colors.len(), 1);
+        assert_eq!(vis_colors.get(0), Some(Rgb([0, 255, 0])));
+        assert_eq!(vis_colors.unparsed().lines.len(), 1);
+        assert_eq!(vis_colors.unparsed().lines[0].line, 1);
+        assert_eq!(vis_colors.unparsed().lines[0].text, "#ff0000");
+    }
+
+    #[test]
+    fn out_of_range_values_are_clamped_in_lenient_mode() {
+        let source = "300,-5,0x1FF // too bright\n";
+        let (vis_colors, warnings) = VisColors::from_string_with_mode(source, ParseMode::Lenient).unwrap();
+        assert_eq!(vis_colors.get(0), Some(Rgb([255, 0, 255])));
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings.iter().all(|warning| warning.line == 1));
+
+        assert!(VisColors::from_string_with_mode(source, ParseMode::Strict).is_err());
+    }
+}
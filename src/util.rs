@@ -0,0 +1,42 @@
+//! Small helpers shared by modules that have nothing else in common
+
+use std::fmt::Write;
+
+/// Quotes and escapes a string for use in JSON
+///
+/// # Arguments
+///
+/// * `value` - The string
+///
+/// # Returns
+///
+/// The string as a JSON string literal, including its quotes
+pub(crate) fn json_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                // Writing to a String cannot fail
+                let _ = write!(quoted, "\\u{:04x}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Joins already indented JSON values into an array nested one level deep
+pub(crate) fn json_array(items: &[String]) -> String {
+    if items.is_empty() {
+        "[]".to_string()
+    } else {
+        format!("[\n{}\n  ]", items.join(",\n"))
+    }
+}
@@ -0,0 +1,341 @@
+//! Checks for whether a skin is complete and will display correctly in Winamp
+
+use std::fmt::{self, Write};
+use std::io::Cursor;
+
+use crate::sprites::{self, SpriteManager};
+use crate::text::config::SkinConfig;
+use crate::text::region::RegionIssue;
+use crate::text::ParseWarning;
+use crate::util::{json_array, json_string};
+use crate::Wsz;
+
+/// Sheets Winamp draws from other sheets, or from its base skin, without it looking wrong
+const OPTIONAL_SHEETS: [&str; 3] = ["EQ_EX.BMP", "GEN.BMP", "MB.BMP"];
+
+/// How serious a problem is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Worth knowing, but Winamp handles it
+    Info,
+    /// Winamp shows the skin, but parts of it will be wrong or fall back to the base skin
+    Warning,
+    /// The skin is broken
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Kinds of problems `Wsz::validate` looks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationIssueKind {
+    /// The skin has no copy of a sprite sheet
+    MissingSheet {
+        /// Name of the sheet, e.g. "MAIN.BMP"
+        sheet: String,
+    },
+    /// A sprite sheet is in the skin but could not be decoded
+    UnreadableSheet {
+        /// Name of the sheet
+        sheet: String,
+    },
+    /// A sprite sheet is too small to hold all of its sprites
+    UndersizedSheet {
+        /// Name of the sheet
+        sheet: String,
+        /// Size of the sheet
+        size: (u32, u32),
+        /// Smallest size that holds every sprite
+        required: (u32, u32),
+    },
+    /// A sprite is not the size Winamp draws it at
+    SpriteSize {
+        /// Name of the sprite
+        sprite: String,
+        /// Size of the sprite
+        size: (u32, u32),
+        /// Size Winamp expects
+        expected: (u32, u32),
+    },
+    /// A text configuration file is missing, so Winamp uses its defaults
+    MissingTextFile {
+        /// Name of the file, e.g. "pledit.txt"
+        file: String,
+    },
+    /// A line of a text configuration file could not be parsed and was skipped
+    Unparsed(ParseWarning),
+    /// A problem with a window region in region.txt
+    Region(RegionIssue),
+}
+
+/// A problem found by `Wsz::validate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// How serious the problem is
+    pub severity: Severity,
+    /// What the problem is
+    pub kind: ValidationIssueKind,
+}
+
+impl fmt::Display for ValidationIssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssueKind::MissingSheet { sheet } => write!(f, "{} is missing", sheet),
+            ValidationIssueKind::UnreadableSheet { sheet } => write!(f, "{} could not be decoded", sheet),
+            ValidationIssueKind::UndersizedSheet { sheet, size, required } => write!(
+                f,
+                "{} is {}x{}, at least {}x{} is needed",
+                sheet, size.0, size.1, required.0, required.1
+            ),
+            ValidationIssueKind::SpriteSize { sprite, size, expected } => write!(
+                f,
+                "sprite {} is {}x{}, expected {}x{}",
+                sprite, size.0, size.1, expected.0, expected.1
+            ),
+            ValidationIssueKind::MissingTextFile { file } => write!(f, "{} is missing", file),
+            ValidationIssueKind::Unparsed(warning) => write!(f, "{}", warning),
+            ValidationIssueKind::Region(issue) => write!(f, "region.txt {}", issue),
+        }
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.kind)
+    }
+}
+
+/// Everything `Wsz::validate` found wrong with a skin
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Get all of the problems, in the order they were found
+    pub fn issues(&self) -> &[ValidationIssue] {
+        &self.issues
+    }
+
+    /// Get the problems of a particular severity
+    pub fn issues_with_severity(&self, severity: Severity) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues.iter().filter(move |issue| issue.severity == severity)
+    }
+
+    /// Get the severity of the most serious problem, or None if nothing was found
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.issues.iter().map(|issue| issue.severity).max()
+    }
+
+    /// Whether any problem is an error
+    pub fn has_errors(&self) -> bool {
+        self.max_severity() == Some(Severity::Error)
+    }
+
+    /// Whether nothing at all was found
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Describes the report as JSON
+    ///
+    /// The document has an `issues` array with each problem's `severity` and `message`, in the order they were found.
+    ///
+    /// # Returns
+    ///
+    /// The JSON document
+    pub fn to_json(&self) -> String {
+        let issues: Vec<String> = self
+            .issues
+            .iter()
+            .map(|issue| {
+                format!(
+                    r#"    {{"severity": "{}", "message": {}}}"#,
+                    issue.severity,
+                    json_string(&issue.kind.to_string())
+                )
+            })
+            .collect();
+
+        let mut json = String::new();
+        let _ = writeln!(json, "{{");
+        let _ = writeln!(json, r#"  "issues": {}"#, json_array(&issues));
+        let _ = writeln!(json, "}}");
+        json
+    }
+
+    fn push(&mut self, severity: Severity, kind: ValidationIssueKind) {
+        self.issues.push(ValidationIssue { severity, kind });
+    }
+}
+
+/// Checks a skin's sprite sheets, sprites and text files
+pub(crate) fn validate(skin: &Wsz) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let sprite_manager = SpriteManager::new();
+    let definitions = sprite_manager.get_sprite_definitions();
+    let has_file = |file: &str| sprites::find_sprite_sheet(&skin.files, file).is_some();
+
+    for sheet in SpriteManager::sprite_sheet_names() {
+        // sheets left out by WszOptions were never decoded, so there is nothing to check
+        if skin.unloaded_sheets.contains(&sheet) {
+            continue;
+        }
+        let mut sheet_defs = definitions
+            .values()
+            .filter(|def| def.sprite_sheet == sheet)
+            .collect::<Vec<_>>();
+        let has_sprites = sheet_defs.iter().any(|def| skin.sprites.contains_key(&def.name));
+
+        let Some(key) = sprites::find_sprite_sheet(&skin.files, &sheet) else {
+            // sprites set without a sheet are drawn onto a new one when the skin is saved
+            if !has_sprites {
+                let severity = match sheet.as_str() {
+                    "MAIN.BMP" => Severity::Error,
+                    // Winamp draws the balance slider from VOLUME.BMP, and numbers from either sheet
+                    "BALANCE.BMP" if has_file("VOLUME.BMP") => Severity::Info,
+                    "NUMBERS.BMP" if has_file("NUMS_EX.BMP") => Severity::Info,
+                    "NUMS_EX.BMP" if has_file("NUMBERS.BMP") => Severity::Info,
+                    sheet if OPTIONAL_SHEETS.contains(&sheet) => Severity::Info,
+                    _ => Severity::Warning,
+                };
+                report.push(severity, ValidationIssueKind::MissingSheet { sheet });
+            }
+            continue;
+        };
+
+        let size = image::ImageReader::new(Cursor::new(&skin.files[key]))
+            .with_guessed_format()
+            .ok()
+            .and_then(|reader| reader.into_dimensions().ok());
+        let Some(size) = size else {
+            report.push(Severity::Error, ValidationIssueKind::UnreadableSheet { sheet });
+            continue;
+        };
+
+        let required = sheet_defs.iter().fold((0, 0), |(width, height), def| {
+            (width.max(def.x + def.width), height.max(def.y + def.height))
+        });
+        if size.0 < required.0 || size.1 < required.1 {
+            // sprites cut off by the sheet's edge are covered by this
+            report.push(
+                Severity::Warning,
+                ValidationIssueKind::UndersizedSheet { sheet, size, required },
+            );
+            continue;
+        }
+        // a sheet large enough for its sprites only contributes none if its pixels fail to decode
+        if !has_sprites {
+            report.push(Severity::Error, ValidationIssueKind::UnreadableSheet { sheet });
+            continue;
+        }
+
+        sheet_defs.sort_by(|a, b| a.name.cmp(&b.name));
+        for def in sheet_defs {
+            let Some(sprite) = skin.sprites.get(&def.name) else {
+                continue;
+            };
+            let size = sprite.dimensions();
+            let expected = (def.width, def.height);
+            if size != expected {
+                report.push(
+                    Severity::Warning,
+                    ValidationIssueKind::SpriteSize {
+                        sprite: def.name.clone(),
+                        size,
+                        expected,
+                    },
+                );
+            }
+        }
+    }
+
+    let mut config = SkinConfig::default();
+    config.set_vis_colors(skin.vis_colors.clone());
+    config.set_pledit(skin.pledit.clone());
+    config.set_regions(skin.regions.clone());
+    for (file, content) in config.files() {
+        // a file set since loading is written when the skin is saved
+        if !has_file(file) && content.is_none() {
+            // a rectangular window is normal, while default colors rarely match a skin
+            let severity = if file == "region.txt" {
+                Severity::Info
+            } else {
+                Severity::Warning
+            };
+            report.push(
+                severity,
+                ValidationIssueKind::MissingTextFile { file: file.to_string() },
+            );
+        }
+    }
+
+    for warning in &skin.parse_warnings {
+        report.push(Severity::Warning, ValidationIssueKind::Unparsed(warning.clone()));
+    }
+    for issue in skin.regions.validate() {
+        report.push(Severity::Warning, ValidationIssueKind::Region(issue));
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, RgbImage};
+
+    fn missing_text_files(report: &ValidationReport) -> Vec<&str> {
+        report
+            .issues()
+            .iter()
+            .filter_map(|issue| match &issue.kind {
+                ValidationIssueKind::MissingTextFile { file } => Some(file.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_empty_text_file_is_not_missing() {
+        let mut archive = crate::archive::WszArchive::new();
+        archive.insert("pledit.txt".to_string(), Vec::new());
+        let report = validate(&Wsz::from_archive(&archive).unwrap());
+
+        assert_eq!(missing_text_files(&report), ["viscolor.txt", "region.txt"]);
+    }
+
+    #[test]
+    fn test_sheet_too_small_for_any_sprite_is_undersized() {
+        let sheet = crate::encode_bmp(&DynamicImage::ImageRgb8(RgbImage::new(1, 1))).unwrap();
+        let mut archive = crate::archive::WszArchive::new();
+        archive.insert("MAIN.BMP".to_string(), sheet);
+        let report = validate(&Wsz::from_archive(&archive).unwrap());
+
+        let main_issues: Vec<&ValidationIssueKind> = report
+            .issues()
+            .iter()
+            .map(|issue| &issue.kind)
+            .filter(|kind| {
+                matches!(kind, ValidationIssueKind::UnreadableSheet { sheet }
+                    | ValidationIssueKind::UndersizedSheet { sheet, .. } if sheet == "MAIN.BMP")
+            })
+            .collect();
+        assert!(
+            matches!(
+                main_issues[..],
+                [ValidationIssueKind::UndersizedSheet { size: (1, 1), .. }]
+            ),
+            "{:?}",
+            main_issues
+        );
+    }
+}